@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use microclaw_protocol::DeviceAction;
+use serde::{Deserialize, Serialize};
+
+/// Opaque handle a manual-ack handler holds onto while its work is in
+/// flight, then passes back to `RuntimeState::ack` once it succeeds to
+/// release the command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AckToken(pub u64);
+
+/// One inbound `DeviceAction` withheld from auto-ack because its action is
+/// in the opt-in manual-ack set. Stays here, unreleased, until the handler
+/// finishes the work and calls `ack(token)`; anything still here on restart
+/// was never released and is redelivered rather than treated as handled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingManualAck {
+    pub packet_id: u64,
+    pub action: DeviceAction,
+    #[serde(default)]
+    pub args: serde_json::Value,
+    pub source: String,
+}
+
+/// Tracks inbound commands withheld from auto-ack under manual-ack mode, so
+/// a device crash mid-handler doesn't silently drop the command. Callers
+/// persist `to_bytes`/restore `from_bytes` through `DeviceStorage`, mirroring
+/// how `RuntimeState::with_storage` restores other persisted counters.
+#[derive(Default)]
+pub struct ManualAckRegistry {
+    pending: HashMap<u64, PendingManualAck>,
+}
+
+impl ManualAckRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hold(&mut self, entry: PendingManualAck) -> AckToken {
+        let token = AckToken(entry.packet_id);
+        self.pending.insert(entry.packet_id, entry);
+        token
+    }
+
+    pub fn release(&mut self, token: AckToken) -> Option<PendingManualAck> {
+        self.pending.remove(&token.0)
+    }
+
+    pub fn is_pending(&self, token: AckToken) -> bool {
+        self.pending.contains_key(&token.0)
+    }
+
+    pub fn pending(&self) -> impl Iterator<Item = &PendingManualAck> {
+        self.pending.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<&PendingManualAck> = self.pending.values().collect();
+        entries.sort_by_key(|entry| entry.packet_id);
+        serde_json::to_vec(&entries).unwrap_or_default()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let entries: Vec<PendingManualAck> = serde_json::from_slice(bytes).unwrap_or_default();
+        Self {
+            pending: entries
+                .into_iter()
+                .map(|entry| (entry.packet_id, entry))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,606 @@
+//! Inbound transport authentication: canonical envelope signing and
+//! nonce-based anti-replay, on top of a pluggable signature backend.
+//!
+//! The concrete signature implementation is selected at compile time by
+//! cargo feature, mirroring the multi-backend layout used by rs-matter so
+//! the same call sites build for both the host binary and the constrained
+//! device firmware:
+//!
+//! - `crypto_rustcrypto`: pure-Rust `ed25519-dalek` / `hmac`+`sha2`.
+//! - `crypto_mbedtls`: bindings onto mbedTLS, for ESP-IDF builds.
+//! - `crypto_openssl`: OpenSSL, for the host binary.
+//!
+//! When no backend feature is enabled, [`NullVerifier`] rejects everything,
+//! so a `RuntimeState` that is never given a verifier passes unsigned frames
+//! on the host allowlist alone but rejects every signed frame, and with it
+//! every `Command`/`StatusSnapshot` frame, since those must be signed.
+
+use std::collections::HashMap;
+
+use microclaw_protocol::{Envelope, MessageKind, TransportMessage};
+use serde_json::Value;
+
+/// Verifies the signature over a canonical envelope encoding for a given
+/// source id. Implementations hold (or look up) the key material bound to
+/// that source; `RuntimeState` never sees key bytes directly.
+pub trait SignatureVerifier {
+    fn verify(&self, source: &str, canonical: &[u8], signature: &str) -> bool;
+}
+
+/// Signs the canonical encoding of an outbound frame, the counterpart to
+/// [`SignatureVerifier`] for a device's own emitted `Command`/
+/// `SnapshotRequest` frames. Separate trait (rather than reusing
+/// `SignatureVerifier`) because a device only ever signs with its own key,
+/// never looks one up by source.
+pub trait Signer {
+    fn sign(&self, source: &str, canonical: &[u8]) -> String;
+}
+
+/// Rejects every signature. Used when no crypto backend feature is enabled,
+/// or as a safe default before provisioning has bound a key to a source.
+pub struct NullVerifier;
+
+impl SignatureVerifier for NullVerifier {
+    fn verify(&self, _source: &str, _canonical: &[u8], _signature: &str) -> bool {
+        false
+    }
+}
+
+/// Canonicalize the fields that are authenticated: envelope, kind and
+/// payload, in a fixed order with length-prefixed variable-length fields so
+/// the byte string is unambiguous regardless of how the frame was decoded.
+pub fn canonical_bytes(envelope: &Envelope, kind: &MessageKind, payload: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_lp(&mut out, envelope.source.as_bytes());
+    push_lp(&mut out, envelope.device_id.as_bytes());
+    push_lp(&mut out, envelope.session_id.as_bytes());
+    push_lp(&mut out, envelope.message_id.as_str().as_bytes());
+    out.extend_from_slice(&envelope.v.to_be_bytes());
+    out.extend_from_slice(&envelope.seq.to_be_bytes());
+    let kind_bytes = serde_json::to_vec(kind).unwrap_or_default();
+    push_lp(&mut out, &kind_bytes);
+    let payload_bytes = serde_json::to_vec(payload).unwrap_or_default();
+    push_lp(&mut out, &payload_bytes);
+    out
+}
+
+fn push_lp(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// The default sliding-window width, in counters, for a [`NonceWindow`]
+/// that wasn't given an explicit size.
+const DEFAULT_WINDOW_SIZE: u64 = 64;
+
+/// A sliding window of up to 64 accepted counters for one source. `highest`
+/// tracks the largest counter accepted so far; `window` bit `i` records
+/// whether `highest - i` has already been seen. `window_size` bounds how far
+/// behind `highest` a counter may still be accepted (clamped to 64, the
+/// width of the bitmap).
+#[derive(Clone, Debug)]
+pub struct NonceWindow {
+    highest: Option<u64>,
+    window: u64,
+    window_size: u64,
+}
+
+impl Default for NonceWindow {
+    fn default() -> Self {
+        Self::with_window_size(DEFAULT_WINDOW_SIZE)
+    }
+}
+
+impl NonceWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a window accepting counters up to `window_size` behind the
+    /// highest one seen (clamped to the 64-bit bitmap width).
+    pub fn with_window_size(window_size: u64) -> Self {
+        Self {
+            highest: None,
+            window: 0,
+            window_size: window_size.clamp(1, 64),
+        }
+    }
+
+    /// Changes how far behind `highest` a counter may still be accepted.
+    /// Does not reset `highest`/the bitmap, so in-flight progress for this
+    /// sender is preserved.
+    pub fn set_window_size(&mut self, window_size: u64) {
+        self.window_size = window_size.clamp(1, 64);
+    }
+
+    /// Accepts `counter` if it is not below the window and not already set,
+    /// sliding the window forward when `counter` becomes the new highest.
+    pub fn accept(&mut self, counter: u64) -> bool {
+        let highest = match self.highest {
+            None => {
+                self.highest = Some(counter);
+                self.window = 1;
+                return true;
+            }
+            Some(highest) => highest,
+        };
+
+        if counter > highest {
+            let shift = counter - highest;
+            self.window = if shift >= self.window_size { 0 } else { self.window << shift };
+            self.window |= 1;
+            self.highest = Some(counter);
+            return true;
+        }
+
+        let back = highest - counter;
+        if back >= self.window_size {
+            return false;
+        }
+        let bit = 1u64 << back;
+        if self.window & bit != 0 {
+            return false;
+        }
+        self.window |= bit;
+        true
+    }
+}
+
+/// Per-source replay filters, created lazily on first sight of a source.
+pub struct ReplayFilter {
+    windows: HashMap<String, NonceWindow>,
+    window_size: u64,
+}
+
+impl Default for ReplayFilter {
+    fn default() -> Self {
+        Self::with_window_size(DEFAULT_WINDOW_SIZE)
+    }
+}
+
+impl ReplayFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a filter whose per-source windows accept counters up to
+    /// `window_size` behind the highest one seen for that source.
+    pub fn with_window_size(window_size: u64) -> Self {
+        Self {
+            windows: HashMap::new(),
+            window_size: window_size.clamp(1, 64),
+        }
+    }
+
+    /// Changes the window size used for every tracked source, including
+    /// ones already seen, without resetting their accepted-counter progress.
+    pub fn set_window_size(&mut self, window_size: u64) {
+        self.window_size = window_size.clamp(1, 64);
+        for window in self.windows.values_mut() {
+            window.set_window_size(self.window_size);
+        }
+    }
+
+    /// Parses `nonce` as a hex-encoded `u64` and checks it against the
+    /// sliding window for `source`. A missing or unparseable nonce is
+    /// treated as a replay failure.
+    pub fn accept(&mut self, source: &str, nonce: Option<&str>) -> bool {
+        let Some(nonce) = nonce.and_then(|raw| u64::from_str_radix(raw.trim(), 16).ok()) else {
+            return false;
+        };
+        self.accept_counter(source, nonce)
+    }
+
+    /// Checks a raw monotonic counter (e.g. `envelope.seq`) against the
+    /// sliding window for `source`, for callers with a plain counter rather
+    /// than a hex-encoded nonce to parse.
+    pub fn accept_counter(&mut self, source: &str, counter: u64) -> bool {
+        let window_size = self.window_size;
+        self.windows
+            .entry(source.to_owned())
+            .or_insert_with(|| NonceWindow::with_window_size(window_size))
+            .accept(counter)
+    }
+}
+
+/// Why an inbound frame's authentication failed, so callers can surface a
+/// distinct reason instead of a single generic rejection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthFailure {
+    InvalidSignature,
+    ReplayedNonce,
+}
+
+/// Runs signature verification followed by the nonce replay check for an
+/// inbound frame. Returns `Ok(())` only if both checks pass.
+pub fn authenticate(
+    verifier: &dyn SignatureVerifier,
+    replay: &mut ReplayFilter,
+    msg: &TransportMessage,
+) -> Result<(), AuthFailure> {
+    let Some(signature) = msg.signature.as_deref() else {
+        return Err(AuthFailure::InvalidSignature);
+    };
+    let canonical = canonical_bytes(&msg.envelope, &msg.kind, &msg.payload);
+    if !verifier.verify(msg.envelope.source.as_str(), &canonical, signature) {
+        return Err(AuthFailure::InvalidSignature);
+    }
+    if !replay.accept(msg.envelope.source.as_str(), msg.nonce.as_deref()) {
+        return Err(AuthFailure::ReplayedNonce);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "crypto_rustcrypto")]
+pub mod rustcrypto {
+    use super::SignatureVerifier;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+    use std::collections::HashMap;
+
+    /// Ed25519 verification backed by `ed25519-dalek`, keyed by source id.
+    pub struct Ed25519Verifier {
+        keys: HashMap<String, VerifyingKey>,
+    }
+
+    impl Ed25519Verifier {
+        pub fn new(keys: HashMap<String, VerifyingKey>) -> Self {
+            Self { keys }
+        }
+    }
+
+    impl SignatureVerifier for Ed25519Verifier {
+        fn verify(&self, source: &str, canonical: &[u8], signature: &str) -> bool {
+            let Some(key) = self.keys.get(source) else {
+                return false;
+            };
+            let Ok(sig_bytes) = hex_decode(signature) else {
+                return false;
+            };
+            let Ok(sig) = Signature::from_slice(&sig_bytes) else {
+                return false;
+            };
+            key.verify(canonical, &sig).is_ok()
+        }
+    }
+
+    fn hex_decode(raw: &str) -> Result<Vec<u8>, ()> {
+        if raw.len() % 2 != 0 {
+            return Err(());
+        }
+        (0..raw.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&raw[i..i + 2], 16).map_err(|_| ()))
+            .collect()
+    }
+
+    fn hex_encode(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    /// Ed25519 signing backed by `ed25519-dalek`, the outbound counterpart
+    /// to [`Ed25519Verifier`].
+    pub struct Ed25519Signer {
+        key: ed25519_dalek::SigningKey,
+    }
+
+    impl Ed25519Signer {
+        pub fn new(key: ed25519_dalek::SigningKey) -> Self {
+            Self { key }
+        }
+    }
+
+    impl super::Signer for Ed25519Signer {
+        fn sign(&self, _source: &str, canonical: &[u8]) -> String {
+            use ed25519_dalek::Signer as _;
+            hex_encode(&self.key.sign(canonical).to_bytes())
+        }
+    }
+
+    /// HMAC-SHA256 signing keyed by a shared secret, the outbound
+    /// counterpart to [`HmacSha256Verifier`].
+    pub struct HmacSha256Signer {
+        key: Vec<u8>,
+    }
+
+    impl HmacSha256Signer {
+        pub fn new(key: Vec<u8>) -> Self {
+            Self { key }
+        }
+    }
+
+    impl super::Signer for HmacSha256Signer {
+        fn sign(&self, _source: &str, canonical: &[u8]) -> String {
+            use hmac::{Hmac, Mac};
+            use sha2::Sha256;
+
+            let mut mac = Hmac::<Sha256>::new_from_slice(&self.key)
+                .expect("HMAC accepts any key length");
+            mac.update(canonical);
+            hex_encode(&mac.finalize().into_bytes())
+        }
+    }
+
+    /// HMAC-SHA256 verification keyed by a per-source shared secret, the
+    /// default signature backend when no asymmetric key exchange has been
+    /// provisioned for a deployment — cheaper to set up than `Ed25519Verifier`
+    /// since both sides just need the same bytes, at the cost of not being
+    /// able to prove which side produced a given message.
+    pub struct HmacSha256Verifier {
+        keys: HashMap<String, Vec<u8>>,
+    }
+
+    impl HmacSha256Verifier {
+        pub fn new(keys: HashMap<String, Vec<u8>>) -> Self {
+            Self { keys }
+        }
+    }
+
+    impl SignatureVerifier for HmacSha256Verifier {
+        fn verify(&self, source: &str, canonical: &[u8], signature: &str) -> bool {
+            use hmac::{Hmac, Mac};
+            use sha2::Sha256;
+
+            let Some(key) = self.keys.get(source) else {
+                return false;
+            };
+            let Ok(expected_tag) = hex_decode(signature) else {
+                return false;
+            };
+            let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(key) else {
+                return false;
+            };
+            mac.update(canonical);
+            mac.verify_slice(&expected_tag).is_ok()
+        }
+    }
+}
+
+/// Encrypted binary framing for `WsTransport`, used in place of plaintext
+/// `serde_json` + `FrameType::Text` once a shared key has been provisioned.
+/// Frames are `[u24 length][u64 counter][AES-256-CTR ciphertext][16-byte MAC]`,
+/// where the MAC folds the counter and ciphertext into a Keccak/SHA3 state
+/// keyed by a secret separate from the encryption key.
+#[cfg(feature = "crypto_rustcrypto")]
+pub mod binary_codec {
+    use aes::cipher::{KeyIvInit, StreamCipher};
+    use aes::Aes256;
+    use sha3::{Digest, Sha3_256};
+
+    type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+    /// Frames above this size are rejected without attempting to decrypt.
+    pub const MAX_PAYLOAD_SIZE: usize = 64 * 1024;
+    const MAC_LEN: usize = 16;
+    const LEN_PREFIX: usize = 3;
+    const COUNTER_LEN: usize = 8;
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum CodecError {
+        PayloadTooLarge,
+        FrameTooShort,
+        LengthMismatch,
+        MacMismatch,
+    }
+
+    /// Key material for one transport session: a distinct encryption key
+    /// and MAC secret, plus the session's base IV. `encode`/`decode` never
+    /// use `iv` on its own -- every message XORs in its own counter (see
+    /// [`message_iv`]) so two messages in the same session never reuse the
+    /// same AES-256-CTR keystream, which would otherwise let an attacker
+    /// cancel it out by XOR-ing the two ciphertexts together.
+    pub struct BinaryCodecKey {
+        pub enc_key: [u8; 32],
+        pub iv: [u8; 16],
+        pub mac_secret: [u8; 32],
+    }
+
+    /// Combines the session's base IV with a per-message `counter`, so every
+    /// message gets a distinct CTR initial counter block without either side
+    /// having to keep the full IV in sync out of band. The low 8 bytes of
+    /// `base_iv` are treated as a block counter and XORed with `counter`;
+    /// the high 8 bytes stay fixed as a per-session nonce prefix.
+    fn message_iv(base_iv: &[u8; 16], counter: u64) -> [u8; 16] {
+        let mut iv = *base_iv;
+        for (byte, counter_byte) in iv[COUNTER_LEN..].iter_mut().zip(counter.to_be_bytes()) {
+            *byte ^= counter_byte;
+        }
+        iv
+    }
+
+    /// Encrypts and frames `plaintext`, ready to send over `FrameType::Binary`.
+    /// `counter` must never repeat within the lifetime of `key` -- callers
+    /// typically draw it from a per-session counter that increments on every
+    /// call, independent of any application-level sequence number.
+    pub fn encode(
+        key: &BinaryCodecKey,
+        counter: u64,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CodecError> {
+        if plaintext.len() > MAX_PAYLOAD_SIZE {
+            return Err(CodecError::PayloadTooLarge);
+        }
+        let iv = message_iv(&key.iv, counter);
+        let mut ciphertext = plaintext.to_vec();
+        Aes256Ctr::new(&key.enc_key.into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+        let counter_bytes = counter.to_be_bytes();
+        let mac = fold_mac(&key.mac_secret, &counter_bytes, &ciphertext);
+        let len = ciphertext.len() as u32;
+        let mut framed =
+            Vec::with_capacity(LEN_PREFIX + COUNTER_LEN + ciphertext.len() + MAC_LEN);
+        framed.push((len >> 16) as u8);
+        framed.push((len >> 8) as u8);
+        framed.push(len as u8);
+        framed.extend_from_slice(&counter_bytes);
+        framed.extend_from_slice(&ciphertext);
+        framed.extend_from_slice(&mac);
+        Ok(framed)
+    }
+
+    /// Verifies the MAC in constant time, then decrypts. Any length or MAC
+    /// failure returns an error without attempting to decrypt further, so
+    /// the caller can count it as a dropped frame rather than parse it. The
+    /// per-message counter travels with the frame (authenticated by the
+    /// MAC), so decoding never depends on the receiver tracking the sender's
+    /// counter out of band.
+    pub fn decode(key: &BinaryCodecKey, framed: &[u8]) -> Result<Vec<u8>, CodecError> {
+        if framed.len() < LEN_PREFIX + COUNTER_LEN + MAC_LEN {
+            return Err(CodecError::FrameTooShort);
+        }
+        let len =
+            ((framed[0] as usize) << 16) | ((framed[1] as usize) << 8) | framed[2] as usize;
+        if len > MAX_PAYLOAD_SIZE {
+            return Err(CodecError::PayloadTooLarge);
+        }
+        if framed.len() != LEN_PREFIX + COUNTER_LEN + len + MAC_LEN {
+            return Err(CodecError::LengthMismatch);
+        }
+
+        let counter_bytes = &framed[LEN_PREFIX..LEN_PREFIX + COUNTER_LEN];
+        let ciphertext_start = LEN_PREFIX + COUNTER_LEN;
+        let ciphertext = &framed[ciphertext_start..ciphertext_start + len];
+        let received_mac = &framed[ciphertext_start + len..];
+        let expected_mac = fold_mac(&key.mac_secret, counter_bytes, ciphertext);
+        if !constant_time_eq(&expected_mac, received_mac) {
+            return Err(CodecError::MacMismatch);
+        }
+
+        let counter = u64::from_be_bytes(counter_bytes.try_into().expect("exactly 8 bytes"));
+        let iv = message_iv(&key.iv, counter);
+        let mut plaintext = ciphertext.to_vec();
+        Aes256Ctr::new(&key.enc_key.into(), &iv.into()).apply_keystream(&mut plaintext);
+        Ok(plaintext)
+    }
+
+    fn fold_mac(mac_secret: &[u8; 32], counter_bytes: &[u8], ciphertext: &[u8]) -> [u8; MAC_LEN] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(mac_secret);
+        hasher.update(counter_bytes);
+        hasher.update(ciphertext);
+        let digest = hasher.finalize();
+        let mut mac = [0u8; MAC_LEN];
+        mac.copy_from_slice(&digest[..MAC_LEN]);
+        mac
+    }
+
+    fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+        a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn test_key() -> BinaryCodecKey {
+            BinaryCodecKey {
+                enc_key: [1u8; 32],
+                iv: [2u8; 16],
+                mac_secret: [3u8; 32],
+            }
+        }
+
+        #[test]
+        fn round_trips_plaintext() {
+            let key = test_key();
+            let framed = encode(&key, 0, b"hello microclaw").unwrap();
+            let decoded = decode(&key, &framed).unwrap();
+            assert_eq!(decoded, b"hello microclaw");
+        }
+
+        #[test]
+        fn rejects_payload_over_max_size() {
+            let key = test_key();
+            let huge = vec![0u8; MAX_PAYLOAD_SIZE + 1];
+            assert_eq!(encode(&key, 0, &huge), Err(CodecError::PayloadTooLarge));
+        }
+
+        #[test]
+        fn rejects_tampered_ciphertext() {
+            let key = test_key();
+            let mut framed = encode(&key, 0, b"hello").unwrap();
+            let last = framed.len() - 1;
+            framed[LEN_PREFIX + COUNTER_LEN] ^= 0xFF;
+            let _ = last;
+            assert_eq!(decode(&key, &framed), Err(CodecError::MacMismatch));
+        }
+
+        #[test]
+        fn rejects_truncated_frame() {
+            let key = test_key();
+            let framed = encode(&key, 0, b"hello").unwrap();
+            assert_eq!(
+                decode(&key, &framed[..framed.len() - 1]),
+                Err(CodecError::LengthMismatch)
+            );
+        }
+
+        #[test]
+        fn different_counters_produce_different_keystreams() {
+            let key = test_key();
+            let plaintext = [0u8; 32];
+            let first = encode(&key, 0, &plaintext).unwrap();
+            let second = encode(&key, 1, &plaintext).unwrap();
+
+            let ciphertext_start = LEN_PREFIX + COUNTER_LEN;
+            let ciphertext_end = ciphertext_start + plaintext.len();
+            assert_ne!(
+                first[ciphertext_start..ciphertext_end],
+                second[ciphertext_start..ciphertext_end]
+            );
+        }
+
+        #[test]
+        fn a_counter_tampered_with_in_transit_fails_the_mac() {
+            let key = test_key();
+            let mut framed = encode(&key, 0, b"hello").unwrap();
+            framed[LEN_PREFIX] ^= 0xFF;
+            assert_eq!(decode(&key, &framed), Err(CodecError::MacMismatch));
+        }
+
+        #[test]
+        fn decode_reconstructs_whatever_counter_the_frame_carries() {
+            let key = test_key();
+            let framed = encode(&key, 42, b"hello microclaw").unwrap();
+            assert_eq!(decode(&key, &framed).unwrap(), b"hello microclaw");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonce_window_rejects_replays_in_order() {
+        let mut window = NonceWindow::new();
+        assert!(window.accept(10));
+        assert!(!window.accept(10));
+        assert!(window.accept(11));
+        assert!(window.accept(9));
+        assert!(!window.accept(9));
+    }
+
+    #[test]
+    fn nonce_window_rejects_below_the_window() {
+        let mut window = NonceWindow::new();
+        assert!(window.accept(100));
+        assert!(!window.accept(30));
+    }
+
+    #[test]
+    fn null_verifier_rejects_everything() {
+        let verifier = NullVerifier;
+        assert!(!verifier.verify("host", b"anything", "deadbeef"));
+    }
+
+    #[test]
+    fn replay_filter_requires_a_parseable_nonce() {
+        let mut replay = ReplayFilter::new();
+        assert!(!replay.accept("host", None));
+        assert!(!replay.accept("host", Some("not-hex")));
+        assert!(replay.accept("host", Some("1a")));
+    }
+}
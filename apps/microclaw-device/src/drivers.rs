@@ -62,6 +62,212 @@ pub trait DisplayDriver {
     }
     fn set_brightness(&mut self, level: u8) -> Result<(), DriverError>;
     fn flush_region(&mut self, _region: Rect, _data: &[u16]) -> Result<(), DriverError>;
+
+    /// Whether this driver can hold two framebuffers and swap them
+    /// atomically on flush. Renderers check this before opting into
+    /// double-buffered presentation; single-buffer displays keep flushing
+    /// directly into the one buffer they have.
+    fn supports_double_buffer(&self) -> bool {
+        false
+    }
+
+    /// Releases the underlying hardware for a low-power sleep while
+    /// preserving logical state (brightness, rotation, ...) so `resume`
+    /// can bring the panel back identical. Defaults to a plain `deinit`;
+    /// override when `deinit` would otherwise discard state `resume`
+    /// needs to reapply.
+    fn pause(&mut self) -> Result<(), DriverError> {
+        self.deinit()
+    }
+
+    /// Re-initializes the hardware after `pause`. Defaults to a plain
+    /// `init`; override to reapply saved state the backend doesn't
+    /// remember across a hardware reset.
+    fn resume(&mut self) -> Result<(), DriverError> {
+        self.init()
+    }
+}
+
+/// Keeps two RGB565 framebuffers for a driver that reports
+/// [`DisplayDriver::supports_double_buffer`]: callers render into
+/// [`DoubleBuffer::back_mut`], then [`DoubleBuffer::present`] flushes and
+/// swaps atomically so a partial write from the next frame can never tear a
+/// frame still being scanned out, the way DRM page-flipping avoids tearing.
+pub struct DoubleBuffer {
+    buffers: [Vec<u16>; 2],
+    front: usize,
+}
+
+impl DoubleBuffer {
+    pub fn new(pixel_count: usize) -> Self {
+        Self {
+            buffers: [vec![0u16; pixel_count], vec![0u16; pixel_count]],
+            front: 0,
+        }
+    }
+
+    pub fn back_mut(&mut self) -> &mut [u16] {
+        &mut self.buffers[1 - self.front]
+    }
+
+    /// Flushes the back buffer to `display` and swaps it to the front only
+    /// if the flush succeeds, so a failed flush doesn't present a half
+    /// updated frame.
+    pub fn present(&mut self, display: &mut dyn DisplayDriver, region: Rect) -> Result<(), DriverError> {
+        let back = 1 - self.front;
+        display.flush_region(region, &self.buffers[back])?;
+        self.front = back;
+        Ok(())
+    }
+}
+
+fn rect_area(rect: Rect) -> u64 {
+    u64::from(rect.w) * u64::from(rect.h)
+}
+
+fn union_rect(a: Rect, b: Rect) -> Rect {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    let right = a.x.saturating_add(a.w).max(b.x.saturating_add(b.w));
+    let bottom = a.y.saturating_add(a.h).max(b.y.saturating_add(b.h));
+    Rect {
+        x,
+        y,
+        w: right.saturating_sub(x),
+        h: bottom.saturating_sub(y),
+    }
+}
+
+/// Whether unioning `a` and `b` is worth it: their combined bounding box
+/// must not be much larger than the sum of their areas (at most 1.5x), a
+/// cheap heuristic that avoids merging two distant rects into one mostly-
+/// empty flush.
+fn should_merge(a: Rect, b: Rect) -> bool {
+    let sum_area = rect_area(a) + rect_area(b);
+    if sum_area == 0 {
+        return true;
+    }
+    let union_area = rect_area(union_rect(a, b));
+    union_area.saturating_mul(2) <= sum_area.saturating_mul(3)
+}
+
+/// Repeatedly unions any two rects whose combined bounding box clears
+/// [`should_merge`]'s area check until no more merges happen, leaving a
+/// minimal set of non-redundant rectangles to flush.
+fn merge_dirty_rects(mut rects: Vec<Rect>) -> Vec<Rect> {
+    rects.sort_by_key(|rect| (rect.x, rect.y));
+    loop {
+        let mut merged_any = false;
+        let mut output: Vec<Rect> = Vec::with_capacity(rects.len());
+        'outer: for rect in rects {
+            for existing in output.iter_mut() {
+                if should_merge(*existing, rect) {
+                    *existing = union_rect(*existing, rect);
+                    merged_any = true;
+                    continue 'outer;
+                }
+            }
+            output.push(rect);
+        }
+        rects = output;
+        if !merged_any {
+            break;
+        }
+    }
+    rects
+}
+
+/// A full `u16` RGB565 backing store plus a list of dirty `Rect`s,
+/// coalesced into a minimal set of bounding rectangles on `present` and
+/// pushed as one `flush_region` call each, mirroring the DRM page-flip
+/// model (queue damage, flip on vsync, get a completion event) instead of
+/// flushing the whole screen for every small UI update.
+pub struct FrameBuffer {
+    pixels: Vec<u16>,
+    width: u16,
+    height: u16,
+    dirty: Vec<Rect>,
+    merged_rect_count: u64,
+    pixels_pushed: u64,
+}
+
+impl FrameBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        let len = usize::from(width).saturating_mul(usize::from(height));
+        Self {
+            pixels: vec![0u16; len],
+            width,
+            height,
+            dirty: Vec::new(),
+            merged_rect_count: 0,
+            pixels_pushed: 0,
+        }
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [u16] {
+        &mut self.pixels
+    }
+
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn mark_dirty(&mut self, rect: Rect) {
+        self.dirty.push(rect);
+    }
+
+    /// Total merged rects pushed across every `present` call, for
+    /// profiling how much the damage-merge heuristic is saving.
+    pub fn merged_rect_count(&self) -> u64 {
+        self.merged_rect_count
+    }
+
+    /// Total pixels pushed across every `present` call.
+    pub fn pixels_pushed(&self) -> u64 {
+        self.pixels_pushed
+    }
+
+    /// Merges the accumulated dirty rects (see [`merge_dirty_rects`]) and
+    /// issues one `flush_region` per merged rect, copying each
+    /// sub-rectangle row-by-row into a contiguous scratch buffer sized
+    /// `w*h` (the invariant `flush_region` enforces). Calls `on_flip_done`
+    /// once every merged rect has been pushed successfully. A no-op (and
+    /// `on_flip_done` is not called) if nothing was marked dirty.
+    pub fn present<D: DisplayDriver>(
+        &mut self,
+        display: &mut D,
+        mut on_flip_done: impl FnMut(),
+    ) -> Result<(), DriverError> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+
+        let merged = merge_dirty_rects(std::mem::take(&mut self.dirty));
+        self.merged_rect_count = self.merged_rect_count.saturating_add(merged.len() as u64);
+
+        let mut scratch = Vec::new();
+        for rect in merged {
+            let len = usize::from(rect.w).saturating_mul(usize::from(rect.h));
+            scratch.clear();
+            scratch.reserve(len);
+            for row in rect.y..rect.y.saturating_add(rect.h) {
+                let row_start =
+                    usize::from(row).saturating_mul(usize::from(self.width)) + usize::from(rect.x);
+                scratch.extend_from_slice(&self.pixels[row_start..row_start + usize::from(rect.w)]);
+            }
+            display.flush_region(rect, &scratch)?;
+            self.pixels_pushed = self
+                .pixels_pushed
+                .saturating_add(u64::from(rect.w) * u64::from(rect.h));
+        }
+
+        on_flip_done();
+        Ok(())
+    }
 }
 
 pub trait TouchDriver {
@@ -73,6 +279,370 @@ pub trait TouchDriver {
     }
     fn clear_interrupt(&mut self) {}
     fn read_event(&mut self) -> Option<TouchEventPayload>;
+
+    /// Releases the underlying hardware for a low-power sleep while
+    /// preserving logical state (the transform, queued/pending events).
+    /// Defaults to a plain `deinit`; override when `deinit` would
+    /// otherwise discard state `resume` needs to reapply.
+    fn pause(&mut self) -> Result<(), DriverError> {
+        self.deinit()
+    }
+
+    /// Re-initializes the hardware after `pause`. Defaults to a plain
+    /// `init`.
+    fn resume(&mut self) -> Result<(), DriverError> {
+        self.init()
+    }
+
+    /// Cumulative successful `init()` calls, so a [`DriverSupervisor`] can
+    /// observe reinit churn without the driver exposing a bespoke counter.
+    /// Defaults to `0` for drivers that don't track it.
+    fn init_calls(&self) -> u64 {
+        0
+    }
+
+    /// Cumulative successful `flush_region()` calls.
+    fn flush_calls(&self) -> u64 {
+        0
+    }
+}
+
+/// Notified when a [`DeviceSession`] transitions, mirroring Smithay's
+/// `SessionObserver` callback on a VT switch so other subsystems (e.g. the
+/// event loop) can react to a pause/resume without polling
+/// `DeviceSession::is_active` every tick.
+pub trait SessionObserver {
+    fn paused(&mut self) {}
+    fn resumed(&mut self) {}
+}
+
+/// Coordinates pausing and resuming a display/touch driver pair together,
+/// the way Smithay's session/seat backend pauses and resumes DRM/libinput
+/// devices when the compositor loses and regains control of the VT. Lets
+/// the firmware enter a low-power sleep (display off, touch IRQ parked)
+/// and wake again without a full application restart.
+pub struct DeviceSession<D: DisplayDriver, T: TouchDriver> {
+    display: D,
+    touch: T,
+    active: bool,
+    observers: Vec<Box<dyn SessionObserver>>,
+}
+
+impl<D: DisplayDriver, T: TouchDriver> DeviceSession<D, T> {
+    pub fn new(display: D, touch: T) -> Self {
+        Self {
+            display,
+            touch,
+            active: true,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn display(&self) -> &D {
+        &self.display
+    }
+
+    pub fn display_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    pub fn touch(&self) -> &T {
+        &self.touch
+    }
+
+    pub fn touch_mut(&mut self) -> &mut T {
+        &mut self.touch
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn register_observer(&mut self, observer: Box<dyn SessionObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Pauses both drivers when transitioning to `false`, resumes both
+    /// when transitioning to `true`, then notifies every registered
+    /// observer. A no-op if already in the requested state. Both drivers
+    /// are always given a chance to transition; this returns the first
+    /// error either one reports.
+    pub fn set_active(&mut self, active: bool) -> Result<(), DriverError> {
+        if active == self.active {
+            return Ok(());
+        }
+
+        let result = if active {
+            let display_result = self.display.resume();
+            let touch_result = self.touch.resume();
+            display_result.and(touch_result)
+        } else {
+            let display_result = self.display.pause();
+            let touch_result = self.touch.pause();
+            display_result.and(touch_result)
+        };
+
+        self.active = active;
+        for observer in &mut self.observers {
+            if active {
+                observer.resumed();
+            } else {
+                observer.paused();
+            }
+        }
+
+        result
+    }
+}
+
+/// Snapshot of a [`DriverSupervisor`]'s health for observability/metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HealthReport {
+    pub display_ok: bool,
+    pub touch_ok: bool,
+    pub flush_calls: u64,
+    pub init_calls: u64,
+    pub error_count: u32,
+    pub uptime_ms: u64,
+}
+
+/// Wraps a `DisplayDriver`+`TouchDriver` pair and enforces liveness with a
+/// periodic cheap probe, modeled on a KWP2000 diagnostic server's
+/// "tester-present" heartbeat and configurable read/write timeouts (as in
+/// the ultimate_nag52 project). After `failure_threshold` consecutive
+/// probe failures it automatically `deinit()`s then `init()`s the display
+/// driver, backing off exponentially between recovery attempts, and
+/// restores the saved brightness so the panel doesn't come back dark. The
+/// backoff timer is only reset by a later successful probe, not
+/// merely by the reinit itself succeeding, so a driver that comes back up
+/// but keeps failing its next probe doesn't get hammered with reinits.
+///
+/// The touch driver is not probed, since `TouchDriver::read_event`/
+/// `is_interrupt_pending` don't return a `Result` to probe against the way
+/// `set_brightness` does for the display: its failure streak stays at zero,
+/// so it is never recovered and `HealthReport::touch_ok` stays true.
+pub struct DriverSupervisor<D: DisplayDriver, T: TouchDriver> {
+    display: D,
+    touch: T,
+    probe_interval_ms: u64,
+    failure_threshold: u32,
+    backoff_base_ms: u64,
+    backoff_cap_ms: u64,
+    last_probe_ms: Option<u64>,
+    started_ms: Option<u64>,
+    display_error_streak: u32,
+    touch_error_streak: u32,
+    error_count: u32,
+    recovery_attempt: u32,
+    next_recovery_ms: u64,
+    last_recovery_ms: Option<u64>,
+    saved_brightness: u8,
+    saved_transform: TouchTransform,
+}
+
+impl<D: DisplayDriver, T: TouchDriver> DriverSupervisor<D, T> {
+    pub fn new(display: D, touch: T) -> Self {
+        Self {
+            display,
+            touch,
+            probe_interval_ms: 5_000,
+            failure_threshold: 3,
+            backoff_base_ms: 500,
+            backoff_cap_ms: 30_000,
+            last_probe_ms: None,
+            started_ms: None,
+            display_error_streak: 0,
+            touch_error_streak: 0,
+            error_count: 0,
+            recovery_attempt: 0,
+            next_recovery_ms: 0,
+            last_recovery_ms: None,
+            saved_brightness: 128,
+            saved_transform: TouchTransform::default(),
+        }
+    }
+
+    pub fn display(&self) -> &D {
+        &self.display
+    }
+
+    pub fn display_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    pub fn touch(&self) -> &T {
+        &self.touch
+    }
+
+    pub fn touch_mut(&mut self) -> &mut T {
+        &mut self.touch
+    }
+
+    /// Sets brightness through the supervisor (rather than reaching
+    /// through `display_mut()`) so a later recovery reapplies it instead
+    /// of leaving the panel at the backend's post-reinit default.
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), DriverError> {
+        self.saved_brightness = level;
+        self.display.set_brightness(level)
+    }
+
+    pub fn set_touch_transform(&mut self, transform: TouchTransform) {
+        self.saved_transform = transform;
+        self.touch.set_transform(transform);
+    }
+
+    fn backoff_ms(&self) -> u64 {
+        let attempt = self.recovery_attempt.max(1).min(7);
+        let backoff = self.backoff_base_ms.saturating_mul(1u64 << (attempt - 1));
+        backoff.min(self.backoff_cap_ms)
+    }
+
+    fn recover_display(&mut self, now_ms: u64) {
+        let _ = self.display.deinit();
+        if self.display.init().is_ok() {
+            let _ = self.display.set_brightness(self.saved_brightness);
+        }
+        self.recovery_attempt = self.recovery_attempt.saturating_add(1);
+        self.next_recovery_ms = now_ms.saturating_add(self.backoff_ms());
+        self.last_recovery_ms = Some(now_ms);
+    }
+
+    fn recover_touch(&mut self, now_ms: u64) {
+        let _ = self.touch.deinit();
+        if self.touch.init().is_ok() {
+            self.touch.set_transform(self.saved_transform);
+        }
+        self.recovery_attempt = self.recovery_attempt.saturating_add(1);
+        self.next_recovery_ms = now_ms.saturating_add(self.backoff_ms());
+        self.last_recovery_ms = Some(now_ms);
+    }
+
+    /// Runs a liveness probe — a no-op brightness re-set for the display —
+    /// at most once per `probe_interval_ms`, recovering the display once
+    /// its consecutive-failure streak reaches `failure_threshold` and the
+    /// backoff window has elapsed.
+    pub fn tick(&mut self, now_ms: u64) {
+        self.started_ms.get_or_insert(now_ms);
+
+        let due = self.last_probe_ms.map_or(true, |last| {
+            now_ms.saturating_sub(last) >= self.probe_interval_ms
+        });
+        if !due {
+            return;
+        }
+        self.last_probe_ms = Some(now_ms);
+
+        match self.display.set_brightness(self.saved_brightness) {
+            Ok(()) => {
+                self.display_error_streak = 0;
+                self.recovery_attempt = 0;
+            }
+            Err(_) => {
+                self.display_error_streak = self.display_error_streak.saturating_add(1);
+                self.error_count = self.error_count.saturating_add(1);
+            }
+        }
+
+        let _ = self.touch.is_interrupt_pending();
+        self.touch_error_streak = 0;
+
+        if self.display_error_streak >= self.failure_threshold && now_ms >= self.next_recovery_ms {
+            self.recover_display(now_ms);
+        }
+        if self.touch_error_streak >= self.failure_threshold && now_ms >= self.next_recovery_ms {
+            self.recover_touch(now_ms);
+        }
+    }
+
+    /// When the most recent display or touch recovery ran, for callers
+    /// that want to log/alert on reinit churn beyond what `HealthReport`
+    /// summarizes.
+    pub fn last_recovery_ms(&self) -> Option<u64> {
+        self.last_recovery_ms
+    }
+
+    pub fn health(&self) -> HealthReport {
+        HealthReport {
+            display_ok: self.display_error_streak < self.failure_threshold,
+            touch_ok: self.touch_error_streak < self.failure_threshold,
+            flush_calls: self.display.flush_calls(),
+            init_calls: self.display.init_calls(),
+            error_count: self.error_count,
+            uptime_ms: self
+                .started_ms
+                .map_or(0, |start| self.last_probe_ms.unwrap_or(start).saturating_sub(start)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonId {
+    Primary,
+    Secondary,
+    Power,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonEdge {
+    Down,
+    Up,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ButtonEvent {
+    pub button: ButtonId,
+    pub edge: ButtonEdge,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncoderEvent {
+    pub delta: i8,
+}
+
+/// A synthetic event injected by the host rather than read off real
+/// hardware, e.g. a scripted input channel driving an integration test the
+/// way yuzu's `input_common` lets a UDP test client stand in for a pad.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostInputEvent {
+    pub label: &'static str,
+}
+
+/// One input of any kind `DeviceEventLoop` can consume in a single pass,
+/// unifying touch, physical buttons, a rotary encoder, and host-injected
+/// synthetic events the way bottom's `BottomEvent` unifies key, mouse, and
+/// update events into one loop.
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputEvent {
+    Touch(TouchEventPayload),
+    Button(ButtonEvent),
+    Encoder(EncoderEvent),
+    Host(HostInputEvent),
+}
+
+/// A source `DeviceEventLoop` can drain into the unified `InputEvent`
+/// queue, analogous to `TouchDriver` but not limited to touch. Any
+/// `TouchDriver` already gets a blanket impl below, so existing touch
+/// drivers need no changes to be registered as an `InputSource`.
+pub trait InputSource {
+    fn is_interrupt_pending(&mut self) -> bool {
+        false
+    }
+    fn clear_interrupt(&mut self) {}
+    fn read_event(&mut self) -> Option<InputEvent>;
+}
+
+impl<T: TouchDriver + ?Sized> InputSource for T {
+    fn is_interrupt_pending(&mut self) -> bool {
+        TouchDriver::is_interrupt_pending(self)
+    }
+
+    fn clear_interrupt(&mut self) {
+        TouchDriver::clear_interrupt(self)
+    }
+
+    fn read_event(&mut self) -> Option<InputEvent> {
+        TouchDriver::read_event(self).map(InputEvent::Touch)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -163,6 +733,14 @@ pub mod host {
             self.flush_calls = self.flush_calls.saturating_add(1);
             Ok(())
         }
+
+        fn init_calls(&self) -> u64 {
+            self.init_calls
+        }
+
+        fn flush_calls(&self) -> u64 {
+            self.flush_calls
+        }
     }
 
     pub struct HostTouchDriver {
@@ -223,6 +801,133 @@ pub mod host {
     }
 }
 
+/// Adapts any [`DisplayDriver`] into an `embedded-graphics` draw target, so
+/// UI code can use the ecosystem's primitive/text/image API instead of
+/// hand-packing RGB565 pixel buffers, the way the embassy SPI-display
+/// examples draw onto an ST7789 through `embedded-graphics`.
+#[cfg(feature = "embedded-graphics")]
+pub mod eg {
+    use super::{DisplayDriver, DriverError, Rect};
+    use embedded_graphics::geometry::{OriginDimensions, Size};
+    use embedded_graphics::pixelcolor::raw::RawU16;
+    use embedded_graphics::pixelcolor::Rgb565;
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::Pixel;
+
+    /// Owns a line/tile framebuffer of `u16` RGB565 pixels sized to the
+    /// wrapped driver's `width()×height()`, tracking a bounding dirty rect
+    /// across `draw_iter` calls so `flush` only copies and pushes the
+    /// sub-rectangle that actually changed instead of the whole screen.
+    /// Geometry is in the same coordinate space `TouchTransform`/rotation
+    /// already use, so drawn UI and touch input stay consistent.
+    pub struct EgDisplay<D: DisplayDriver> {
+        display: D,
+        framebuffer: Vec<u16>,
+        width: u16,
+        height: u16,
+        dirty: Option<(u16, u16, u16, u16)>,
+        scratch: Vec<u16>,
+    }
+
+    impl<D: DisplayDriver> EgDisplay<D> {
+        pub fn new(display: D) -> Self {
+            let width = display.width();
+            let height = display.height();
+            let len = usize::from(width).saturating_mul(usize::from(height));
+            Self {
+                display,
+                framebuffer: vec![0u16; len],
+                width,
+                height,
+                dirty: None,
+                scratch: Vec::new(),
+            }
+        }
+
+        pub fn display(&self) -> &D {
+            &self.display
+        }
+
+        pub fn display_mut(&mut self) -> &mut D {
+            &mut self.display
+        }
+
+        fn mark_dirty(&mut self, x: u16, y: u16) {
+            self.dirty = Some(match self.dirty {
+                Some((min_x, min_y, max_x, max_y)) => {
+                    (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+
+        /// Copies the accumulated dirty rect row-by-row into a contiguous
+        /// scratch buffer sized `w*h` (the invariant `flush_region`
+        /// enforces) and issues one `flush_region` call, then clears the
+        /// dirty rect. A no-op if nothing was drawn since the last flush.
+        pub fn flush(&mut self) -> Result<(), DriverError> {
+            let Some((min_x, min_y, max_x, max_y)) = self.dirty else {
+                return Ok(());
+            };
+            let w = max_x - min_x + 1;
+            let h = max_y - min_y + 1;
+            let len = usize::from(w).saturating_mul(usize::from(h));
+            self.scratch.clear();
+            self.scratch.reserve(len);
+            for row in min_y..=max_y {
+                let row_start = usize::from(row).saturating_mul(usize::from(self.width))
+                    + usize::from(min_x);
+                self.scratch
+                    .extend_from_slice(&self.framebuffer[row_start..row_start + usize::from(w)]);
+            }
+            self.display.flush_region(
+                Rect {
+                    x: min_x,
+                    y: min_y,
+                    w,
+                    h,
+                },
+                &self.scratch,
+            )?;
+            self.dirty = None;
+            Ok(())
+        }
+    }
+
+    impl<D: DisplayDriver> OriginDimensions for EgDisplay<D> {
+        fn size(&self) -> Size {
+            Size::new(u32::from(self.width), u32::from(self.height))
+        }
+    }
+
+    impl<D: DisplayDriver> DrawTarget for EgDisplay<D> {
+        type Color = Rgb565;
+        type Error = DriverError;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            for Pixel(point, color) in pixels {
+                if point.x < 0 || point.y < 0 {
+                    continue;
+                }
+                if point.x as u32 >= u32::from(self.width) || point.y as u32 >= u32::from(self.height) {
+                    continue;
+                }
+                let x = point.x as u16;
+                let y = point.y as u16;
+                let idx = usize::from(y).saturating_mul(usize::from(self.width)) + usize::from(x);
+                if let Some(px) = self.framebuffer.get_mut(idx) {
+                    *px = RawU16::from(color).into_inner();
+                }
+                self.mark_dirty(x, y);
+            }
+            Ok(())
+        }
+    }
+}
+
 #[cfg(feature = "esp")]
 pub mod esp {
     use super::{DisplayDriver, DisplayRotation, DriverError, Rect, TouchDriver, TouchTransform};
@@ -515,6 +1220,32 @@ pub mod esp {
             self.last_region_area = Some(u32::from(region.w).saturating_mul(u32::from(region.h)));
             Ok(())
         }
+
+        /// Releases the st77916 handle but keeps `brightness`/`rotation` in
+        /// the struct untouched, so `resume` can reapply them instead of
+        /// coming back at the backend's hardcoded defaults.
+        fn pause(&mut self) -> Result<(), DriverError> {
+            self.backend.deinit()?;
+            self.inited = false;
+            Ok(())
+        }
+
+        fn resume(&mut self) -> Result<(), DriverError> {
+            self.backend.init()?;
+            self.inited = true;
+            self.init_calls = self.init_calls.saturating_add(1);
+            self.backend.set_brightness(self.brightness)?;
+            self.backend.set_rotation(self.rotation)?;
+            Ok(())
+        }
+
+        fn init_calls(&self) -> u64 {
+            self.init_calls
+        }
+
+        fn flush_calls(&self) -> u64 {
+            self.flush_calls
+        }
     }
 
     pub struct EspTouchDriver {
@@ -637,6 +1368,23 @@ pub mod esp {
             transformed.y = y;
             Some(transformed)
         }
+
+        /// Releases the cst816 handle without clearing `queue` or
+        /// `transform`, so events queued before the pause and the active
+        /// touch transform both survive into the next `resume`. `resume`
+        /// keeps the default `init`-based impl, since `init` already
+        /// leaves both alone.
+        fn pause(&mut self) -> Result<(), DriverError> {
+            self.inited = false;
+            self.irq_pending = false;
+            if let Some(handle) = self.handle.take() {
+                let rc = unsafe { cst816_deinit(handle) };
+                if rc != 0 {
+                    return Err(DriverError::Unsupported("cst816 deinit failed"));
+                }
+            }
+            Ok(())
+        }
     }
 
     extern "C" fn cst816_irq_handler(
@@ -1,8 +1,10 @@
-use crate::drivers::TouchDriver;
-use crate::pipeline::TouchPipeline;
+use crate::drivers::{InputEvent, InputSource, TouchDriver};
+use crate::flow::Flow;
+use crate::pipeline::{SwipeDetector, TouchPipeline};
 use crate::renderer::SceneRenderer;
 use crate::runtime::{RuntimeAction, RuntimeState};
-use microclaw_protocol::TransportMessage;
+use crate::transport::TransportBus;
+use microclaw_protocol::{TouchPhase, TransportMessage};
 use crate::pipeline::TOUCH_EVENT_STALE_MS;
 
 #[derive(Clone, Debug)]
@@ -12,6 +14,28 @@ pub struct LoopOutput {
     pub rendered: bool,
     pub offline_entered: bool,
     pub in_safe_mode: bool,
+    /// Whether `transport.is_connected()` read `true` at the end of this
+    /// step, so a caller doesn't have to hold onto the transport separately
+    /// just to check connectivity after the fact.
+    pub transport_connected: bool,
+    /// How many in-flight commands `reclaim_stale_inflight` timed out this
+    /// step.
+    pub stale_inflight_reclaimed: u32,
+    /// How many inbound transport frames were drained and applied this
+    /// step. `step_until_idle` uses this (alongside `outbound`/`rendered`)
+    /// to decide whether another pass could still make progress.
+    pub inbound_processed: usize,
+    /// The release velocity (px/ms) of a pointer that came up fast enough
+    /// this step to count as a fling, so a scrollable scene can decelerate
+    /// from it instead of stopping dead at finger-up. `None` if no `Up`
+    /// this step crossed `pipeline::FLING_MIN_VELOCITY_PX_PER_MS`.
+    pub touch_fling: Option<(f32, f32)>,
+    /// Non-touch `InputEvent`s (buttons, encoder turns, host-injected
+    /// synthetic events) drained this step by `step_with_input_sources`.
+    /// Touch events don't appear here; they're folded into the usual
+    /// `touch_pipeline`/`RuntimeAction` path instead. Empty for the other
+    /// `step*` variants, which don't take `InputSource`s.
+    pub input_events: Vec<InputEvent>,
 }
 
 impl LoopOutput {
@@ -22,6 +46,11 @@ impl LoopOutput {
             rendered: false,
             offline_entered: false,
             in_safe_mode: false,
+            transport_connected: false,
+            stale_inflight_reclaimed: 0,
+            inbound_processed: 0,
+            touch_fling: None,
+            input_events: Vec::new(),
         }
     }
 }
@@ -30,6 +59,27 @@ impl LoopOutput {
 pub struct EventLoopConfig {
     pub render_interval_ms: u64,
     pub offline_timeout_ms: u64,
+    /// Width, in counters, of the per-sender sliding window
+    /// `RuntimeState::apply_transport_message` uses to reject replayed
+    /// `envelope.seq` values. Clamped to 64, the width of the underlying
+    /// bitmap.
+    pub replay_window_size: u64,
+    /// `base` delay for the transport reconnect supervisor's
+    /// decorrelated-jitter backoff — the first retry after a drop lands
+    /// somewhere in `[transport_reconnect_backoff_ms, transport_reconnect_backoff_ms * 3]`.
+    pub transport_reconnect_backoff_ms: u64,
+    /// `cap` on the reconnect supervisor's backoff delay, regardless of how
+    /// many consecutive failures have occurred.
+    pub transport_reconnect_backoff_cap_ms: u64,
+    /// How long an in-flight command may sit unacked before
+    /// `step_with_transport`/`step_with_transport_driver` reclaims it via
+    /// `RuntimeState::reclaim_stale_inflight`.
+    pub stale_inflight_ms: u64,
+    /// Upper bound on how many passes `step_until_idle`/`step_until_idle_with_driver`
+    /// will run in a single call before returning, regardless of whether a
+    /// pass still made progress. Guards against a misbehaving host that
+    /// keeps the loop permanently busy from livelocking the caller.
+    pub max_passes: u32,
 }
 
 impl Default for EventLoopConfig {
@@ -37,6 +87,11 @@ impl Default for EventLoopConfig {
         Self {
             render_interval_ms: 250,
             offline_timeout_ms: 15_000,
+            replay_window_size: 64,
+            transport_reconnect_backoff_ms: 500,
+            transport_reconnect_backoff_cap_ms: 30_000,
+            stale_inflight_ms: 30_000,
+            max_passes: 8,
         }
     }
 }
@@ -46,6 +101,20 @@ pub struct DeviceEventLoop {
     last_render_ms: Option<u64>,
     last_touch_ms: Option<u64>,
     scene_cache: Option<crate::ui::Scene>,
+    /// Delay currently scheduled before the next transport-level reconnect
+    /// attempt, doubling on each consecutive failure (capped at
+    /// `transport_reconnect_backoff_cap_ms`). Distinct from
+    /// `RuntimeState`'s `ReconnectSupervisor`, which paces WiFi-link-gated
+    /// reconnects rather than this event loop's own transport dial retries.
+    transport_retry_delay_ms: u64,
+    next_transport_retry_ms: u64,
+    transport_retry_attempt: u32,
+    /// Classifies horizontal swipes out of pointer 0's touch frames, feeding
+    /// `flow` below. Only pointer 0 is considered for swipe navigation; a
+    /// second active pointer is left for pinch gestures rather than
+    /// confusing the swipe it's built for.
+    swipe_detector: SwipeDetector,
+    flow: Flow,
 }
 
 impl DeviceEventLoop {
@@ -55,9 +124,68 @@ impl DeviceEventLoop {
             last_render_ms: None,
             last_touch_ms: None,
             scene_cache: None,
+            transport_retry_delay_ms: 0,
+            next_transport_retry_ms: 0,
+            transport_retry_attempt: 0,
+            swipe_detector: SwipeDetector::new(),
+            flow: Flow::new(),
         }
     }
 
+    /// Feeds pointer 0's touch frame into `swipe_detector`, and on an
+    /// accepted swipe, asks `flow` to resolve it against the scene
+    /// currently in effect. Shared by `step_with_touch_driver`,
+    /// `step_with_input_sources` and `step_with_transport_driver` so the
+    /// loop variants can't drift.
+    fn apply_swipe_navigation(
+        &mut self,
+        state: &mut RuntimeState,
+        point_x: u16,
+        point_y: u16,
+        pointer_id: u8,
+        phase: TouchPhase,
+        now_ms: u64,
+        out: &mut LoopOutput,
+    ) -> bool {
+        if pointer_id != 0 {
+            return false;
+        }
+
+        let swipe = match phase {
+            TouchPhase::Down => self.swipe_detector.on_down(point_x, point_y),
+            TouchPhase::Move => self.swipe_detector.on_move(point_x, point_y),
+            TouchPhase::Up => self.swipe_detector.on_up(point_x, point_y),
+            TouchPhase::Cancel => {
+                self.swipe_detector.cancel();
+                None
+            }
+            TouchPhase::Unknown => None,
+        };
+
+        let Some(direction) = swipe else {
+            return false;
+        };
+        let Some(target) = self.flow.accept_swipe(state.scene(), direction, now_ms) else {
+            return false;
+        };
+        state.set_nav_scene_override(Some(target));
+        out.ui_messages.push("flow_swipe_navigated");
+        true
+    }
+
+    /// The swipe-driven scene transition `flow` is still mid-animating, if
+    /// any. A renderer that wants to animate scene changes reads this after
+    /// `step`/`step_with_touch_driver` and calls
+    /// `crate::flow::Flow::clear_pending_transition`'s counterpart here,
+    /// [`Self::clear_pending_scene_transition`], once it's done.
+    pub fn pending_scene_transition(&self) -> Option<crate::flow::PendingTransition> {
+        self.flow.pending_transition()
+    }
+
+    pub fn clear_pending_scene_transition(&mut self) {
+        self.flow.clear_pending_transition();
+    }
+
     pub fn step<R: SceneRenderer>(
         &mut self,
         state: &mut RuntimeState,
@@ -88,6 +216,12 @@ impl DeviceEventLoop {
         let mut out = LoopOutput::new();
         let mut frame_dirty = false;
 
+        state.set_replay_window_size(self.config.replay_window_size);
+        state.set_reconnect_backoff_params(
+            self.config.transport_reconnect_backoff_ms,
+            self.config.transport_reconnect_backoff_cap_ms,
+        );
+
         for msg in inbound_transport {
             let action = state.apply_transport_message(msg);
             frame_dirty |= self.process_action(state, action, &mut out);
@@ -99,9 +233,9 @@ impl DeviceEventLoop {
         }
         touch_pipeline.purge_stale(now_ms, TOUCH_EVENT_STALE_MS, &mut self.last_touch_ms);
 
-        while let Some(event) = touch_pipeline.next_frame() {
+        while let Some(event) = touch_pipeline.next_frame(now_ms) {
             let payload = microclaw_protocol::TouchEventPayload {
-                pointer_id: 0,
+                pointer_id: event.pointer_id,
                 phase: event.phase,
                 x: event.point.x,
                 y: event.point.y,
@@ -110,6 +244,19 @@ impl DeviceEventLoop {
             };
             let action = state.apply_touch_event(&payload);
             frame_dirty |= self.process_action(state, action, &mut out);
+            frame_dirty |= self.apply_swipe_navigation(
+                state,
+                event.point.x,
+                event.point.y,
+                event.pointer_id,
+                event.phase,
+                now_ms,
+                &mut out,
+            );
+            if let Some(fling) = event.fling {
+                out.touch_fling = Some((fling.vx, fling.vy));
+                out.ui_messages.push("touch_fling");
+            }
             self.last_touch_ms = Some(now_ms);
         }
 
@@ -129,7 +276,8 @@ impl DeviceEventLoop {
         let force_render = self.last_render_ms.map_or(true, |last| {
             now_ms.saturating_sub(last) >= self.config.render_interval_ms
         }) || self.scene_cache != Some(target_scene)
-            || frame_dirty;
+            || frame_dirty
+            || renderer.has_active_animation(now_ms);
         if force_render {
             out.rendered = renderer.render(state, now_ms);
             self.last_render_ms = Some(now_ms);
@@ -143,6 +291,368 @@ impl DeviceEventLoop {
         out
     }
 
+    /// Like `step_with_touch_driver`, but generalized to any number of
+    /// [`InputSource`]s instead of a single touch driver: every source
+    /// still pending interrupt is drained in order into one queue before
+    /// processing, so buttons, an encoder, or host-injected synthetic
+    /// events (e.g. a scripted test input channel) can be registered
+    /// alongside touch without a parallel step variant per device. `Touch`
+    /// events are folded into `touch_pipeline` exactly as
+    /// `step_with_touch_driver` does; every other `InputEvent` is surfaced
+    /// via `LoopOutput::input_events` for the caller to interpret, since
+    /// `RuntimeState` doesn't yet have button/encoder semantics of its own.
+    pub fn step_with_input_sources<R: SceneRenderer>(
+        &mut self,
+        state: &mut RuntimeState,
+        touch_pipeline: &mut TouchPipeline,
+        sources: &mut [&mut dyn InputSource],
+        inbound_transport: &[TransportMessage],
+        now_ms: u64,
+        renderer: &mut R,
+    ) -> LoopOutput {
+        let mut out = LoopOutput::new();
+        let mut frame_dirty = false;
+
+        state.set_replay_window_size(self.config.replay_window_size);
+        state.set_reconnect_backoff_params(
+            self.config.transport_reconnect_backoff_ms,
+            self.config.transport_reconnect_backoff_cap_ms,
+        );
+
+        for msg in inbound_transport {
+            let action = state.apply_transport_message(msg);
+            frame_dirty |= self.process_action(state, action, &mut out);
+        }
+
+        for source in sources.iter_mut() {
+            if !source.is_interrupt_pending() {
+                continue;
+            }
+            while let Some(event) = source.read_event() {
+                frame_dirty = true;
+                match event {
+                    InputEvent::Touch(payload) => touch_pipeline.push_event(payload),
+                    other => out.input_events.push(other),
+                }
+            }
+            source.clear_interrupt();
+        }
+        touch_pipeline.purge_stale(now_ms, TOUCH_EVENT_STALE_MS, &mut self.last_touch_ms);
+
+        while let Some(event) = touch_pipeline.next_frame(now_ms) {
+            let payload = microclaw_protocol::TouchEventPayload {
+                pointer_id: event.pointer_id,
+                phase: event.phase,
+                x: event.point.x,
+                y: event.point.y,
+                pressure: None,
+                raw_timestamp_ms: None,
+            };
+            let action = state.apply_touch_event(&payload);
+            frame_dirty |= self.process_action(state, action, &mut out);
+            frame_dirty |= self.apply_swipe_navigation(
+                state,
+                event.point.x,
+                event.point.y,
+                event.pointer_id,
+                event.phase,
+                now_ms,
+                &mut out,
+            );
+            if let Some(fling) = event.fling {
+                out.touch_fling = Some((fling.vx, fling.vy));
+                out.ui_messages.push("touch_fling");
+            }
+            self.last_touch_ms = Some(now_ms);
+        }
+
+        if state.mark_offline_if_stale(now_ms, self.config.offline_timeout_ms) {
+            frame_dirty = true;
+            out.offline_entered = true;
+            out.ui_messages.push("offline_timeout");
+        }
+
+        if state.safety_lockdown_check() {
+            frame_dirty = true;
+            out.in_safe_mode = true;
+            out.ui_messages.push("safety_lockdown");
+        }
+
+        let target_scene = state.scene();
+        let force_render = self.last_render_ms.map_or(true, |last| {
+            now_ms.saturating_sub(last) >= self.config.render_interval_ms
+        }) || self.scene_cache != Some(target_scene)
+            || frame_dirty
+            || renderer.has_active_animation(now_ms);
+        if force_render {
+            out.rendered = renderer.render(state, now_ms);
+            self.last_render_ms = Some(now_ms);
+            self.scene_cache = Some(target_scene);
+        }
+
+        if out.in_safe_mode {
+            self.scene_cache = Some(target_scene);
+        }
+
+        out
+    }
+
+    /// Like `step`/`step_with_touch_driver`, but drives a live
+    /// [`TransportBus`] directly instead of being handed an already-polled
+    /// `&[TransportMessage]`: it polls inbound frames, services the
+    /// transport-level reconnect ladder while disconnected, forwards
+    /// outbound commands/snapshot requests to the bus, and reclaims stale
+    /// in-flight commands, all in one pass.
+    pub fn step_with_transport<R: SceneRenderer, T: TransportBus>(
+        &mut self,
+        state: &mut RuntimeState,
+        touch_pipeline: &mut TouchPipeline,
+        transport: &mut T,
+        now_ms: u64,
+        renderer: &mut R,
+    ) -> LoopOutput {
+        self.step_with_transport_driver(state, touch_pipeline, transport, None, now_ms, renderer)
+    }
+
+    pub fn step_with_transport_driver<R: SceneRenderer, T: TransportBus>(
+        &mut self,
+        state: &mut RuntimeState,
+        touch_pipeline: &mut TouchPipeline,
+        transport: &mut T,
+        touch_driver: Option<&mut dyn TouchDriver>,
+        now_ms: u64,
+        renderer: &mut R,
+    ) -> LoopOutput {
+        let mut out = LoopOutput::new();
+        let mut frame_dirty = false;
+
+        state.set_replay_window_size(self.config.replay_window_size);
+        state.set_reconnect_backoff_params(
+            self.config.transport_reconnect_backoff_ms,
+            self.config.transport_reconnect_backoff_cap_ms,
+        );
+
+        self.service_transport_recovery(state, transport, now_ms, &mut out);
+
+        let mut inbound = Vec::new();
+        transport.poll_frames_into(&mut inbound);
+        out.inbound_processed = inbound.len();
+        for msg in &inbound {
+            let action = state.apply_transport_message(msg);
+            frame_dirty |= self.process_action_with_transport(state, action, &mut out, transport);
+        }
+
+        if let Some(driver) = touch_driver {
+            let drained = touch_pipeline.drain_from_driver(driver);
+            frame_dirty |= drained > 0;
+        }
+        touch_pipeline.purge_stale(now_ms, TOUCH_EVENT_STALE_MS, &mut self.last_touch_ms);
+
+        while let Some(event) = touch_pipeline.next_frame(now_ms) {
+            let payload = microclaw_protocol::TouchEventPayload {
+                pointer_id: event.pointer_id,
+                phase: event.phase,
+                x: event.point.x,
+                y: event.point.y,
+                pressure: None,
+                raw_timestamp_ms: None,
+            };
+            let action = state.apply_touch_event(&payload);
+            frame_dirty |= self.process_action_with_transport(state, action, &mut out, transport);
+            frame_dirty |= self.apply_swipe_navigation(
+                state,
+                event.point.x,
+                event.point.y,
+                event.pointer_id,
+                event.phase,
+                now_ms,
+                &mut out,
+            );
+            if let Some(fling) = event.fling {
+                out.touch_fling = Some((fling.vx, fling.vy));
+                out.ui_messages.push("touch_fling");
+            }
+            self.last_touch_ms = Some(now_ms);
+        }
+
+        let reclaimed = state.reclaim_stale_inflight(now_ms, self.config.stale_inflight_ms);
+        if reclaimed > 0 {
+            frame_dirty = true;
+            out.stale_inflight_reclaimed =
+                out.stale_inflight_reclaimed.saturating_add(reclaimed as u32);
+            out.ui_messages.push("stale_inflight_reclaimed");
+        }
+
+        if state.mark_offline_if_stale(now_ms, self.config.offline_timeout_ms) {
+            frame_dirty = true;
+            out.offline_entered = true;
+            out.ui_messages.push("offline_timeout");
+        }
+
+        if state.safety_lockdown_check() {
+            frame_dirty = true;
+            out.in_safe_mode = true;
+            out.ui_messages.push("safety_lockdown");
+        }
+
+        let target_scene = state.scene();
+        let force_render = self.last_render_ms.map_or(true, |last| {
+            now_ms.saturating_sub(last) >= self.config.render_interval_ms
+        }) || self.scene_cache != Some(target_scene)
+            || frame_dirty
+            || renderer.has_active_animation(now_ms);
+        if force_render {
+            out.rendered = renderer.render(state, now_ms);
+            self.last_render_ms = Some(now_ms);
+            self.scene_cache = Some(target_scene);
+        }
+
+        if out.in_safe_mode {
+            self.scene_cache = Some(target_scene);
+        }
+
+        out.transport_connected = transport.is_connected();
+        out.ui_messages.push("transport_step_completed");
+        out
+    }
+
+    /// Repeatedly calls `step_with_transport` until a full pass makes no
+    /// further progress (no inbound frames processed, no outbound frames
+    /// emitted, no render, and no transport reconnect attempt), or
+    /// `max_passes` is reached —
+    /// whichever comes first. Useful under bursty inbound traffic, where a
+    /// single `step_with_transport` call only drains one batch and leaves
+    /// latency proportional to queue depth.
+    ///
+    /// The returned `LoopOutput` aggregates every pass: `outbound` and
+    /// `ui_messages` are the concatenation across passes (so a recovery
+    /// message emitted on an early pass is never overwritten by a later
+    /// one), `stale_inflight_reclaimed`/`inbound_processed` are summed, and
+    /// `rendered`/`offline_entered`/`in_safe_mode` are true if any pass set
+    /// them.
+    pub fn step_until_idle<R: SceneRenderer, T: TransportBus>(
+        &mut self,
+        state: &mut RuntimeState,
+        touch_pipeline: &mut TouchPipeline,
+        transport: &mut T,
+        now_ms: u64,
+        renderer: &mut R,
+    ) -> LoopOutput {
+        self.step_until_idle_with_driver(
+            state,
+            touch_pipeline,
+            transport,
+            None,
+            now_ms,
+            renderer,
+        )
+    }
+
+    pub fn step_until_idle_with_driver<R: SceneRenderer, T: TransportBus>(
+        &mut self,
+        state: &mut RuntimeState,
+        touch_pipeline: &mut TouchPipeline,
+        transport: &mut T,
+        mut touch_driver: Option<&mut dyn TouchDriver>,
+        now_ms: u64,
+        renderer: &mut R,
+    ) -> LoopOutput {
+        let mut aggregated = LoopOutput::new();
+        let max_passes = self.config.max_passes.max(1);
+
+        for _ in 0..max_passes {
+            let pass = self.step_with_transport_driver(
+                state,
+                touch_pipeline,
+                transport,
+                touch_driver.as_mut().map(|driver| &mut **driver),
+                now_ms,
+                renderer,
+            );
+
+            let attempted_reconnect = pass.ui_messages.iter().any(|message| {
+                *message == "transport_reconnect_failed" || *message == "transport_reconnect_success"
+            });
+            let made_progress = pass.inbound_processed > 0
+                || !pass.outbound.is_empty()
+                || pass.rendered
+                || attempted_reconnect;
+
+            aggregated.rendered |= pass.rendered;
+            aggregated.offline_entered |= pass.offline_entered;
+            aggregated.in_safe_mode |= pass.in_safe_mode;
+            aggregated.transport_connected = pass.transport_connected;
+            aggregated.stale_inflight_reclaimed = aggregated
+                .stale_inflight_reclaimed
+                .saturating_add(pass.stale_inflight_reclaimed);
+            aggregated.inbound_processed =
+                aggregated.inbound_processed.saturating_add(pass.inbound_processed);
+            if pass.touch_fling.is_some() {
+                aggregated.touch_fling = pass.touch_fling;
+            }
+            aggregated.outbound.extend(pass.outbound);
+            aggregated.ui_messages.extend(pass.ui_messages);
+
+            if !made_progress {
+                break;
+            }
+        }
+
+        aggregated
+    }
+
+    /// Services the event loop's own transport-level reconnect ladder: while
+    /// `transport.is_connected()` is false, retries (with a doubling, capped
+    /// delay seeded from `transport_reconnect_backoff_ms`) no more than once
+    /// per backoff window. On success, marks `state` connected and requests
+    /// a full snapshot, mirroring what a fresh `HelloAck` would have done.
+    fn service_transport_recovery<T: TransportBus>(
+        &mut self,
+        state: &mut RuntimeState,
+        transport: &mut T,
+        now_ms: u64,
+        out: &mut LoopOutput,
+    ) {
+        if transport.is_connected() {
+            self.transport_retry_delay_ms = 0;
+            self.transport_retry_attempt = 0;
+            return;
+        }
+
+        if now_ms < self.next_transport_retry_ms {
+            out.ui_messages.push("transport_step_disconnected");
+            return;
+        }
+
+        self.transport_retry_attempt = self.transport_retry_attempt.saturating_add(1);
+        let connected = transport.reconnect(self.transport_retry_attempt, now_ms);
+
+        if connected {
+            self.transport_retry_delay_ms = 0;
+            self.transport_retry_attempt = 0;
+            self.next_transport_retry_ms = 0;
+            state.mark_boot_success();
+            out.ui_messages.push("transport_reconnect_success");
+            out.ui_messages.push("transport_connected");
+            let request = state.emit_snapshot_request();
+            transport.send_frame(request.clone());
+            out.outbound.push(request);
+            out.ui_messages.push("snapshot_request_sent");
+        } else {
+            let base = self.config.transport_reconnect_backoff_ms;
+            let cap = self.config.transport_reconnect_backoff_cap_ms.max(base);
+            self.transport_retry_delay_ms = if self.transport_retry_delay_ms == 0 {
+                base
+            } else {
+                self.transport_retry_delay_ms.saturating_mul(2)
+            }
+            .min(cap);
+            self.next_transport_retry_ms = now_ms.saturating_add(self.transport_retry_delay_ms);
+            state.mark_offline_with_reason("transport_reconnect_failed", now_ms);
+            out.ui_messages.push("transport_reconnect_failed");
+        }
+    }
+
     fn process_action(
         &mut self,
         state: &mut RuntimeState,
@@ -161,10 +671,59 @@ impl DeviceEventLoop {
                 out.ui_messages.push("emit_command");
                 true
             }
+            RuntimeAction::EmitSnapshotRequest { reason } => {
+                let request = state.emit_snapshot_request();
+                out.outbound.push(request);
+                out.ui_messages.push(reason);
+                true
+            }
+            RuntimeAction::EmitCommandAck { packet_id, message } => {
+                let ack = state.build_command_ack(packet_id);
+                out.outbound.push(ack);
+                out.ui_messages.push(message);
+                true
+            }
             RuntimeAction::RaiseUiState { message } => {
                 out.ui_messages.push(message);
                 true
             }
         }
     }
+
+    /// Same as `process_action`, but also forwards any outbound frame
+    /// (`EmitCommand`/`EmitSnapshotRequest`/`EmitCommandAck`) to `transport`, so a caller
+    /// driving a live `TransportBus` doesn't have to duplicate that
+    /// dispatch itself.
+    fn process_action_with_transport<T: TransportBus>(
+        &mut self,
+        state: &mut RuntimeState,
+        action: RuntimeAction,
+        out: &mut LoopOutput,
+        transport: &mut T,
+    ) -> bool {
+        match action {
+            RuntimeAction::EmitCommand { action } => {
+                let cmd = state.emit_command(action);
+                transport.send_frame(cmd.clone());
+                out.outbound.push(cmd);
+                out.ui_messages.push("emit_command");
+                true
+            }
+            RuntimeAction::EmitSnapshotRequest { reason } => {
+                let request = state.emit_snapshot_request();
+                transport.send_frame(request.clone());
+                out.outbound.push(request);
+                out.ui_messages.push(reason);
+                true
+            }
+            RuntimeAction::EmitCommandAck { packet_id, message } => {
+                let ack = state.build_command_ack(packet_id);
+                transport.send_frame(ack.clone());
+                out.outbound.push(ack);
+                out.ui_messages.push(message);
+                true
+            }
+            other => self.process_action(state, other, out),
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use crate::pipeline::SwipeDirection;
+use crate::ui::Scene;
+
+/// The in-app scenes a swipe can navigate between, in display order. `Left`
+/// moves forward through this list, `Right` moves back; boot/offline/error/
+/// safe-mode scenes aren't in it at all, so they're simply never swipable.
+const FLOW_ORDER: &[Scene] = &[
+    Scene::ConnectSetup,
+    Scene::Paired,
+    Scene::Conversation,
+    Scene::AgentThinking,
+    Scene::AgentStreaming,
+    Scene::AgentTaskProgress,
+    Scene::Settings,
+    Scene::NotificationList,
+];
+
+/// Marks scenes that accept a swipe at all, so `Flow` can skip a scene it
+/// has no edge for instead of treating a missing edge as an error.
+pub trait Swipable {
+    fn is_swipable(&self) -> bool;
+}
+
+impl Swipable for Scene {
+    fn is_swipable(&self) -> bool {
+        FLOW_ORDER.contains(self)
+    }
+}
+
+/// One edge of the flow graph: swiping `direction` while on `from` leads to
+/// `to`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct FlowEdge {
+    from: Scene,
+    direction: SwipeDirection,
+    to: Scene,
+}
+
+/// A transition the flow has accepted but the renderer hasn't finished
+/// animating yet, recorded so a renderer (e.g. the `chunk10-5` animation
+/// scheduler) can interpolate between `from` and `to` instead of cutting
+/// over instantly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingTransition {
+    pub from: Scene,
+    pub to: Scene,
+    pub direction: SwipeDirection,
+    pub started_ms: u64,
+}
+
+/// A directed graph of scenes with `SwipeDirection`-labelled edges, the way
+/// Trezor's UI flows model a store of pages connected by swipe gestures.
+/// `DeviceEventLoop` feeds it swipes `SwipeDetector` accepts on a swipable
+/// scene; it resolves the target scene and records a `PendingTransition` so
+/// scenes don't each have to wire up their own swipe handling.
+pub struct Flow {
+    edges: Vec<FlowEdge>,
+    pending: Option<PendingTransition>,
+}
+
+impl Flow {
+    pub fn new() -> Self {
+        let mut edges = Vec::with_capacity(FLOW_ORDER.len().saturating_sub(1) * 2);
+        for pair in FLOW_ORDER.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            edges.push(FlowEdge {
+                from: a,
+                direction: SwipeDirection::Left,
+                to: b,
+            });
+            edges.push(FlowEdge {
+                from: b,
+                direction: SwipeDirection::Right,
+                to: a,
+            });
+        }
+        Self { edges, pending: None }
+    }
+
+    /// The scene a swipe in `direction` would lead to from `scene`, if any
+    /// edge declares one.
+    pub fn target_for(&self, scene: Scene, direction: SwipeDirection) -> Option<Scene> {
+        self.edges
+            .iter()
+            .find(|edge| edge.from == scene && edge.direction == direction)
+            .map(|edge| edge.to)
+    }
+
+    /// Resolves `direction` against `current`, records the transition as
+    /// pending, and returns the target scene for the caller to apply via
+    /// `RuntimeState::set_nav_scene_override`. Returns `None` (and records
+    /// nothing) if `current` isn't swipable or has no edge for `direction`.
+    pub fn accept_swipe(
+        &mut self,
+        current: Scene,
+        direction: SwipeDirection,
+        now_ms: u64,
+    ) -> Option<Scene> {
+        if !current.is_swipable() {
+            return None;
+        }
+        let target = self.target_for(current, direction)?;
+        self.pending = Some(PendingTransition {
+            from: current,
+            to: target,
+            direction,
+            started_ms: now_ms,
+        });
+        Some(target)
+    }
+
+    pub fn pending_transition(&self) -> Option<PendingTransition> {
+        self.pending
+    }
+
+    /// Clears the recorded transition once the renderer has finished
+    /// animating it.
+    pub fn clear_pending_transition(&mut self) {
+        self.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_swipe_advances_and_right_swipe_returns() {
+        let mut flow = Flow::new();
+        assert_eq!(
+            flow.accept_swipe(Scene::Paired, SwipeDirection::Left, 0),
+            Some(Scene::Conversation)
+        );
+        assert_eq!(
+            flow.pending_transition(),
+            Some(PendingTransition {
+                from: Scene::Paired,
+                to: Scene::Conversation,
+                direction: SwipeDirection::Left,
+                started_ms: 0,
+            })
+        );
+
+        flow.clear_pending_transition();
+        assert_eq!(flow.pending_transition(), None);
+
+        assert_eq!(
+            flow.accept_swipe(Scene::Conversation, SwipeDirection::Right, 10),
+            Some(Scene::Paired)
+        );
+    }
+
+    #[test]
+    fn swipe_past_either_end_of_the_flow_is_rejected() {
+        let mut flow = Flow::new();
+        assert_eq!(
+            flow.accept_swipe(Scene::ConnectSetup, SwipeDirection::Right, 0),
+            None
+        );
+        assert_eq!(
+            flow.accept_swipe(Scene::NotificationList, SwipeDirection::Left, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn non_swipable_scenes_never_accept_a_swipe() {
+        let mut flow = Flow::new();
+        assert!(!Scene::Boot.is_swipable());
+        assert_eq!(
+            flow.accept_swipe(Scene::Boot, SwipeDirection::Left, 0),
+            None
+        );
+    }
+}
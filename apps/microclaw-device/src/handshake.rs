@@ -0,0 +1,451 @@
+//! Authenticated session handshake establishing mutually-authenticated,
+//! per-session symmetric keys between a device and its host, replacing the
+//! plaintext `Hello`/`HelloAck` exchange the event loop otherwise uses to
+//! enter `Connected` mode.
+//!
+//! This follows the shape of Noise's `IK` pattern (the device already knows
+//! the host's static public key, so it can authenticate the host from the
+//! very first message) rather than implementing the full Noise framework:
+//! this crate only ever runs this one pattern between exactly two known
+//! parties, so there's no payoff to the generality a `snow`-style state
+//! machine would need. Key derivation (HKDF over the concatenated DH
+//! outputs) and the AEAD confirmation follow the same construction Noise_IK
+//! uses, just without Noise's exact wire encoding.
+//!
+//! Gated behind `secure-session`, mirroring how [`crate::crypto`] gates its
+//! backends by feature so constrained `esp` builds only pay for X25519 +
+//! ChaCha20-Poly1305 when they opt in.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+/// A device's long-lived identity plus the host it expects to talk to:
+/// configured once at provisioning time, mirroring how
+/// `RuntimeState::with_host_allowlist` configures the allowlist up front.
+pub struct HostConfig {
+    pub device_static_secret: StaticSecret,
+    pub device_static_public: PublicKey,
+    pub host_static_public: PublicKey,
+}
+
+impl HostConfig {
+    pub fn new(device_static_secret: StaticSecret, host_static_public: PublicKey) -> Self {
+        let device_static_public = PublicKey::from(&device_static_secret);
+        Self {
+            device_static_secret,
+            device_static_public,
+            host_static_public,
+        }
+    }
+}
+
+/// The host-side counterpart of [`HostConfig`]: its own static keypair plus
+/// how far a device's claimed handshake timestamp may drift from "now"
+/// before being rejected as replayed or clock-skewed.
+pub struct HostHandshakeConfig {
+    pub host_static_secret: StaticSecret,
+    pub max_clock_skew_ms: u64,
+}
+
+/// Per-session symmetric keys derived once the handshake completes. Noise
+/// never reuses one key for both directions, so `send`/`recv` are distinct
+/// even though both sides derive them from the same DH transcript.
+#[derive(Clone)]
+pub struct SessionKeys {
+    pub send_key: [u8; 32],
+    pub recv_key: [u8; 32],
+}
+
+/// Why a handshake failed to establish a session. A connection that hits
+/// any of these must be dropped rather than allowed into `Connected`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HandshakeFailure {
+    /// The encrypted static key (message 1) or confirmation tag (message 2)
+    /// failed to authenticate — wrong key, tampered frame, or talking to an
+    /// impostor.
+    AeadFailed,
+    /// The device's claimed timestamp in message 1 is outside
+    /// `max_clock_skew_ms` of the host's clock.
+    TimestampOutOfRange,
+}
+
+/// Wire payload for the device's `Hello`: its ephemeral public key plus its
+/// static public key and a timestamp, both AEAD-sealed under a key derived
+/// from `DH(ephemeral, host_static)` so only the real host can read them.
+#[derive(Clone, Debug)]
+pub struct HelloPayload {
+    pub device_ephemeral_public: [u8; 32],
+    /// `AEAD(static_public(32) || timestamp_ms_be(8))`, sealed with the
+    /// `es` key and keyed to `device_ephemeral_public` as associated data.
+    pub sealed_static_and_timestamp: Vec<u8>,
+}
+
+/// Wire payload for the host's `HelloAck`: its ephemeral public key plus an
+/// AEAD tag over the handshake transcript, proving it derived the same
+/// session keys as the device without leaking anything that would.
+#[derive(Clone, Debug)]
+pub struct HelloAckPayload {
+    pub host_ephemeral_public: [u8; 32],
+    /// `AEAD("")` over the running transcript hash, sealed with the
+    /// host-to-device key. An empty-plaintext AEAD call is just a MAC here;
+    /// we reuse `Aead` instead of adding a second primitive for one tag.
+    pub confirm_tag: Vec<u8>,
+}
+
+/// State the device keeps between sending `Hello` and receiving `HelloAck`.
+pub struct PendingHandshake {
+    ephemeral_secret: EphemeralSecret,
+    ephemeral_public: PublicKey,
+    es: [u8; 32],
+    ss: [u8; 32],
+}
+
+/// Starts a handshake from the device side: generates a fresh ephemeral
+/// keypair, seals the device's static key and `now_ms` under the `es` key,
+/// and returns the `Hello` payload alongside the state needed to process
+/// the matching `HelloAck`.
+pub fn initiate(config: &HostConfig, now_ms: u64) -> (HelloPayload, PendingHandshake) {
+    let ephemeral_secret = EphemeralSecret::random_from_rng(rand_core::OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral_secret);
+    let es = dh(&ephemeral_secret, &config.host_static_public);
+    let ss = dh(&config.device_static_secret, &config.host_static_public);
+
+    let mut plaintext = Vec::with_capacity(40);
+    plaintext.extend_from_slice(config.device_static_public.as_bytes());
+    plaintext.extend_from_slice(&now_ms.to_be_bytes());
+
+    let sealed = aead_seal(&es, ephemeral_public.as_bytes(), &plaintext);
+
+    (
+        HelloPayload {
+            device_ephemeral_public: *ephemeral_public.as_bytes(),
+            sealed_static_and_timestamp: sealed,
+        },
+        PendingHandshake {
+            ephemeral_secret,
+            ephemeral_public,
+            es,
+            ss,
+        },
+    )
+}
+
+/// Processes a device's `Hello` from the host side: recovers the device's
+/// claimed static key, checks the timestamp against `now_ms`, and on
+/// success returns the `HelloAck` payload plus the derived [`SessionKeys`].
+pub fn respond(
+    config: &HostHandshakeConfig,
+    hello: &HelloPayload,
+    now_ms: u64,
+) -> Result<(HelloAckPayload, SessionKeys), HandshakeFailure> {
+    let device_ephemeral_public = PublicKey::from(hello.device_ephemeral_public);
+    let es = dh(&config.host_static_secret, &device_ephemeral_public);
+
+    let plaintext = aead_open(
+        &es,
+        &hello.device_ephemeral_public,
+        &hello.sealed_static_and_timestamp,
+    )
+    .ok_or(HandshakeFailure::AeadFailed)?;
+    if plaintext.len() != 40 {
+        return Err(HandshakeFailure::AeadFailed);
+    }
+    let mut device_static_bytes = [0u8; 32];
+    device_static_bytes.copy_from_slice(&plaintext[..32]);
+    let device_static_public = PublicKey::from(device_static_bytes);
+    let timestamp_ms = u64::from_be_bytes(plaintext[32..40].try_into().unwrap());
+
+    if now_ms.abs_diff(timestamp_ms) > config.max_clock_skew_ms {
+        return Err(HandshakeFailure::TimestampOutOfRange);
+    }
+
+    let ss = dh(&config.host_static_secret, &device_static_public);
+    let host_ephemeral_secret = EphemeralSecret::random_from_rng(rand_core::OsRng);
+    let host_ephemeral_public = PublicKey::from(&host_ephemeral_secret);
+    let ee = dh(&host_ephemeral_secret, &device_ephemeral_public);
+    let se = dh(&host_ephemeral_secret, &device_static_public);
+
+    let transcript = transcript_hash(
+        &hello.device_ephemeral_public,
+        host_ephemeral_public.as_bytes(),
+    );
+    let keys = derive_session_keys(&es, &ss, &ee, &se);
+    let confirm_tag = aead_seal(&keys.recv_key, &transcript, b"");
+
+    Ok((
+        HelloAckPayload {
+            host_ephemeral_public: *host_ephemeral_public.as_bytes(),
+            confirm_tag,
+        },
+        SessionKeys {
+            // From the host's perspective `recv_key` authenticates frames
+            // the device sends it, and `send_key` is what it signs with;
+            // swap them here so the struct returned to the host matches
+            // the host's own send/recv roles rather than the device's.
+            send_key: keys.recv_key,
+            recv_key: keys.send_key,
+        },
+    ))
+}
+
+/// Finalizes the device side after receiving `HelloAck`: rederives the
+/// shared DH transcript, verifies the host's confirmation tag, and returns
+/// the device's [`SessionKeys`] on success.
+pub fn finalize(
+    pending: PendingHandshake,
+    config: &HostConfig,
+    ack: &HelloAckPayload,
+) -> Result<SessionKeys, HandshakeFailure> {
+    let host_ephemeral_public = PublicKey::from(ack.host_ephemeral_public);
+    let ee = dh(&pending.ephemeral_secret, &host_ephemeral_public);
+    let se = dh(&config.device_static_secret, &host_ephemeral_public);
+
+    let transcript = transcript_hash(
+        pending.ephemeral_public.as_bytes(),
+        &ack.host_ephemeral_public,
+    );
+    let keys = derive_session_keys(&pending.es, &pending.ss, &ee, &se);
+
+    aead_open(&keys.recv_key, &transcript, &ack.confirm_tag)
+        .ok_or(HandshakeFailure::AeadFailed)?;
+
+    Ok(keys)
+}
+
+impl HelloPayload {
+    /// Encodes this payload as a `TransportMessage` JSON payload, the same
+    /// way other frame kinds carry a strongly-typed body inside the
+    /// envelope's generic `Value` payload (see
+    /// `TransportMessage::as_status_snapshot`).
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "device_ephemeral_public": hex_encode(&self.device_ephemeral_public),
+            "sealed_static_and_timestamp": hex_encode(&self.sealed_static_and_timestamp),
+        })
+    }
+
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            device_ephemeral_public: hex_decode_32(value.get("device_ephemeral_public")?.as_str()?)?,
+            sealed_static_and_timestamp: hex_decode(
+                value.get("sealed_static_and_timestamp")?.as_str()?,
+            )?,
+        })
+    }
+}
+
+impl HelloAckPayload {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "host_ephemeral_public": hex_encode(&self.host_ephemeral_public),
+            "confirm_tag": hex_encode(&self.confirm_tag),
+        })
+    }
+
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            host_ephemeral_public: hex_decode_32(value.get("host_ephemeral_public")?.as_str()?)?,
+            confirm_tag: hex_decode(value.get("confirm_tag")?.as_str()?)?,
+        })
+    }
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn hex_decode(raw: &str) -> Option<Vec<u8>> {
+    if raw.len() % 2 != 0 {
+        return None;
+    }
+    (0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&raw[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn hex_decode_32(raw: &str) -> Option<[u8; 32]> {
+    hex_decode(raw)?.try_into().ok()
+}
+
+fn dh(secret: impl AsDhSecret, public: &PublicKey) -> [u8; 32] {
+    secret.diffie_hellman(public).to_bytes()
+}
+
+/// Lets [`dh`] accept both `&StaticSecret` and `&EphemeralSecret` (x25519
+/// doesn't give them a shared trait for `diffie_hellman`).
+trait AsDhSecret {
+    fn diffie_hellman(self, public: &PublicKey) -> x25519_dalek::SharedSecret;
+}
+
+impl AsDhSecret for &StaticSecret {
+    fn diffie_hellman(self, public: &PublicKey) -> x25519_dalek::SharedSecret {
+        StaticSecret::diffie_hellman(self, public)
+    }
+}
+
+impl AsDhSecret for &EphemeralSecret {
+    fn diffie_hellman(self, public: &PublicKey) -> x25519_dalek::SharedSecret {
+        EphemeralSecret::diffie_hellman(self, public)
+    }
+}
+
+fn transcript_hash(device_ephemeral: &[u8; 32], host_ephemeral: &[u8; 32]) -> [u8; 32] {
+    use sha2::Digest;
+    let mut hasher = Sha256::new();
+    hasher.update(b"nanoclaw-handshake-v1");
+    hasher.update(device_ephemeral);
+    hasher.update(host_ephemeral);
+    hasher.finalize().into()
+}
+
+/// HKDF-style extract over the four DH outputs (`es`, `ss`, `ee`, `se`, in
+/// that fixed order so both sides derive identical input keying material),
+/// then one expand per direction so the device-to-host and host-to-device
+/// keys are independent even though both sides compute the same DH values.
+fn derive_session_keys(es: &[u8; 32], ss: &[u8; 32], ee: &[u8; 32], se: &[u8; 32]) -> SessionKeys {
+    let mut ikm = Vec::with_capacity(128);
+    ikm.extend_from_slice(es);
+    ikm.extend_from_slice(ss);
+    ikm.extend_from_slice(ee);
+    ikm.extend_from_slice(se);
+
+    let prk = hmac_sha256(b"nanoclaw-handshake-salt", &ikm);
+    SessionKeys {
+        send_key: hmac_sha256(&prk, b"device-to-host"),
+        recv_key: hmac_sha256(&prk, b"host-to-device"),
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn aead_seal(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = Nonce::default();
+    cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .expect("encryption over a fixed-size payload cannot fail")
+}
+
+fn aead_open(key: &[u8; 32], aad: &[u8], ciphertext: &[u8]) -> Option<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = Nonce::default();
+    cipher
+        .decrypt(
+            &nonce,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> (StaticSecret, PublicKey) {
+        let secret = StaticSecret::random_from_rng(rand_core::OsRng);
+        let public = PublicKey::from(&secret);
+        (secret, public)
+    }
+
+    #[test]
+    fn handshake_round_trip_derives_matching_keys() {
+        let (device_secret, _device_public) = keypair();
+        let (host_secret, host_public) = keypair();
+
+        let device_config = HostConfig::new(device_secret, host_public);
+        let (hello, pending) = initiate(&device_config, 1_000);
+
+        let host_config = HostHandshakeConfig {
+            host_static_secret: host_secret,
+            max_clock_skew_ms: 5_000,
+        };
+        let (ack, host_keys) = respond(&host_config, &hello, 1_000).unwrap();
+
+        let device_keys = finalize(pending, &device_config, &ack).unwrap();
+
+        assert_eq!(device_keys.send_key, host_keys.recv_key);
+        assert_eq!(device_keys.recv_key, host_keys.send_key);
+    }
+
+    #[test]
+    fn stale_timestamp_is_rejected() {
+        let (device_secret, _) = keypair();
+        let (host_secret, host_public) = keypair();
+
+        let device_config = HostConfig::new(device_secret, host_public);
+        let (hello, _pending) = initiate(&device_config, 1_000);
+
+        let host_config = HostHandshakeConfig {
+            host_static_secret: host_secret,
+            max_clock_skew_ms: 100,
+        };
+        let err = respond(&host_config, &hello, 10_000).unwrap_err();
+        assert_eq!(err, HandshakeFailure::TimestampOutOfRange);
+    }
+
+    #[test]
+    fn wrong_host_static_key_fails_the_handshake() {
+        let (device_secret, _) = keypair();
+        let (_host_secret, host_public) = keypair();
+        let (wrong_host_secret, _wrong_host_public) = keypair();
+
+        let device_config = HostConfig::new(device_secret, host_public);
+        let (hello, _pending) = initiate(&device_config, 1_000);
+
+        let host_config = HostHandshakeConfig {
+            host_static_secret: wrong_host_secret,
+            max_clock_skew_ms: 5_000,
+        };
+        let err = respond(&host_config, &hello, 1_000).unwrap_err();
+        assert_eq!(err, HandshakeFailure::AeadFailed);
+    }
+
+    #[test]
+    fn hello_and_ack_payloads_round_trip_through_json() {
+        let (device_secret, _) = keypair();
+        let (_host_secret, host_public) = keypair();
+        let device_config = HostConfig::new(device_secret, host_public);
+        let (hello, _pending) = initiate(&device_config, 1_000);
+
+        let decoded = HelloPayload::from_json(&hello.to_json()).unwrap();
+        assert_eq!(
+            decoded.device_ephemeral_public,
+            hello.device_ephemeral_public
+        );
+        assert_eq!(
+            decoded.sealed_static_and_timestamp,
+            hello.sealed_static_and_timestamp
+        );
+    }
+
+    #[test]
+    fn tampered_ack_fails_finalize() {
+        let (device_secret, _) = keypair();
+        let (host_secret, host_public) = keypair();
+
+        let device_config = HostConfig::new(device_secret, host_public);
+        let (hello, pending) = initiate(&device_config, 1_000);
+
+        let host_config = HostHandshakeConfig {
+            host_static_secret: host_secret,
+            max_clock_skew_ms: 5_000,
+        };
+        let (mut ack, _host_keys) = respond(&host_config, &hello, 1_000).unwrap();
+        ack.confirm_tag[0] ^= 0xFF;
+
+        let err = finalize(pending, &device_config, &ack).unwrap_err();
+        assert_eq!(err, HandshakeFailure::AeadFailed);
+    }
+}
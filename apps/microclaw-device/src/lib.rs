@@ -1,18 +1,29 @@
+pub mod ack;
 pub mod boards;
+pub mod crypto;
 pub mod display;
 pub mod drivers;
 pub mod event_loop;
+pub mod flow;
+#[cfg(feature = "secure-session")]
+pub mod handshake;
+#[cfg(feature = "capture")]
+pub mod pcap;
 pub mod pipeline;
+pub mod reconnect;
 pub mod renderer;
 mod runtime;
 pub mod slint_platform;
 pub mod storage;
+pub mod timing_wheel;
 pub mod transport;
 pub mod ui;
 
+pub use ack::{AckToken, ManualAckRegistry, PendingManualAck};
 pub use runtime::{
-    now_ms, AgentActivity, InFlightCommand, NotificationItem, RuntimeAction, RuntimeMode,
-    RuntimeState, ToastNotification, ToastSeverity,
+    now_ms, ActionPolicy, AgentActivity, Clock, EventMask, InFlightCommand, LinkQuality,
+    ManualClock, NotificationItem, RuntimeAction, RuntimeEvent, RuntimeMode, RuntimeState,
+    SubscriberId, SystemClock, ToastNotification, ToastSeverity,
 };
 
 pub fn boot_message() -> &'static str {
@@ -87,6 +98,105 @@ pub mod esp_runtime {
     use esp_idf_svc::sys::EspError;
     use std::env;
 
+    /// Mirrors the mode selection embassy's esp-hosted driver exposes: join
+    /// an existing network, host a provisioning network, or both at once
+    /// while onboarding.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum WifiMode {
+        Sta,
+        Ap,
+        ApSta,
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct ScannedNetwork {
+        pub ssid: String,
+        pub rssi: i8,
+        pub auth_method: String,
+        pub channel: u8,
+    }
+
+    const PROVISIONING_AP_SSID: &str = "microclaw-setup";
+
+    /// Scans for nearby networks so the UI can render a picker. Returns
+    /// results sorted strongest-first.
+    pub fn scan_networks() -> Result<Vec<ScannedNetwork>, EspError> {
+        use esp_idf_svc::eventloop::EspSystemEventLoop;
+        use esp_idf_svc::hal::peripherals::Peripherals;
+        use esp_idf_svc::wifi::{AuthMethod, EspWifi};
+
+        let peripherals = Peripherals::take()?;
+        let sys_loop = EspSystemEventLoop::take()?;
+        let mut wifi = EspWifi::new(peripherals.modem, sys_loop, None)?;
+        wifi.start()?;
+        let results = wifi.scan()?;
+        let mut networks: Vec<ScannedNetwork> = results
+            .into_iter()
+            .map(|ap| ScannedNetwork {
+                ssid: ap.ssid.to_string(),
+                rssi: ap.signal_strength,
+                auth_method: match ap.auth_method.unwrap_or(AuthMethod::None) {
+                    AuthMethod::None => "open".to_owned(),
+                    AuthMethod::WEP => "wep".to_owned(),
+                    AuthMethod::WPA => "wpa".to_owned(),
+                    AuthMethod::WPA2Personal => "wpa2".to_owned(),
+                    AuthMethod::WPA3Personal => "wpa3".to_owned(),
+                    _ => "unknown".to_owned(),
+                },
+                channel: ap.channel,
+            })
+            .collect();
+        networks.sort_by(|a, b| b.rssi.cmp(&a.rssi));
+        Ok(networks)
+    }
+
+    /// Configures the modem in AP (or AP+STA) mode with an open
+    /// `microclaw-setup` network and starts it; `WifiMode::Sta` joins through
+    /// [`init_wifi`] instead. No HTTP/WebSocket endpoint is served, and the
+    /// wifi handle is not returned, so it is dropped when this returns.
+    pub fn start_provisioning_ap(mode: WifiMode) -> Result<(), EspError> {
+        use esp_idf_svc::eventloop::EspSystemEventLoop;
+        use esp_idf_svc::hal::peripherals::Peripherals;
+        use esp_idf_svc::nvs::EspDefaultNvsPartition;
+        use esp_idf_svc::wifi::{AccessPointConfiguration, AuthMethod, BlockingWifi, Configuration, EspWifi};
+
+        let peripherals = Peripherals::take()?;
+        let sys_loop = EspSystemEventLoop::take()?;
+        let nvs = EspDefaultNvsPartition::take()?;
+        let mut wifi = BlockingWifi::wrap(EspWifi::new(peripherals.modem, sys_loop.clone(), Some(nvs))?, sys_loop)?;
+
+        let ap_config = AccessPointConfiguration {
+            ssid: PROVISIONING_AP_SSID.try_into().map_err(|_| {
+                EspError::from_infallible::<{ esp_idf_svc::sys::ESP_ERR_INVALID_ARG }>()
+            })?,
+            auth_method: AuthMethod::None,
+            ..Default::default()
+        };
+
+        let configuration = match mode {
+            WifiMode::Ap => Configuration::AccessPoint(ap_config),
+            WifiMode::ApSta => Configuration::Mixed(Default::default(), ap_config),
+            WifiMode::Sta => return init_wifi(),
+        };
+
+        wifi.set_configuration(&configuration)?;
+        wifi.start()?;
+        Ok(())
+    }
+
+    /// Persists the user's chosen SSID/password through `DeviceStorage`
+    /// under `keys::WIFI_SSID`/`keys::WIFI_PASSWORD`. The modem is not
+    /// touched: no AP is torn down here, and `init_wifi` still reads its
+    /// credentials from the `MICROCLAW_WIFI_*` environment variables.
+    pub fn complete_provisioning(
+        storage: &mut dyn crate::storage::DeviceStorage,
+        ssid: &str,
+        password: &str,
+    ) {
+        storage.set_string(crate::storage::keys::WIFI_SSID, ssid);
+        storage.set_string(crate::storage::keys::WIFI_PASSWORD, password);
+    }
+
     pub fn init_wifi() -> Result<(), EspError> {
         use esp_idf_svc::eventloop::EspSystemEventLoop;
         use esp_idf_svc::hal::peripherals::Peripherals;
@@ -0,0 +1,291 @@
+//! pcapng capture of transport frames, so a failing session can be replayed
+//! deterministically offline the way the pica UWB emulator logs UCI traffic.
+//!
+//! The writer emits a Section Header Block, one Interface Description Block
+//! for a private link type, then one Enhanced Packet Block per captured
+//! frame. Direction and the resulting `RuntimeAction` verdict are stashed in
+//! an EPB options field (a custom option code) so a replay tool can assert
+//! the same decisions were made the second time around.
+
+use microclaw_protocol::TransportMessage;
+
+/// Private LINKTYPE value (in the user-reserved range) for microclaw frames.
+pub const LINKTYPE_MICROCLAW: u32 = 147;
+
+/// Custom EPB option code carrying direction + verdict, also in the
+/// vendor-reserved range.
+const OPT_MICROCLAW_META: u16 = 0x8001;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    fn as_byte(self) -> u8 {
+        match self {
+            Direction::Inbound => 0,
+            Direction::Outbound => 1,
+        }
+    }
+}
+
+/// Appends pcapng blocks to an in-memory byte buffer. Call [`PcapWriter::new`]
+/// once per capture file, then [`PcapWriter::write_frame`] per message.
+pub struct PcapWriter {
+    buf: Vec<u8>,
+}
+
+impl PcapWriter {
+    pub fn new() -> Self {
+        let mut writer = Self { buf: Vec::new() };
+        writer.write_section_header();
+        writer.write_interface_description();
+        writer
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    fn write_section_header(&mut self) {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0x1A2B_3C4Du32.to_le_bytes()); // byte-order magic
+        body.extend_from_slice(&1u16.to_le_bytes()); // major
+        body.extend_from_slice(&0u16.to_le_bytes()); // minor
+        body.extend_from_slice(&(-1i64).to_le_bytes()); // section length unknown
+        self.write_block(0x0A0D_0D0A, &body);
+    }
+
+    fn write_interface_description(&mut self) {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(LINKTYPE_MICROCLAW as u16).to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes()); // reserved
+        body.extend_from_slice(&0u32.to_le_bytes()); // snaplen: unlimited
+        self.write_block(0x0000_0001, &body);
+    }
+
+    /// Writes one Enhanced Packet Block for `frame`, tagging it with
+    /// `direction` and the stringified `verdict` in an options field.
+    pub fn write_frame(
+        &mut self,
+        frame: &TransportMessage,
+        direction: Direction,
+        verdict: &str,
+        timestamp_us: u64,
+    ) {
+        let bytes = serde_json::to_vec(frame).unwrap_or_default();
+        let mut body = Vec::new();
+        body.extend_from_slice(&0u32.to_le_bytes()); // interface id
+        body.extend_from_slice(&((timestamp_us >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp_us as u32).to_le_bytes());
+        body.extend_from_slice(&(bytes.len() as u32).to_le_bytes()); // captured length
+        body.extend_from_slice(&(bytes.len() as u32).to_le_bytes()); // original length
+        body.extend_from_slice(&bytes);
+        pad_to_32(&mut body);
+
+        let mut option_value = Vec::with_capacity(1 + verdict.len());
+        option_value.push(direction.as_byte());
+        option_value.extend_from_slice(verdict.as_bytes());
+        write_option(&mut body, OPT_MICROCLAW_META, &option_value);
+        write_option(&mut body, 0, &[]); // opt_endofopt
+
+        self.write_block(0x0000_0006, &body);
+    }
+
+    fn write_block(&mut self, block_type: u32, body: &[u8]) {
+        let total_len = 12 + body.len() as u32;
+        self.buf.extend_from_slice(&block_type.to_le_bytes());
+        self.buf.extend_from_slice(&total_len.to_le_bytes());
+        self.buf.extend_from_slice(body);
+        self.buf.extend_from_slice(&total_len.to_le_bytes());
+    }
+}
+
+impl Default for PcapWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad_to_32(body);
+}
+
+fn pad_to_32(body: &mut Vec<u8>) {
+    while body.len() % 4 != 0 {
+        body.push(0);
+    }
+}
+
+/// Destination a [`RecordingTap`] flushes captured bytes to. Host/dev builds
+/// typically target a file; firmware without a filesystem flushes over the
+/// existing transport link into a bounded ring buffer instead.
+pub trait CaptureSink {
+    fn write_all(&mut self, bytes: &[u8]);
+}
+
+/// Appends to a plain file, for host and dev builds where a filesystem is
+/// available and a Wireshark-openable `.pcapng` on disk is the goal.
+#[cfg(not(feature = "esp"))]
+pub struct FileSink {
+    file: std::fs::File,
+}
+
+#[cfg(not(feature = "esp"))]
+impl FileSink {
+    pub fn create(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Ok(Self {
+            file: std::fs::File::create(path)?,
+        })
+    }
+}
+
+#[cfg(not(feature = "esp"))]
+impl CaptureSink for FileSink {
+    fn write_all(&mut self, bytes: &[u8]) {
+        use std::io::Write;
+        let _ = self.file.write_all(bytes);
+    }
+}
+
+/// Fixed-capacity, overwrite-oldest buffer for firmware builds with no
+/// filesystem: capture data accumulates here until something (a diagnostics
+/// command, a debug build flashed over serial) drains it off-device.
+pub struct RingBufferSink {
+    buf: VecDeque<u8>,
+    capacity: usize,
+}
+
+impl RingBufferSink {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn drain(&mut self) -> Vec<u8> {
+        self.buf.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+impl CaptureSink for RingBufferSink {
+    fn write_all(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if self.buf.len() >= self.capacity {
+                self.buf.pop_front();
+            }
+            self.buf.push_back(byte);
+        }
+    }
+}
+
+use std::collections::VecDeque;
+
+/// Tees traffic into a [`PcapWriter`] as it flows through `apply_transport_message`
+/// / `emit_command`, without changing `RuntimeState`'s own call sites.
+pub struct RecordingTap {
+    writer: PcapWriter,
+}
+
+impl RecordingTap {
+    pub fn new() -> Self {
+        Self {
+            writer: PcapWriter::new(),
+        }
+    }
+
+    pub fn record_inbound(&mut self, frame: &TransportMessage, verdict: &str, timestamp_us: u64) {
+        self.writer
+            .write_frame(frame, Direction::Inbound, verdict, timestamp_us);
+    }
+
+    pub fn record_outbound(&mut self, frame: &TransportMessage, timestamp_us: u64) {
+        self.writer
+            .write_frame(frame, Direction::Outbound, "sent", timestamp_us);
+    }
+
+    /// Flushes everything captured so far to `sink` and resets the internal
+    /// buffer to a fresh section, so repeated flushes don't re-send bytes.
+    pub fn flush_to(&mut self, sink: &mut dyn CaptureSink) {
+        sink.write_all(&self.writer.buf);
+        self.writer = PcapWriter::new();
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        self.writer.bytes()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.writer.buf
+    }
+}
+
+impl Default for RecordingTap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use microclaw_protocol::{Envelope, MessageId, MessageKind};
+    use serde_json::json;
+
+    fn sample_frame() -> TransportMessage {
+        TransportMessage::new(
+            Envelope::new("host", "device", "boot", MessageId::new("m1")),
+            MessageKind::Heartbeat,
+            json!({}),
+        )
+    }
+
+    #[test]
+    fn writer_emits_section_header_magic_first() {
+        let writer = PcapWriter::new();
+        assert_eq!(&writer.bytes()[0..4], &0x0A0D_0D0Au32.to_le_bytes());
+        assert_eq!(&writer.bytes()[8..12], &0x1A2B_3C4Du32.to_le_bytes());
+    }
+
+    #[test]
+    fn recording_tap_grows_with_each_frame() {
+        let mut tap = RecordingTap::new();
+        let before = tap.writer.bytes().len();
+        tap.record_inbound(&sample_frame(), "accepted", 1_000);
+        assert!(tap.writer.bytes().len() > before);
+    }
+
+    #[test]
+    fn ring_buffer_sink_drops_oldest_past_capacity() {
+        let mut sink = RingBufferSink::new(4);
+        sink.write_all(&[1, 2, 3, 4, 5, 6]);
+        assert_eq!(sink.drain(), vec![3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn flush_to_resets_tap_to_fresh_section() {
+        let mut tap = RecordingTap::new();
+        tap.record_inbound(&sample_frame(), "accepted", 1_000);
+        let mut sink = RingBufferSink::new(4096);
+        tap.flush_to(&mut sink);
+        assert!(!sink.is_empty());
+        let after_flush_len = tap.writer.bytes().len();
+        let mut sink2 = RingBufferSink::new(4096);
+        tap.flush_to(&mut sink2);
+        assert_eq!(sink2.len(), after_flush_len);
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use microclaw_protocol::TouchEventPayload;
+use microclaw_protocol::{TouchEventPayload, TouchPhase};
 
 use crate::display::{clamp_and_validate_touch, DisplayPoint};
 use crate::drivers::TouchDriver;
@@ -8,14 +8,180 @@ use crate::drivers::TouchDriver;
 pub const TOUCH_QUEUE_CAPACITY: usize = 32;
 pub const TOUCH_EVENT_STALE_MS: u64 = 2_000;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Most touch controllers this device targets report at most this many
+/// simultaneous contacts, so `ActivePointers` tracks them in a fixed-size
+/// array instead of a heap-allocated map.
+pub const MAX_ACTIVE_POINTERS: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct TouchEventFrame {
+    pub pointer_id: u8,
     pub point: DisplayPoint,
-    pub phase: microclaw_protocol::TouchPhase,
+    pub phase: TouchPhase,
+    /// Set on an `Up` frame whose release velocity (computed from
+    /// `ActivePointers::velocity` over the trailing `VELOCITY_SAMPLE_WINDOW_MS`)
+    /// exceeds `FLING_MIN_VELOCITY_PX_PER_MS`, so the event loop can let a
+    /// scrollable scene decelerate instead of stopping dead at finger-up.
+    pub fling: Option<Fling>,
+}
+
+/// How far back `ActivePointers::velocity` looks when estimating a
+/// pointer's current speed, and how many recent position samples it keeps
+/// per pointer to look back over -- egui's input state retains pointer
+/// history the same way rather than trusting one instantaneous delta.
+pub const VELOCITY_SAMPLE_WINDOW_MS: u64 = 100;
+const VELOCITY_SAMPLE_CAPACITY: usize = 8;
+
+/// Release speed (px/ms, either axis) above which `TouchPipeline::next_frame`
+/// attaches a `Fling` to the `Up` frame.
+pub const FLING_MIN_VELOCITY_PX_PER_MS: f32 = 0.5;
+
+/// A pointer released while still moving fast enough to be a fling/momentum
+/// gesture rather than a plain tap-off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fling {
+    pub vx: f32,
+    pub vy: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct VelocitySample {
+    point: DisplayPoint,
+    at_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ActivePointer {
+    pointer_id: u8,
+    point: DisplayPoint,
+    phase: TouchPhase,
+    samples: [Option<VelocitySample>; VELOCITY_SAMPLE_CAPACITY],
+    next_sample_slot: usize,
+}
+
+impl ActivePointer {
+    fn new(pointer_id: u8, point: DisplayPoint, phase: TouchPhase, now_ms: u64) -> Self {
+        let mut pointer = Self {
+            pointer_id,
+            point,
+            phase,
+            samples: [None; VELOCITY_SAMPLE_CAPACITY],
+            next_sample_slot: 0,
+        };
+        pointer.push_sample(point, now_ms);
+        pointer
+    }
+
+    fn push_sample(&mut self, point: DisplayPoint, at_ms: u64) {
+        self.samples[self.next_sample_slot] = Some(VelocitySample { point, at_ms });
+        self.next_sample_slot = (self.next_sample_slot + 1) % VELOCITY_SAMPLE_CAPACITY;
+    }
+
+    /// Velocity in px/ms over the samples still within `VELOCITY_SAMPLE_WINDOW_MS`
+    /// of `now_ms`, computed from the oldest and newest such sample. `None`
+    /// if fewer than two samples fall in that window (not enough history
+    /// yet, or the pointer has been still longer than the window).
+    fn velocity(&self, now_ms: u64) -> Option<(f32, f32)> {
+        let mut oldest: Option<VelocitySample> = None;
+        let mut newest: Option<VelocitySample> = None;
+        for sample in self.samples.iter().flatten() {
+            if now_ms.saturating_sub(sample.at_ms) > VELOCITY_SAMPLE_WINDOW_MS {
+                continue;
+            }
+            if oldest.map_or(true, |o| sample.at_ms < o.at_ms) {
+                oldest = Some(*sample);
+            }
+            if newest.map_or(true, |n| sample.at_ms > n.at_ms) {
+                newest = Some(*sample);
+            }
+        }
+        let (oldest, newest) = (oldest?, newest?);
+        let dt = newest.at_ms.saturating_sub(oldest.at_ms);
+        if dt == 0 {
+            return None;
+        }
+        let dx = newest.point.x as f32 - oldest.point.x as f32;
+        let dy = newest.point.y as f32 - oldest.point.y as f32;
+        Some((dx / dt as f32, dy / dt as f32))
+    }
+}
+
+/// Per-pointer tracking for contacts currently down, keyed by
+/// `TouchEventPayload::pointer_id`, so multi-touch gestures (two-finger
+/// pinch, independent drags) can be told apart instead of every contact
+/// collapsing into one stream.
+struct ActivePointers {
+    slots: [Option<ActivePointer>; MAX_ACTIVE_POINTERS],
+}
+
+impl ActivePointers {
+    fn new() -> Self {
+        Self {
+            slots: [None; MAX_ACTIVE_POINTERS],
+        }
+    }
+
+    fn find(&self, pointer_id: u8) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| matches!(slot, Some(active) if active.pointer_id == pointer_id))
+    }
+
+    fn is_active(&self, pointer_id: u8) -> bool {
+        self.find(pointer_id).is_some()
+    }
+
+    /// Starts tracking `pointer_id` in a free slot. Returns `false` (and
+    /// tracks nothing) if every slot is already in use by a different
+    /// pointer.
+    fn start(&mut self, pointer_id: u8, point: DisplayPoint, phase: TouchPhase, now_ms: u64) -> bool {
+        match self.slots.iter().position(Option::is_none) {
+            Some(index) => {
+                self.slots[index] = Some(ActivePointer::new(pointer_id, point, phase, now_ms));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Updates the last known point/phase for an already-tracked pointer,
+    /// recording a new velocity sample. Starts tracking it instead if it
+    /// isn't active yet, so a stream that begins mid-gesture (a missed
+    /// `Down`) still gets picked up.
+    fn update(&mut self, pointer_id: u8, point: DisplayPoint, phase: TouchPhase, now_ms: u64) {
+        if let Some(index) = self.find(pointer_id) {
+            if let Some(press) = self.slots[index].as_mut() {
+                press.point = point;
+                press.phase = phase;
+                press.push_sample(point, now_ms);
+            }
+        } else {
+            self.start(pointer_id, point, phase, now_ms);
+        }
+    }
+
+    /// The tracked pointer's current velocity in px/ms, if it has enough
+    /// recent history; see `ActivePointer::velocity`.
+    fn velocity(&self, pointer_id: u8, now_ms: u64) -> Option<(f32, f32)> {
+        let index = self.find(pointer_id)?;
+        self.slots[index].as_ref()?.velocity(now_ms)
+    }
+
+    /// Stops tracking `pointer_id`. Returns `false` if it wasn't active.
+    fn release(&mut self, pointer_id: u8) -> bool {
+        match self.find(pointer_id) {
+            Some(index) => {
+                self.slots[index] = None;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 pub struct TouchPipeline {
     queue: VecDeque<TouchEventPayload>,
+    active: ActivePointers,
     dropped: u64,
 }
 
@@ -23,6 +189,7 @@ impl TouchPipeline {
     pub fn new() -> Self {
         Self {
             queue: VecDeque::with_capacity(TOUCH_QUEUE_CAPACITY),
+            active: ActivePointers::new(),
             dropped: 0,
         }
     }
@@ -63,14 +230,60 @@ impl TouchPipeline {
         drained
     }
 
-    pub fn next_frame(&mut self) -> Option<TouchEventFrame> {
+    /// Pops queued events until it finds one worth surfacing, keying it by
+    /// `pointer_id` against `active` so independent contacts stay
+    /// independent. A `Down` for a pointer id that's already active (or
+    /// for which every tracking slot is full), or an `Up` for a pointer id
+    /// that isn't active, is a protocol error: the event is dropped and
+    /// counted in `dropped_count` like a queue overflow, and the loop moves
+    /// on to the next queued event rather than surfacing it. `now_ms` feeds
+    /// the per-pointer velocity samples `ActivePointers` keeps, the same
+    /// tick timestamp `step` already threads everywhere else.
+    pub fn next_frame(&mut self, now_ms: u64) -> Option<TouchEventFrame> {
         while let Some(event) = self.pop_event() {
-            if let Some(point) = clamp_and_validate_touch(event.x, event.y) {
-                return Some(TouchEventFrame {
-                    point,
-                    phase: event.phase,
-                });
+            let Some(point) = clamp_and_validate_touch(event.x, event.y) else {
+                continue;
+            };
+            let pointer_id = event.pointer_id;
+            let mut fling = None;
+
+            let accepted = match event.phase {
+                TouchPhase::Down => {
+                    !self.active.is_active(pointer_id)
+                        && self.active.start(pointer_id, point, event.phase, now_ms)
+                }
+                TouchPhase::Up => {
+                    if let Some((vx, vy)) = self.active.velocity(pointer_id, now_ms) {
+                        if vx.abs() >= FLING_MIN_VELOCITY_PX_PER_MS
+                            || vy.abs() >= FLING_MIN_VELOCITY_PX_PER_MS
+                        {
+                            fling = Some(Fling { vx, vy });
+                        }
+                    }
+                    self.active.release(pointer_id)
+                }
+                TouchPhase::Move => {
+                    self.active.update(pointer_id, point, event.phase, now_ms);
+                    true
+                }
+                TouchPhase::Cancel => {
+                    self.active.release(pointer_id);
+                    true
+                }
+                TouchPhase::Unknown => true,
+            };
+
+            if !accepted {
+                self.dropped = self.dropped.saturating_add(1);
+                continue;
             }
+
+            return Some(TouchEventFrame {
+                pointer_id,
+                point,
+                phase: event.phase,
+                fling,
+            });
         }
         None
     }
@@ -135,3 +348,421 @@ impl SwipeDetector {
         self.down_y = None;
     }
 }
+
+/// How long after a tap's `Up` a second tap must land, and how close to it,
+/// for `GestureRecognizer` to report `Gesture::DoubleTap` instead of two
+/// separate `Gesture::Tap`s.
+pub const DOUBLE_TAP_MAX_INTERVAL_MS: u64 = 300;
+pub const DOUBLE_TAP_MAX_DISTANCE_PX: i32 = 20;
+
+/// How long a pointer must stay down without moving beyond
+/// `LONG_PRESS_SLOP_PX` before `GestureRecognizer::check_long_press` reports
+/// `Gesture::LongPress` for it.
+pub const LONG_PRESS_MIN_HOLD_MS: u64 = 500;
+pub const LONG_PRESS_SLOP_PX: i32 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    Tap,
+    DoubleTap,
+    LongPress,
+    Swipe(SwipeDirection),
+    Pinch { scale: f32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+struct PointerPress {
+    pointer_id: u8,
+    down_x: u16,
+    down_y: u16,
+    down_ms: u64,
+    moved: bool,
+    long_press_fired: bool,
+}
+
+/// Classifies `Tap`/`DoubleTap`/`LongPress`/`Pinch` out of the same
+/// per-pointer touch frames
+/// `TouchPipeline::next_frame` produces, the way egui's input state tracks
+/// pointer presses across frames to recognize clicks and drags. `SwipeDetector`
+/// above only ever looked at one pointer's horizontal motion; this sits
+/// alongside it and additionally needs two simultaneously active pointers
+/// (for `Pinch`) and wall-clock timing (for `DoubleTap`/`LongPress`), so it
+/// keeps its own bookkeeping rather than extending `SwipeDetector` in place.
+/// It never reports `Gesture::Swipe`: a press that moved past
+/// `LONG_PRESS_SLOP_PX` ends with no gesture on `Up`.
+pub struct GestureRecognizer {
+    presses: [Option<PointerPress>; MAX_ACTIVE_POINTERS],
+    last_tap: Option<(u64, u16, u16)>,
+    pinch_initial_distance: Option<f32>,
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self {
+            presses: [None; MAX_ACTIVE_POINTERS],
+            last_tap: None,
+            pinch_initial_distance: None,
+        }
+    }
+
+    fn find(&self, pointer_id: u8) -> Option<usize> {
+        self.presses
+            .iter()
+            .position(|slot| matches!(slot, Some(press) if press.pointer_id == pointer_id))
+    }
+
+    fn active_count(&self) -> usize {
+        self.presses.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    fn distance(ax: u16, ay: u16, bx: u16, by: u16) -> f32 {
+        let dx = ax as f32 - bx as f32;
+        let dy = ay as f32 - by as f32;
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Feeds one raw `TouchEventPayload` straight off a `TouchDriver::read_event`
+    /// stream, bypassing `TouchPipeline`'s queueing/validation/fling tracking
+    /// for callers that just want gestures off the wire. Equivalent to
+    /// wrapping the payload in a fling-less `TouchEventFrame` and calling
+    /// [`Self::on_frame`], which remains the entry point for callers that
+    /// already pull frames from `TouchPipeline`.
+    pub fn feed(&mut self, event: &TouchEventPayload, now_ms: u64) -> Option<Gesture> {
+        let frame = TouchEventFrame {
+            pointer_id: event.pointer_id,
+            phase: event.phase,
+            point: DisplayPoint {
+                x: event.x,
+                y: event.y,
+            },
+            fling: None,
+        };
+        self.on_frame(&frame, now_ms)
+    }
+
+    /// Polls for a long press that should fire eagerly even before the
+    /// pointer lifts. An alias for [`Self::check_long_press`] under the
+    /// `feed`/`poll` naming a raw-driver caller drives this recognizer
+    /// with.
+    pub fn poll(&mut self, now_ms: u64) -> Option<Gesture> {
+        self.check_long_press(now_ms)
+    }
+
+    /// Feeds one `TouchEventFrame` into the recognizer. `now_ms` is the same
+    /// tick timestamp the event loop already has on hand when it pops the
+    /// frame from `TouchPipeline::next_frame`.
+    pub fn on_frame(&mut self, frame: &TouchEventFrame, now_ms: u64) -> Option<Gesture> {
+        match frame.phase {
+            TouchPhase::Down => {
+                if self.find(frame.pointer_id).is_none() {
+                    if let Some(index) = self.presses.iter().position(Option::is_none) {
+                        self.presses[index] = Some(PointerPress {
+                            pointer_id: frame.pointer_id,
+                            down_x: frame.point.x,
+                            down_y: frame.point.y,
+                            down_ms: now_ms,
+                            moved: false,
+                            long_press_fired: false,
+                        });
+                    }
+                }
+                if self.active_count() == 2 {
+                    self.pinch_initial_distance = self.two_pointer_distance();
+                }
+                None
+            }
+            TouchPhase::Move => {
+                if let Some(index) = self.find(frame.pointer_id) {
+                    if let Some(press) = self.presses[index].as_mut() {
+                        if Self::distance(press.down_x, press.down_y, frame.point.x, frame.point.y)
+                            > LONG_PRESS_SLOP_PX as f32
+                        {
+                            press.moved = true;
+                        }
+                        press.down_x = frame.point.x;
+                        press.down_y = frame.point.y;
+                    }
+                }
+                if self.active_count() == 2 {
+                    if let (Some(initial), Some(current)) =
+                        (self.pinch_initial_distance, self.two_pointer_distance())
+                    {
+                        if initial > 0.0 {
+                            return Some(Gesture::Pinch { scale: current / initial });
+                        }
+                    }
+                }
+                None
+            }
+            TouchPhase::Up => {
+                let index = self.find(frame.pointer_id)?;
+                let press = self.presses[index].take()?;
+                self.pinch_initial_distance = None;
+
+                if press.long_press_fired {
+                    return None;
+                }
+                if press.moved {
+                    return None;
+                }
+
+                let gesture = match self.last_tap {
+                    Some((last_ms, last_x, last_y))
+                        if now_ms.saturating_sub(last_ms) <= DOUBLE_TAP_MAX_INTERVAL_MS
+                            && Self::distance(last_x, last_y, frame.point.x, frame.point.y)
+                                <= DOUBLE_TAP_MAX_DISTANCE_PX as f32 =>
+                    {
+                        self.last_tap = None;
+                        Gesture::DoubleTap
+                    }
+                    _ => {
+                        self.last_tap = Some((now_ms, frame.point.x, frame.point.y));
+                        Gesture::Tap
+                    }
+                };
+                Some(gesture)
+            }
+            TouchPhase::Cancel => {
+                if let Some(index) = self.find(frame.pointer_id) {
+                    self.presses[index] = None;
+                }
+                self.pinch_initial_distance = None;
+                None
+            }
+            TouchPhase::Unknown => None,
+        }
+    }
+
+    fn two_pointer_distance(&self) -> Option<f32> {
+        let mut active = self.presses.iter().flatten();
+        let first = active.next()?;
+        let second = active.next()?;
+        Some(Self::distance(first.down_x, first.down_y, second.down_x, second.down_y))
+    }
+
+    /// Called once per event-loop tick to check whether any still-held
+    /// pointer has crossed `LONG_PRESS_MIN_HOLD_MS` without moving beyond
+    /// `LONG_PRESS_SLOP_PX`. Reports at most one `Gesture::LongPress` per
+    /// pointer (guarded by `long_press_fired`), so a pointer held well past
+    /// the threshold doesn't re-fire on every subsequent tick.
+    pub fn check_long_press(&mut self, now_ms: u64) -> Option<Gesture> {
+        for slot in self.presses.iter_mut() {
+            if let Some(press) = slot {
+                if !press.moved
+                    && !press.long_press_fired
+                    && now_ms.saturating_sub(press.down_ms) >= LONG_PRESS_MIN_HOLD_MS
+                {
+                    press.long_press_fired = true;
+                    return Some(Gesture::LongPress);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(pointer_id: u8, phase: TouchPhase, x: u16, y: u16) -> TouchEventPayload {
+        TouchEventPayload {
+            pointer_id,
+            phase,
+            x,
+            y,
+            pressure: None,
+            raw_timestamp_ms: None,
+        }
+    }
+
+    #[test]
+    fn tracks_independent_pointers_across_down_move_up() {
+        let mut pipeline = TouchPipeline::new();
+        pipeline.push_event(event(0, TouchPhase::Down, 10, 10));
+        pipeline.push_event(event(1, TouchPhase::Down, 200, 200));
+        pipeline.push_event(event(0, TouchPhase::Move, 12, 11));
+        pipeline.push_event(event(1, TouchPhase::Up, 205, 205));
+
+        assert_eq!(pipeline.next_frame(0).unwrap().pointer_id, 0);
+        assert_eq!(pipeline.next_frame(0).unwrap().pointer_id, 1);
+        assert_eq!(pipeline.next_frame(0).unwrap().pointer_id, 0);
+        assert_eq!(pipeline.next_frame(0).unwrap().pointer_id, 1);
+        assert_eq!(pipeline.dropped_count(), 0);
+    }
+
+    #[test]
+    fn down_for_already_active_pointer_is_dropped_as_protocol_error() {
+        let mut pipeline = TouchPipeline::new();
+        pipeline.push_event(event(0, TouchPhase::Down, 10, 10));
+        pipeline.push_event(event(0, TouchPhase::Down, 20, 20));
+
+        assert_eq!(pipeline.next_frame(0).unwrap().phase, TouchPhase::Down);
+        assert!(pipeline.next_frame(0).is_none());
+        assert_eq!(pipeline.dropped_count(), 1);
+    }
+
+    #[test]
+    fn up_for_unknown_pointer_is_dropped_as_protocol_error() {
+        let mut pipeline = TouchPipeline::new();
+        pipeline.push_event(event(0, TouchPhase::Up, 10, 10));
+
+        assert!(pipeline.next_frame(0).is_none());
+        assert_eq!(pipeline.dropped_count(), 1);
+    }
+
+    #[test]
+    fn down_beyond_max_active_pointers_is_dropped() {
+        let mut pipeline = TouchPipeline::new();
+        for pointer_id in 0..MAX_ACTIVE_POINTERS as u8 {
+            pipeline.push_event(event(pointer_id, TouchPhase::Down, 10, 10));
+        }
+        pipeline.push_event(event(MAX_ACTIVE_POINTERS as u8, TouchPhase::Down, 10, 10));
+
+        for _ in 0..MAX_ACTIVE_POINTERS {
+            assert!(pipeline.next_frame(0).is_some());
+        }
+        assert!(pipeline.next_frame(0).is_none());
+        assert_eq!(pipeline.dropped_count(), 1);
+    }
+
+    fn frame(pointer_id: u8, phase: TouchPhase, x: u16, y: u16) -> TouchEventFrame {
+        TouchEventFrame {
+            pointer_id,
+            point: DisplayPoint { x, y },
+            phase,
+            fling: None,
+        }
+    }
+
+    #[test]
+    fn quick_release_without_movement_is_a_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        assert_eq!(recognizer.on_frame(&frame(0, TouchPhase::Down, 50, 50), 0), None);
+        assert_eq!(
+            recognizer.on_frame(&frame(0, TouchPhase::Up, 50, 50), 100),
+            Some(Gesture::Tap)
+        );
+    }
+
+    #[test]
+    fn second_tap_within_window_and_distance_is_a_double_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 50, 50), 0);
+        assert_eq!(
+            recognizer.on_frame(&frame(0, TouchPhase::Up, 50, 50), 50),
+            Some(Gesture::Tap)
+        );
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 55, 52), 100);
+        assert_eq!(
+            recognizer.on_frame(&frame(0, TouchPhase::Up, 55, 52), 200),
+            Some(Gesture::DoubleTap)
+        );
+    }
+
+    #[test]
+    fn second_tap_after_window_is_a_separate_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 50, 50), 0);
+        recognizer.on_frame(&frame(0, TouchPhase::Up, 50, 50), 50);
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 50, 50), 1_000);
+        assert_eq!(
+            recognizer.on_frame(&frame(0, TouchPhase::Up, 50, 50), 1_050),
+            Some(Gesture::Tap)
+        );
+    }
+
+    #[test]
+    fn held_pointer_past_threshold_without_moving_is_a_long_press() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 50, 50), 0);
+        assert_eq!(recognizer.check_long_press(400), None);
+        assert_eq!(recognizer.check_long_press(500), Some(Gesture::LongPress));
+        assert_eq!(recognizer.check_long_press(600), None);
+        assert_eq!(recognizer.on_frame(&frame(0, TouchPhase::Up, 50, 50), 700), None);
+    }
+
+    #[test]
+    fn moving_beyond_slop_suppresses_long_press_and_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 50, 50), 0);
+        recognizer.on_frame(&frame(0, TouchPhase::Move, 80, 80), 10);
+        assert_eq!(recognizer.check_long_press(600), None);
+        assert_eq!(recognizer.on_frame(&frame(0, TouchPhase::Up, 80, 80), 700), None);
+    }
+
+    #[test]
+    fn two_pointer_move_emits_pinch_scale() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.on_frame(&frame(0, TouchPhase::Down, 100, 100), 0);
+        recognizer.on_frame(&frame(1, TouchPhase::Down, 200, 100), 0);
+        let gesture = recognizer.on_frame(&frame(1, TouchPhase::Move, 300, 100), 10);
+        match gesture {
+            Some(Gesture::Pinch { scale }) => assert!(scale > 1.0),
+            other => panic!("expected Pinch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn feed_recognizes_a_tap_straight_off_a_raw_event_stream() {
+        let mut recognizer = GestureRecognizer::new();
+        assert_eq!(recognizer.feed(&event(0, TouchPhase::Down, 50, 50), 0), None);
+        assert_eq!(
+            recognizer.feed(&event(0, TouchPhase::Up, 50, 50), 100),
+            Some(Gesture::Tap)
+        );
+    }
+
+    #[test]
+    fn poll_is_an_alias_for_check_long_press() {
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.feed(&event(0, TouchPhase::Down, 50, 50), 0);
+        assert_eq!(recognizer.poll(400), None);
+        assert_eq!(recognizer.poll(500), Some(Gesture::LongPress));
+    }
+
+    #[test]
+    fn fast_release_is_reported_as_a_fling() {
+        let mut pipeline = TouchPipeline::new();
+        pipeline.push_event(event(0, TouchPhase::Down, 0, 0));
+        pipeline.push_event(event(0, TouchPhase::Move, 60, 0));
+        pipeline.push_event(event(0, TouchPhase::Up, 120, 0));
+
+        assert!(pipeline.next_frame(0).unwrap().fling.is_none());
+        assert!(pipeline.next_frame(20).unwrap().fling.is_none());
+        let up = pipeline.next_frame(40).unwrap();
+        let fling = up.fling.expect("fast release should be a fling");
+        assert!(fling.vx > FLING_MIN_VELOCITY_PX_PER_MS);
+        assert_eq!(fling.vy, 0.0);
+    }
+
+    #[test]
+    fn slow_release_is_not_a_fling() {
+        let mut pipeline = TouchPipeline::new();
+        pipeline.push_event(event(0, TouchPhase::Down, 0, 0));
+        pipeline.push_event(event(0, TouchPhase::Move, 1, 0));
+        pipeline.push_event(event(0, TouchPhase::Up, 2, 0));
+
+        assert!(pipeline.next_frame(0).unwrap().fling.is_none());
+        assert!(pipeline.next_frame(80).unwrap().fling.is_none());
+        assert!(pipeline.next_frame(160).unwrap().fling.is_none());
+    }
+
+    #[test]
+    fn velocity_samples_outside_the_window_are_ignored() {
+        let mut pipeline = TouchPipeline::new();
+        pipeline.push_event(event(0, TouchPhase::Down, 0, 0));
+        pipeline.push_event(event(0, TouchPhase::Move, 500, 0));
+        pipeline.push_event(event(0, TouchPhase::Up, 500, 0));
+
+        assert!(pipeline.next_frame(0).unwrap().fling.is_none());
+        assert!(pipeline.next_frame(10).unwrap().fling.is_none());
+        // The pointer then sits still well past the velocity window before
+        // releasing, so the old fast sample shouldn't count.
+        assert!(pipeline
+            .next_frame(10 + VELOCITY_SAMPLE_WINDOW_MS * 2)
+            .unwrap()
+            .fling
+            .is_none());
+    }
+}
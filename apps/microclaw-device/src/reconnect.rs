@@ -0,0 +1,332 @@
+//! Connection supervisor that turns WiFi/transport link-state signals into
+//! reconnect attempts, pacing them with decorrelated-jitter backoff so a
+//! fleet of devices that all dropped their link at once don't all retry in
+//! lockstep, the way cyw43/esp-hosted expose link state via an event
+//! channel rather than leaving callers to poll.
+
+/// AWS's "decorrelated jitter": each delay is uniform in
+/// `[base_ms, last_delay_ms * 3]`, capped at `cap_ms`. Spreads retries wider
+/// than resampling independently each time (full jitter) because each
+/// device's next delay is correlated with its own previous one, not just
+/// its attempt number.
+///
+/// Reuses [`crate::transport::splitmix64`] as the seeded PRNG rather than
+/// introducing a second algorithm under a different name — it already
+/// exists in this crate for exactly this "no `rand` dependency" reason and
+/// is just as suitable fed by `now_ms`/`attempt` as a from-scratch xorshift
+/// would be.
+fn decorrelated_jitter(base_ms: u64, last_delay_ms: u64, cap_ms: u64, seed: u64) -> u64 {
+    let high = last_delay_ms.max(base_ms).saturating_mul(3).max(base_ms);
+    let span = high - base_ms;
+    let jittered = base_ms.saturating_add(crate::transport::splitmix64(seed) % (span + 1));
+    jittered.min(cap_ms)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkState {
+    Down,
+    Up,
+}
+
+/// Tracks WiFi link state separately from transport-connected state, since
+/// the link can be up while the WebSocket handshake is still retrying.
+pub struct ReconnectSupervisor {
+    link_state: LinkState,
+    transport_connected: bool,
+    attempt: u32,
+    next_attempt_ms: u64,
+    base_ms: u64,
+    cap_ms: u64,
+    last_delay_ms: u64,
+}
+
+/// Matches the old flat ladder's first rung and ceiling
+/// ([`crate::reconnect_backoff_ms`]), now used as the decorrelated-jitter
+/// `base`/`cap` instead of a deterministic doubling sequence.
+const DEFAULT_BASE_MS: u64 = 500;
+const DEFAULT_CAP_MS: u64 = 30_000;
+
+impl ReconnectSupervisor {
+    pub fn new() -> Self {
+        Self {
+            link_state: LinkState::Down,
+            transport_connected: false,
+            attempt: 0,
+            next_attempt_ms: 0,
+            base_ms: DEFAULT_BASE_MS,
+            cap_ms: DEFAULT_CAP_MS,
+            last_delay_ms: 0,
+        }
+    }
+
+    pub fn link_state(&self) -> LinkState {
+        self.link_state
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// The most recently computed decorrelated-jitter delay, in
+    /// milliseconds — `0` until the first `note_connect_failed`, or after a
+    /// `note_hello_ack` reset.
+    pub fn current_delay_ms(&self) -> u64 {
+        self.last_delay_ms
+    }
+
+    /// Changes the `base`/`cap` the decorrelated-jitter backoff draws from.
+    /// Does not reset `attempt`/`last_delay_ms`, so an in-flight backoff
+    /// keeps its progress; only the bounds of the *next* computed delay
+    /// change.
+    pub fn set_backoff_params(&mut self, base_ms: u64, cap_ms: u64) {
+        self.base_ms = base_ms;
+        self.cap_ms = cap_ms;
+    }
+
+    pub fn set_link_state(&mut self, state: LinkState) {
+        if state == LinkState::Down {
+            self.transport_connected = false;
+        }
+        self.link_state = state;
+    }
+
+    /// Called on every `HelloAck`: resets the attempt counter and backoff
+    /// delay so the next drop starts from `base_ms` again.
+    pub fn note_hello_ack(&mut self) {
+        self.attempt = 0;
+        self.transport_connected = true;
+        self.next_attempt_ms = 0;
+        self.last_delay_ms = 0;
+    }
+
+    pub fn note_connect_failed(&mut self, now_ms: u64) {
+        self.transport_connected = false;
+        self.attempt = self.attempt.saturating_add(1);
+        let seed = now_ms.wrapping_add(self.attempt as u64);
+        self.last_delay_ms =
+            decorrelated_jitter(self.base_ms, self.last_delay_ms, self.cap_ms, seed);
+        self.next_attempt_ms = now_ms.saturating_add(self.last_delay_ms);
+    }
+
+    /// Returns `true` if a reconnect attempt is due at `now_ms` and the link
+    /// is up; it keeps returning `true` until `note_connect_failed` schedules
+    /// the next attempt or the transport connects — there is no point
+    /// reconnecting the transport while the link itself is down.
+    pub fn should_attempt_reconnect(&mut self, now_ms: u64) -> bool {
+        if self.link_state != LinkState::Up || self.transport_connected {
+            return false;
+        }
+        if now_ms < self.next_attempt_ms {
+            return false;
+        }
+        true
+    }
+
+    /// Human-readable toast text for "reconnecting (attempt N)" style UI.
+    pub fn status_label(&self) -> String {
+        if self.transport_connected {
+            "connected".to_owned()
+        } else if self.link_state == LinkState::Down {
+            "link_down".to_owned()
+        } else {
+            format!("reconnecting_attempt_{}", self.attempt)
+        }
+    }
+}
+
+impl Default for ReconnectSupervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configurable pacing for `RuntimeState::poll_reconnect`'s application-level
+/// retries while offline — distinct from the WiFi-link-gated backoff ladder
+/// `ReconnectSupervisor` uses above, since this schedules when to re-emit a
+/// Hello/snapshot-request once the link itself is already back up.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReconnectStrategy {
+    FixedInterval {
+        interval_ms: u64,
+        max_retries: u32,
+    },
+    ExponentialBackoff {
+        base_ms: u64,
+        factor: u32,
+        max_ms: u64,
+        max_retries: u32,
+    },
+    ExponentialWithJitter {
+        base_ms: u64,
+        factor: u32,
+        max_ms: u64,
+        max_retries: u32,
+    },
+}
+
+impl ReconnectStrategy {
+    /// Base delay (before any jitter) before the attempt numbered `attempt`
+    /// (1-based), or `None` once `max_retries` is exhausted.
+    pub fn delay_ms(&self, attempt: u32) -> Option<u64> {
+        match *self {
+            ReconnectStrategy::FixedInterval {
+                interval_ms,
+                max_retries,
+            } => {
+                if attempt > max_retries {
+                    None
+                } else {
+                    Some(interval_ms)
+                }
+            }
+            ReconnectStrategy::ExponentialBackoff {
+                base_ms,
+                factor,
+                max_ms,
+                max_retries,
+            }
+            | ReconnectStrategy::ExponentialWithJitter {
+                base_ms,
+                factor,
+                max_ms,
+                max_retries,
+            } => {
+                if attempt > max_retries {
+                    return None;
+                }
+                let shift = attempt.saturating_sub(1).min(31);
+                Some(base_ms.saturating_mul((factor as u64).saturating_pow(shift)).min(max_ms))
+            }
+        }
+    }
+
+    pub(crate) fn wants_jitter(&self) -> bool {
+        matches!(self, ReconnectStrategy::ExponentialWithJitter { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_attempt_counter_resets_on_hello_ack() {
+        let mut supervisor = ReconnectSupervisor::new();
+        supervisor.set_link_state(LinkState::Up);
+        supervisor.note_connect_failed(0);
+        supervisor.note_connect_failed(0);
+        assert_eq!(supervisor.attempt(), 2);
+        supervisor.note_hello_ack();
+        assert_eq!(supervisor.attempt(), 0);
+    }
+
+    #[test]
+    fn reconnect_not_attempted_while_link_down() {
+        let mut supervisor = ReconnectSupervisor::new();
+        assert!(!supervisor.should_attempt_reconnect(100_000));
+    }
+
+    #[test]
+    fn reconnect_waits_for_backoff_window() {
+        let mut supervisor = ReconnectSupervisor::new();
+        supervisor.set_link_state(LinkState::Up);
+        supervisor.note_connect_failed(1_000);
+        assert!(!supervisor.should_attempt_reconnect(1_000));
+        assert!(supervisor.should_attempt_reconnect(1_000 + supervisor.current_delay_ms()));
+    }
+
+    #[test]
+    fn decorrelated_jitter_stays_within_base_and_cap() {
+        for seed in 0..50u64 {
+            let delay = decorrelated_jitter(500, 0, 30_000, seed);
+            assert!((500..=30_000).contains(&delay));
+        }
+    }
+
+    #[test]
+    fn decorrelated_jitter_can_grow_past_the_previous_delay() {
+        let mut max_seen = 0;
+        for seed in 0..50u64 {
+            max_seen = max_seen.max(decorrelated_jitter(500, 4_000, 30_000, seed));
+        }
+        assert!(max_seen > 4_000, "expected growth above last delay, got {max_seen}");
+    }
+
+    #[test]
+    fn decorrelated_jitter_never_exceeds_cap() {
+        for seed in 0..50u64 {
+            let delay = decorrelated_jitter(500, 100_000, 1_000, seed);
+            assert!(delay <= 1_000);
+        }
+    }
+
+    #[test]
+    fn backoff_delay_resets_to_zero_on_hello_ack() {
+        let mut supervisor = ReconnectSupervisor::new();
+        supervisor.set_link_state(LinkState::Up);
+        supervisor.note_connect_failed(0);
+        assert!(supervisor.current_delay_ms() > 0);
+        supervisor.note_hello_ack();
+        assert_eq!(supervisor.current_delay_ms(), 0);
+    }
+
+    #[test]
+    fn backoff_params_are_configurable() {
+        let mut supervisor = ReconnectSupervisor::new();
+        supervisor.set_backoff_params(1_000, 2_000);
+        supervisor.set_link_state(LinkState::Up);
+        for attempt in 0..10 {
+            supervisor.note_connect_failed(attempt);
+            assert!(supervisor.current_delay_ms() >= 1_000);
+            assert!(supervisor.current_delay_ms() <= 2_000);
+        }
+    }
+
+    #[test]
+    fn status_label_reflects_state() {
+        let mut supervisor = ReconnectSupervisor::new();
+        assert_eq!(supervisor.status_label(), "link_down");
+        supervisor.set_link_state(LinkState::Up);
+        supervisor.note_connect_failed(0);
+        assert_eq!(supervisor.status_label(), "reconnecting_attempt_1");
+        supervisor.note_hello_ack();
+        assert_eq!(supervisor.status_label(), "connected");
+    }
+
+    #[test]
+    fn fixed_interval_strategy_stops_after_max_retries() {
+        let strategy = ReconnectStrategy::FixedInterval {
+            interval_ms: 500,
+            max_retries: 2,
+        };
+        assert_eq!(strategy.delay_ms(1), Some(500));
+        assert_eq!(strategy.delay_ms(2), Some(500));
+        assert_eq!(strategy.delay_ms(3), None);
+    }
+
+    #[test]
+    fn exponential_backoff_strategy_doubles_and_caps() {
+        let strategy = ReconnectStrategy::ExponentialBackoff {
+            base_ms: 100,
+            factor: 2,
+            max_ms: 1_000,
+            max_retries: 10,
+        };
+        assert_eq!(strategy.delay_ms(1), Some(100));
+        assert_eq!(strategy.delay_ms(2), Some(200));
+        assert_eq!(strategy.delay_ms(3), Some(400));
+        assert_eq!(strategy.delay_ms(10), Some(1_000));
+    }
+
+    #[test]
+    fn exponential_with_jitter_wants_jitter_but_shares_base_delay() {
+        let strategy = ReconnectStrategy::ExponentialWithJitter {
+            base_ms: 100,
+            factor: 2,
+            max_ms: 1_000,
+            max_retries: 10,
+        };
+        assert!(strategy.wants_jitter());
+        assert_eq!(strategy.delay_ms(2), Some(200));
+    }
+}
@@ -26,9 +26,39 @@ impl Default for RenderStats {
     }
 }
 
+/// A timed animation window, e.g. a swipe-driven scene transition a
+/// renderer is interpolating. `finished` follows the Trezor UI flow model
+/// of gating repaint on an explicit `finished(now)` check rather than a
+/// fixed number of frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Animation {
+    pub start: u64,
+    pub duration: u64,
+}
+
+impl Animation {
+    pub fn new(start: u64, duration: u64) -> Self {
+        Self { start, duration }
+    }
+
+    pub fn finished(&self, now: u64) -> bool {
+        now.saturating_sub(self.start) >= self.duration
+    }
+}
+
 pub trait SceneRenderer {
     fn render(&mut self, state: &RuntimeState, now_ms: u64) -> bool;
     fn stats(&self) -> &RenderStats;
+
+    /// Whether the renderer is still mid-animation at `now_ms`. While this
+    /// is `true`, every `DeviceEventLoop` step variant renders on each step
+    /// regardless of `render_interval_ms`; once it goes back to `false` the
+    /// loop falls back to the idle interval. No extra render is forced on
+    /// the step where the animation ends. Renderers with nothing to animate
+    /// can rely on this default.
+    fn has_active_animation(&self, _now_ms: u64) -> bool {
+        false
+    }
 }
 
 pub struct NullRenderer {
@@ -125,6 +155,12 @@ impl SceneRenderer for NullRenderer {
     }
 }
 
+/// How long a scene transition keeps forcing a render after the scene
+/// changes, so a renderer that wants to animate the handoff (e.g. cross-
+/// fading `SceneFramePlan`s) gets repainted every frame for the duration
+/// instead of waiting on `render_interval_ms`.
+const SCENE_TRANSITION_ANIMATION_MS: u64 = 200;
+
 pub struct DisplaySceneRenderer<D: DisplayDriver> {
     display: D,
     current_scene: Option<crate::ui::Scene>,
@@ -132,6 +168,7 @@ pub struct DisplaySceneRenderer<D: DisplayDriver> {
     force_next_render: bool,
     framebuffer: Vec<u16>,
     queued_feedback: u16,
+    transition: Option<Animation>,
 }
 
 impl<D: DisplayDriver> DisplaySceneRenderer<D> {
@@ -148,6 +185,7 @@ impl<D: DisplayDriver> DisplaySceneRenderer<D> {
             force_next_render: true,
             framebuffer,
             queued_feedback: 0,
+            transition: None,
         }
     }
 
@@ -161,11 +199,14 @@ impl<D: DisplayDriver> DisplaySceneRenderer<D> {
 }
 
 impl<D: DisplayDriver> SceneRenderer for DisplaySceneRenderer<D> {
-    fn render(&mut self, state: &RuntimeState, _now_ms: u64) -> bool {
+    fn render(&mut self, state: &RuntimeState, now_ms: u64) -> bool {
         self.stats_.frames_requested = self.stats_.frames_requested.saturating_add(1);
         let target = state.scene();
         let show_feedback = self.queued_feedback > 0;
         self.queued_feedback = 0;
+        if self.current_scene.is_some() && self.current_scene != Some(target) {
+            self.transition = Some(Animation::new(now_ms, SCENE_TRANSITION_ANIMATION_MS));
+        }
         if self.force_next_render || self.current_scene != Some(target) {
             let width = self.display.width();
             let height = self.display.height();
@@ -214,6 +255,10 @@ impl<D: DisplayDriver> SceneRenderer for DisplaySceneRenderer<D> {
     fn stats(&self) -> &RenderStats {
         &self.stats_
     }
+
+    fn has_active_animation(&self, now_ms: u64) -> bool {
+        self.transition.is_some_and(|anim| !anim.finished(now_ms))
+    }
 }
 
 fn scene_to_index(scene: crate::ui::Scene) -> i32 {
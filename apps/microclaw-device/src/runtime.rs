@@ -3,17 +3,94 @@ use std::collections::{HashMap, VecDeque};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use microclaw_protocol::{
-    DeviceAction, DeviceStatus, Envelope, MessageId, MessageKind, TouchEventPayload,
-    TransportMessage,
+    DeviceAction, DeviceStatus, Envelope, InputInjectionPayload, MessageId, MessageKind,
+    SwipeDirectionWire, TouchEventPayload, TransportMessage,
 };
 use serde_json::{json, Value};
 
+use crate::ack::{AckToken, ManualAckRegistry, PendingManualAck};
+use crate::crypto::{NullVerifier, ReplayFilter, SignatureVerifier};
 use crate::display::DisplayPoint;
+use crate::reconnect::{LinkState, ReconnectStrategy, ReconnectSupervisor};
+use crate::transport::full_jitter;
 use crate::storage::{self, DeviceStorage};
 use crate::ui::Scene;
 
 const DEFAULT_SAFETY_RETRIES: u32 = 5;
 
+/// Bound on how many out-of-order `StatusDelta` frames a single sender can
+/// have buffered while waiting for a gap to fill, mirroring how
+/// [`crate::crypto::ReplayFilter`] bounds its per-sender window rather than
+/// growing unbounded with a misbehaving or compromised sender.
+const DELTA_REORDER_BUFFER_CAPACITY: usize = 8;
+const DEFAULT_DELTA_GAP_STEP_LIMIT: u32 = 3;
+
+/// Per-sender ordering state for `StatusDelta` gap detection: the next
+/// `envelope.seq` expected from this sender, any higher-numbered deltas
+/// buffered ahead of it, and how many further deltas have arrived since the
+/// gap first opened.
+struct DeltaOrderState {
+    expected_seq: u64,
+    buffer: VecDeque<(u64, DeviceStatus)>,
+    gap_steps: u32,
+}
+
+/// Abstracts wall-clock time behind a trait instead of `RuntimeState`
+/// calling the free-function `now_ms()` directly, so tests can drive time
+/// deterministically with [`ManualClock`] rather than depending on real
+/// `SystemTime` elapsing mid-test.
+pub trait Clock {
+    fn now_ms(&self) -> u64;
+}
+
+/// Default clock, backed by `SystemTime` via the free-function `now_ms()` —
+/// this is what every `RuntimeState` used before `Clock` existed, so
+/// `RuntimeState::new()` keeps defaulting to it.
+///
+/// Only this impl touches `SystemTime` directly; full `no_std` support would
+/// also need `HashMap`/`Box`/`String` usage elsewhere in this crate replaced
+/// with `alloc` equivalents, which is out of scope here.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        now_ms()
+    }
+}
+
+/// A clock tests can set to an arbitrary value, so time-dependent behavior
+/// (heartbeat staleness, TTL expiry, in-flight reclaim) can be exercised
+/// without waiting on real time to pass. Uses interior mutability so a test
+/// can advance it after handing a `Box<dyn Clock>` off to `RuntimeState`.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    current_ms: std::rc::Rc<Cell<u64>>,
+}
+
+impl ManualClock {
+    pub fn new(start_ms: u64) -> Self {
+        Self {
+            current_ms: std::rc::Rc::new(Cell::new(start_ms)),
+        }
+    }
+
+    pub fn set(&self, now_ms: u64) {
+        self.current_ms.set(now_ms);
+    }
+
+    pub fn advance(&self, delta_ms: u64) {
+        self.current_ms
+            .set(self.current_ms.get().saturating_add(delta_ms));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> u64 {
+        self.current_ms.get()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum RuntimeMode {
     Booting,
@@ -23,6 +100,26 @@ pub enum RuntimeMode {
     SafeMode(String),
 }
 
+/// A graded read on link health, so the UI and host can see a connection
+/// degrading before it actually drops to `RuntimeMode::Offline`, rather than
+/// the previous binary Connected/Offline cliff edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkQuality {
+    Weak,
+    Good,
+    Strong,
+}
+
+/// Above this many unacked in-flight commands, quality is downgraded one
+/// step from what heartbeat freshness alone would suggest.
+const IN_FLIGHT_BACKLOG_DOWNGRADE_THRESHOLD: usize = 4;
+
+/// Heartbeat timeout `scene()` grades link quality against, mirroring
+/// `EventLoopConfig::default().offline_timeout_ms` since `scene()` itself
+/// takes no parameters and callers (the renderer) expect a self-contained
+/// read of the current scene.
+const DEFAULT_HEARTBEAT_TIMEOUT_MS: u64 = 15_000;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum RuntimeAction {
     None,
@@ -33,6 +130,17 @@ pub enum RuntimeAction {
     EmitCommand {
         action: DeviceAction,
     },
+    /// Auto-acks a just-handled inbound `DeviceAction` back to the host:
+    /// `packet_id` is the one the host's delivery tracker assigned when it
+    /// dispatched the command. Not raised for actions in the opt-in
+    /// manual-ack set -- those stay pending until `RuntimeState::ack`.
+    EmitCommandAck {
+        packet_id: u64,
+        message: &'static str,
+    },
+    EmitSnapshotRequest {
+        reason: &'static str,
+    },
     RaiseUiState {
         message: &'static str,
     },
@@ -45,6 +153,109 @@ pub struct InFlightCommand {
     pub enqueued_at_ms: u64,
 }
 
+pub type SubscriberId = u64;
+
+/// Typed notification emitted whenever `RuntimeState` changes something a
+/// subscriber (host bridge, diagnostics UI, test harness) might care about,
+/// so callers don't have to diff `mode()`/`status()` snapshots themselves to
+/// notice a transition.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeEvent {
+    ModeChanged(RuntimeMode),
+    StatusUpdated,
+    OtaStateChanged { in_progress: bool },
+    SafetyTripped { fail_count: u32 },
+    CommandAcked { corr_id: String },
+    HeartbeatStale,
+}
+
+impl RuntimeEvent {
+    fn mask(&self) -> EventMask {
+        match self {
+            RuntimeEvent::ModeChanged(_) => EventMask::MODE_CHANGED,
+            RuntimeEvent::StatusUpdated => EventMask::STATUS_UPDATED,
+            RuntimeEvent::OtaStateChanged { .. } => EventMask::OTA_STATE_CHANGED,
+            RuntimeEvent::SafetyTripped { .. } => EventMask::SAFETY_TRIPPED,
+            RuntimeEvent::CommandAcked { .. } => EventMask::COMMAND_ACKED,
+            RuntimeEvent::HeartbeatStale => EventMask::HEARTBEAT_STALE,
+        }
+    }
+}
+
+/// Bitset selecting which [`RuntimeEvent`] variants a subscriber receives,
+/// so a lightweight listener (e.g. a toast banner) doesn't have to filter
+/// out event kinds it has no use for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventMask(u16);
+
+impl EventMask {
+    pub const MODE_CHANGED: EventMask = EventMask(1 << 0);
+    pub const STATUS_UPDATED: EventMask = EventMask(1 << 1);
+    pub const OTA_STATE_CHANGED: EventMask = EventMask(1 << 2);
+    pub const SAFETY_TRIPPED: EventMask = EventMask(1 << 3);
+    pub const COMMAND_ACKED: EventMask = EventMask(1 << 4);
+    pub const HEARTBEAT_STALE: EventMask = EventMask(1 << 5);
+    pub const NONE: EventMask = EventMask(0);
+    pub const ALL: EventMask = EventMask(0b11_1111);
+
+    pub fn contains(self, other: EventMask) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    fn matches(self, event: &RuntimeEvent) -> bool {
+        self.contains(event.mask())
+    }
+}
+
+impl std::ops::BitOr for EventMask {
+    type Output = EventMask;
+
+    fn bitor(self, rhs: EventMask) -> EventMask {
+        EventMask(self.0 | rhs.0)
+    }
+}
+
+/// Per-subscriber backlog cap, mirroring the trim discipline `push_diagnostic`
+/// already uses so a subscriber that stops polling can't grow unbounded.
+const MAX_EVENTS_PER_SUBSCRIBER: usize = 16;
+
+/// Per-source capability scoping layered on top of the flat
+/// `host_allowlist`: a source that passes `is_host_allowed` can still be
+/// restricted to a subset of [`DeviceAction`]s, so e.g. a monitoring host
+/// can read diagnostics without being able to trigger `OtaStart`/`Restart`.
+/// A `"*"` entry sets the default for any source with no entry of its own;
+/// a source with neither an exact nor a `"*"` entry falls back to
+/// read-only access (`DiagnosticsSnapshot`) rather than full trust.
+#[derive(Clone, Debug, Default)]
+pub struct ActionPolicy {
+    by_source: HashMap<String, std::collections::HashSet<DeviceAction>>,
+}
+
+impl ActionPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow(
+        &mut self,
+        source: impl Into<String>,
+        actions: impl IntoIterator<Item = DeviceAction>,
+    ) {
+        self.by_source
+            .insert(source.into(), actions.into_iter().collect());
+    }
+
+    fn is_allowed(&self, source: &str, action: &DeviceAction) -> bool {
+        if let Some(actions) = self.by_source.get(source) {
+            return actions.contains(action);
+        }
+        if let Some(actions) = self.by_source.get("*") {
+            return actions.contains(action);
+        }
+        matches!(action, DeviceAction::DiagnosticsSnapshot)
+    }
+}
+
 pub struct RuntimeState {
     mode: RuntimeMode,
     last_seq: u64,
@@ -57,6 +268,7 @@ pub struct RuntimeState {
     offline_since_ms: Option<u64>,
     last_heartbeat_ms: Option<u64>,
     host_allowlist: Vec<String>,
+    action_policy: ActionPolicy,
     safety_fail_count: u32,
     safety_fail_limit: u32,
     ota_in_progress: bool,
@@ -65,8 +277,34 @@ pub struct RuntimeState {
     boot_failure_count: u32,
     boot_retry_limit: u32,
     scene_cache: Cell<Scene>,
+    /// Which scene `crate::flow::Flow` has navigated to via an accepted
+    /// swipe, if any. Only consulted while `mode` is `Connected` -- `scene()`
+    /// still takes priority over it for boot/offline/error/safe-mode so a
+    /// stale in-app scene never masks those.
+    nav_scene_override: Option<Scene>,
     storage: Option<Box<dyn DeviceStorage>>,
     pending_reconciliation: bool,
+    verifier: Box<dyn SignatureVerifier>,
+    signer: Option<Box<dyn crate::crypto::Signer>>,
+    replay_filter: ReplayFilter,
+    seq_replay: ReplayFilter,
+    delta_order: HashMap<String, DeltaOrderState>,
+    delta_gap_step_limit: u32,
+    #[cfg(feature = "secure-session")]
+    host_config: Option<crate::handshake::HostConfig>,
+    #[cfg(feature = "secure-session")]
+    pending_handshake: Option<crate::handshake::PendingHandshake>,
+    #[cfg(feature = "secure-session")]
+    session_keys: Option<crate::handshake::SessionKeys>,
+    reconnect: ReconnectSupervisor,
+    reconnect_strategy: Option<ReconnectStrategy>,
+    reconnect_attempts: u32,
+    next_reconnect_at_ms: Option<u64>,
+    subscribers: HashMap<SubscriberId, (EventMask, VecDeque<RuntimeEvent>)>,
+    next_subscriber_id: SubscriberId,
+    clock: Box<dyn Clock>,
+    manual_ack_actions: std::collections::HashSet<DeviceAction>,
+    manual_acks: ManualAckRegistry,
 }
 
 impl RuntimeState {
@@ -83,6 +321,7 @@ impl RuntimeState {
             offline_since_ms: None,
             last_heartbeat_ms: None,
             host_allowlist: Vec::new(),
+            action_policy: ActionPolicy::default(),
             safety_fail_count: 0,
             safety_fail_limit: DEFAULT_SAFETY_RETRIES,
             ota_in_progress: false,
@@ -91,8 +330,30 @@ impl RuntimeState {
             boot_failure_count: 0,
             boot_retry_limit: 3,
             scene_cache: Cell::new(Scene::Boot),
+            nav_scene_override: None,
             storage: None,
             pending_reconciliation: false,
+            verifier: Box::new(NullVerifier),
+            signer: None,
+            replay_filter: ReplayFilter::new(),
+            seq_replay: ReplayFilter::new(),
+            delta_order: HashMap::new(),
+            delta_gap_step_limit: DEFAULT_DELTA_GAP_STEP_LIMIT,
+            #[cfg(feature = "secure-session")]
+            host_config: None,
+            #[cfg(feature = "secure-session")]
+            pending_handshake: None,
+            #[cfg(feature = "secure-session")]
+            session_keys: None,
+            reconnect: ReconnectSupervisor::new(),
+            reconnect_strategy: None,
+            reconnect_attempts: 0,
+            next_reconnect_at_ms: None,
+            subscribers: HashMap::new(),
+            next_subscriber_id: 0,
+            clock: Box::new(SystemClock),
+            manual_ack_actions: std::collections::HashSet::new(),
+            manual_acks: ManualAckRegistry::new(),
         }
     }
 
@@ -103,9 +364,14 @@ impl RuntimeState {
         let device_id = storage
             .get_string(storage::keys::DEVICE_ID)
             .unwrap_or_else(|| "device".to_owned());
+        let manual_acks = storage
+            .get_bytes(storage::keys::PENDING_MANUAL_ACKS)
+            .map(|bytes| ManualAckRegistry::from_bytes(&bytes))
+            .unwrap_or_default();
         let mut state = Self::new();
         state.boot_failure_count = boot_failure_count;
         state.device_id = device_id;
+        state.manual_acks = manual_acks;
         state.storage = Some(storage);
 
         if boot_failure_count >= state.boot_retry_limit {
@@ -114,16 +380,185 @@ impl RuntimeState {
         state
     }
 
+    /// Opts `actions` into manual-ack mode: a matching inbound `DeviceAction`
+    /// is still handled immediately, but its `CommandAck` isn't sent until
+    /// the caller explicitly calls [`RuntimeState::ack`], and the pending
+    /// entry is persisted so a crash mid-handler redelivers it on restart
+    /// instead of silently dropping it.
+    pub fn with_manual_ack_actions(
+        mut self,
+        actions: impl IntoIterator<Item = DeviceAction>,
+    ) -> Self {
+        self.manual_ack_actions.extend(actions);
+        self
+    }
+
     pub fn with_host_allowlist(hosts: impl IntoIterator<Item = impl Into<String>>) -> Self {
         let mut state = Self::new();
         state.host_allowlist = hosts.into_iter().map(Into::into).collect();
         state
     }
 
+    /// Grants `source` (or `"*"` for any source without its own entry)
+    /// exactly `actions`, replacing whatever was previously granted to it.
+    pub fn with_action_policy(
+        mut self,
+        source: impl Into<String>,
+        actions: impl IntoIterator<Item = DeviceAction>,
+    ) -> Self {
+        self.action_policy.allow(source, actions);
+        self
+    }
+
     pub fn set_storage(&mut self, storage: Box<dyn DeviceStorage>) {
         self.storage = Some(storage);
     }
 
+    /// Installs the signature backend used to authenticate inbound frames.
+    /// `Command`/`StatusSnapshot` frames are required to carry a signature
+    /// regardless of this setting (see `apply_transport_message`); every
+    /// other kind is only checked if it arrives with one.
+    pub fn set_verifier(&mut self, verifier: Box<dyn SignatureVerifier>) {
+        self.verifier = verifier;
+    }
+
+    /// Chainable form of `set_verifier`, for construction sites that build a
+    /// `RuntimeState` through a sequence of `with_*` calls. Leaving this
+    /// unset keeps the default `NullVerifier`, which fails every signature:
+    /// unsigned frames of other kinds still pass on the host allowlist alone,
+    /// but every signed frame and every `Command`/`StatusSnapshot` frame is
+    /// rejected.
+    pub fn with_verifier(mut self, verifier: Box<dyn SignatureVerifier>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Installs the backend used to sign this device's own outbound
+    /// `Command`/`SnapshotRequest` frames. Leaving this unset keeps
+    /// `signature`/`nonce` as `None` on emitted frames, the prior
+    /// unauthenticated behavior.
+    pub fn set_signer(&mut self, signer: Box<dyn crate::crypto::Signer>) {
+        self.signer = Some(signer);
+    }
+
+    /// Chainable form of `set_signer`.
+    pub fn with_signer(mut self, signer: Box<dyn crate::crypto::Signer>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Installs the clock internal time-dependent bookkeeping (command
+    /// expiry, heartbeat timestamps, OTA/command emission) reads from.
+    /// Defaults to [`SystemClock`]; tests typically install a
+    /// [`ManualClock`] instead.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Installs this device's static identity and its host's known static
+    /// public key, enabling the authenticated handshake in
+    /// [`crate::handshake`]. Until this is set, `HelloAck` frames are
+    /// accepted as plaintext (the pre-handshake behavior).
+    #[cfg(feature = "secure-session")]
+    pub fn set_host_config(&mut self, config: crate::handshake::HostConfig) {
+        self.host_config = Some(config);
+    }
+
+    /// Chainable form of `set_host_config`, for construction sites that
+    /// build a `RuntimeState` through a sequence of `with_*` calls.
+    #[cfg(feature = "secure-session")]
+    pub fn with_host_config(mut self, config: crate::handshake::HostConfig) -> Self {
+        self.host_config = Some(config);
+        self
+    }
+
+    /// The session keys derived by the most recently completed handshake,
+    /// if any. `None` until `complete_handshake` succeeds at least once.
+    #[cfg(feature = "secure-session")]
+    pub fn session_keys(&self) -> Option<&crate::handshake::SessionKeys> {
+        self.session_keys.as_ref()
+    }
+
+    /// Builds the device's `Hello` frame, starting a handshake against the
+    /// configured [`crate::handshake::HostConfig`]. Panics if no host
+    /// config was installed.
+    #[cfg(feature = "secure-session")]
+    pub fn emit_hello(&mut self) -> TransportMessage {
+        let config = self
+            .host_config
+            .as_ref()
+            .expect("emit_hello requires set_host_config to have been called");
+        let (hello, pending) = crate::handshake::initiate(config, self.clock.now_ms());
+        self.pending_handshake = Some(pending);
+
+        let seq = self.outbound_seq.saturating_add(1);
+        self.outbound_seq = seq;
+        let message_id = MessageId::new(format!("hello-{seq}"));
+        let envelope = Envelope {
+            v: 1,
+            seq,
+            source: self.device_id.clone(),
+            device_id: self.device_id.clone(),
+            session_id: "handshake".to_owned(),
+            message_id,
+        };
+
+        TransportMessage {
+            envelope,
+            kind: MessageKind::Hello,
+            corr_id: None,
+            ttl_ms: None,
+            issued_at: Some(self.clock.now_ms()),
+            signature: None,
+            nonce: None,
+            payload: hello.to_json(),
+        }
+    }
+
+    /// Finalizes the pending handshake against an inbound `HelloAck`,
+    /// storing the derived session keys on success. Returns the rejection
+    /// action to surface when there either was no handshake in flight or
+    /// the host failed to authenticate.
+    #[cfg(feature = "secure-session")]
+    fn complete_handshake(&mut self, msg: &TransportMessage) -> Result<(), RuntimeAction> {
+        let pending = self.pending_handshake.take().ok_or(RuntimeAction::RaiseUiState {
+            message: "handshake_failed",
+        })?;
+        let ack = crate::handshake::HelloAckPayload::from_json(&msg.payload).ok_or(
+            RuntimeAction::RaiseUiState {
+                message: "handshake_failed",
+            },
+        )?;
+        let config = self
+            .host_config
+            .as_ref()
+            .expect("complete_handshake only runs once host_config is set");
+
+        match crate::handshake::finalize(pending, config, &ack) {
+            Ok(keys) => {
+                self.session_keys = Some(keys);
+                Ok(())
+            }
+            Err(_) => Err(RuntimeAction::RaiseUiState {
+                message: "handshake_failed",
+            }),
+        }
+    }
+
+    /// Sets the width, in counters, of the per-sender sliding window used
+    /// to reject replayed `envelope.seq` values (see
+    /// [`crate::event_loop::EventLoopConfig::replay_window_size`]).
+    /// Already-tracked senders keep their progress; only how far behind the
+    /// highest seen counter they may still fall changes.
+    pub fn set_replay_window_size(&mut self, window_size: u64) {
+        self.seq_replay.set_window_size(window_size);
+    }
+
+    pub fn with_clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn set_device_id(&mut self, device_id: impl Into<String>) {
         self.device_id = device_id.into();
     }
@@ -190,14 +625,183 @@ impl RuntimeState {
         self.boot_failure_count
     }
 
+    pub fn link_state(&self) -> LinkState {
+        self.reconnect.link_state()
+    }
+
+    pub fn reconnect_attempt(&self) -> u32 {
+        self.reconnect.attempt()
+    }
+
+    /// Called by the WiFi layer whenever the radio's link-up/link-down
+    /// event fires, independent of whether the transport has reconnected.
+    pub fn note_link_state(&mut self, state: LinkState) {
+        self.reconnect.set_link_state(state);
+    }
+
+    /// Called when a transport (re)connect attempt fails; advances the
+    /// supervisor's backoff ladder and persists the boot failure count the
+    /// same way a boot-time failure would.
+    pub fn note_connect_failed(&mut self, now_ms: u64) -> RuntimeAction {
+        self.reconnect.note_connect_failed(now_ms);
+        self.mark_boot_failure(now_ms, "reconnect_attempt_failed");
+        RuntimeAction::RaiseUiState {
+            message: "reconnect_attempt_failed",
+        }
+    }
+
+    /// Whether the supervisor's backoff window has elapsed for `now_ms` and
+    /// a reconnect attempt should be made.
+    pub fn should_attempt_reconnect(&mut self, now_ms: u64) -> bool {
+        self.reconnect.should_attempt_reconnect(now_ms)
+    }
+
+    /// Sets the `base`/`cap` the supervisor's decorrelated-jitter backoff
+    /// draws from (see
+    /// [`crate::event_loop::EventLoopConfig::transport_reconnect_backoff_ms`]).
+    pub fn set_reconnect_backoff_params(&mut self, base_ms: u64, cap_ms: u64) {
+        self.reconnect.set_backoff_params(base_ms, cap_ms);
+    }
+
+    /// The supervisor's most recently computed reconnect delay, in
+    /// milliseconds, exposed so tests and transport stats can assert the
+    /// jittered backoff stays within bounds and grows on repeated failures.
+    pub fn reconnect_current_delay_ms(&self) -> u64 {
+        self.reconnect.current_delay_ms()
+    }
+
+    /// Configures the pacing `poll_reconnect` uses while `Offline`. Leaving
+    /// this unset means `poll_reconnect` never fires, matching the prior
+    /// behavior of callers polling blindly with their own timer.
+    pub fn set_reconnect_strategy(&mut self, strategy: ReconnectStrategy) {
+        self.reconnect_strategy = Some(strategy);
+    }
+
+    /// Number of application-level reconnect attempts made since the last
+    /// successful `HelloAck`.
+    pub fn reconnect_attempts(&self) -> u32 {
+        self.reconnect_attempts
+    }
+
+    /// While `Offline` and a strategy has been configured, returns a fresh
+    /// snapshot-request exactly when `now_ms` has reached the scheduled
+    /// retry time, then advances the schedule and increments the attempt
+    /// counter. Once the strategy's `max_retries` is exhausted, transitions
+    /// into `SafeMode` instead of scheduling a further attempt, so the
+    /// existing safety machinery takes over.
+    pub fn poll_reconnect(&mut self, now_ms: u64) -> Option<TransportMessage> {
+        if !matches!(self.mode, RuntimeMode::Offline) {
+            return None;
+        }
+        let strategy = self.reconnect_strategy.clone()?;
+        if now_ms < self.next_reconnect_at_ms.unwrap_or(0) {
+            return None;
+        }
+
+        let attempt = self.reconnect_attempts.saturating_add(1);
+        let Some(base_delay) = strategy.delay_ms(attempt) else {
+            self.set_mode(RuntimeMode::SafeMode("reconnect_retries_exhausted".to_owned()));
+            self.next_reconnect_at_ms = None;
+            return None;
+        };
+        let delay = if strategy.wants_jitter() {
+            full_jitter(base_delay, now_ms.wrapping_add(attempt as u64))
+        } else {
+            base_delay
+        };
+
+        self.reconnect_attempts = attempt;
+        self.next_reconnect_at_ms = Some(now_ms.saturating_add(delay));
+        Some(self.emit_snapshot_request())
+    }
+
+    /// Registers a new subscriber that receives every future [`RuntimeEvent`]
+    /// matching `mask`, and returns the handle `poll_events` reads back with.
+    pub fn subscribe(&mut self, mask: EventMask) -> SubscriberId {
+        let id = self.next_subscriber_id;
+        self.next_subscriber_id = self.next_subscriber_id.wrapping_add(1);
+        self.subscribers.insert(id, (mask, VecDeque::new()));
+        id
+    }
+
+    pub fn unsubscribe(&mut self, id: SubscriberId) {
+        self.subscribers.remove(&id);
+    }
+
+    /// Drains and returns every event `id` has accumulated since the last
+    /// call, oldest first. Returns an empty `Vec` for an unknown or
+    /// unsubscribed id rather than panicking.
+    pub fn poll_events(&mut self, id: SubscriberId) -> Vec<RuntimeEvent> {
+        match self.subscribers.get_mut(&id) {
+            Some((_, queue)) => queue.drain(..).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn emit_event(&mut self, event: RuntimeEvent) {
+        for (mask, queue) in self.subscribers.values_mut() {
+            if mask.matches(&event) {
+                queue.push_back(event.clone());
+                while queue.len() > MAX_EVENTS_PER_SUBSCRIBER {
+                    queue.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Sets `self.mode`, emitting a `ModeChanged` event to subscribers only
+    /// when it actually differs from the current mode.
+    fn set_mode(&mut self, mode: RuntimeMode) {
+        if self.mode != mode {
+            self.mode = mode.clone();
+            self.emit_event(RuntimeEvent::ModeChanged(mode));
+        }
+    }
+
+    fn bump_safety_fail_count(&mut self) {
+        self.safety_fail_count = self.safety_fail_count.saturating_add(1);
+        self.emit_event(RuntimeEvent::SafetyTripped {
+            fail_count: self.safety_fail_count,
+        });
+    }
+
     pub fn in_flight_ids(&self) -> Vec<String> {
         self.in_flight.keys().cloned().collect()
     }
 
+    /// Grades link health from two signals: how stale the last heartbeat is
+    /// relative to `heartbeat_timeout_ms` (fresh < 1/3 of the timeout is
+    /// `Strong`, < 2/3 is `Good`, otherwise `Weak`), and whether the
+    /// in-flight command backlog has grown large enough to suggest acks
+    /// aren't keeping up, which downgrades one step.
+    pub fn link_quality(&self, now_ms: u64, heartbeat_timeout_ms: u64) -> LinkQuality {
+        let age = self.heartbeat_age_ms(now_ms);
+        let mut quality = if age < heartbeat_timeout_ms / 3 {
+            LinkQuality::Strong
+        } else if age < heartbeat_timeout_ms * 2 / 3 {
+            LinkQuality::Good
+        } else {
+            LinkQuality::Weak
+        };
+
+        if self.in_flight.len() > IN_FLIGHT_BACKLOG_DOWNGRADE_THRESHOLD {
+            quality = match quality {
+                LinkQuality::Strong => LinkQuality::Good,
+                LinkQuality::Good | LinkQuality::Weak => LinkQuality::Weak,
+            };
+        }
+        quality
+    }
+
     pub fn scene(&self) -> Scene {
         let scene = match &self.mode {
             RuntimeMode::Booting => Scene::Boot,
-            RuntimeMode::Connected => Scene::Paired,
+            RuntimeMode::Connected
+                if self.link_quality(self.clock.now_ms(), DEFAULT_HEARTBEAT_TIMEOUT_MS) == LinkQuality::Weak =>
+            {
+                Scene::Offline
+            }
+            RuntimeMode::Connected => self.nav_scene_override.unwrap_or(Scene::Paired),
             RuntimeMode::Offline => Scene::Offline,
             RuntimeMode::Error(_) => Scene::Error,
             RuntimeMode::SafeMode(_) => Scene::Settings,
@@ -219,6 +823,14 @@ impl RuntimeState {
             .any(|allowed| allowed == source || allowed == "*")
     }
 
+    /// Sets (or clears, via `None`) the in-app scene `crate::flow::Flow`
+    /// navigated to. Takes effect the next time `scene()` is read while
+    /// `mode` is `Connected`; it's otherwise ignored so a swipe made just
+    /// before going offline can't mask the offline/error/safe-mode scene.
+    pub fn set_nav_scene_override(&mut self, scene: Option<Scene>) {
+        self.nav_scene_override = scene;
+    }
+
     pub fn process_touch(&mut self, point: DisplayPoint) -> RuntimeAction {
         let action = self.scene().action_for_touch(point);
         match action {
@@ -246,88 +858,163 @@ impl RuntimeState {
 
     pub fn apply_transport_message(&mut self, msg: &TransportMessage) -> RuntimeAction {
         if !self.is_host_allowed(msg.envelope.source.as_str()) {
-            self.safety_fail_count = self.safety_fail_count.saturating_add(1);
+            self.bump_safety_fail_count();
             return RuntimeAction::RaiseUiState {
                 message: "command_denied_unauthorized_source",
             };
         }
 
-        if msg.is_expired(now_ms()) {
+        if msg.is_expired(self.clock.now_ms()) {
             return RuntimeAction::RaiseUiState {
                 message: "message_expired_ttl",
             };
         }
 
-        if self.is_duplicate_or_stale(msg.envelope.seq, &msg.envelope.message_id) {
+        let requires_signature =
+            matches!(msg.kind, MessageKind::Command | MessageKind::StatusSnapshot);
+        if requires_signature && msg.signature.is_none() {
+            self.bump_safety_fail_count();
+            return RuntimeAction::RaiseUiState {
+                message: "signature_invalid",
+            };
+        }
+
+        if msg.signature.is_some() {
+            if let Err(failure) =
+                crate::crypto::authenticate(self.verifier.as_ref(), &mut self.replay_filter, msg)
+            {
+                self.bump_safety_fail_count();
+                return RuntimeAction::RaiseUiState {
+                    message: match failure {
+                        crate::crypto::AuthFailure::InvalidSignature => "signature_invalid",
+                        crate::crypto::AuthFailure::ReplayedNonce => "nonce_replayed",
+                    },
+                };
+            }
+        }
+
+        if self.is_duplicate_message_id(&msg.envelope.message_id) {
             return RuntimeAction::RaiseUiState {
                 message: "replay_or_duplicate_rejected",
             };
         }
 
-        self.last_seq = msg.envelope.seq;
+        if !self
+            .seq_replay
+            .accept_counter(msg.envelope.source.as_str(), msg.envelope.seq)
+        {
+            return RuntimeAction::RaiseUiState {
+                message: "replay_rejected",
+            };
+        }
+
+        self.last_seq = self.last_seq.max(msg.envelope.seq);
         self.track_message_id(msg.envelope.seq, &msg.envelope.message_id);
         self.note_heartbeat(msg.issued_at);
 
         match &msg.kind {
             MessageKind::HelloAck => {
+                #[cfg(feature = "secure-session")]
+                if self.host_config.is_some() {
+                    if let Err(action) = self.complete_handshake(msg) {
+                        self.bump_safety_fail_count();
+                        return action;
+                    }
+                }
                 self.mark_boot_success();
+                self.reconnect.note_hello_ack();
+                self.reconnect_attempts = 0;
+                self.next_reconnect_at_ms = None;
                 self.offline_since_ms = None;
                 self.safety_fail_count = 0;
                 RuntimeAction::RaiseUiState {
                     message: "connected",
                 }
             }
-            MessageKind::StatusDelta | MessageKind::StatusSnapshot => {
+            MessageKind::StatusSnapshot => {
                 if let Some(status) = msg.as_status_snapshot() {
                     self.apply_status_snapshot(status);
                 }
-                if msg.kind == MessageKind::StatusSnapshot {
-                    self.pending_reconciliation = false;
-                }
+                self.pending_reconciliation = false;
                 self.offline_since_ms = None;
+                self.delta_order.insert(
+                    msg.envelope.source.clone(),
+                    DeltaOrderState {
+                        expected_seq: msg.envelope.seq.saturating_add(1),
+                        buffer: VecDeque::new(),
+                        gap_steps: 0,
+                    },
+                );
                 RuntimeAction::RaiseUiState {
                     message: "status_updated",
                 }
             }
+            MessageKind::StatusDelta => {
+                let Some(status) = msg.as_status_snapshot() else {
+                    return RuntimeAction::RaiseUiState {
+                        message: "status_updated",
+                    };
+                };
+                self.offline_since_ms = None;
+                self.apply_status_delta(msg.envelope.source.as_str(), msg.envelope.seq, status)
+            }
             MessageKind::Command | MessageKind::HostCommand => match msg.as_device_command() {
-                Some(command) => match command.action {
-                    DeviceAction::Reconnect => {
-                        self.mode = RuntimeMode::Offline;
-                        RuntimeAction::RaiseUiState {
-                            message: "command_reconnect",
-                        }
+                Some(command)
+                    if !self
+                        .action_policy
+                        .is_allowed(msg.envelope.source.as_str(), &command.action) =>
+                {
+                    self.bump_safety_fail_count();
+                    RuntimeAction::RaiseUiState {
+                        message: "command_denied_insufficient_privilege",
                     }
-                    DeviceAction::Retry => {
-                        self.mode = RuntimeMode::Booting;
-                        RuntimeAction::RaiseUiState {
-                            message: "command_retry",
+                }
+                Some(command) => {
+                    let action = command.action.clone();
+                    let message = match action {
+                        DeviceAction::Reconnect => {
+                            self.set_mode(RuntimeMode::Offline);
+                            "command_reconnect"
                         }
-                    }
-                    DeviceAction::Restart => {
-                        self.mode = RuntimeMode::Booting;
-                        RuntimeAction::RaiseUiState {
-                            message: "command_restart",
+                        DeviceAction::Retry => {
+                            self.set_mode(RuntimeMode::Booting);
+                            "command_retry"
                         }
-                    }
-                    DeviceAction::OtaStart => {
-                        self.ota_target_version = command
-                            .args
-                            .get("version")
-                            .and_then(|value| value.as_str())
-                            .map(|value| value.to_owned());
-                        self.ota_error_reason = None;
-                        self.ota_in_progress = true;
-                        RuntimeAction::RaiseUiState {
-                            message: "command_ota_start",
+                        DeviceAction::Restart => {
+                            self.set_mode(RuntimeMode::Booting);
+                            "command_restart"
+                        }
+                        DeviceAction::OtaStart => {
+                            self.ota_target_version = command
+                                .args
+                                .get("version")
+                                .and_then(|value| value.as_str())
+                                .map(|value| value.to_owned());
+                            self.ota_error_reason = None;
+                            self.ota_in_progress = true;
+                            self.emit_event(RuntimeEvent::OtaStateChanged { in_progress: true });
+                            "command_ota_start"
+                        }
+                        DeviceAction::DiagnosticsSnapshot => "command_diagnostics",
+                        _ => "command_received",
+                    };
+
+                    let packet_id = msg.payload.get("packet_id").and_then(Value::as_u64);
+                    match packet_id {
+                        Some(packet_id) if self.manual_ack_actions.contains(&action) => {
+                            self.manual_acks.hold(PendingManualAck {
+                                packet_id,
+                                action,
+                                args: command.args,
+                                source: msg.envelope.source.clone(),
+                            });
+                            self.persist_manual_acks();
+                            RuntimeAction::RaiseUiState { message }
                         }
+                        Some(packet_id) => RuntimeAction::EmitCommandAck { packet_id, message },
+                        None => RuntimeAction::RaiseUiState { message },
                     }
-                    DeviceAction::DiagnosticsSnapshot => RuntimeAction::RaiseUiState {
-                        message: "command_diagnostics",
-                    },
-                    _ => RuntimeAction::RaiseUiState {
-                        message: "command_received",
-                    },
-                },
+                }
                 None => RuntimeAction::RaiseUiState {
                     message: "command_parse_error",
                 },
@@ -335,6 +1022,9 @@ impl RuntimeState {
             MessageKind::CommandAck => {
                 if let Some(corr_id) = msg.corr_id.as_ref() {
                     self.in_flight.remove(corr_id);
+                    self.emit_event(RuntimeEvent::CommandAcked {
+                        corr_id: corr_id.clone(),
+                    });
                     RuntimeAction::EmitAck {
                         corr_id: corr_id.clone(),
                         status: "command_ack",
@@ -378,17 +1068,90 @@ impl RuntimeState {
                     message: "command_result",
                 }
             }
+            MessageKind::InjectInput => match msg.as_input_injection() {
+                Some(gesture) => self.apply_injected_input(gesture),
+                None => RuntimeAction::RaiseUiState {
+                    message: "inject_input_parse_error",
+                },
+            },
             MessageKind::Error => RuntimeAction::RaiseUiState {
                 message: "host_error",
             },
             MessageKind::Heartbeat => {
-                self.mode = RuntimeMode::Connected;
+                self.set_mode(RuntimeMode::Connected);
                 RuntimeAction::None
             }
             _ => RuntimeAction::None,
         }
     }
 
+    /// Applies a host-injected gesture, going through the same
+    /// `process_touch`/`SwipeDetector` machinery a physical touch would so
+    /// integration tests and remote operators get identical outcomes
+    /// without touch hardware.
+    fn apply_injected_input(&mut self, gesture: InputInjectionPayload) -> RuntimeAction {
+        match gesture {
+            InputInjectionPayload::Tap { x, y } => {
+                match crate::display::clamp_and_validate_touch(x, y) {
+                    Some(point) => self.process_touch(point),
+                    None => RuntimeAction::RaiseUiState {
+                        message: "inject_input_out_of_bounds",
+                    },
+                }
+            }
+            InputInjectionPayload::LongPress { x, y } => {
+                match crate::display::clamp_and_validate_touch(x, y) {
+                    Some(point) => self.process_touch(point),
+                    None => RuntimeAction::RaiseUiState {
+                        message: "inject_input_out_of_bounds",
+                    },
+                }
+            }
+            InputInjectionPayload::Swipe { direction } => {
+                let mut detector = crate::pipeline::SwipeDetector::new();
+                detector.on_down(200, 200);
+                let outcome = match direction {
+                    SwipeDirectionWire::Right => detector.on_up(260, 200),
+                    SwipeDirectionWire::Left => detector.on_up(140, 200),
+                };
+                match outcome {
+                    Some(crate::pipeline::SwipeDirection::Right) => RuntimeAction::RaiseUiState {
+                        message: "inject_input_swipe_right",
+                    },
+                    Some(crate::pipeline::SwipeDirection::Left) => RuntimeAction::RaiseUiState {
+                        message: "inject_input_swipe_left",
+                    },
+                    None => RuntimeAction::RaiseUiState {
+                        message: "inject_input_swipe_rejected",
+                    },
+                }
+            }
+        }
+    }
+
+    /// Signs `(envelope, kind, payload)` with the configured `signer`, if
+    /// any, returning the `(signature, nonce)` pair to stamp on an outbound
+    /// frame. `nonce` is the envelope's own `seq` hex-encoded, so a host's
+    /// [`crate::crypto::ReplayFilter`] can anti-replay it the same way
+    /// `apply_transport_message` anti-replays inbound `seq` values. Frames
+    /// stay unsigned (both `None`) while no signer is configured, preserving
+    /// the prior plaintext behavior.
+    fn sign_outbound(
+        &self,
+        envelope: &Envelope,
+        kind: &MessageKind,
+        payload: &Value,
+    ) -> (Option<String>, Option<String>) {
+        match &self.signer {
+            Some(signer) => {
+                let canonical = crate::crypto::canonical_bytes(envelope, kind, payload);
+                let signature = signer.sign(envelope.source.as_str(), &canonical);
+                (Some(signature), Some(format!("{:x}", envelope.seq)))
+            }
+            None => (None, None),
+        }
+    }
+
     pub fn emit_command(&mut self, action: DeviceAction) -> TransportMessage {
         let seq = self.outbound_seq.saturating_add(1);
         self.outbound_seq = seq;
@@ -407,21 +1170,24 @@ impl RuntimeState {
             InFlightCommand {
                 corr_id: corr_id.clone(),
                 action: action.clone(),
-                enqueued_at_ms: now_ms(),
+                enqueued_at_ms: self.clock.now_ms(),
             },
         );
 
+        let payload = json!({
+            "action": action,
+        });
+        let (signature, nonce) = self.sign_outbound(&envelope, &MessageKind::Command, &payload);
+
         TransportMessage {
             envelope,
             kind: MessageKind::Command,
             corr_id: Some(corr_id),
             ttl_ms: None,
-            issued_at: Some(now_ms()),
-            signature: None,
-            nonce: None,
-            payload: json!({
-                "action": action,
-            }),
+            issued_at: Some(self.clock.now_ms()),
+            signature,
+            nonce,
+            payload,
         }
     }
 
@@ -438,15 +1204,22 @@ impl RuntimeState {
             message_id,
         };
         self.pending_reconciliation = true;
+        let quality = self.link_quality(self.clock.now_ms(), DEFAULT_HEARTBEAT_TIMEOUT_MS);
+        let payload = json!({
+            "reason": "transport_reconnect",
+            "link_quality": format!("{quality:?}"),
+        });
+        let (signature, nonce) =
+            self.sign_outbound(&envelope, &MessageKind::SnapshotRequest, &payload);
         TransportMessage {
             envelope,
             kind: MessageKind::SnapshotRequest,
             corr_id: None,
             ttl_ms: None,
-            issued_at: Some(now_ms()),
-            signature: None,
-            nonce: None,
-            payload: json!({"reason": "transport_reconnect"}),
+            issued_at: Some(self.clock.now_ms()),
+            signature,
+            nonce,
+            payload,
         }
     }
 
@@ -456,14 +1229,14 @@ impl RuntimeState {
 
     pub fn mark_offline_with_reason(&mut self, reason: impl Into<String>, now_ms: u64) {
         if !matches!(self.mode, RuntimeMode::Offline) {
-            self.mode = RuntimeMode::Offline;
+            self.set_mode(RuntimeMode::Offline);
             self.offline_since_ms = Some(now_ms);
             self.push_diagnostic(reason.into());
         }
     }
 
     pub fn mark_error_with_reason(&mut self, reason: impl Into<String>) {
-        self.mode = RuntimeMode::Error(reason.into());
+        self.set_mode(RuntimeMode::Error(reason.into()));
     }
 
     pub fn mark_offline_if_stale(&mut self, now_ms: u64, heartbeat_timeout_ms: u64) -> bool {
@@ -472,6 +1245,7 @@ impl RuntimeState {
         }
         let last_seen = self.last_heartbeat_ms.unwrap_or_else(|| now_ms);
         if now_ms.saturating_sub(last_seen) > heartbeat_timeout_ms {
+            self.emit_event(RuntimeEvent::HeartbeatStale);
             self.mark_offline_with_reason("heartbeat_stale", now_ms);
             true
         } else {
@@ -484,8 +1258,9 @@ impl RuntimeState {
             return false;
         }
         if self.safety_fail_count >= self.safety_fail_limit {
-            self.mode =
-                RuntimeMode::SafeMode("safety_retries_exhausted_entering_safe_mode".to_owned());
+            self.set_mode(RuntimeMode::SafeMode(
+                "safety_retries_exhausted_entering_safe_mode".to_owned(),
+            ));
             true
         } else {
             false
@@ -500,9 +1275,10 @@ impl RuntimeState {
     pub fn mark_ota_complete(&mut self, success: bool, reason: Option<String>) -> RuntimeAction {
         self.ota_in_progress = false;
         self.ota_error_reason = reason.clone();
+        self.emit_event(RuntimeEvent::OtaStateChanged { in_progress: false });
         if success {
             self.last_status.ota_state = Some("active".to_owned());
-            self.mode = RuntimeMode::Connected;
+            self.set_mode(RuntimeMode::Connected);
             RuntimeAction::RaiseUiState {
                 message: "ota_complete",
             }
@@ -516,41 +1292,119 @@ impl RuntimeState {
 
     pub fn mark_boot_success(&mut self) {
         self.clear_boot_failure_count();
-        self.mode = RuntimeMode::Connected;
+        self.set_mode(RuntimeMode::Connected);
         self.last_status.mode = Some("connected".to_owned());
     }
 
     fn apply_status_snapshot(&mut self, status: DeviceStatus) {
         self.last_status = status.clone();
+        self.emit_event(RuntimeEvent::StatusUpdated);
         if !status.wifi_ok {
-            self.mark_offline_with_reason("status_wifi_not_ok", now_ms());
+            self.mark_offline_with_reason("status_wifi_not_ok", self.clock.now_ms());
             return;
         }
 
         if let Some(mode) = status.mode.as_deref() {
             match mode {
-                "boot" => self.mode = RuntimeMode::Booting,
-                "connected" | "paired" | "ready" => self.mode = RuntimeMode::Connected,
-                "offline" => self.mode = RuntimeMode::Offline,
+                "boot" => self.set_mode(RuntimeMode::Booting),
+                "connected" | "paired" | "ready" => self.set_mode(RuntimeMode::Connected),
+                "offline" => self.set_mode(RuntimeMode::Offline),
                 "safe_mode" => {
-                    self.mode = RuntimeMode::SafeMode("host_reported_safe_mode".to_owned())
+                    self.set_mode(RuntimeMode::SafeMode("host_reported_safe_mode".to_owned()))
                 }
-                "error" => self.mode = RuntimeMode::Error("host_reported_error".to_owned()),
+                "error" => self.set_mode(RuntimeMode::Error("host_reported_error".to_owned())),
                 _ => {}
             }
         }
     }
 
+    /// Configures how many further `StatusDelta` frames may arrive from a
+    /// sender with a gap still open before resyncing via a full snapshot.
+    /// Lower values notice loss sooner at the cost of resyncing on mere
+    /// reordering; higher values tolerate reordering but let a sender drift
+    /// longer before the gap is detected.
+    pub fn set_delta_gap_step_limit(&mut self, step_limit: u32) {
+        self.delta_gap_step_limit = step_limit.max(1);
+    }
+
+    /// Applies an in-order `StatusDelta` immediately, buffers one that
+    /// arrives ahead of the expected `seq` in a small per-sender reorder
+    /// queue, and once buffered deltas become contiguous with what's already
+    /// applied, flushes them in sequence. If the gap isn't filled within
+    /// `delta_gap_step_limit` further deltas from the same sender, gives up
+    /// on reordering and requests a full resync instead.
+    fn apply_status_delta(
+        &mut self,
+        source: &str,
+        seq: u64,
+        status: DeviceStatus,
+    ) -> RuntimeAction {
+        let mut state = self.delta_order.remove(source).unwrap_or(DeltaOrderState {
+            expected_seq: seq,
+            buffer: VecDeque::new(),
+            gap_steps: 0,
+        });
+
+        let action = if seq < state.expected_seq {
+            RuntimeAction::RaiseUiState {
+                message: "stale_delta_ignored",
+            }
+        } else if seq > state.expected_seq {
+            if state.buffer.len() >= DELTA_REORDER_BUFFER_CAPACITY {
+                state.buffer.pop_back();
+            }
+            let insert_at = state
+                .buffer
+                .iter()
+                .position(|(buffered_seq, _)| *buffered_seq >= seq)
+                .unwrap_or(state.buffer.len());
+            if state.buffer.get(insert_at).map(|(s, _)| *s) != Some(seq) {
+                state.buffer.insert(insert_at, (seq, status));
+            }
+            state.gap_steps = state.gap_steps.saturating_add(1);
+
+            if state.gap_steps > self.delta_gap_step_limit {
+                state.buffer.clear();
+                state.gap_steps = 0;
+                RuntimeAction::EmitSnapshotRequest {
+                    reason: "delta_gap_detected",
+                }
+            } else {
+                RuntimeAction::RaiseUiState {
+                    message: "delta_gap_buffered",
+                }
+            }
+        } else {
+            self.apply_status_snapshot(status);
+            state.expected_seq = state.expected_seq.saturating_add(1);
+            while let Some((buffered_seq, _)) = state.buffer.front() {
+                if *buffered_seq != state.expected_seq {
+                    break;
+                }
+                let (_, buffered_status) = state.buffer.pop_front().expect("front checked above");
+                self.apply_status_snapshot(buffered_status);
+                state.expected_seq = state.expected_seq.saturating_add(1);
+            }
+            state.gap_steps = 0;
+            RuntimeAction::RaiseUiState {
+                message: "status_updated",
+            }
+        };
+
+        self.delta_order.insert(source.to_owned(), state);
+        action
+    }
+
     pub fn mark_boot_failure(&mut self, now_ms: u64, reason: impl Into<String>) {
         self.boot_failure_count = self.boot_failure_count.saturating_add(1);
         self.persist_boot_failure_count();
         self.push_diagnostic(reason.into());
         if self.boot_failure_count >= self.boot_retry_limit {
-            self.mode = RuntimeMode::SafeMode("boot_failures_exceeded".to_owned());
+            self.set_mode(RuntimeMode::SafeMode("boot_failures_exceeded".to_owned()));
             self.offline_since_ms = Some(now_ms);
             self.push_diagnostic("boot_failure_detected".to_owned());
         } else {
-            self.mode = RuntimeMode::Error("boot_retry".to_owned());
+            self.set_mode(RuntimeMode::Error("boot_retry".to_owned()));
             self.mark_offline_with_reason("boot_failure_detected", now_ms);
         }
     }
@@ -566,6 +1420,63 @@ impl RuntimeState {
         }
     }
 
+    fn persist_manual_acks(&mut self) {
+        if let Some(storage) = self.storage.as_mut() {
+            storage.set_bytes(storage::keys::PENDING_MANUAL_ACKS, &self.manual_acks.to_bytes());
+        }
+    }
+
+    /// Releases a manual-ack command the caller has finished handling,
+    /// returning the `CommandAck` frame to send to the host. `None` if
+    /// `token` isn't (or is no longer) pending -- e.g. it was already
+    /// released, or never held because its action wasn't in the manual-ack
+    /// set.
+    pub fn ack(&mut self, token: AckToken) -> Option<TransportMessage> {
+        let pending = self.manual_acks.release(token)?;
+        self.persist_manual_acks();
+        Some(self.build_command_ack(pending.packet_id))
+    }
+
+    /// Number of inbound commands withheld from auto-ack, awaiting an
+    /// explicit `ack(token)` call. Exposed for diagnostics.
+    pub fn pending_manual_ack_count(&self) -> usize {
+        self.manual_acks.len()
+    }
+
+    /// Commands still withheld from auto-ack, e.g. to redeliver after a
+    /// restart recovers them from storage via `with_storage`.
+    pub fn pending_manual_acks(&self) -> impl Iterator<Item = &PendingManualAck> {
+        self.manual_acks.pending()
+    }
+
+    pub(crate) fn build_command_ack(&mut self, packet_id: u64) -> TransportMessage {
+        let seq = self.outbound_seq.saturating_add(1);
+        self.outbound_seq = seq;
+        let envelope = Envelope {
+            v: 1,
+            seq,
+            source: self.device_id.clone(),
+            device_id: self.device_id.clone(),
+            session_id: "boot".to_owned(),
+            message_id: MessageId::new(format!("ack-{seq}")),
+        };
+        let payload = json!({
+            "packet_id": packet_id,
+            "phase": "complete",
+        });
+        let (signature, nonce) = self.sign_outbound(&envelope, &MessageKind::CommandAck, &payload);
+        TransportMessage {
+            envelope,
+            kind: MessageKind::CommandAck,
+            corr_id: None,
+            ttl_ms: None,
+            issued_at: Some(self.clock.now_ms()),
+            signature,
+            nonce,
+            payload,
+        }
+    }
+
     pub fn reclaim_stale_inflight(&mut self, now_ms: u64, max_ms: u64) -> usize {
         let before = self.in_flight.len();
         let stale = self
@@ -581,19 +1492,19 @@ impl RuntimeState {
             .collect::<Vec<_>>();
         for id in stale {
             self.in_flight.remove(&id);
-            self.safety_fail_count = self.safety_fail_count.saturating_add(1);
+            self.bump_safety_fail_count();
         }
         before.saturating_sub(self.in_flight.len())
     }
 
     fn note_heartbeat(&mut self, issued_at: Option<u64>) {
-        self.last_heartbeat_ms = Some(issued_at.unwrap_or_else(now_ms));
+        self.last_heartbeat_ms = Some(issued_at.unwrap_or_else(|| self.clock.now_ms()));
     }
 
-    fn is_duplicate_or_stale(&self, seq: u64, message_id: &MessageId) -> bool {
-        if seq <= self.last_seq {
-            return true;
-        }
+    /// Catches a resent `message_id` even when its `seq` would otherwise
+    /// pass the per-sender sliding-window check (e.g. a forged/bumped `seq`
+    /// on a captured frame).
+    fn is_duplicate_message_id(&self, message_id: &MessageId) -> bool {
         self.seen_message_ids.get(message_id.as_str()).is_some()
     }
 
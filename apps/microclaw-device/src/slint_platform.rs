@@ -37,7 +37,8 @@ impl Platform for MicroClawPlatform {
     }
 }
 
-/// Render the Slint scene into the framebuffer and flush to display.
+/// Render the Slint scene into the framebuffer and flush only the region
+/// Slint's software renderer reports as dirty to the display.
 ///
 /// Returns `true` if pixels were actually drawn (Slint had pending changes).
 pub fn render_to_display(
@@ -46,28 +47,53 @@ pub fn render_to_display(
     framebuffer: &mut [Rgb565Pixel],
 ) -> bool {
     let width = display.width();
+    let height = display.height();
     let stride = usize::from(width);
 
     window.draw_if_needed(|renderer| {
-        renderer.render(framebuffer, stride);
-        let _ = display.flush_region(
-            Rect {
-                x: 0,
-                y: 0,
-                w: width,
-                h: display.height(),
-            },
-            // SAFETY: Rgb565Pixel is repr(transparent) around u16
-            unsafe {
-                core::slice::from_raw_parts(
-                    framebuffer.as_ptr() as *const u16,
-                    framebuffer.len(),
-                )
-            },
-        );
+        let dirty = renderer.render(framebuffer, stride);
+        let origin = dirty.bounding_box_origin();
+        let size = dirty.bounding_box_size();
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let region = Rect {
+            x: origin.x.max(0) as u16,
+            y: origin.y.max(0) as u16,
+            w: (size.width as u16).min(width.saturating_sub(origin.x.max(0) as u16)),
+            h: (size.height as u16).min(height.saturating_sub(origin.y.max(0) as u16)),
+        };
+        flush_dirty_rows(display, framebuffer, stride, region);
     })
 }
 
+/// Flushes just the rows covered by `region`, copying each row out of the
+/// full framebuffer so `flush_region` only sees the changed pixels — this is
+/// what cuts SPI/bus traffic for small UI updates like a spinner tick.
+fn flush_dirty_rows(
+    display: &mut dyn DisplayDriver,
+    framebuffer: &[Rgb565Pixel],
+    stride: usize,
+    region: Rect,
+) {
+    // SAFETY: Rgb565Pixel is repr(transparent) around u16.
+    let pixels: &[u16] =
+        unsafe { core::slice::from_raw_parts(framebuffer.as_ptr() as *const u16, framebuffer.len()) };
+
+    let mut scratch = vec![0u16; usize::from(region.w) * usize::from(region.h)];
+    for row in 0..usize::from(region.h) {
+        let src_start = (usize::from(region.y) + row) * stride + usize::from(region.x);
+        let src_end = src_start + usize::from(region.w);
+        let dst_start = row * usize::from(region.w);
+        if src_end <= pixels.len() {
+            scratch[dst_start..dst_start + usize::from(region.w)]
+                .copy_from_slice(&pixels[src_start..src_end]);
+        }
+    }
+    let _ = display.flush_region(region, &scratch);
+}
+
 /// Dispatch a touch event to the Slint window.
 pub fn dispatch_touch(
     window: &MinimalSoftwareWindow,
@@ -64,6 +64,83 @@ impl DeviceStorage for InMemoryStorage {
     }
 }
 
+/// `DeviceStorage` backed by the esp-idf-svc encrypted NVS partition, so
+/// secrets like `keys::WIFI_PASSWORD` survive a reboot without sitting in
+/// plaintext flash the way they would on a stolen device's dump.
+///
+/// NVS entries are strongly typed, so each typed accessor gets its own
+/// key namespace (`u32:`, `str:`, `blob:`) to avoid colliding when the same
+/// logical key is used with more than one accessor.
+#[cfg(feature = "esp")]
+pub struct EspNvsStorage {
+    nvs: esp_idf_svc::nvs::EspNvs<esp_idf_svc::nvs::NvsDefault>,
+}
+
+#[cfg(feature = "esp")]
+impl EspNvsStorage {
+    pub fn new(
+        partition: esp_idf_svc::nvs::EspNvsPartition<esp_idf_svc::nvs::NvsDefault>,
+        namespace: &str,
+    ) -> Result<Self, esp_idf_svc::sys::EspError> {
+        let nvs = esp_idf_svc::nvs::EspNvs::new(partition, namespace, true)?;
+        Ok(Self { nvs })
+    }
+
+    fn u32_key(key: &str) -> String {
+        format!("u32:{key}")
+    }
+
+    fn string_key(key: &str) -> String {
+        format!("str:{key}")
+    }
+
+    fn bytes_key(key: &str) -> String {
+        format!("blob:{key}")
+    }
+}
+
+#[cfg(feature = "esp")]
+impl DeviceStorage for EspNvsStorage {
+    fn get_u32(&self, key: &str) -> Option<u32> {
+        self.nvs.get_u32(&Self::u32_key(key)).ok().flatten()
+    }
+
+    fn set_u32(&mut self, key: &str, value: u32) {
+        let _ = self.nvs.set_u32(&Self::u32_key(key), value);
+    }
+
+    fn get_string(&self, key: &str) -> Option<String> {
+        let nvs_key = Self::string_key(key);
+        let len = self.nvs.str_len(&nvs_key).ok().flatten()?;
+        let mut buf = vec![0u8; len];
+        self.nvs.get_str(&nvs_key, &mut buf).ok().flatten()?;
+        let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+        String::from_utf8(buf[..end].to_vec()).ok()
+    }
+
+    fn set_string(&mut self, key: &str, value: &str) {
+        let _ = self.nvs.set_str(&Self::string_key(key), value);
+    }
+
+    fn get_bytes(&self, key: &str) -> Option<Vec<u8>> {
+        let nvs_key = Self::bytes_key(key);
+        let len = self.nvs.blob_len(&nvs_key).ok().flatten()?;
+        let mut buf = vec![0u8; len];
+        self.nvs.get_blob(&nvs_key, &mut buf).ok().flatten()?;
+        Some(buf)
+    }
+
+    fn set_bytes(&mut self, key: &str, value: &[u8]) {
+        let _ = self.nvs.set_blob(&Self::bytes_key(key), value);
+    }
+
+    fn remove(&mut self, key: &str) {
+        let _ = self.nvs.remove(&Self::u32_key(key));
+        let _ = self.nvs.remove(&Self::string_key(key));
+        let _ = self.nvs.remove(&Self::bytes_key(key));
+    }
+}
+
 pub mod keys {
     pub const BOOT_FAILURE_COUNT: &str = "boot_failure_count";
     pub const BOOT_SUCCESS: &str = "boot_success";
@@ -72,4 +149,5 @@ pub mod keys {
     pub const WIFI_PASSWORD: &str = "wifi_password";
     pub const HOST_URL: &str = "host_url";
     pub const HOST_ALLOWLIST: &str = "host_allowlist";
+    pub const PENDING_MANUAL_ACKS: &str = "pending_manual_acks";
 }
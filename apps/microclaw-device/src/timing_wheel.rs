@@ -0,0 +1,137 @@
+//! Hierarchical timing wheel for device deadlines. Nothing in `RuntimeState`
+//! or `DeviceEventLoop` schedules on it: the `mark_offline_if_stale` /
+//! `reclaim_stale_inflight` staleness sweeps still re-scan `RuntimeState`
+//! with an explicit timestamp.
+//!
+//! Modeled on mio-extras' timer: a hashed wheel of `NEAR_SLOTS` buckets of
+//! `NEAR_RESOLUTION_MS` (16 ms) each handles near-term deadlines, and entries
+//! that don't fit in the near wheel cascade down from a coarser second-level
+//! wheel once they become close enough. `advance(now_ms)` steps both wheels
+//! and returns the tokens whose deadline has arrived.
+
+const NEAR_SLOTS: usize = 256;
+const NEAR_RESOLUTION_MS: u64 = 16;
+const COARSE_SLOTS: usize = 64;
+const COARSE_RESOLUTION_MS: u64 = NEAR_RESOLUTION_MS * NEAR_SLOTS as u64;
+
+/// What a fired deadline should cause `RuntimeState` to do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimerToken {
+    MarkOffline,
+    ReclaimInflight(String),
+    ExpireMessage(String),
+}
+
+struct Entry {
+    deadline_tick: u64,
+    token: TimerToken,
+}
+
+/// A hashed timing wheel with a coarse second-level wheel for long delays.
+pub struct TimingWheel {
+    current_tick: u64,
+    near: Vec<Vec<Entry>>,
+    coarse: Vec<Vec<Entry>>,
+}
+
+impl TimingWheel {
+    pub fn new() -> Self {
+        Self {
+            current_tick: 0,
+            near: (0..NEAR_SLOTS).map(|_| Vec::new()).collect(),
+            coarse: (0..COARSE_SLOTS).map(|_| Vec::new()).collect(),
+        }
+    }
+
+    /// Schedules `token` to fire `delay_ms` after the current tick.
+    pub fn schedule(&mut self, delay_ms: u64, token: TimerToken) {
+        let delay_ticks = (delay_ms / NEAR_RESOLUTION_MS).max(1);
+        let deadline_tick = self.current_tick + delay_ticks;
+        let entry = Entry {
+            deadline_tick,
+            token,
+        };
+
+        if delay_ticks < NEAR_SLOTS as u64 {
+            let slot = (deadline_tick as usize) % NEAR_SLOTS;
+            self.near[slot].push(entry);
+        } else {
+            // `advance` drains coarse slot `r - 1` when it enters near-wheel
+            // rotation `r`, so file the entry under the slot for the rotation
+            // its deadline falls in.
+            let slot = ((deadline_tick / NEAR_SLOTS as u64) as usize - 1) % COARSE_SLOTS;
+            self.coarse[slot].push(entry);
+        }
+    }
+
+    /// Advances the wheel to `now_ms` (interpreted relative to the tick
+    /// resolution), firing every entry whose deadline has been reached and
+    /// cascading coarse-wheel entries down into the near wheel as their
+    /// slot is crossed.
+    pub fn advance(&mut self, now_ms: u64) -> Vec<TimerToken> {
+        let target_tick = now_ms / NEAR_RESOLUTION_MS;
+        let mut fired = Vec::new();
+
+        while self.current_tick < target_tick {
+            self.current_tick += 1;
+
+            if self.current_tick % NEAR_SLOTS as u64 == 0 {
+                let coarse_slot = ((self.current_tick / NEAR_SLOTS as u64) as usize - 1)
+                    % COARSE_SLOTS;
+                for entry in self.coarse[coarse_slot].drain(..) {
+                    if entry.deadline_tick <= self.current_tick {
+                        fired.push(entry.token);
+                    } else {
+                        let slot = (entry.deadline_tick as usize) % NEAR_SLOTS;
+                        self.near[slot].push(entry);
+                    }
+                }
+            }
+
+            let slot = (self.current_tick as usize) % NEAR_SLOTS;
+            for entry in self.near[slot].drain(..) {
+                fired.push(entry.token);
+            }
+        }
+
+        fired
+    }
+}
+
+impl Default for TimingWheel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule_fires_after_delay_not_before() {
+        let mut wheel = TimingWheel::new();
+        wheel.schedule(100, TimerToken::MarkOffline);
+        assert!(wheel.advance(50).is_empty());
+        let fired = wheel.advance(150);
+        assert_eq!(fired, vec![TimerToken::MarkOffline]);
+    }
+
+    #[test]
+    fn long_delay_cascades_from_coarse_wheel() {
+        let mut wheel = TimingWheel::new();
+        wheel.schedule(10_000, TimerToken::ReclaimInflight("corr-1".to_string()));
+        assert!(wheel.advance(5_000).is_empty());
+        let fired = wheel.advance(10_100);
+        assert_eq!(fired, vec![TimerToken::ReclaimInflight("corr-1".to_string())]);
+    }
+
+    #[test]
+    fn multiple_tokens_fire_independently() {
+        let mut wheel = TimingWheel::new();
+        wheel.schedule(50, TimerToken::ExpireMessage("m1".to_string()));
+        wheel.schedule(60, TimerToken::ExpireMessage("m2".to_string()));
+        let fired = wheel.advance(200);
+        assert_eq!(fired.len(), 2);
+    }
+}
@@ -1,5 +1,5 @@
 use std::collections::VecDeque;
-#[cfg(feature = "esp")]
+#[cfg(any(feature = "esp", feature = "mqtt"))]
 use std::sync::mpsc::{self, Receiver, SyncSender};
 #[cfg(feature = "esp")]
 use std::time::Duration as StdDuration;
@@ -9,15 +9,21 @@ use esp_idf_svc::ws::client::{
     EspWebSocketClient, EspWebSocketClientConfig, FrameType, WebSocketEvent, WebSocketEventType,
 };
 use microclaw_protocol::TransportMessage;
-#[cfg(feature = "esp")]
+#[cfg(feature = "mqtt")]
+use microclaw_protocol::MessageKind;
+#[cfg(any(feature = "esp", feature = "mqtt"))]
 use serde_json;
 
+#[cfg(feature = "capture")]
+pub use crate::pcap::{CaptureSink, Direction, PcapWriter, RecordingTap, RingBufferSink};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TransportStats {
     pub inbound_frames: u64,
     pub outbound_frames: u64,
     pub dropped_inbound: u64,
     pub dropped_outbound: u64,
+    pub missed_heartbeats: u64,
 }
 
 impl TransportStats {
@@ -27,6 +33,7 @@ impl TransportStats {
             outbound_frames: 0,
             dropped_inbound: 0,
             dropped_outbound: 0,
+            missed_heartbeats: 0,
         }
     }
 }
@@ -37,17 +44,240 @@ impl Default for TransportStats {
     }
 }
 
+/// Why a dial was refused before a socket was even opened, surfaced through
+/// `BootPhase::Failed(reason)` the same way other boot-time failures are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectGuardError {
+    HostNotAllowlisted(String),
+    CertPinMismatch(String),
+    CertPinningUnsupported(String),
+}
+
+impl ConnectGuardError {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ConnectGuardError::HostNotAllowlisted(_) => "boot_transport_host_not_allowlisted",
+            ConnectGuardError::CertPinMismatch(_) => "boot_transport_cert_pin_mismatch",
+            ConnectGuardError::CertPinningUnsupported(_) => "boot_transport_cert_pinning_unsupported",
+        }
+    }
+}
+
+/// Extracts the host portion of a `wss://host[:port]/path` URL, the same
+/// shape `device_ws_url` produces.
+pub fn host_from_ws_url(url: &str) -> Option<String> {
+    let without_scheme = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let host_and_port = without_scheme
+        .split(['/', '?'])
+        .next()
+        .unwrap_or(without_scheme);
+    let host = host_and_port.split(':').next().unwrap_or(host_and_port);
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_owned())
+    }
+}
+
+/// Rejects `url` unless its host is present in `allowlist` (or `allowlist`
+/// is empty, matching `RuntimeState::is_host_allowed`'s "no allowlist means
+/// unrestricted" default).
+pub fn check_host_allowlist(url: &str, allowlist: &[String]) -> Result<(), ConnectGuardError> {
+    if allowlist.is_empty() {
+        return Ok(());
+    }
+    let Some(host) = host_from_ws_url(url) else {
+        return Err(ConnectGuardError::HostNotAllowlisted(url.to_owned()));
+    };
+    if allowlist.iter().any(|allowed| allowed == &host || allowed == "*") {
+        Ok(())
+    } else {
+        Err(ConnectGuardError::HostNotAllowlisted(host))
+    }
+}
+
+/// Pins trusted server certificates by the SHA-256 of their SubjectPublicKeyInfo,
+/// in the spirit of how a FIDO authenticator transport binds a transaction to
+/// one trusted device rather than trusting any CA-issued cert for the host.
+#[derive(Default)]
+pub struct CertPinStore {
+    pins: std::collections::HashMap<String, String>,
+}
+
+impl CertPinStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `spki_sha256_hex` is the lowercase-hex SHA-256 digest of the
+    /// certificate's DER-encoded SubjectPublicKeyInfo.
+    pub fn add_pin(&mut self, host: impl Into<String>, spki_sha256_hex: impl Into<String>) {
+        self.pins.insert(host.into(), spki_sha256_hex.into());
+    }
+
+    /// Hosts with no configured pin are accepted on certificate alone
+    /// (allowlist enforcement still applies); a pinned host must match.
+    pub fn verify(&self, host: &str, spki_der: &[u8]) -> Result<(), ConnectGuardError> {
+        let Some(expected) = self.pins.get(host) else {
+            return Ok(());
+        };
+        if &spki_sha256_hex(spki_der) == expected {
+            Ok(())
+        } else {
+            Err(ConnectGuardError::CertPinMismatch(host.to_owned()))
+        }
+    }
+
+    pub fn has_pin(&self, host: &str) -> bool {
+        self.pins.contains_key(host)
+    }
+}
+
+/// `WsTransport::connect` has no hook into `EspWebSocketClientConfig`'s TLS
+/// handshake to hand a peer certificate to [`CertPinStore::verify`] -- the
+/// client config exposes no certificate-inspection callback, so there is
+/// nothing for `connect()` to check a pin against. Rather than dial and
+/// silently accept any CA-valid certificate while claiming the host is
+/// pinned, refuse to connect at all when a pin is configured for `host`,
+/// so a misplaced assumption of protection fails loudly instead of
+/// providing none.
+pub fn check_cert_pinning_supported(
+    host: &str,
+    cert_pins: &CertPinStore,
+) -> Result<(), ConnectGuardError> {
+    if cert_pins.has_pin(host) {
+        Err(ConnectGuardError::CertPinningUnsupported(host.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "crypto_rustcrypto")]
+pub fn spki_sha256_hex(spki_der: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    let digest = Sha256::digest(spki_der);
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(not(feature = "crypto_rustcrypto"))]
+pub fn spki_sha256_hex(_spki_der: &[u8]) -> String {
+    String::new()
+}
+
+/// Paces repeated reconnect attempts so a fleet of devices reconnecting to a
+/// server that just came back up doesn't hammer it in lockstep.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReconnectStrategy {
+    /// No pacing: `reconnect` is attempted every time it's called. This is
+    /// the default, matching prior behavior for callers that already pace
+    /// calls themselves (e.g. the event loop's own backoff timer).
+    Off,
+    FixedInterval { interval_ms: u64 },
+    ExponentialBackoff {
+        base_ms: u64,
+        max_ms: u64,
+        max_retries: u32,
+    },
+}
+
+impl ReconnectStrategy {
+    /// Base delay before full jitter is applied, or `None` if no further
+    /// attempt should be scheduled (`Off`, or `max_retries` exhausted).
+    fn delay_ms(&self, attempt: u32) -> Option<u64> {
+        match *self {
+            ReconnectStrategy::Off => None,
+            ReconnectStrategy::FixedInterval { interval_ms } => Some(interval_ms),
+            ReconnectStrategy::ExponentialBackoff {
+                base_ms,
+                max_ms,
+                max_retries,
+            } => {
+                if attempt > max_retries {
+                    return None;
+                }
+                let shift = attempt.min(31);
+                Some(base_ms.saturating_mul(1u64 << shift).min(max_ms))
+            }
+        }
+    }
+}
+
+/// splitmix64, used only to derive a deterministic jitter value from
+/// `(attempt, now_ms)` without pulling in a `rand` dependency for firmware
+/// builds.
+pub(crate) fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Full jitter: uniform in `[0, delay_ms]`.
+pub(crate) fn full_jitter(delay_ms: u64, seed: u64) -> u64 {
+    if delay_ms == 0 {
+        return 0;
+    }
+    splitmix64(seed) % (delay_ms + 1)
+}
+
 pub trait TransportBus {
     fn is_connected(&self) -> bool;
     fn poll_frames(&mut self) -> Vec<TransportMessage>;
     fn send_frame(&mut self, frame: TransportMessage);
     fn transport_stats(&self) -> TransportStats;
     fn set_connected(&mut self, connected: bool);
-    fn reconnect(&mut self, attempt: u32, now_ms: u64) -> bool {
+
+    /// Drains available inbound frames into the caller-owned `out` buffer
+    /// instead of allocating a fresh `Vec` every poll. The default just
+    /// forwards to `poll_frames`, which is fine for buses that already drain
+    /// a plain `VecDeque`; `WsTransport` overrides this to also bound how
+    /// many mpsc events it drains in one call, so a burst can't stall the
+    /// loop parsing an unbounded backlog in a single tick.
+    fn poll_frames_into(&mut self, out: &mut Vec<TransportMessage>) {
+        out.extend(self.poll_frames());
+    }
+
+    fn reconnect_strategy(&self) -> ReconnectStrategy {
+        ReconnectStrategy::Off
+    }
+
+    /// Earliest `now_ms` at which the next reconnect attempt should be made.
+    fn next_retry_ms(&self) -> u64 {
+        0
+    }
+
+    fn set_next_retry_ms(&mut self, when_ms: u64) {
+        let _ = when_ms;
+    }
+
+    /// The transport-specific connect attempt. Implementations override
+    /// this rather than `reconnect` directly so the default pacing logic
+    /// below still applies.
+    fn do_reconnect(&mut self, attempt: u32, now_ms: u64) -> bool {
         let _ = self.is_connected();
         let _ = (attempt, now_ms);
         false
     }
+
+    /// Consults `reconnect_strategy()` to decide whether it's time to try
+    /// again, applying full jitter on top of the strategy's base delay, then
+    /// delegates to `do_reconnect` when the backoff window has elapsed.
+    fn reconnect(&mut self, attempt: u32, now_ms: u64) -> bool {
+        if matches!(self.reconnect_strategy(), ReconnectStrategy::Off) {
+            return self.do_reconnect(attempt, now_ms);
+        }
+        if now_ms < self.next_retry_ms() {
+            return false;
+        }
+        let connected = self.do_reconnect(attempt, now_ms);
+        if connected {
+            self.set_next_retry_ms(0);
+        } else if let Some(delay) = self.reconnect_strategy().delay_ms(attempt) {
+            let jittered = full_jitter(delay, now_ms.wrapping_add(attempt as u64));
+            self.set_next_retry_ms(now_ms.saturating_add(jittered));
+        }
+        connected
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -60,6 +290,10 @@ pub struct InMemoryTransport {
     stats: TransportStats,
     reconnect_attempts: u64,
     reconnect_failures_remaining: u64,
+    reconnect_strategy: ReconnectStrategy,
+    next_retry_ms: u64,
+    #[cfg(feature = "capture")]
+    capture: Option<RecordingTap>,
 }
 
 impl InMemoryTransport {
@@ -73,9 +307,29 @@ impl InMemoryTransport {
             stats: TransportStats::new(),
             reconnect_attempts: 0,
             reconnect_failures_remaining: 0,
+            reconnect_strategy: ReconnectStrategy::Off,
+            next_retry_ms: 0,
+            #[cfg(feature = "capture")]
+            capture: None,
         }
     }
 
+    pub fn set_reconnect_strategy(&mut self, strategy: ReconnectStrategy) {
+        self.reconnect_strategy = strategy;
+    }
+
+    /// Starts (or replaces) the pcapng capture tap for this transport. Pass
+    /// `None` to stop recording.
+    #[cfg(feature = "capture")]
+    pub fn set_capture(&mut self, capture: Option<RecordingTap>) {
+        self.capture = capture;
+    }
+
+    #[cfg(feature = "capture")]
+    pub fn capture_mut(&mut self) -> Option<&mut RecordingTap> {
+        self.capture.as_mut()
+    }
+
     pub fn with_queue_depth(max_inbound: usize, max_outbound: usize) -> Self {
         Self {
             inbound: VecDeque::new(),
@@ -86,6 +340,10 @@ impl InMemoryTransport {
             stats: TransportStats::new(),
             reconnect_attempts: 0,
             reconnect_failures_remaining: 0,
+            reconnect_strategy: ReconnectStrategy::Off,
+            next_retry_ms: 0,
+            #[cfg(feature = "capture")]
+            capture: None,
         }
     }
 
@@ -137,7 +395,19 @@ impl TransportBus for InMemoryTransport {
         self.connected = connected;
     }
 
-    fn reconnect(&mut self, _attempt: u32, _now_ms: u64) -> bool {
+    fn reconnect_strategy(&self) -> ReconnectStrategy {
+        self.reconnect_strategy
+    }
+
+    fn next_retry_ms(&self) -> u64 {
+        self.next_retry_ms
+    }
+
+    fn set_next_retry_ms(&mut self, when_ms: u64) {
+        self.next_retry_ms = when_ms;
+    }
+
+    fn do_reconnect(&mut self, _attempt: u32, _now_ms: u64) -> bool {
         self.reconnect_attempts = self.reconnect_attempts.saturating_add(1);
         if self.reconnect_failures_remaining != 0 {
             self.reconnect_failures_remaining = self.reconnect_failures_remaining.saturating_sub(1);
@@ -151,6 +421,10 @@ impl TransportBus for InMemoryTransport {
         let mut out = Vec::with_capacity(self.inbound.len());
         while let Some(frame) = self.inbound.pop_front() {
             self.stats.inbound_frames = self.stats.inbound_frames.saturating_add(1);
+            #[cfg(feature = "capture")]
+            if let Some(tap) = self.capture.as_mut() {
+                tap.record_inbound(&frame, "polled", crate::now_ms().saturating_mul(1_000));
+            }
             out.push(frame);
         }
         out
@@ -162,6 +436,10 @@ impl TransportBus for InMemoryTransport {
             self.stats.dropped_outbound = self.stats.dropped_outbound.saturating_add(1);
         }
         self.stats.outbound_frames = self.stats.outbound_frames.saturating_add(1);
+        #[cfg(feature = "capture")]
+        if let Some(tap) = self.capture.as_mut() {
+            tap.record_outbound(&frame, crate::now_ms().saturating_mul(1_000));
+        }
         self.outbound.push_back(frame);
     }
 
@@ -170,19 +448,40 @@ impl TransportBus for InMemoryTransport {
     }
 }
 
+/// Default interval between liveness Pings, and how long to wait for any
+/// inbound frame (data or Pong) before declaring the socket dead.
+#[cfg(feature = "esp")]
+const DEFAULT_PING_INTERVAL_MS: u64 = 15_000;
+#[cfg(feature = "esp")]
+const DEFAULT_IDLE_TIMEOUT_MS: u64 = 45_000;
+
+/// Upper bound on how many queued `WsEvent`s a single `poll_frames` call
+/// drains, so a burst of inbound traffic can't stall the event loop parsing
+/// thousands of frames in one tick.
+#[cfg(feature = "esp")]
+const MAX_EVENTS_PER_POLL: usize = 64;
+
+/// Guard against a malicious or buggy peer streaming continuation frames
+/// forever: once a partially-reassembled Text/Binary message exceeds this
+/// size, it's discarded and counted as a dropped inbound frame instead of
+/// growing without bound.
+#[cfg(feature = "esp")]
+const MAX_REASSEMBLED_SIZE: usize = 256 * 1024;
+
+/// A chunk of a Text/Binary message as delivered by the ESP-IDF websocket
+/// client, plus whether it's the terminal fragment of that message. A
+/// server sending one message per frame (the common case) always sets
+/// `is_final: true`; a server that fragments a large payload across
+/// continuation frames sets it only on the last one, and `WsTransport`
+/// concatenates the rest before handing the complete message onward.
 #[cfg(feature = "esp")]
 #[derive(Debug)]
 enum WsEvent {
     Connected,
     Disconnected,
-    TextFrame(String),
-}
-
-#[cfg(feature = "esp")]
-impl WsEvent {
-    fn text(raw: &str) -> Self {
-        Self::TextFrame(raw.to_owned())
-    }
+    TextFragment { payload: String, is_final: bool },
+    BinaryFragment { payload: Vec<u8>, is_final: bool },
+    Pong,
 }
 
 #[cfg(feature = "esp")]
@@ -199,6 +498,21 @@ pub struct WsTransport {
     event_receiver: Receiver<WsEvent>,
     ws: Option<EspWebSocketClient<'static>>,
     timeout: StdDuration,
+    host_allowlist: Vec<String>,
+    cert_pins: CertPinStore,
+    last_connect_error: Option<ConnectGuardError>,
+    last_inbound_ms: u64,
+    last_ping_sent_ms: u64,
+    ping_interval_ms: u64,
+    idle_timeout_ms: u64,
+    reconnect_strategy: ReconnectStrategy,
+    next_retry_ms: u64,
+    #[cfg(feature = "crypto_rustcrypto")]
+    binary_codec_key: Option<crate::crypto::binary_codec::BinaryCodecKey>,
+    #[cfg(feature = "crypto_rustcrypto")]
+    binary_codec_counter: u64,
+    reassembled_text: String,
+    reassembled_binary: Vec<u8>,
 }
 
 #[cfg(feature = "esp")]
@@ -218,6 +532,88 @@ impl WsTransport {
             event_receiver,
             ws: None,
             timeout: StdDuration::from_secs(10),
+            host_allowlist: Vec::new(),
+            cert_pins: CertPinStore::new(),
+            last_connect_error: None,
+            last_inbound_ms: 0,
+            last_ping_sent_ms: 0,
+            ping_interval_ms: DEFAULT_PING_INTERVAL_MS,
+            idle_timeout_ms: DEFAULT_IDLE_TIMEOUT_MS,
+            reconnect_strategy: ReconnectStrategy::Off,
+            next_retry_ms: 0,
+            #[cfg(feature = "crypto_rustcrypto")]
+            binary_codec_key: None,
+            #[cfg(feature = "crypto_rustcrypto")]
+            binary_codec_counter: 0,
+            reassembled_text: String::new(),
+            reassembled_binary: Vec::new(),
+        }
+    }
+
+    /// Switches this transport from plaintext `FrameType::Text` JSON to the
+    /// encrypted `FrameType::Binary` codec once a shared key is available
+    /// (e.g. after a handshake has provisioned one). Pass `None` to go back
+    /// to plaintext.
+    #[cfg(feature = "crypto_rustcrypto")]
+    pub fn set_binary_codec_key(
+        &mut self,
+        key: Option<crate::crypto::binary_codec::BinaryCodecKey>,
+    ) {
+        self.binary_codec_key = key;
+        self.binary_codec_counter = 0;
+    }
+
+    pub fn set_reconnect_strategy(&mut self, strategy: ReconnectStrategy) {
+        self.reconnect_strategy = strategy;
+    }
+
+    pub fn set_host_allowlist(&mut self, allowlist: Vec<String>) {
+        self.host_allowlist = allowlist;
+    }
+
+    /// Configuring a pin for `host` does not yet get the protection its
+    /// name implies: `connect()` has no TLS-handshake hook to check the
+    /// peer certificate against it (see [`check_cert_pinning_supported`]),
+    /// so instead it refuses to dial a pinned host at all, until real
+    /// verification is wired in.
+    pub fn add_cert_pin(&mut self, host: impl Into<String>, spki_sha256_hex: impl Into<String>) {
+        self.cert_pins.add_pin(host, spki_sha256_hex);
+    }
+
+    /// The allowlist-violation or pinned-host (`CertPinningUnsupported`)
+    /// reason from the most recent refused dial, if any, for the caller to
+    /// surface via
+    /// `BootPhase::Failed`.
+    pub fn last_connect_error(&self) -> Option<&ConnectGuardError> {
+        self.last_connect_error.as_ref()
+    }
+
+    pub fn set_heartbeat_intervals(&mut self, ping_interval_ms: u64, idle_timeout_ms: u64) {
+        self.ping_interval_ms = ping_interval_ms;
+        self.idle_timeout_ms = idle_timeout_ms;
+    }
+
+    /// Sends a liveness Ping if `ping_interval_ms` has elapsed since the
+    /// last one, and declares the socket dead (dropping it so the next
+    /// `reconnect` rebuilds it) if `idle_timeout_ms` has elapsed with no
+    /// inbound frame or Pong.
+    fn check_heartbeat(&mut self, now_ms: u64) {
+        if !self.connected || self.ws.is_none() {
+            return;
+        }
+
+        if now_ms.saturating_sub(self.last_inbound_ms) > self.idle_timeout_ms {
+            self.stats.missed_heartbeats = self.stats.missed_heartbeats.saturating_add(1);
+            self.connected = false;
+            self.ws = None;
+            return;
+        }
+
+        if now_ms.saturating_sub(self.last_ping_sent_ms) >= self.ping_interval_ms {
+            if let Some(client) = self.ws.as_mut() {
+                let _ = client.send(FrameType::Ping, &[]);
+            }
+            self.last_ping_sent_ms = now_ms;
         }
     }
 
@@ -262,18 +658,75 @@ impl WsTransport {
         }
     }
 
-    fn sync_events(&mut self) {
-        loop {
+    /// Decrypts and authenticates a `FrameType::Binary` frame produced by
+    /// [`crate::crypto::binary_codec::encode`]. Any MAC mismatch, length
+    /// violation, or oversized payload is counted as a dropped inbound frame
+    /// rather than handed on to `parse_incoming_text` half-decoded.
+    #[cfg(feature = "crypto_rustcrypto")]
+    fn parse_incoming_binary(&mut self, framed: &[u8]) {
+        let Some(key) = self.binary_codec_key.as_ref() else {
+            self.stats.dropped_inbound = self.stats.dropped_inbound.saturating_add(1);
+            return;
+        };
+        match crate::crypto::binary_codec::decode(key, framed) {
+            Ok(plaintext) => match std::str::from_utf8(&plaintext) {
+                Ok(payload) => self.parse_incoming_text(payload),
+                Err(_) => {
+                    self.stats.dropped_inbound = self.stats.dropped_inbound.saturating_add(1);
+                }
+            },
+            Err(_) => {
+                self.stats.dropped_inbound = self.stats.dropped_inbound.saturating_add(1);
+            }
+        }
+    }
+
+    #[cfg(not(feature = "crypto_rustcrypto"))]
+    fn parse_incoming_binary(&mut self, _framed: &[u8]) {
+        self.stats.dropped_inbound = self.stats.dropped_inbound.saturating_add(1);
+    }
+
+    /// Drains at most `max_events` pending `WsEvent`s, so a burst queued up
+    /// by the callback thread can't force a single `poll_frames` call to
+    /// parse an unbounded backlog before returning. Any events left over sit
+    /// in the channel for the next call.
+    fn sync_events_bounded(&mut self, max_events: usize) {
+        for _ in 0..max_events {
             match self.event_receiver.try_recv() {
                 Ok(event) => match event {
                     WsEvent::Connected => {
                         self.connected = true;
+                        self.last_inbound_ms = crate::now_ms();
                     }
                     WsEvent::Disconnected => {
                         self.connected = false;
                     }
-                    WsEvent::TextFrame(payload) => {
-                        self.parse_incoming_text(&payload);
+                    WsEvent::TextFragment { payload, is_final } => {
+                        self.last_inbound_ms = crate::now_ms();
+                        self.reassembled_text.push_str(&payload);
+                        if self.reassembled_text.len() > MAX_REASSEMBLED_SIZE {
+                            self.reassembled_text.clear();
+                            self.stats.dropped_inbound =
+                                self.stats.dropped_inbound.saturating_add(1);
+                        } else if is_final {
+                            let complete = std::mem::take(&mut self.reassembled_text);
+                            self.parse_incoming_text(&complete);
+                        }
+                    }
+                    WsEvent::BinaryFragment { payload, is_final } => {
+                        self.last_inbound_ms = crate::now_ms();
+                        self.reassembled_binary.extend_from_slice(&payload);
+                        if self.reassembled_binary.len() > MAX_REASSEMBLED_SIZE {
+                            self.reassembled_binary.clear();
+                            self.stats.dropped_inbound =
+                                self.stats.dropped_inbound.saturating_add(1);
+                        } else if is_final {
+                            let complete = std::mem::take(&mut self.reassembled_binary);
+                            self.parse_incoming_binary(&complete);
+                        }
+                    }
+                    WsEvent::Pong => {
+                        self.last_inbound_ms = crate::now_ms();
                     }
                 },
                 Err(mpsc::TryRecvError::Empty) => break,
@@ -285,6 +738,10 @@ impl WsTransport {
         }
     }
 
+    fn sync_events(&mut self) {
+        self.sync_events_bounded(MAX_EVENTS_PER_POLL);
+    }
+
     fn send_queued_outbound(&mut self) {
         let Some(client) = self.ws.as_mut() else {
             return;
@@ -294,7 +751,7 @@ impl WsTransport {
             let Some(frame) = self.outbound.front() else {
                 break;
             };
-            let payload = match serde_json::to_vec(frame) {
+            let json = match serde_json::to_vec(frame) {
                 Ok(bytes) => bytes,
                 Err(_) => {
                     self.stats.dropped_outbound = self.stats.dropped_outbound.saturating_add(1);
@@ -303,7 +760,31 @@ impl WsTransport {
                 }
             };
 
-            if client.send(FrameType::Text(false), &payload).is_err() {
+            #[cfg(feature = "crypto_rustcrypto")]
+            let framed = match self.binary_codec_key.as_ref() {
+                Some(key) => {
+                    let counter = self.binary_codec_counter;
+                    self.binary_codec_counter += 1;
+                    match crate::crypto::binary_codec::encode(key, counter, &json) {
+                        Ok(bytes) => Some(bytes),
+                        Err(_) => {
+                            self.stats.dropped_outbound =
+                                self.stats.dropped_outbound.saturating_add(1);
+                            self.outbound.pop_front();
+                            continue;
+                        }
+                    }
+                }
+                None => None,
+            };
+            #[cfg(not(feature = "crypto_rustcrypto"))]
+            let framed: Option<Vec<u8>> = None;
+
+            let send_result = match framed {
+                Some(bytes) => client.send(FrameType::Binary(false), &bytes),
+                None => client.send(FrameType::Text(false), &json),
+            };
+            if send_result.is_err() {
                 self.connected = false;
                 break;
             }
@@ -319,6 +800,18 @@ impl WsTransport {
             return false;
         }
 
+        if let Err(err) = check_host_allowlist(url, &self.host_allowlist) {
+            self.last_connect_error = Some(err);
+            return false;
+        }
+        if let Some(host) = host_from_ws_url(url) {
+            if let Err(err) = check_cert_pinning_supported(&host, &self.cert_pins) {
+                self.last_connect_error = Some(err);
+                return false;
+            }
+        }
+        self.last_connect_error = None;
+
         // If we already have a live connection, just verify it
         if let Some(client) = self.ws.as_ref() {
             if client.is_connected() {
@@ -333,7 +826,7 @@ impl WsTransport {
         let sender = self.event_sender.clone();
 
         let callback = move |event| match event {
-            Ok(WebSocketEvent { event_type, .. }) => match event_type {
+            Ok(WebSocketEvent { event_type, fin, .. }) => match event_type {
                 WebSocketEventType::Connected => {
                     sender.try_send(WsEvent::Connected).ok();
                 }
@@ -341,7 +834,23 @@ impl WsTransport {
                     sender.try_send(WsEvent::Disconnected).ok();
                 }
                 WebSocketEventType::Text(payload) => {
-                    sender.try_send(WsEvent::text(payload)).ok();
+                    sender
+                        .try_send(WsEvent::TextFragment {
+                            payload: payload.to_owned(),
+                            is_final: fin,
+                        })
+                        .ok();
+                }
+                WebSocketEventType::Binary(payload) => {
+                    sender
+                        .try_send(WsEvent::BinaryFragment {
+                            payload: payload.to_vec(),
+                            is_final: fin,
+                        })
+                        .ok();
+                }
+                WebSocketEventType::Pong(_) => {
+                    sender.try_send(WsEvent::Pong).ok();
                 }
                 _ => {}
             },
@@ -357,9 +866,9 @@ impl WsTransport {
         };
         self.connected = ws.is_connected();
         self.ws = Some(ws);
-        if !self.connected {
-            // keep handle so we can continue to process the async connect path
-            // without rebuilding a new socket every loop tick.
+        if self.connected {
+            self.last_inbound_ms = crate::now_ms();
+            self.last_ping_sent_ms = self.last_inbound_ms;
         }
         self.connected
     }
@@ -386,20 +895,38 @@ impl TransportBus for WsTransport {
         }
     }
 
-    fn reconnect(&mut self, attempt: u32, _now_ms: u64) -> bool {
+    fn reconnect_strategy(&self) -> ReconnectStrategy {
+        self.reconnect_strategy
+    }
+
+    fn next_retry_ms(&self) -> u64 {
+        self.next_retry_ms
+    }
+
+    fn set_next_retry_ms(&mut self, when_ms: u64) {
+        self.next_retry_ms = when_ms;
+    }
+
+    fn do_reconnect(&mut self, attempt: u32, _now_ms: u64) -> bool {
         self.connect(attempt)
     }
 
     fn poll_frames(&mut self) -> Vec<TransportMessage> {
+        let mut out = Vec::with_capacity(self.inbound.len());
+        self.poll_frames_into(&mut out);
+        out
+    }
+
+    fn poll_frames_into(&mut self, out: &mut Vec<TransportMessage>) {
         self.sync_events();
+        self.check_heartbeat(crate::now_ms());
         self.send_queued_outbound();
 
-        let mut out = Vec::with_capacity(self.inbound.len());
+        out.reserve(self.inbound.len());
         while let Some(frame) = self.inbound.pop_front() {
             self.stats.inbound_frames = self.stats.inbound_frames.saturating_add(1);
             out.push(frame);
         }
-        out
     }
 
     fn send_frame(&mut self, frame: TransportMessage) {
@@ -416,3 +943,315 @@ impl TransportBus for WsTransport {
         self.stats
     }
 }
+
+/// Config for [`MqttTransport`]: where to dial, how the device is
+/// identified on the broker, and the delivery guarantees to request.
+#[cfg(feature = "mqtt")]
+pub struct MqttTransportConfig {
+    pub broker_host: String,
+    pub broker_port: u16,
+    pub client_id: String,
+    pub topic_prefix: String,
+    pub source: String,
+    pub qos: u8,
+    pub keep_alive_secs: u16,
+}
+
+#[cfg(feature = "mqtt")]
+enum MqttEvent {
+    Connected,
+    Disconnected,
+    Message(Vec<u8>),
+}
+
+#[cfg(feature = "mqtt")]
+fn qos_from_u8(qos: u8) -> rumqttc::QoS {
+    match qos {
+        1 => rumqttc::QoS::AtLeastOnce,
+        _ => rumqttc::QoS::AtMostOnce,
+    }
+}
+
+/// `TransportBus` backed by an MQTT broker instead of a bespoke WebSocket
+/// server: outbound frames publish to `<prefix>/<source>/out`, inbound
+/// frames are delivered via a subscription to `<prefix>/<source>/in`, and a
+/// retained last-will on `<prefix>/<source>/status` makes a dropped device
+/// observable to anyone else watching the broker.
+#[cfg(feature = "mqtt")]
+pub struct MqttTransport {
+    source: String,
+    topic_prefix: String,
+    qos: rumqttc::QoS,
+    connected: bool,
+    max_inbound: usize,
+    max_outbound: usize,
+    inbound: VecDeque<TransportMessage>,
+    outbound: VecDeque<TransportMessage>,
+    stats: TransportStats,
+    client: rumqttc::Client,
+    event_receiver: Receiver<MqttEvent>,
+    reconnect_strategy: ReconnectStrategy,
+    next_retry_ms: u64,
+}
+
+#[cfg(feature = "mqtt")]
+impl MqttTransport {
+    pub fn new(config: MqttTransportConfig) -> Self {
+        let mut options = rumqttc::MqttOptions::new(
+            config.client_id.clone(),
+            config.broker_host.clone(),
+            config.broker_port,
+        );
+        options.set_keep_alive(std::time::Duration::from_secs(config.keep_alive_secs as u64));
+        let status_topic = format!("{}/{}/status", config.topic_prefix, config.source);
+        options.set_last_will(rumqttc::LastWill::new(
+            status_topic,
+            b"offline".to_vec(),
+            qos_from_u8(config.qos),
+            true,
+        ));
+
+        let (client, mut connection) = rumqttc::Client::new(options, 64);
+        let inbound_topic = format!("{}/{}/in", config.topic_prefix, config.source);
+        let _ = client.subscribe(&inbound_topic, qos_from_u8(config.qos));
+
+        let (sender, event_receiver) = mpsc::sync_channel(256);
+        std::thread::spawn(move || {
+            for notification in connection.iter() {
+                match notification {
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::ConnAck(_))) => {
+                        sender.try_send(MqttEvent::Connected).ok();
+                    }
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::Publish(publish))) => {
+                        sender
+                            .try_send(MqttEvent::Message(publish.payload.to_vec()))
+                            .ok();
+                    }
+                    Ok(_) => {}
+                    Err(_) => {
+                        sender.try_send(MqttEvent::Disconnected).ok();
+                    }
+                }
+            }
+        });
+
+        Self {
+            source: config.source,
+            topic_prefix: config.topic_prefix,
+            qos: qos_from_u8(config.qos),
+            connected: false,
+            max_inbound: 128,
+            max_outbound: 128,
+            inbound: VecDeque::new(),
+            outbound: VecDeque::new(),
+            stats: TransportStats::new(),
+            client,
+            event_receiver,
+            reconnect_strategy: ReconnectStrategy::Off,
+            next_retry_ms: 0,
+        }
+    }
+
+    pub fn set_reconnect_strategy(&mut self, strategy: ReconnectStrategy) {
+        self.reconnect_strategy = strategy;
+    }
+
+    fn outbound_topic(&self) -> String {
+        format!("{}/{}/out", self.topic_prefix, self.source)
+    }
+
+    /// `Command`/`HostCommand` frames always publish at-least-once
+    /// regardless of the configured default, so a dropped publish can't
+    /// silently starve `step_with_transport`'s stale-inflight reclaim of the
+    /// broker-level redelivery it relies on; everything else uses the
+    /// configured default.
+    fn publish_qos(&self, kind: &MessageKind) -> rumqttc::QoS {
+        match kind {
+            MessageKind::Command | MessageKind::HostCommand => rumqttc::QoS::AtLeastOnce,
+            _ => self.qos,
+        }
+    }
+
+    fn sync_events(&mut self) {
+        loop {
+            match self.event_receiver.try_recv() {
+                Ok(MqttEvent::Connected) => self.connected = true,
+                Ok(MqttEvent::Disconnected) => self.connected = false,
+                Ok(MqttEvent::Message(payload)) => {
+                    match serde_json::from_slice::<TransportMessage>(&payload) {
+                        Ok(msg) => {
+                            if self.inbound.len() >= self.max_inbound {
+                                self.inbound.pop_front();
+                                self.stats.dropped_inbound =
+                                    self.stats.dropped_inbound.saturating_add(1);
+                            }
+                            self.inbound.push_back(msg);
+                        }
+                        Err(_) => {
+                            self.stats.dropped_inbound =
+                                self.stats.dropped_inbound.saturating_add(1);
+                        }
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mqtt")]
+impl TransportBus for MqttTransport {
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+    }
+
+    fn reconnect_strategy(&self) -> ReconnectStrategy {
+        self.reconnect_strategy
+    }
+
+    fn next_retry_ms(&self) -> u64 {
+        self.next_retry_ms
+    }
+
+    fn set_next_retry_ms(&mut self, when_ms: u64) {
+        self.next_retry_ms = when_ms;
+    }
+
+    fn do_reconnect(&mut self, _attempt: u32, _now_ms: u64) -> bool {
+        self.sync_events();
+        self.connected
+    }
+
+    fn poll_frames(&mut self) -> Vec<TransportMessage> {
+        self.sync_events();
+        let mut out = Vec::with_capacity(self.inbound.len());
+        while let Some(frame) = self.inbound.pop_front() {
+            self.stats.inbound_frames = self.stats.inbound_frames.saturating_add(1);
+            out.push(frame);
+        }
+        out
+    }
+
+    fn send_frame(&mut self, frame: TransportMessage) {
+        let payload = match serde_json::to_vec(&frame) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                self.stats.dropped_outbound = self.stats.dropped_outbound.saturating_add(1);
+                return;
+            }
+        };
+        let topic = self.outbound_topic();
+        let qos = self.publish_qos(&frame.kind);
+        if self.client.publish(topic, qos, false, payload).is_ok() {
+            self.stats.outbound_frames = self.stats.outbound_frames.saturating_add(1);
+        } else {
+            if self.outbound.len() >= self.max_outbound {
+                self.outbound.pop_front();
+            }
+            self.outbound.push_back(frame);
+            self.stats.dropped_outbound = self.stats.dropped_outbound.saturating_add(1);
+        }
+    }
+
+    fn transport_stats(&self) -> TransportStats {
+        self.stats
+    }
+}
+
+/// Owns one [`TransportBus`] per connected peer, keyed by `source`, so a
+/// gateway node can serve many devices instead of assuming a single
+/// point-to-point link. Registering a new bus for an already-known source
+/// atomically drops the stale entry first, so a duplicate reconnect can't
+/// leak the old socket behind a forgotten map entry.
+#[derive(Default)]
+pub struct TransportRouter {
+    buses: std::collections::HashMap<String, Box<dyn TransportBus>>,
+}
+
+impl TransportRouter {
+    pub fn new() -> Self {
+        Self {
+            buses: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Registers `bus` under `source`, replacing (and dropping) whatever bus
+    /// was previously registered for that source.
+    pub fn register(&mut self, source: impl Into<String>, bus: Box<dyn TransportBus>) {
+        self.buses.insert(source.into(), bus);
+    }
+
+    pub fn unregister(&mut self, source: &str) {
+        self.buses.remove(source);
+    }
+
+    pub fn is_registered(&self, source: &str) -> bool {
+        self.buses.contains_key(source)
+    }
+
+    pub fn len(&self) -> usize {
+        self.buses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buses.is_empty()
+    }
+
+    /// Drains every registered bus, tagging each frame with the source it
+    /// arrived on.
+    pub fn poll_all(&mut self) -> Vec<(String, TransportMessage)> {
+        let mut out = Vec::new();
+        for (source, bus) in self.buses.iter_mut() {
+            for frame in bus.poll_frames() {
+                out.push((source.clone(), frame));
+            }
+        }
+        out
+    }
+
+    /// Routes `frame` to the bus registered for `source`. If `source` is not
+    /// registered, the frame is broadcast to every known bus instead of being
+    /// silently dropped.
+    pub fn send_to(&mut self, source: &str, frame: TransportMessage) {
+        if let Some(bus) = self.buses.get_mut(source) {
+            bus.send_frame(frame);
+            return;
+        }
+        for bus in self.buses.values_mut() {
+            bus.send_frame(frame.clone());
+        }
+    }
+
+    /// Sums `transport_stats()` across every registered bus into one
+    /// fleet-level total.
+    pub fn aggregate_stats(&self) -> TransportStats {
+        let mut total = TransportStats::new();
+        for bus in self.buses.values() {
+            let stats = bus.transport_stats();
+            total.inbound_frames = total.inbound_frames.saturating_add(stats.inbound_frames);
+            total.outbound_frames = total.outbound_frames.saturating_add(stats.outbound_frames);
+            total.dropped_inbound = total.dropped_inbound.saturating_add(stats.dropped_inbound);
+            total.dropped_outbound = total
+                .dropped_outbound
+                .saturating_add(stats.dropped_outbound);
+            total.missed_heartbeats = total
+                .missed_heartbeats
+                .saturating_add(stats.missed_heartbeats);
+        }
+        total
+    }
+
+    /// `transport_stats()` for a single registered bus, for callers (and
+    /// tests) that want to inspect one peer without aggregating the fleet.
+    pub fn stats_for(&self, source: &str) -> Option<TransportStats> {
+        self.buses.get(source).map(|bus| bus.transport_stats())
+    }
+}
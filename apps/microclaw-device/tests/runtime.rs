@@ -1,5 +1,9 @@
-use microclaw_device::{now_ms, protocol::*, AgentActivity, RuntimeAction, RuntimeMode, RuntimeState};
+use microclaw_device::{
+    now_ms, protocol::*, AckToken, AgentActivity, EventMask, LinkQuality, ManualClock,
+    RuntimeAction, RuntimeEvent, RuntimeMode, RuntimeState,
+};
 use microclaw_device::pipeline::{SwipeDetector, SwipeDirection};
+use microclaw_device::reconnect::LinkState;
 use microclaw_device::ui::Scene;
 use microclaw_protocol::TouchEventPayload;
 use serde_json::json;
@@ -69,6 +73,85 @@ fn duplicate_message_ids_are_rejected() {
     ));
 }
 
+fn heartbeat_with_seq(source: &str, seq: u64, message_id: &str) -> TransportMessage {
+    TransportMessage {
+        envelope: {
+            let mut e = Envelope::new(source, "microclaw-device", "boot", MessageId::new(message_id));
+            e.seq = seq;
+            e
+        },
+        kind: MessageKind::Heartbeat,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    }
+}
+
+#[test]
+fn replayed_seq_is_rejected_distinctly() {
+    let mut state = RuntimeState::new();
+    state.apply_transport_message(&heartbeat_with_seq("host", 5, "m-1"));
+
+    let action = state.apply_transport_message(&heartbeat_with_seq("host", 5, "m-2"));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "replay_rejected"
+        }
+    ));
+}
+
+#[test]
+fn reordered_seq_within_window_is_still_accepted() {
+    let mut state = RuntimeState::new();
+    state.apply_transport_message(&heartbeat_with_seq("host", 10, "m-1"));
+    state.apply_transport_message(&heartbeat_with_seq("host", 12, "m-2"));
+
+    // seq 11 arrived late but is still within the sliding window.
+    let action = state.apply_transport_message(&heartbeat_with_seq("host", 11, "m-3"));
+    assert!(!matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "replay_rejected"
+        }
+    ));
+}
+
+#[test]
+fn seq_outside_the_window_is_rejected() {
+    let mut state = RuntimeState::new();
+    state.set_replay_window_size(4);
+    state.apply_transport_message(&heartbeat_with_seq("host", 10, "m-1"));
+
+    // seq 5 is more than 4 behind the highest accepted counter (10).
+    let action = state.apply_transport_message(&heartbeat_with_seq("host", 5, "m-2"));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "replay_rejected"
+        }
+    ));
+}
+
+#[test]
+fn seq_replay_guard_is_tracked_per_sender() {
+    let mut state = RuntimeState::new();
+    state.apply_transport_message(&heartbeat_with_seq("host", 5, "m-1"));
+
+    // A different source starting at the same seq is unaffected by "host"'s
+    // progress.
+    let action = state.apply_transport_message(&heartbeat_with_seq("other-host", 5, "m-2"));
+    assert!(!matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "replay_rejected"
+        }
+    ));
+}
+
 #[test]
 fn touch_events_drive_scene_action() {
     let mut state = RuntimeState::new();
@@ -104,7 +187,7 @@ fn touch_events_drive_scene_action() {
 
 #[test]
 fn status_snapshot_updates_wifi_state_and_mode() {
-    let mut state = RuntimeState::new();
+    let mut state = RuntimeState::new().with_verifier(Box::new(AcceptAllVerifier));
     let status = TransportMessage {
         envelope: Envelope::new(
             "host",
@@ -116,8 +199,8 @@ fn status_snapshot_updates_wifi_state_and_mode() {
         corr_id: None,
         ttl_ms: None,
         issued_at: Some(0),
-        signature: None,
-        nonce: None,
+        signature: Some("anything".to_string()),
+        nonce: Some("01".to_string()),
         payload: json!({
             "wifi_ok": true,
             "host_reachable": true,
@@ -165,15 +248,17 @@ fn unauthorized_host_messages_increment_safety_and_deny() {
 
 #[test]
 fn ota_start_marks_ota_in_progress() {
-    let mut state = RuntimeState::new();
+    let mut state = RuntimeState::new()
+        .with_action_policy("host", [DeviceAction::OtaStart])
+        .with_verifier(Box::new(AcceptAllVerifier));
     let cmd = TransportMessage {
         envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("ota-1")),
         kind: MessageKind::Command,
         corr_id: None,
         ttl_ms: None,
         issued_at: Some(0),
-        signature: None,
-        nonce: None,
+        signature: Some("anything".to_string()),
+        nonce: Some("01".to_string()),
         payload: json!({
             "action":"ota_start",
             "args":{"version":"1.2.3"}
@@ -467,3 +552,820 @@ fn swipe_gesture_detects_horizontal_swipe() {
     assert_eq!(detector.on_down(100, 100), None);
     assert_eq!(detector.on_up(180, 180), None);
 }
+
+#[test]
+fn signed_message_with_unverifiable_signature_is_denied() {
+    let mut state = RuntimeState::new();
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("sig-1")),
+        kind: MessageKind::StatusDelta,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: Some("deadbeef".to_string()),
+        nonce: Some("01".to_string()),
+        payload: json!({"connected": true}),
+    };
+
+    let action = state.apply_transport_message(&msg);
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "signature_invalid"
+        }
+    ));
+    assert_eq!(state.safety_fail_count(), 1);
+}
+
+#[test]
+fn unsigned_command_frames_are_rejected_as_signature_invalid() {
+    let mut state = RuntimeState::new();
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("cmd-unsigned")),
+        kind: MessageKind::Command,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({"action": "restart"}),
+    };
+
+    let action = state.apply_transport_message(&msg);
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "signature_invalid"
+        }
+    ));
+    assert_eq!(state.safety_fail_count(), 1);
+}
+
+#[test]
+fn unsigned_status_snapshot_frames_are_rejected_as_signature_invalid() {
+    let mut state = RuntimeState::new();
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("snap-unsigned")),
+        kind: MessageKind::StatusSnapshot,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({"wifi_ok": true, "host_reachable": true, "mode": "ready"}),
+    };
+
+    let action = state.apply_transport_message(&msg);
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "signature_invalid"
+        }
+    ));
+}
+
+struct StubSigner;
+
+impl microclaw_device::crypto::Signer for StubSigner {
+    fn sign(&self, _source: &str, _canonical: &[u8]) -> String {
+        "stub-signature".to_string()
+    }
+}
+
+#[test]
+fn emit_command_signs_outbound_frames_once_a_signer_is_configured() {
+    let mut state = RuntimeState::new().with_signer(Box::new(StubSigner));
+    let cmd = state.emit_command(DeviceAction::StatusGet);
+    assert_eq!(cmd.signature.as_deref(), Some("stub-signature"));
+    assert!(cmd.nonce.is_some());
+}
+
+#[test]
+fn emit_command_stays_unsigned_without_a_signer() {
+    let mut state = RuntimeState::new();
+    let cmd = state.emit_command(DeviceAction::StatusGet);
+    assert_eq!(cmd.signature, None);
+    assert_eq!(cmd.nonce, None);
+}
+
+struct AcceptAllVerifier;
+
+impl microclaw_device::crypto::SignatureVerifier for AcceptAllVerifier {
+    fn verify(&self, _source: &str, _canonical: &[u8], _signature: &str) -> bool {
+        true
+    }
+}
+
+fn signed_message(nonce: &str, message_id: &str) -> TransportMessage {
+    TransportMessage {
+        envelope: Envelope::new(
+            "host",
+            "microclaw-device",
+            "boot",
+            MessageId::new(message_id),
+        ),
+        kind: MessageKind::StatusDelta,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: Some("anything".to_string()),
+        nonce: Some(nonce.to_string()),
+        payload: json!({"connected": true}),
+    }
+}
+
+#[test]
+fn replayed_nonce_is_rejected_with_distinct_message() {
+    let mut state = RuntimeState::new().with_verifier(Box::new(AcceptAllVerifier));
+
+    let first = state.apply_transport_message(&signed_message("01", "n-1"));
+    assert!(!matches!(
+        first,
+        RuntimeAction::RaiseUiState {
+            message: "signature_invalid" | "nonce_replayed"
+        }
+    ));
+
+    let mut replayed = signed_message("01", "n-2");
+    replayed.envelope.seq = 2;
+    let second = state.apply_transport_message(&replayed);
+    assert!(matches!(
+        second,
+        RuntimeAction::RaiseUiState {
+            message: "nonce_replayed"
+        }
+    ));
+    assert_eq!(state.safety_fail_count(), 1);
+}
+
+#[test]
+fn injected_swipe_gesture_reports_direction() {
+    let mut state = RuntimeState::new();
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("inject-1")),
+        kind: MessageKind::InjectInput,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({"gesture": "swipe", "direction": "right"}),
+    };
+
+    let action = state.apply_transport_message(&msg);
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "inject_input_swipe_right"
+        }
+    ));
+}
+
+#[test]
+fn unsigned_messages_still_flow_through_allowlist_only() {
+    let mut state = RuntimeState::new();
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("unsigned-1")),
+        kind: MessageKind::StatusDelta,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({"connected": true}),
+    };
+
+    let action = state.apply_transport_message(&msg);
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "status_updated"
+        }
+    ));
+}
+
+#[test]
+fn reconnect_supervisor_resets_attempt_count_on_hello_ack() {
+    let mut state = RuntimeState::new();
+    state.note_link_state(LinkState::Up);
+    state.note_connect_failed(0);
+    state.note_connect_failed(0);
+    assert_eq!(state.reconnect_attempt(), 2);
+
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("hello-1")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    };
+    state.apply_transport_message(&msg);
+    assert_eq!(state.reconnect_attempt(), 0);
+}
+
+#[test]
+fn reconnect_not_attempted_while_link_is_down() {
+    let mut state = RuntimeState::new();
+    assert!(!state.should_attempt_reconnect(now_ms()));
+}
+
+#[test]
+fn reconnect_backoff_delay_stays_within_configured_bounds_as_attempts_grow() {
+    let mut state = RuntimeState::new();
+    state.set_reconnect_backoff_params(500, 30_000);
+    state.note_link_state(LinkState::Up);
+
+    assert_eq!(state.reconnect_current_delay_ms(), 0);
+    for attempt in 0..8 {
+        state.note_connect_failed(attempt);
+        assert_eq!(state.reconnect_attempt(), attempt as u32 + 1);
+        let delay = state.reconnect_current_delay_ms();
+        assert!((500..=30_000).contains(&delay), "delay {delay} out of bounds");
+    }
+
+    state.apply_transport_message(&TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("hello-3")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    });
+    assert_eq!(state.reconnect_current_delay_ms(), 0);
+}
+
+fn heartbeat_at(state: &mut RuntimeState, issued_at: u64) {
+    state.apply_transport_message(&TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("hb")),
+        kind: MessageKind::Heartbeat,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(issued_at),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    });
+}
+
+#[test]
+fn link_quality_is_strong_when_heartbeat_is_fresh() {
+    let mut state = RuntimeState::new();
+    heartbeat_at(&mut state, 1_000);
+    assert_eq!(state.link_quality(1_000, 15_000), LinkQuality::Strong);
+}
+
+#[test]
+fn link_quality_degrades_as_heartbeat_ages_towards_timeout() {
+    let mut state = RuntimeState::new();
+    heartbeat_at(&mut state, 0);
+    assert_eq!(state.link_quality(4_000, 15_000), LinkQuality::Strong);
+    assert_eq!(state.link_quality(8_000, 15_000), LinkQuality::Good);
+    assert_eq!(state.link_quality(11_000, 15_000), LinkQuality::Weak);
+}
+
+#[test]
+fn link_quality_downgrades_one_step_on_large_in_flight_backlog() {
+    let mut state = RuntimeState::new();
+    heartbeat_at(&mut state, 0);
+    for _ in 0..6 {
+        state.emit_command(DeviceAction::StatusGet);
+    }
+    assert_eq!(state.link_quality(0, 15_000), LinkQuality::Good);
+}
+
+#[test]
+fn poll_reconnect_emits_snapshot_request_when_due_while_offline() {
+    use microclaw_device::reconnect::ReconnectStrategy;
+
+    let mut state = RuntimeState::new();
+    state.mark_offline_with_reason("link_dropped", 0);
+    state.set_reconnect_strategy(ReconnectStrategy::FixedInterval {
+        interval_ms: 1_000,
+        max_retries: 3,
+    });
+
+    let msg = state.poll_reconnect(0).expect("first attempt should fire");
+    assert_eq!(msg.kind, MessageKind::SnapshotRequest);
+    assert_eq!(state.reconnect_attempts(), 1);
+
+    assert!(state.poll_reconnect(500).is_none());
+    assert!(state.poll_reconnect(1_000).is_some());
+    assert_eq!(state.reconnect_attempts(), 2);
+}
+
+#[test]
+fn poll_reconnect_enters_safe_mode_once_retries_exhausted() {
+    use microclaw_device::reconnect::ReconnectStrategy;
+
+    let mut state = RuntimeState::new();
+    state.mark_offline_with_reason("link_dropped", 0);
+    state.set_reconnect_strategy(ReconnectStrategy::FixedInterval {
+        interval_ms: 0,
+        max_retries: 1,
+    });
+
+    assert!(state.poll_reconnect(0).is_some());
+    assert!(state.poll_reconnect(0).is_none());
+    assert!(matches!(state.mode(), RuntimeMode::SafeMode(_)));
+}
+
+#[test]
+fn hello_ack_resets_reconnect_attempts() {
+    use microclaw_device::reconnect::ReconnectStrategy;
+
+    let mut state = RuntimeState::new();
+    state.mark_offline_with_reason("link_dropped", 0);
+    state.set_reconnect_strategy(ReconnectStrategy::FixedInterval {
+        interval_ms: 0,
+        max_retries: 5,
+    });
+    state.poll_reconnect(0);
+    assert_eq!(state.reconnect_attempts(), 1);
+
+    state.apply_transport_message(&TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("hello-2")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    });
+    assert_eq!(state.reconnect_attempts(), 0);
+}
+
+#[test]
+fn subscriber_receives_only_events_in_its_mask() {
+    let mut state = RuntimeState::new();
+    let mode_only = state.subscribe(EventMask::MODE_CHANGED);
+    let status_only = state.subscribe(EventMask::STATUS_UPDATED);
+
+    let msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("hello-3")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    };
+    state.apply_transport_message(&msg);
+
+    let mode_events = state.poll_events(mode_only);
+    assert!(mode_events
+        .iter()
+        .any(|event| matches!(event, RuntimeEvent::ModeChanged(RuntimeMode::Connected))));
+    assert!(state.poll_events(status_only).is_empty());
+}
+
+#[test]
+fn poll_events_drains_the_backlog() {
+    let mut state = RuntimeState::new();
+    let sub = state.subscribe(EventMask::ALL);
+
+    state.apply_transport_message(&TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("hello-4")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    });
+
+    assert!(!state.poll_events(sub).is_empty());
+    assert!(state.poll_events(sub).is_empty());
+}
+
+#[test]
+fn unauthorized_host_messages_emit_safety_tripped_event() {
+    let mut state = RuntimeState::with_host_allowlist(["trusted_host"]);
+    let sub = state.subscribe(EventMask::SAFETY_TRIPPED);
+
+    state.apply_transport_message(&TransportMessage {
+        envelope: Envelope::new(
+            "untrusted_host",
+            "microclaw-device",
+            "boot",
+            MessageId::new("hello-5"),
+        ),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    });
+
+    let events = state.poll_events(sub);
+    assert!(matches!(
+        events.as_slice(),
+        [RuntimeEvent::SafetyTripped { fail_count: 1 }]
+    ));
+}
+
+#[test]
+fn command_ack_emits_command_acked_event() {
+    let mut state = RuntimeState::new();
+    let sub = state.subscribe(EventMask::COMMAND_ACKED);
+    let cmd = state.emit_command(DeviceAction::StatusGet);
+    let corr_id = cmd.corr_id.clone().unwrap();
+
+    state.apply_transport_message(&TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("ack-1")),
+        kind: MessageKind::CommandAck,
+        corr_id: Some(corr_id.clone()),
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({}),
+    });
+
+    let events = state.poll_events(sub);
+    assert!(matches!(
+        events.as_slice(),
+        [RuntimeEvent::CommandAcked { corr_id: acked }] if *acked == corr_id
+    ));
+}
+
+fn command_from(source: &str, seq: u64, action: DeviceAction) -> TransportMessage {
+    let mut envelope = Envelope::new(
+        source,
+        "microclaw-device",
+        "boot",
+        MessageId::new(format!("cmd-{seq}")),
+    );
+    envelope.seq = seq;
+    TransportMessage {
+        envelope,
+        kind: MessageKind::Command,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: Some("anything".to_string()),
+        nonce: Some(format!("{seq:02x}")),
+        payload: json!({ "action": action }),
+    }
+}
+
+#[test]
+fn unlisted_source_defaults_to_read_only_diagnostics_access() {
+    let mut state = RuntimeState::new().with_verifier(Box::new(AcceptAllVerifier));
+    let action =
+        state.apply_transport_message(&command_from("any_host", 1, DeviceAction::Restart));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "command_denied_insufficient_privilege"
+        }
+    ));
+    assert_eq!(state.safety_fail_count(), 1);
+
+    let action = state.apply_transport_message(&command_from(
+        "any_host",
+        2,
+        DeviceAction::DiagnosticsSnapshot,
+    ));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "command_diagnostics"
+        }
+    ));
+}
+
+#[test]
+fn action_policy_grants_only_listed_actions_for_source() {
+    let mut state = RuntimeState::new()
+        .with_action_policy("controller", [DeviceAction::Restart, DeviceAction::Retry])
+        .with_verifier(Box::new(AcceptAllVerifier));
+
+    let action =
+        state.apply_transport_message(&command_from("controller", 1, DeviceAction::Restart));
+    assert!(matches!(state.mode(), RuntimeMode::Booting));
+    assert!(!matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "command_denied_insufficient_privilege"
+        }
+    ));
+
+    let action =
+        state.apply_transport_message(&command_from("controller", 2, DeviceAction::OtaStart));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "command_denied_insufficient_privilege"
+        }
+    ));
+}
+
+#[test]
+fn manual_clock_drives_emitted_command_timestamps() {
+    let clock = ManualClock::new(1_000);
+    let mut state = RuntimeState::new().with_clock(Box::new(clock));
+
+    let cmd = state.emit_command(DeviceAction::StatusGet);
+    assert_eq!(cmd.issued_at, Some(1_000));
+}
+
+#[test]
+fn manual_clock_advance_is_reflected_in_emitted_timestamps() {
+    let clock = ManualClock::new(1_000);
+    let clock_handle = clock.clone();
+    let mut state = RuntimeState::new().with_clock(Box::new(clock));
+
+    clock_handle.advance(500);
+    let cmd = state.emit_command(DeviceAction::StatusGet);
+    assert_eq!(cmd.issued_at, Some(1_500));
+}
+
+#[cfg(feature = "secure-session")]
+fn keypair() -> (x25519_dalek::StaticSecret, x25519_dalek::PublicKey) {
+    let secret = x25519_dalek::StaticSecret::random_from_rng(rand_core::OsRng);
+    let public = x25519_dalek::PublicKey::from(&secret);
+    (secret, public)
+}
+
+#[cfg(feature = "secure-session")]
+#[test]
+fn handshake_round_trip_reaches_connected_with_session_keys() {
+    use microclaw_device::handshake::{HelloAckPayload, HostConfig, HostHandshakeConfig};
+
+    let (device_secret, _device_public) = keypair();
+    let (host_secret, host_public) = keypair();
+
+    let mut state = RuntimeState::new().with_host_config(HostConfig::new(device_secret, host_public));
+    let hello = state.emit_hello();
+    let hello_payload =
+        microclaw_device::handshake::HelloPayload::from_json(&hello.payload).unwrap();
+
+    let host_config = HostHandshakeConfig {
+        host_static_secret: host_secret,
+        max_clock_skew_ms: 5_000,
+    };
+    let (ack, _host_keys) =
+        microclaw_device::handshake::respond(&host_config, &hello_payload, now_ms()).unwrap();
+
+    let ack_msg = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("ack-1")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: ack.to_json(),
+    };
+
+    let action = state.apply_transport_message(&ack_msg);
+    assert!(matches!(state.mode(), RuntimeMode::Connected));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "connected"
+        }
+    ));
+    assert!(state.session_keys().is_some());
+
+    let _ = HelloAckPayload::from_json(&ack.to_json());
+}
+
+#[cfg(feature = "secure-session")]
+#[test]
+fn tampered_hello_ack_is_rejected_as_handshake_failed() {
+    use microclaw_device::handshake::HostConfig;
+
+    let (device_secret, _device_public) = keypair();
+    let (_host_secret, host_public) = keypair();
+
+    let mut state = RuntimeState::new().with_host_config(HostConfig::new(device_secret, host_public));
+    let hello = state.emit_hello();
+    let _ = hello;
+
+    let bogus_ack = TransportMessage {
+        envelope: Envelope::new("host", "microclaw-device", "boot", MessageId::new("ack-2")),
+        kind: MessageKind::HelloAck,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({
+            "host_ephemeral_public": "00".repeat(32),
+            "confirm_tag": "00".repeat(16),
+        }),
+    };
+
+    let action = state.apply_transport_message(&bogus_ack);
+    assert!(!matches!(state.mode(), RuntimeMode::Connected));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "handshake_failed"
+        }
+    ));
+    assert!(state.session_keys().is_none());
+}
+
+fn delta_from(source: &str, seq: u64) -> TransportMessage {
+    let mut envelope = Envelope::new(
+        source,
+        "microclaw-device",
+        "boot",
+        MessageId::new(format!("delta-{seq}")),
+    );
+    envelope.seq = seq;
+    TransportMessage {
+        envelope,
+        kind: MessageKind::StatusDelta,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: None,
+        nonce: None,
+        payload: json!({ "wifi_ok": true, "battery_percent": seq as u8 }),
+    }
+}
+
+#[test]
+fn in_order_deltas_apply_immediately() {
+    let mut state = RuntimeState::new();
+    state.apply_transport_message(&delta_from("host", 1));
+    let action = state.apply_transport_message(&delta_from("host", 2));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "status_updated"
+        }
+    ));
+}
+
+#[test]
+fn out_of_order_delta_is_buffered_then_flushed_once_gap_fills() {
+    let mut state = RuntimeState::new();
+    state.apply_transport_message(&delta_from("host", 1));
+
+    let buffered = state.apply_transport_message(&delta_from("host", 3));
+    assert!(matches!(
+        buffered,
+        RuntimeAction::RaiseUiState {
+            message: "delta_gap_buffered"
+        }
+    ));
+
+    let filled = state.apply_transport_message(&delta_from("host", 2));
+    assert!(matches!(
+        filled,
+        RuntimeAction::RaiseUiState {
+            message: "status_updated"
+        }
+    ));
+    assert!(!state.pending_reconciliation());
+}
+
+#[test]
+fn unfilled_gap_triggers_snapshot_request_after_step_limit() {
+    let mut state = RuntimeState::new();
+    state.set_delta_gap_step_limit(2);
+    state.apply_transport_message(&delta_from("host", 1));
+
+    state.apply_transport_message(&delta_from("host", 5));
+    let still_waiting = state.apply_transport_message(&delta_from("host", 6));
+    assert!(matches!(
+        still_waiting,
+        RuntimeAction::RaiseUiState {
+            message: "delta_gap_buffered"
+        }
+    ));
+
+    let gap_detected = state.apply_transport_message(&delta_from("host", 7));
+    assert!(matches!(
+        gap_detected,
+        RuntimeAction::EmitSnapshotRequest {
+            reason: "delta_gap_detected"
+        }
+    ));
+    assert!(state.pending_reconciliation());
+}
+
+#[test]
+fn status_snapshot_resets_expected_seq_and_drains_buffer() {
+    let mut state = RuntimeState::new().with_verifier(Box::new(AcceptAllVerifier));
+    state.apply_transport_message(&delta_from("host", 1));
+    state.apply_transport_message(&delta_from("host", 9));
+
+    let msg = TransportMessage {
+        envelope: {
+            // A fresh seq, so the snapshot passes the per-sender replay guard.
+            let mut e = Envelope::new("host", "microclaw-device", "boot", MessageId::new("snap-reset"));
+            e.seq = 10;
+            e
+        },
+        kind: MessageKind::StatusSnapshot,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(0),
+        signature: Some("anything".to_string()),
+        nonce: Some("01".to_string()),
+        payload: json!({ "wifi_ok": true }),
+    };
+    state.apply_transport_message(&msg);
+    assert!(!state.pending_reconciliation());
+
+    let next = state.apply_transport_message(&delta_from("host", msg.envelope.seq + 1));
+    assert!(matches!(
+        next,
+        RuntimeAction::RaiseUiState {
+            message: "status_updated"
+        }
+    ));
+}
+
+fn command_with_packet_id(source: &str, seq: u64, action: DeviceAction, packet_id: u64) -> TransportMessage {
+    let mut msg = command_from(source, seq, action);
+    msg.payload = json!({ "action": msg.payload["action"].clone(), "packet_id": packet_id });
+    msg
+}
+
+#[test]
+fn command_with_packet_id_auto_acks_back_to_host() {
+    let mut state = RuntimeState::new()
+        .with_action_policy("host", [DeviceAction::StatusGet])
+        .with_verifier(Box::new(AcceptAllVerifier));
+    let action = state.apply_transport_message(&command_with_packet_id(
+        "host",
+        1,
+        DeviceAction::StatusGet,
+        7,
+    ));
+    assert!(matches!(
+        action,
+        RuntimeAction::EmitCommandAck {
+            packet_id: 7,
+            message: "command_received"
+        }
+    ));
+    assert_eq!(state.pending_manual_ack_count(), 0);
+}
+
+#[test]
+fn manual_ack_action_withholds_ack_until_explicitly_acked() {
+    let mut state = RuntimeState::new()
+        .with_manual_ack_actions([DeviceAction::OtaStart])
+        .with_action_policy("host", [DeviceAction::OtaStart])
+        .with_verifier(Box::new(AcceptAllVerifier));
+    let action = state.apply_transport_message(&command_with_packet_id(
+        "host",
+        1,
+        DeviceAction::OtaStart,
+        42,
+    ));
+    assert!(matches!(
+        action,
+        RuntimeAction::RaiseUiState {
+            message: "command_ota_start"
+        }
+    ));
+    assert_eq!(state.pending_manual_ack_count(), 1);
+
+    let ack = state.ack(AckToken(42)).expect("pending manual ack");
+    assert_eq!(ack.kind, MessageKind::CommandAck);
+    assert_eq!(ack.payload["packet_id"], 42);
+    assert_eq!(state.pending_manual_ack_count(), 0);
+    assert!(state.ack(AckToken(42)).is_none());
+}
+
+#[test]
+fn with_storage_recovers_pending_manual_acks_for_redelivery() {
+    use microclaw_device::storage::DeviceStorage;
+
+    // Simulates a crash mid-handler on a prior boot: a manual-ack command
+    // was persisted but never released.
+    let mut storage = microclaw_device::storage::InMemoryStorage::new();
+    let mut registry = microclaw_device::ManualAckRegistry::new();
+    registry.hold(microclaw_device::PendingManualAck {
+        packet_id: 9,
+        action: DeviceAction::OtaStart,
+        args: json!({}),
+        source: "host".to_string(),
+    });
+    storage.set_bytes(
+        microclaw_device::storage::keys::PENDING_MANUAL_ACKS,
+        &registry.to_bytes(),
+    );
+
+    let state = RuntimeState::with_storage(Box::new(storage));
+    assert_eq!(state.pending_manual_ack_count(), 1);
+    assert_eq!(state.pending_manual_acks().next().unwrap().packet_id, 9);
+}
@@ -5,10 +5,18 @@ use microclaw_device::{
     protocol::{Envelope, MessageId, MessageKind},
     protocol::{TouchEventPayload, TransportMessage},
     renderer::NullRenderer,
-    transport::{InMemoryTransport, TransportBus},
+    transport::{InMemoryTransport, TransportBus, TransportRouter},
     RuntimeMode, RuntimeState,
 };
 
+fn sample_frame() -> TransportMessage {
+    TransportMessage::new(
+        Envelope::new("host", "device", "boot", MessageId::new("m1")),
+        MessageKind::Heartbeat,
+        serde_json::json!({}),
+    )
+}
+
 #[test]
 fn in_memory_transport_buffers_inbound_and_outbound_with_cap() {
     let mut transport = InMemoryTransport::with_queue_depth(2, 2);
@@ -294,9 +302,17 @@ fn reconnect_emits_snapshot_request_and_sets_pending_reconciliation() {
     );
 }
 
+struct AcceptAllVerifier;
+
+impl microclaw_device::crypto::SignatureVerifier for AcceptAllVerifier {
+    fn verify(&self, _source: &str, _canonical: &[u8], _signature: &str) -> bool {
+        true
+    }
+}
+
 #[test]
 fn status_snapshot_clears_pending_reconciliation() {
-    let mut state = RuntimeState::new();
+    let mut state = RuntimeState::new().with_verifier(Box::new(AcceptAllVerifier));
 
     // Emit a snapshot request to set pending_reconciliation
     let _req = state.emit_snapshot_request();
@@ -309,8 +325,8 @@ fn status_snapshot_clears_pending_reconciliation() {
         corr_id: None,
         ttl_ms: None,
         issued_at: Some(now_ms()),
-        signature: None,
-        nonce: None,
+        signature: Some("anything".to_string()),
+        nonce: Some("01".to_string()),
         payload: serde_json::json!({
             "wifi_ok": true,
             "host_reachable": true,
@@ -353,3 +369,262 @@ fn status_delta_does_not_clear_pending_reconciliation() {
         "StatusDelta should not clear pending_reconciliation"
     );
 }
+
+#[cfg(feature = "capture")]
+#[test]
+fn capture_tap_records_polled_and_sent_frames() {
+    use microclaw_device::transport::RecordingTap;
+
+    let mut transport = InMemoryTransport::new();
+    transport.set_connected(true);
+    transport.set_capture(Some(RecordingTap::new()));
+
+    transport.push_inbound(TransportMessage {
+        envelope: Envelope::new("host", "dev", "boot", MessageId::new("cap-1")),
+        kind: MessageKind::Hello,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(now_ms()),
+        signature: None,
+        nonce: None,
+        payload: serde_json::json!({}),
+    });
+    transport.poll_frames();
+    transport.send_frame(TransportMessage {
+        envelope: Envelope::new("dev", "host", "boot", MessageId::new("cap-2")),
+        kind: MessageKind::Heartbeat,
+        corr_id: None,
+        ttl_ms: None,
+        issued_at: Some(now_ms()),
+        signature: None,
+        nonce: None,
+        payload: serde_json::json!({}),
+    });
+
+    assert!(!transport.capture_mut().unwrap().bytes().is_empty());
+}
+
+#[test]
+fn host_from_ws_url_strips_scheme_port_and_path() {
+    use microclaw_device::transport::host_from_ws_url;
+
+    assert_eq!(
+        host_from_ws_url("wss://api.example.com:8443/ws?device_id=1"),
+        Some("api.example.com".to_owned())
+    );
+    assert_eq!(host_from_ws_url(""), None);
+}
+
+#[test]
+fn check_host_allowlist_rejects_unlisted_hosts() {
+    use microclaw_device::transport::{check_host_allowlist, ConnectGuardError};
+
+    let allowlist = vec!["api.example.com".to_owned()];
+    assert!(check_host_allowlist("wss://api.example.com/ws", &allowlist).is_ok());
+    assert_eq!(
+        check_host_allowlist("wss://evil.example.com/ws", &allowlist),
+        Err(ConnectGuardError::HostNotAllowlisted("evil.example.com".to_owned()))
+    );
+    assert!(check_host_allowlist("wss://anything/ws", &[]).is_ok());
+}
+
+#[test]
+fn exponential_backoff_strategy_paces_reconnect_attempts() {
+    use microclaw_device::transport::ReconnectStrategy;
+
+    let mut transport = InMemoryTransport::new();
+    transport.set_reconnect_strategy(ReconnectStrategy::ExponentialBackoff {
+        base_ms: 100,
+        max_ms: 10_000,
+        max_retries: 10,
+    });
+    transport.set_reconnect_failures_until_success(u64::MAX);
+
+    assert!(!transport.reconnect(0, 0));
+    assert_eq!(transport.reconnect_attempts(), 1);
+
+    // Immediately retrying before the backoff window elapses is a no-op.
+    assert!(!transport.reconnect(1, 0));
+    assert_eq!(transport.reconnect_attempts(), 1);
+
+    // Once enough time has passed (base_ms * 2^0, plus jitter bound), the
+    // attempt count goes up again.
+    assert!(!transport.reconnect(1, 10_000));
+    assert_eq!(transport.reconnect_attempts(), 2);
+}
+
+#[test]
+fn off_strategy_retries_every_call_like_before() {
+    let mut transport = InMemoryTransport::new();
+    transport.set_reconnect_failures_until_success(1);
+    assert!(!transport.reconnect(0, 0));
+    assert_eq!(transport.reconnect_attempts(), 1);
+
+    // No backoff window: an immediate second call dials again.
+    assert!(transport.reconnect(1, 0));
+    assert_eq!(transport.reconnect_attempts(), 2);
+}
+
+#[test]
+fn cert_pin_store_rejects_mismatched_pin() {
+    use microclaw_device::transport::CertPinStore;
+
+    let mut pins = CertPinStore::new();
+    pins.add_pin("api.example.com", "deadbeef");
+    assert!(pins.verify("unpinned-host", b"anything").is_ok());
+    assert!(pins.verify("api.example.com", b"wrong-key").is_err());
+}
+
+#[test]
+fn connecting_to_a_pinned_host_is_refused_since_pinning_is_not_wired_in() {
+    use microclaw_device::transport::{check_cert_pinning_supported, CertPinStore, ConnectGuardError};
+
+    let mut pins = CertPinStore::new();
+    pins.add_pin("api.example.com", "deadbeef");
+
+    assert!(check_cert_pinning_supported("unpinned-host", &pins).is_ok());
+    assert!(matches!(
+        check_cert_pinning_supported("api.example.com", &pins),
+        Err(ConnectGuardError::CertPinningUnsupported(host)) if host == "api.example.com"
+    ));
+}
+#[test]
+fn router_register_replaces_stale_entry_for_same_source() {
+    let mut router = TransportRouter::new();
+    router.register("device-a", Box::new(InMemoryTransport::new()));
+    assert_eq!(router.len(), 1);
+    router.register("device-a", Box::new(InMemoryTransport::new()));
+    assert_eq!(router.len(), 1);
+}
+
+#[test]
+fn router_send_to_known_source_routes_only_there() {
+    let mut router = TransportRouter::new();
+    router.register("device-a", Box::new(InMemoryTransport::new()));
+    router.register("device-b", Box::new(InMemoryTransport::new()));
+    router.send_to("device-a", sample_frame());
+
+    assert_eq!(router.stats_for("device-a").unwrap().outbound_frames, 1);
+    assert_eq!(router.stats_for("device-b").unwrap().outbound_frames, 0);
+}
+
+#[test]
+fn router_send_to_unknown_source_broadcasts() {
+    let mut router = TransportRouter::new();
+    router.register("device-a", Box::new(InMemoryTransport::new()));
+    router.register("device-b", Box::new(InMemoryTransport::new()));
+    router.send_to("device-unknown", sample_frame());
+
+    assert_eq!(router.stats_for("device-a").unwrap().outbound_frames, 1);
+    assert_eq!(router.stats_for("device-b").unwrap().outbound_frames, 1);
+}
+
+#[test]
+fn router_aggregate_stats_sums_across_buses() {
+    let mut router = TransportRouter::new();
+    router.register("device-a", Box::new(InMemoryTransport::new()));
+    router.register("device-b", Box::new(InMemoryTransport::new()));
+    router.send_to("device-a", sample_frame());
+    router.send_to("device-b", sample_frame());
+
+    assert_eq!(router.aggregate_stats().outbound_frames, 2);
+}
+
+#[test]
+fn poll_frames_into_default_matches_poll_frames() {
+    let mut transport = InMemoryTransport::with_queue_depth(4, 4);
+    transport.push_inbound(sample_frame());
+    transport.push_inbound(sample_frame());
+
+    let mut collected = Vec::new();
+    transport.poll_frames_into(&mut collected);
+    assert_eq!(collected.len(), 2);
+    assert!(transport.poll_frames().is_empty());
+}
+
+#[test]
+fn step_until_idle_reports_total_inbound_processed_and_completion_message() {
+    let mut state = RuntimeState::new();
+    let mut transport = InMemoryTransport::new();
+    transport.set_connected(true);
+    for _ in 0..3 {
+        transport.push_inbound(sample_frame());
+    }
+
+    let mut loop_state = DeviceEventLoop::new(EventLoopConfig {
+        render_interval_ms: 50,
+        offline_timeout_ms: 60_000,
+        ..Default::default()
+    });
+    let mut pipeline = TouchPipeline::new();
+    let mut renderer = NullRenderer::new();
+
+    let out = loop_state.step_until_idle(
+        &mut state,
+        &mut pipeline,
+        &mut transport,
+        now_ms(),
+        &mut renderer,
+    );
+
+    assert_eq!(out.inbound_processed, 3);
+    assert!(out
+        .ui_messages
+        .iter()
+        .any(|message| *message == "transport_step_completed"));
+}
+
+#[test]
+fn step_until_idle_stops_at_max_passes_even_if_still_busy() {
+    let mut state = RuntimeState::new();
+    let mut transport = InMemoryTransport::new();
+    transport.set_connected(false);
+    transport.set_reconnect_failures_until_success(u64::MAX);
+
+    let mut loop_state = DeviceEventLoop::new(EventLoopConfig {
+        transport_reconnect_backoff_ms: 0,
+        max_passes: 3,
+        ..Default::default()
+    });
+    let mut pipeline = TouchPipeline::new();
+    let mut renderer = NullRenderer::new();
+
+    let out = loop_state.step_until_idle(
+        &mut state,
+        &mut pipeline,
+        &mut transport,
+        now_ms(),
+        &mut renderer,
+    );
+
+    assert_eq!(transport.reconnect_attempts(), 3);
+    assert!(!out.transport_connected);
+}
+
+#[test]
+fn step_until_idle_preserves_recovery_message_once_connected() {
+    let mut state = RuntimeState::new();
+    let mut transport = InMemoryTransport::new();
+    transport.set_connected(false);
+    transport.set_reconnect_failures_until_success(0);
+
+    let mut loop_state = DeviceEventLoop::new(EventLoopConfig {
+        transport_reconnect_backoff_ms: 100,
+        ..Default::default()
+    });
+    let mut pipeline = TouchPipeline::new();
+    let mut renderer = NullRenderer::new();
+
+    let out = loop_state.step_until_idle(
+        &mut state,
+        &mut pipeline,
+        &mut transport,
+        now_ms(),
+        &mut renderer,
+    );
+
+    assert!(out
+        .ui_messages
+        .iter()
+        .any(|m| *m == "transport_reconnect_success"));
+}
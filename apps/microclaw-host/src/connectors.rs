@@ -0,0 +1,35 @@
+use microclaw_core::Message;
+
+/// Error from a [`Connector`] operation, surfaced to `Host` the same way
+/// sandbox/bus errors are (stashed in `HostStatus::last_error`).
+#[derive(Debug)]
+pub struct ConnectorError(pub String);
+
+impl std::fmt::Display for ConnectorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConnectorError {}
+
+/// One inbound message from a [`Connector`], paired with the room/channel it
+/// arrived in so a reply can be routed back to the same place.
+#[derive(Clone, Debug)]
+pub struct RoomMessage {
+    pub room_id: String,
+    pub message: Message,
+}
+
+/// A chat network transport `Host` can drive its trigger pipeline over.
+/// `Host` calls `connect` once at startup, then polls `next_message` on
+/// every tick (`Ok(None)` means "nothing new right now", not an error) and
+/// routes a matched handler's reply back through `send_reply`.
+pub trait Connector {
+    fn connect(&mut self) -> Result<(), ConnectorError>;
+    fn next_message(&mut self) -> Result<Option<RoomMessage>, ConnectorError>;
+    fn send_reply(&mut self, room_id: &str, reply: &str) -> Result<(), ConnectorError>;
+}
+
+#[cfg(feature = "matrix")]
+pub mod matrix;
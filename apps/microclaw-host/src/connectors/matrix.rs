@@ -0,0 +1,113 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use matrix_sdk::config::SyncSettings;
+use matrix_sdk::ruma::events::room::message::{MessageType, OriginalSyncRoomMessageEvent, RoomMessageEventContent};
+use matrix_sdk::ruma::RoomId;
+use matrix_sdk::{Client, Room};
+use microclaw_config::MatrixConnectorConfig;
+use microclaw_core::Message;
+use tokio::runtime::Runtime;
+
+use super::{Connector, ConnectorError, RoomMessage};
+
+/// `Connector` backed by `matrix-sdk`. Keeps its own multi-threaded Tokio
+/// runtime (`Runtime::new`), on which the background sync task keeps running
+/// between calls, so `Host`'s synchronous tick loop doesn't need to become async
+/// just to drive one connector; inbound events land in an mpsc channel that
+/// `next_message` drains without blocking.
+pub struct MatrixConnector {
+    config: MatrixConnectorConfig,
+    runtime: Runtime,
+    client: Option<Client>,
+    inbox: Receiver<RoomMessage>,
+    inbox_sender: Sender<RoomMessage>,
+}
+
+impl MatrixConnector {
+    pub fn new(config: MatrixConnectorConfig) -> Self {
+        let (inbox_sender, inbox) = channel();
+        let runtime = Runtime::new().expect("tokio runtime should start");
+        Self {
+            config,
+            runtime,
+            client: None,
+            inbox,
+            inbox_sender,
+        }
+    }
+}
+
+impl Connector for MatrixConnector {
+    fn connect(&mut self) -> Result<(), ConnectorError> {
+        let config = self.config.clone();
+        let sender = self.inbox_sender.clone();
+
+        let client = self.runtime.block_on(async {
+            let client = Client::builder()
+                .homeserver_url(&config.homeserver_url)
+                .build()
+                .await
+                .map_err(|error| ConnectorError(format!("matrix client build failed: {}", error)))?;
+
+            client
+                .matrix_auth()
+                .login_username(&config.username, &config.password)
+                .send()
+                .await
+                .map_err(|error| ConnectorError(format!("matrix login failed: {}", error)))?;
+
+            for room_id in &config.joined_rooms {
+                let parsed = RoomId::parse(room_id.as_str()).map_err(|error| {
+                    ConnectorError(format!("invalid room id {}: {}", room_id, error))
+                })?;
+                client
+                    .join_room_by_id(&parsed)
+                    .await
+                    .map_err(|error| ConnectorError(format!("join {} failed: {}", room_id, error)))?;
+            }
+
+            client.add_event_handler(move |event: OriginalSyncRoomMessageEvent, room: Room| {
+                let sender = sender.clone();
+                async move {
+                    let MessageType::Text(text) = event.content.msgtype else {
+                        return;
+                    };
+                    let _ = sender.send(RoomMessage {
+                        room_id: room.room_id().to_string(),
+                        message: Message::new(text.body),
+                    });
+                }
+            });
+
+            let sync_client = client.clone();
+            tokio::spawn(async move {
+                let _ = sync_client.sync(SyncSettings::default()).await;
+            });
+
+            Ok::<Client, ConnectorError>(client)
+        })?;
+
+        self.client = Some(client);
+        Ok(())
+    }
+
+    fn next_message(&mut self) -> Result<Option<RoomMessage>, ConnectorError> {
+        Ok(self.inbox.try_recv().ok())
+    }
+
+    fn send_reply(&mut self, room_id: &str, reply: &str) -> Result<(), ConnectorError> {
+        let client = self
+            .client
+            .as_ref()
+            .ok_or_else(|| ConnectorError("matrix connector not connected".to_string()))?;
+        let parsed = RoomId::parse(room_id)
+            .map_err(|error| ConnectorError(format!("invalid room id {}: {}", room_id, error)))?;
+        let room = client
+            .get_room(&parsed)
+            .ok_or_else(|| ConnectorError(format!("not joined to room {}", room_id)))?;
+        self.runtime
+            .block_on(room.send(RoomMessageEventContent::text_plain(reply)))
+            .map_err(|error| ConnectorError(format!("matrix send failed: {}", error)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use microclaw_protocol::DeviceAction;
+
+/// Two-phase handshake state for critical `DeviceAction`s (`OtaStart`,
+/// `Unpair`, `EndSession`) that must execute exactly once even across a
+/// reconnect: `Requested` (sent, awaiting the device's first ack) ->
+/// `Received` (device confirmed receipt) -> `Released` (host told the
+/// device to proceed) -> `Complete` (device confirmed execution). Actions
+/// that aren't critical only ever pass through `Requested` before being
+/// acked straight to completion.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryPhase {
+    Requested,
+    Received,
+    Released,
+    Complete,
+}
+
+impl DeliveryPhase {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "requested" => Some(Self::Requested),
+            "received" => Some(Self::Received),
+            "released" => Some(Self::Released),
+            "complete" => Some(Self::Complete),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Requested => "requested",
+            Self::Received => "received",
+            Self::Released => "released",
+            Self::Complete => "complete",
+        }
+    }
+}
+
+/// One outgoing `DeviceAction` awaiting the device's `CommandAck`.
+#[derive(Clone, Debug)]
+pub struct PendingCommand {
+    pub packet_id: u64,
+    pub action: DeviceAction,
+    pub args: serde_json::Value,
+    pub destination: String,
+    pub sent_at_ms: u64,
+    pub attempts: u32,
+    pub phase: DeliveryPhase,
+    pub critical: bool,
+}
+
+/// At-least-once delivery tracking for outbound `DeviceAction` commands,
+/// modeled on MQTT QoS-1/2: each command gets a monotonic packet id and
+/// stays in `pending` with its last-sent timestamp until the device's
+/// `CommandAck` resolves it. `Host` re-sends anything `due_packet_ids`
+/// surfaces, flagged as a duplicate, until it's acked or (for non-critical
+/// actions) abandoned after too many attempts.
+pub struct DeliveryTracker {
+    next_packet_id: u64,
+    pending: HashMap<u64, PendingCommand>,
+}
+
+impl DeliveryTracker {
+    pub fn new() -> Self {
+        Self {
+            next_packet_id: 1,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn track(
+        &mut self,
+        action: DeviceAction,
+        args: serde_json::Value,
+        destination: String,
+        critical: bool,
+        now_ms: u64,
+    ) -> PendingCommand {
+        let packet_id = self.allocate_packet_id();
+        self.track_with_id(packet_id, action, args, destination, critical, now_ms)
+    }
+
+    /// Allocates the next packet id without tracking anything against it
+    /// yet. Used by `Host::dispatch_device_action` to stamp a command held
+    /// in `offline_queue` while the transport is disconnected, so the id
+    /// stays stable from enqueue through the eventual `track_with_id` call.
+    pub fn allocate_packet_id(&mut self) -> u64 {
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        packet_id
+    }
+
+    /// Like `track`, but for a packet id already allocated via
+    /// `allocate_packet_id` rather than one minted on the spot.
+    pub fn track_with_id(
+        &mut self,
+        packet_id: u64,
+        action: DeviceAction,
+        args: serde_json::Value,
+        destination: String,
+        critical: bool,
+        now_ms: u64,
+    ) -> PendingCommand {
+        let entry = PendingCommand {
+            packet_id,
+            action,
+            args,
+            destination,
+            sent_at_ms: now_ms,
+            attempts: 1,
+            phase: DeliveryPhase::Requested,
+            critical,
+        };
+        self.pending.insert(packet_id, entry.clone());
+        entry
+    }
+
+    pub fn get(&self, packet_id: u64) -> Option<&PendingCommand> {
+        self.pending.get(&packet_id)
+    }
+
+    pub fn set_phase(&mut self, packet_id: u64, phase: DeliveryPhase) {
+        if let Some(entry) = self.pending.get_mut(&packet_id) {
+            entry.phase = phase;
+        }
+    }
+
+    pub fn drop_packet(&mut self, packet_id: u64) -> Option<PendingCommand> {
+        self.pending.remove(&packet_id)
+    }
+
+    /// Packet ids that haven't been re-sent or acked within `timeout_ms`.
+    pub fn due_packet_ids(&self, now_ms: u64, timeout_ms: u64) -> Vec<u64> {
+        self.pending
+            .iter()
+            .filter(|(_, entry)| now_ms.saturating_sub(entry.sent_at_ms) >= timeout_ms)
+            .map(|(packet_id, _)| *packet_id)
+            .collect()
+    }
+
+    pub fn record_resend(&mut self, packet_id: u64, now_ms: u64) {
+        if let Some(entry) = self.pending.get_mut(&packet_id) {
+            entry.attempts = entry.attempts.saturating_add(1);
+            entry.sent_at_ms = now_ms;
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.pending.len()
+    }
+}
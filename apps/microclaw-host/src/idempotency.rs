@@ -0,0 +1,41 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Bounded cache of recently-accepted command results, keyed on `(source,
+/// idempotency_key)`, so a transport-level redelivery (reconnect, peer
+/// retry) re-emits the cached `CommandResult` body instead of re-running
+/// the work. Evicts the oldest entry once `capacity` is exceeded -- a
+/// deliberately simple FIFO policy rather than true LRU, since entries here
+/// are short-lived request/response pairs rather than a working set that
+/// benefits from recency-based promotion.
+pub struct IdempotencyCache {
+    capacity: usize,
+    order: VecDeque<(String, String)>,
+    entries: HashMap<(String, String), serde_json::Value>,
+}
+
+impl IdempotencyCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, source: &str, key: &str) -> Option<&serde_json::Value> {
+        self.entries.get(&(source.to_string(), key.to_string()))
+    }
+
+    pub fn insert(&mut self, source: String, key: String, result: serde_json::Value) {
+        let cache_key = (source, key);
+        if !self.entries.contains_key(&cache_key) {
+            self.order.push_back(cache_key.clone());
+            while self.order.len() > self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.entries.insert(cache_key, result);
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::Utc;
 use microclaw_bus::Bus;
 use microclaw_config::HostConfig;
+use microclaw_core::{should_process, Message};
 use microclaw_protocol::{
     DeviceAction, DeviceCommand, Envelope, MessageId, MessageKind, ProtocolError, TransportMessage,
 };
@@ -13,7 +14,7 @@ use microclaw_sandbox::{
     RunSpec, SecretBroker,
 };
 use microclaw_store::Store;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::thread;
@@ -22,6 +23,27 @@ use std::sync::{
     Arc,
 };
 
+pub mod connectors;
+pub mod delivery;
+pub mod idempotency;
+pub mod metrics;
+pub mod offline_queue;
+pub mod scripting;
+pub mod throttle;
+pub mod topic;
+pub mod transport;
+pub mod worker;
+use connectors::Connector;
+use delivery::{DeliveryPhase, DeliveryTracker};
+use idempotency::IdempotencyCache;
+use metrics::MetricsSnapshot;
+use offline_queue::{OfflineQueue, QueuedCommand};
+use scripting::LoadedScript;
+use throttle::Tranquilizer;
+use topic::TopicMatcher;
+use transport::{LoopTransport, Transport};
+use worker::{BusWorker, InboundWorker, QueueWorker, SchedulerWorker, Worker, WorkerState, WorkerStatus};
+
 const DEFAULT_LOOPBACK_DEPTH: usize = 128;
 
 #[derive(Debug)]
@@ -50,7 +72,41 @@ pub struct HostStatus {
     pub work_retries: u64,
     pub work_failed: u64,
     pub commands_rejected: u64,
+    /// Total inbound commands recognized as a redelivery (same source +
+    /// idempotency key already cached) and answered from the cache instead
+    /// of being re-enqueued.
+    pub commands_deduplicated: u64,
+    /// Total outgoing `DeviceAction` commands the device has acked
+    /// (completing their delivery, including the critical two-phase
+    /// handshake).
+    pub commands_acked: u64,
+    /// Current number of `DeviceAction`s held in `offline_queue` awaiting
+    /// the transport to reconnect.
+    pub offline_queue_depth: usize,
+    /// Total `DeviceAction`s evicted or rejected from `offline_queue` to
+    /// stay within `HostConfig::offline_queue_max_len`.
+    pub commands_dropped_offline: u64,
+    /// Current state of the sandbox backend circuit breaker.
+    pub backend_breaker: BackendBreakerState,
+    /// Total times the sandbox backend circuit breaker has tripped open.
+    pub backend_breaker_trips: u64,
+    /// Consecutive failed transport reconnect attempts since the last
+    /// successful connection.
+    pub transport_recoveries: u64,
+    /// Set once `transport_recoveries` reaches
+    /// `HostConfig::transport_reconnect_max_attempts`, so operators can
+    /// alert on a transport that's been down for a while. `Host` keeps
+    /// retrying with capped, jittered backoff regardless.
+    pub transport_reconnect_exhausted: bool,
     pub last_error: Option<String>,
+    /// Sleep (ms) the tranquilizer computed before the most recent tick.
+    pub current_sleep_ms: u64,
+    /// Fraction of the most recent tick-plus-sleep window spent actively
+    /// stepping, in `[0.0, 1.0]`.
+    pub duty_cycle: f64,
+    /// Busy/idle/throttled snapshot of each per-tick subsystem, as of the
+    /// most recent `step()`.
+    pub workers: Vec<WorkerStatus>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -64,93 +120,33 @@ pub struct StepReport {
     pub work_failed: u32,
     pub transport_enqueued: u32,
     pub transport_dropped: u32,
+    pub connector_messages: u32,
 }
 
 #[derive(Debug, Clone)]
 enum Work {
     Command {
         action: DeviceAction,
-        corr_id: Option<String>,
-        group: String,
         source: String,
         args: serde_json::Value,
+        batch_id: String,
+        batch_index: usize,
     },
     ScheduledTask(ScheduledTask),
 }
 
+/// Tracks an in-flight batch of `Work::Command` items enqueued together from
+/// one `OneOrVec<DeviceCommand>` frame, so their per-item outcomes can be
+/// collected into a single aggregated `CommandResult` instead of N separate
+/// frames. Indexed by the batch id `enqueue_commands` generates.
 #[derive(Debug)]
-struct LoopTransport {
-    inbound: VecDeque<TransportMessage>,
-    outbound: VecDeque<TransportMessage>,
-    connected: bool,
-    max_inbound: usize,
-    max_outbound: usize,
-    drops_in: u64,
-    drops_out: u64,
-    inbound_frames: u64,
-    outbound_frames: u64,
-}
-
-impl LoopTransport {
-    fn new(depth: usize) -> Self {
-        Self {
-            inbound: VecDeque::new(),
-            outbound: VecDeque::new(),
-            connected: true,
-            max_inbound: depth,
-            max_outbound: depth,
-            drops_in: 0,
-            drops_out: 0,
-            inbound_frames: 0,
-            outbound_frames: 0,
-        }
-    }
-
-    fn push_inbound(&mut self, frame: TransportMessage) {
-        if self.inbound.len() >= self.max_inbound {
-            self.inbound.pop_front();
-            self.drops_in = self.drops_in.saturating_add(1);
-        }
-        self.inbound.push_back(frame);
-    }
-
-    fn poll_frames(&mut self) -> Vec<TransportMessage> {
-        let mut frames = Vec::with_capacity(self.inbound.len());
-        while let Some(frame) = self.inbound.pop_front() {
-            self.inbound_frames = self.inbound_frames.saturating_add(1);
-            frames.push(frame);
-        }
-        frames
-    }
-
-    fn send_frame(&mut self, frame: TransportMessage) {
-        if self.outbound.len() >= self.max_outbound {
-            self.outbound.pop_front();
-            self.drops_out = self.drops_out.saturating_add(1);
-        }
-        self.outbound.push_back(frame);
-        self.outbound_frames = self.outbound_frames.saturating_add(1);
-    }
-
-    fn take_outbound(&mut self) -> Vec<TransportMessage> {
-        let mut out = Vec::with_capacity(self.outbound.len());
-        while let Some(frame) = self.outbound.pop_front() {
-            out.push(frame);
-        }
-        out
-    }
-
-    fn outbound_depth(&self) -> usize {
-        self.outbound.len()
-    }
-
-    fn connected(&self) -> bool {
-        self.connected
-    }
-
-    fn set_connected(&mut self, connected: bool) {
-        self.connected = connected;
-    }
+struct CommandBatch {
+    source: String,
+    destination: String,
+    corr_id: Option<String>,
+    idempotency_key: Option<String>,
+    remaining: usize,
+    results: Vec<Option<serde_json::Value>>,
 }
 
 fn now_ms() -> u64 {
@@ -160,11 +156,48 @@ fn now_ms() -> u64 {
         .as_millis() as u64
 }
 
+fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Decorrelated-jitter backoff: uniform in `[base_ms, last_delay_ms * 3]`,
+/// capped at `cap_ms`. Used instead of a flat `transport_reconnect_backoff_ms`
+/// delay so many hosts recovering from the same outage don't all retry in
+/// lockstep.
+fn decorrelated_jitter(base_ms: u64, last_delay_ms: u64, cap_ms: u64, seed: u64) -> u64 {
+    let high = last_delay_ms.max(base_ms).saturating_mul(3).max(base_ms);
+    let span = high - base_ms;
+    let jittered = base_ms.saturating_add(splitmix64(seed) % (span + 1));
+    jittered.min(cap_ms)
+}
+
+/// State of the sandbox backend circuit breaker. `Closed` dispatches
+/// normally; `Open` rejects sandbox work until `until_ms` elapses; `HalfOpen`
+/// lets exactly one task through as a probe to decide whether to close again
+/// or re-open with the next backoff step. The transition from `Open` to
+/// `HalfOpen` happens lazily, the moment `run_in_sandbox` is next called
+/// after `until_ms`, rather than on a separate timer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackendBreakerState {
+    Closed,
+    Open { until_ms: u64 },
+    HalfOpen,
+}
+
+impl Default for BackendBreakerState {
+    fn default() -> Self {
+        BackendBreakerState::Closed
+    }
+}
+
 pub struct Host {
     config: HostConfig,
     store: Store,
     bus: Bus,
-    transport: LoopTransport,
+    transport: Box<dyn Transport>,
     queue: ExecutionQueue<Work>,
     mount_policy: MountPolicy,
     egress_policy: EgressPolicy,
@@ -175,14 +208,23 @@ pub struct Host {
     last_scheduler_ms: u64,
     next_health_log_ms: u64,
     next_transport_retry_ms: u64,
-    allowed_sources: HashSet<String>,
+    allowed_sources: TopicMatcher,
     allowed_actions: HashSet<DeviceAction>,
     inflight_task_ids: HashSet<String>,
+    batches: HashMap<String, CommandBatch>,
+    idempotency_cache: IdempotencyCache,
+    delivery: DeliveryTracker,
+    offline_queue: OfflineQueue,
     transport_recoveries: u64,
+    transport_backoff_ms: u64,
     backend_failures: u64,
-    backend_circuit_until: u64,
+    backend_backoff_ms: u64,
+    backend_breaker: BackendBreakerState,
     status: HostStatus,
     started_at: Instant,
+    scripts: Vec<LoadedScript>,
+    connectors: Vec<Box<dyn Connector>>,
+    workers: Vec<Box<dyn Worker>>,
 }
 
 impl Host {
@@ -199,7 +241,7 @@ impl Host {
             Bus::open_in_memory().map_err(|error| HostError(error.to_string()))?
         };
 
-        let allowed_sources = config.allowed_sources.iter().cloned().collect();
+        let allowed_sources = TopicMatcher::new(config.allowed_sources.iter());
         let allowed_actions = config
             .allowed_host_actions
             .iter()
@@ -213,10 +255,19 @@ impl Host {
         let mount_policy = MountPolicy::new(config.mount_allowlist.clone());
         let egress_policy = EgressPolicy::new(config.egress_allowlist.clone());
 
+        let scripts = if let Some(dir) = &config.scripts_dir {
+            scripting::load_scripts_dir(dir).map_err(|error| HostError(error.to_string()))?
+        } else {
+            Vec::new()
+        };
+
+        let connectors = Self::connect_connectors(&config)?;
+        let transport = Self::create_transport(&config)?;
+
         Ok(Self {
             store,
             bus,
-            transport: LoopTransport::new(DEFAULT_LOOPBACK_DEPTH),
+            transport,
             queue,
             mount_policy,
             egress_policy,
@@ -230,34 +281,146 @@ impl Host {
             allowed_sources,
             allowed_actions,
             inflight_task_ids: HashSet::new(),
+            batches: HashMap::new(),
+            idempotency_cache: IdempotencyCache::new(config.idempotency_cache_capacity),
+            delivery: DeliveryTracker::new(),
+            offline_queue: OfflineQueue::new(config.offline_queue_max_len),
             transport_recoveries: 0,
+            transport_backoff_ms: 0,
             backend_failures: 0,
-            backend_circuit_until: 0,
+            backend_backoff_ms: 0,
+            backend_breaker: BackendBreakerState::Closed,
             status: HostStatus {
                 started_at_ms: now_ms(),
                 ..HostStatus::default()
             },
             started_at: Instant::now(),
             config,
+            scripts,
+            connectors,
+            workers: vec![
+                Box::new(InboundWorker),
+                Box::new(BusWorker),
+                Box::new(SchedulerWorker),
+                Box::new(QueueWorker),
+            ],
         })
     }
 
+    #[cfg(feature = "matrix")]
+    fn connect_connectors(config: &HostConfig) -> Result<Vec<Box<dyn Connector>>, HostError> {
+        let mut connectors: Vec<Box<dyn Connector>> = Vec::new();
+        if let Some(matrix_config) = &config.matrix {
+            let mut connector = connectors::matrix::MatrixConnector::new(matrix_config.clone());
+            connector
+                .connect()
+                .map_err(|error| HostError(error.to_string()))?;
+            connectors.push(Box::new(connector));
+        }
+        Ok(connectors)
+    }
+
+    #[cfg(not(feature = "matrix"))]
+    fn connect_connectors(_config: &HostConfig) -> Result<Vec<Box<dyn Connector>>, HostError> {
+        Ok(Vec::new())
+    }
+
+    /// Picks a real-network transport over the in-memory loopback default
+    /// when `HostConfig` opts into one, preferring QUIC over WebSocket when
+    /// both happen to be configured since it's the richer backend (stream
+    /// multiplexing, connection migration).
+    fn create_transport(config: &HostConfig) -> Result<Box<dyn Transport>, HostError> {
+        if let Some(transport) = Self::create_quic_transport(config) {
+            return Ok(transport);
+        }
+        if let Some(transport) = Self::create_websocket_transport(config) {
+            return Ok(transport);
+        }
+        Ok(Box::new(LoopTransport::new(DEFAULT_LOOPBACK_DEPTH)))
+    }
+
+    #[cfg(feature = "quic")]
+    fn create_quic_transport(config: &HostConfig) -> Option<Box<dyn Transport>> {
+        let quic_config = config.quic.as_ref()?;
+        let mut quic_transport = transport::quic::QuicTransport::new(quic_config.clone());
+        quic_transport.set_connected(true);
+        Some(Box::new(quic_transport))
+    }
+
+    #[cfg(not(feature = "quic"))]
+    fn create_quic_transport(_config: &HostConfig) -> Option<Box<dyn Transport>> {
+        None
+    }
+
+    #[cfg(feature = "websocket")]
+    fn create_websocket_transport(config: &HostConfig) -> Option<Box<dyn Transport>> {
+        let ws_config = config.websocket.as_ref()?;
+        let mut ws_transport = transport::websocket::WebSocketTransport::new(ws_config.clone());
+        ws_transport.set_connected(true);
+        Some(Box::new(ws_transport))
+    }
+
+    #[cfg(not(feature = "websocket"))]
+    fn create_websocket_transport(_config: &HostConfig) -> Option<Box<dyn Transport>> {
+        None
+    }
+
+    /// Registers a [`Connector`] that has already been connected, e.g. a
+    /// fake used in tests. `Host::new` does not go through this: it installs
+    /// the connectors `connect_connectors` builds from `HostConfig` directly.
+    pub fn push_connector(&mut self, connector: Box<dyn Connector>) {
+        self.connectors.push(connector);
+    }
+
+    /// Number of `*.lua` scripts loaded from `scripts_dir` at startup.
+    pub fn loaded_script_count(&self) -> usize {
+        self.scripts.len()
+    }
+
+    /// Tries `content` against every loaded script's registered triggers, in
+    /// load order, returning the first matching script's reply.
+    pub fn dispatch_script_trigger(&self, content: &str) -> Result<Option<String>, HostError> {
+        for script in &self.scripts {
+            if let Some(reply) = script
+                .dispatch(content)
+                .map_err(|error| HostError(error.to_string()))?
+            {
+                return Ok(Some(reply));
+            }
+        }
+        Ok(None)
+    }
+
     pub fn run(&mut self, shutdown: Arc<AtomicBool>) -> Result<(), HostError> {
+        let mode = if self.config.websocket.is_some() {
+            "websocket"
+        } else {
+            "loopback"
+        };
         self.emit_status(
             "host_boot",
-            serde_json::json!({ "host_id": self.config.host_id.as_str(), "mode": "loopback" }),
+            serde_json::json!({ "host_id": self.config.host_id.as_str(), "mode": mode }),
+        );
+        let mut tranquilizer = Tranquilizer::new(
+            self.config.tick_interval_ms,
+            self.config.min_tick_ms,
+            self.config.tranquilizer_cap_ms,
+            self.config.tranquility,
         );
-        let mut next_tick = now_ms();
         while !shutdown.load(Ordering::Acquire) {
-            let sample_ms = now_ms();
-            let _ = self.step(sample_ms);
-
-            if sample_ms.saturating_sub(next_tick) < self.config.tick_interval_ms {
-                thread::sleep(Duration::from_millis(
-                    self.config.tick_interval_ms.saturating_sub(sample_ms.saturating_sub(next_tick)),
-                ));
+            let started = Instant::now();
+            let report = self.step(now_ms());
+            let duration_ms = started.elapsed().as_millis() as u64;
+            let items_processed =
+                report.inbound_frames + report.work_dispatched + report.scheduled_count;
+
+            let sleep_ms = tranquilizer.record_step(duration_ms, items_processed);
+            self.status.current_sleep_ms = sleep_ms;
+            self.status.duty_cycle = tranquilizer.duty_cycle();
+
+            if sleep_ms > 0 {
+                thread::sleep(Duration::from_millis(sleep_ms));
             }
-            next_tick = now_ms();
         }
 
         self.emit_status("host_shutdown", serde_json::json!({ "ticks": self.status.ticks }));
@@ -276,7 +439,11 @@ impl Host {
                     "ticks": self.status.ticks,
                     "in_flight_tasks": self.inflight_task_ids.len(),
                     "backend_failures": self.backend_failures,
+                    "backend_breaker": format!("{:?}", self.backend_breaker),
                     "transport_connected": self.transport.connected(),
+                    "transport_recoveries": self.transport_recoveries,
+                    "transport_reconnect_exhausted": self.transport_recoveries
+                        >= self.config.transport_reconnect_max_attempts,
                     "transport_outbound_depth": self.transport.outbound_depth(),
                     "bus_replayed": self.status.bus_replayed,
                 }),
@@ -284,18 +451,31 @@ impl Host {
         }
 
         self.recover_transport_if_needed(now_ms);
-        report.inbound_frames = self.process_inbound(now_ms) as u32;
-        report.bus_frames = self.process_bus() as u32;
-        report.scheduled_count = self.poll_scheduler(now_ms, &mut report) as u32;
-        report.work_dispatched = self.process_queue(now_ms, &mut report) as u32;
+        if self.transport.connected() && !self.offline_queue.is_empty() {
+            self.flush_offline_queue(now_ms);
+        }
+        self.resend_due_commands(now_ms);
+        report.connector_messages = self.process_connectors() as u32;
+
+        let mut workers = std::mem::take(&mut self.workers);
+        let mut worker_statuses = Vec::with_capacity(workers.len());
+        for worker in workers.iter_mut() {
+            let state = worker.run_once(self, &mut report, now_ms);
+            worker_statuses.push(WorkerStatus {
+                name: worker.name(),
+                state,
+            });
+        }
+        self.workers = workers;
 
-        report.transport_enqueued = self.transport.outbound_frames as u32;
-        report.transport_dropped = self.transport.drops_out as u32;
+        report.transport_enqueued = self.transport.outbound_frame_count() as u32;
+        report.transport_dropped = self.transport.dropped_outbound_count() as u32;
         self.status.inbound_frames = self
             .status
             .inbound_frames
             .saturating_add(report.inbound_frames as u64);
         self.status.scheduler_polls = self.status.scheduler_polls.saturating_add(1);
+        self.status.workers = worker_statuses;
         report
     }
 
@@ -314,10 +494,38 @@ impl Host {
             work_retries: self.status.work_retries,
             work_failed: self.status.work_failed,
             commands_rejected: self.status.commands_rejected,
+            commands_deduplicated: self.status.commands_deduplicated,
+            commands_acked: self.status.commands_acked,
+            offline_queue_depth: self.offline_queue.len(),
+            commands_dropped_offline: self.offline_queue.dropped(),
+            backend_breaker: self.backend_breaker,
+            backend_breaker_trips: self.status.backend_breaker_trips,
+            transport_recoveries: self.transport_recoveries,
+            transport_reconnect_exhausted: self.transport_recoveries
+                >= self.config.transport_reconnect_max_attempts,
             last_error: self.status.last_error.clone(),
+            current_sleep_ms: self.status.current_sleep_ms,
+            duty_cycle: self.status.duty_cycle,
+            workers: self.status.workers.clone(),
         }
     }
 
+    /// Renders `HostStatus` plus the host's instantaneous gauges as
+    /// OpenMetrics/Prometheus text exposition, so an operator can scrape
+    /// health without parsing `emit_status`'s JSON log lines.
+    pub fn render_metrics(&self) -> String {
+        metrics::render(&MetricsSnapshot {
+            host_id: self.config.host_id.clone(),
+            container_backend: self.config.container_backend.clone(),
+            status: self.status(),
+            transport_outbound_depth: self.transport.outbound_depth(),
+            backend_circuit_until: match self.backend_breaker {
+                BackendBreakerState::Open { until_ms } => until_ms,
+                BackendBreakerState::Closed | BackendBreakerState::HalfOpen => 0,
+            },
+        })
+    }
+
     pub fn inject_transport_frame(&mut self, frame: TransportMessage) {
         self.transport.push_inbound(frame);
     }
@@ -334,7 +542,55 @@ impl Host {
         &self.store
     }
 
-    fn process_inbound(&mut self, now_ms: u64) -> usize {
+    /// Drains every connector's pending messages, running each one through
+    /// `should_process`/the trigger logic and routing a matched script's
+    /// reply back to the originating room via `send_reply`.
+    fn process_connectors(&mut self) -> usize {
+        let mut processed = 0usize;
+        for index in 0..self.connectors.len() {
+            loop {
+                let next = self.connectors[index].next_message();
+                match next {
+                    Ok(Some(room_message)) => {
+                        processed = processed.saturating_add(1);
+                        if let Err(error) = self.handle_connector_message(index, room_message) {
+                            self.status.last_error = Some(error.to_string());
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(error) => {
+                        self.status.last_error = Some(error.to_string());
+                        break;
+                    }
+                }
+            }
+        }
+        processed
+    }
+
+    fn handle_connector_message(
+        &mut self,
+        connector_index: usize,
+        room_message: connectors::RoomMessage,
+    ) -> Result<(), HostError> {
+        let messages = [Message::new(room_message.message.content.clone())];
+        if !should_process(false, Some(true), &self.config.connector_trigger, &messages) {
+            return Ok(());
+        }
+
+        let Some(reply) = self
+            .dispatch_script_trigger(&room_message.message.content)
+            .map_err(|error| HostError(error.to_string()))?
+        else {
+            return Ok(());
+        };
+
+        self.connectors[connector_index]
+            .send_reply(&room_message.room_id, &reply)
+            .map_err(|error| HostError(error.to_string()))
+    }
+
+    fn process_inbound(&mut self, now_ms: u64, report: &mut StepReport) -> WorkerState {
         let mut count: usize = 0;
         let frames = self.transport.poll_frames();
         for frame in frames {
@@ -346,12 +602,42 @@ impl Host {
             }
 
             if matches!(frame.kind, MessageKind::Command | MessageKind::HostCommand) {
-                if let Some(cmd) = frame.as_device_command() {
-                    self.enqueue_command(now_ms, frame.envelope.source, frame.envelope.session_id, frame.corr_id.clone(), cmd, frame.envelope.device_id);
+                if let Some(commands) = frame.as_device_commands() {
+                    let idempotency_key = frame.nonce.clone().or_else(|| frame.corr_id.clone());
+                    if let Some(key) = &idempotency_key {
+                        if let Some(cached) =
+                            self.idempotency_cache.get(&frame.envelope.source, key).cloned()
+                        {
+                            self.status.commands_deduplicated =
+                                self.status.commands_deduplicated.saturating_add(1);
+                            let replay = self.build_outbound(
+                                &frame.envelope.device_id,
+                                MessageKind::CommandResult,
+                                cached,
+                                frame.corr_id.clone(),
+                            );
+                            self.emit_outbound(replay);
+                            continue;
+                        }
+                    }
+                    self.enqueue_commands(
+                        now_ms,
+                        frame.envelope.source,
+                        frame.envelope.session_id,
+                        frame.corr_id.clone(),
+                        commands,
+                        frame.envelope.device_id,
+                        idempotency_key,
+                    );
                 }
                 continue;
             }
 
+            if matches!(frame.kind, MessageKind::CommandAck) {
+                self.handle_command_ack(&frame);
+                continue;
+            }
+
             if matches!(frame.kind, MessageKind::Heartbeat) {
                 let status = serde_json::json!({
                     "ok": true,
@@ -366,31 +652,38 @@ impl Host {
                 self.emit_outbound(outbound);
             }
         }
-        count
+        report.inbound_frames = count as u32;
+        if count == 0 {
+            WorkerState::Idle
+        } else {
+            WorkerState::Busy(count)
+        }
     }
 
-    fn enqueue_command(
+    /// Enqueues one `Work::Command` per item in `commands`, sharing `corr_id`
+    /// and `group`, and tracks them as a batch so their outcomes land in a
+    /// single aggregated `CommandResult` (keyed by index) instead of one
+    /// frame per item. A single-command frame is just a batch of one.
+    /// `idempotency_key` (the frame's nonce, falling back to its `corr_id`)
+    /// is cached against the aggregated result so a redelivery of the same
+    /// frame can be answered from `idempotency_cache` instead of re-running.
+    fn enqueue_commands(
         &mut self,
         now_ms: u64,
         source: String,
         group: String,
         corr_id: Option<String>,
-        cmd: DeviceCommand,
+        commands: Vec<DeviceCommand>,
         destination: String,
+        idempotency_key: Option<String>,
     ) {
-        if !self.config.allowed_host_actions.is_empty() && !self.allowed_actions.contains(&cmd.action)
-        {
-            self.status.commands_rejected = self.status.commands_rejected.saturating_add(1);
-            let rejected = self.build_outbound(
-                &destination,
-                MessageKind::Error,
-                serde_json::json!({
-                    "error": ProtocolError::new("command_denied", "command is not in host allowlist", false),
-                    "source": source,
-                }),
-                corr_id,
-            );
-            self.emit_outbound(rejected);
+        if commands.is_empty() {
+            let body = serde_json::json!({ "results": Vec::<serde_json::Value>::new() });
+            if let Some(key) = idempotency_key {
+                self.idempotency_cache.insert(source, key, body.clone());
+            }
+            let empty = self.build_outbound(&destination, MessageKind::CommandResult, body, corr_id);
+            self.emit_outbound(empty);
             return;
         }
 
@@ -399,22 +692,84 @@ impl Host {
         } else {
             group
         };
-        let queue_group = work_group.clone();
-
-        self.queue.enqueue(
-            &queue_group,
-            &format!("cmd-{}-{}", destination, now_ms),
-            Work::Command {
-                action: cmd.action,
-                corr_id,
-                group: work_group,
-                source,
-                args: cmd.args,
+        let batch_id = format!("batch-{}-{}", destination, now_ms);
+        self.batches.insert(
+            batch_id.clone(),
+            CommandBatch {
+                source: source.clone(),
+                destination,
+                corr_id: corr_id.clone(),
+                idempotency_key,
+                remaining: commands.len(),
+                results: vec![None; commands.len()],
             },
         );
+
+        for (index, cmd) in commands.into_iter().enumerate() {
+            if !self.config.allowed_host_actions.is_empty()
+                && !self.allowed_actions.contains(&cmd.action)
+            {
+                self.status.commands_rejected = self.status.commands_rejected.saturating_add(1);
+                let outcome = serde_json::json!({
+                    "index": index,
+                    "status": "denied",
+                    "error": ProtocolError::new("command_denied", "command is not in host allowlist", false),
+                    "source": source,
+                });
+                if let Some(aggregated) = self.record_batch_outcome(&batch_id, index, outcome) {
+                    self.emit_outbound(aggregated);
+                }
+                continue;
+            }
+
+            self.queue.enqueue(
+                &work_group,
+                &format!("{}-{}", batch_id, index),
+                Work::Command {
+                    action: cmd.action,
+                    source: source.clone(),
+                    args: cmd.args,
+                    batch_id: batch_id.clone(),
+                    batch_index: index,
+                },
+            );
+        }
     }
 
-    fn process_bus(&mut self) -> usize {
+    /// Records `outcome` at `index` in the named batch; once every item has
+    /// reported in, removes the batch and returns the aggregated
+    /// `CommandResult` frame ready to emit.
+    fn record_batch_outcome(
+        &mut self,
+        batch_id: &str,
+        index: usize,
+        outcome: serde_json::Value,
+    ) -> Option<TransportMessage> {
+        let done = {
+            let batch = self.batches.get_mut(batch_id)?;
+            batch.results[index] = Some(outcome);
+            batch.remaining = batch.remaining.saturating_sub(1);
+            batch.remaining == 0
+        };
+        if !done {
+            return None;
+        }
+
+        let batch = self.batches.remove(batch_id)?;
+        let results: Vec<serde_json::Value> = batch.results.into_iter().flatten().collect();
+        let body = serde_json::json!({ "results": results });
+        if let Some(key) = batch.idempotency_key {
+            self.idempotency_cache.insert(batch.source, key, body.clone());
+        }
+        Some(self.build_outbound(
+            &batch.destination,
+            MessageKind::CommandResult,
+            body,
+            batch.corr_id,
+        ))
+    }
+
+    fn process_bus(&mut self, report: &mut StepReport) -> WorkerState {
         let mut count: usize = 0;
         match self.bus.replay_from_seq(self.last_bus_seq) {
             Ok(events) => {
@@ -428,14 +783,22 @@ impl Host {
                 self.status.last_error = Some(error.to_string());
             }
         }
-        count
+        report.bus_frames = count as u32;
+        if count == 0 {
+            WorkerState::Idle
+        } else {
+            WorkerState::Busy(count)
+        }
     }
 
-    fn poll_scheduler(&mut self, now_ms: u64, report: &mut StepReport) -> usize {
+    fn poll_scheduler(&mut self, now_ms: u64, report: &mut StepReport) -> WorkerState {
         if self.last_scheduler_ms != 0
             && now_ms.saturating_sub(self.last_scheduler_ms) < self.config.scheduler_poll_interval_ms
         {
-            return 0;
+            let until_ms = self
+                .last_scheduler_ms
+                .saturating_add(self.config.scheduler_poll_interval_ms);
+            return WorkerState::Throttled { until_ms };
         }
         self.last_scheduler_ms = now_ms;
 
@@ -444,7 +807,8 @@ impl Host {
             Ok(list) => list,
             Err(error) => {
                 self.status.last_error = Some(error.to_string());
-                return 0;
+                report.scheduled_count = 0;
+                return WorkerState::Idle;
             }
         };
 
@@ -463,17 +827,28 @@ impl Host {
             scheduled = scheduled.saturating_add(1);
         }
 
-        scheduled
+        report.scheduled_count = scheduled as u32;
+        if scheduled == 0 {
+            WorkerState::Idle
+        } else {
+            WorkerState::Busy(scheduled)
+        }
     }
 
-    fn process_queue(&mut self, now_ms: u64, report: &mut StepReport) -> usize {
+    fn process_queue(&mut self, now_ms: u64, report: &mut StepReport) -> WorkerState {
+        if let BackendBreakerState::Open { until_ms } = self.backend_breaker {
+            if now_ms < until_ms {
+                return WorkerState::Throttled { until_ms };
+            }
+        }
+
         let mut processed: usize = 0;
         while let Some(item) = self.queue.next_ready(now_ms) {
             let id = item.id.clone();
             processed = processed.saturating_add(1);
 
             let mut ok = false;
-            match self.run_work(item.payload.clone()) {
+            match self.run_work(item.payload.clone(), now_ms) {
                 Ok(Some(outbound)) => {
                     self.emit_outbound(outbound);
                     report.work_succeeded = report.work_succeeded.saturating_add(1);
@@ -503,31 +878,59 @@ impl Host {
             }
             self.queue.complete(item, ok, now_ms);
         }
-        processed
+        report.work_dispatched = processed as u32;
+        if processed == 0 {
+            WorkerState::Idle
+        } else {
+            WorkerState::Busy(processed)
+        }
     }
 
-    fn run_work(&mut self, work: Work) -> Result<Option<TransportMessage>, HostError> {
+    fn run_work(&mut self, work: Work, now_ms: u64) -> Result<Option<TransportMessage>, HostError> {
         match work {
             Work::Command {
                 action,
-                corr_id,
-                group,
                 source,
                 args,
-            } => self.handle_command(action, corr_id, group, source, args),
+                batch_id,
+                batch_index,
+            } => {
+                let _ = source;
+                let destination = self
+                    .batches
+                    .get(&batch_id)
+                    .map(|batch| batch.destination.clone())
+                    .unwrap_or_default();
+                let outcome = match self.compute_command_result(action.clone(), args, &destination, now_ms) {
+                    Ok(body) => serde_json::json!({
+                        "index": batch_index,
+                        "status": "ok",
+                        "result": body,
+                    }),
+                    Err(error) => serde_json::json!({
+                        "index": batch_index,
+                        "status": "error",
+                        "action": format!("{:?}", action),
+                        "error": error.to_string(),
+                    }),
+                };
+                Ok(self.record_batch_outcome(&batch_id, batch_index, outcome))
+            }
             Work::ScheduledTask(task) => self.handle_scheduled_task(task),
         }
     }
 
-    fn handle_command(
+    /// Computes the result body for one batched command. A per-item error
+    /// here becomes an `"error"` entry in the aggregated `CommandResult`
+    /// rather than a queue retry, since one item in a batch failing
+    /// shouldn't hold up or re-run the rest of the batch.
+    fn compute_command_result(
         &mut self,
         action: DeviceAction,
-        corr_id: Option<String>,
-        group: String,
-        source: String,
         args: serde_json::Value,
-    ) -> Result<Option<TransportMessage>, HostError> {
-        let _ = source;
+        destination: &str,
+        now_ms: u64,
+    ) -> Result<serde_json::Value, HostError> {
         let body = match action {
             DeviceAction::StatusGet => serde_json::json!({
                 "host_id": self.config.host_id.as_str(),
@@ -546,18 +949,167 @@ impl Host {
                     "due_count": tasks.len(),
                 })
             }
-            _ => serde_json::json!({
+            DeviceAction::DiagnosticsSnapshot => serde_json::json!({
                 "status": "accepted",
                 "action": format!("{:?}", action),
                 "args": args,
+                "pending_commands": self.delivery.depth(),
             }),
+            other => self.dispatch_device_action(other, args, destination, now_ms),
         };
-        Ok(Some(self.build_outbound(
-            &group,
-            MessageKind::CommandResult,
-            body,
-            corr_id,
-        )))
+        Ok(body)
+    }
+
+    /// Forwards a device-facing `DeviceAction` to `destination` with an
+    /// at-least-once delivery guarantee: the action gets a monotonic packet
+    /// id and stays in `self.delivery` until the device's `CommandAck`
+    /// resolves it, re-sent (flagged as a duplicate) by
+    /// `resend_due_commands` if no ack arrives in time. `OtaStart`,
+    /// `Unpair`, and `EndSession` are critical -- they additionally require
+    /// the `received` -> `released` -> `complete` handshake driven by
+    /// `handle_command_ack` so they execute exactly once across a
+    /// reconnect.
+    fn dispatch_device_action(
+        &mut self,
+        action: DeviceAction,
+        args: serde_json::Value,
+        destination: &str,
+        now_ms: u64,
+    ) -> serde_json::Value {
+        let critical = is_critical_action(&action);
+
+        if !self.transport.connected() {
+            let packet_id = self.delivery.allocate_packet_id();
+            let accepted = self.offline_queue.push(QueuedCommand {
+                packet_id,
+                action: action.clone(),
+                args,
+                destination: destination.to_string(),
+                critical,
+            });
+            return serde_json::json!({
+                "status": if accepted { "queued" } else { "dropped" },
+                "action": format!("{:?}", action),
+                "packet_id": packet_id,
+                "critical": critical,
+            });
+        }
+
+        let pending = self.delivery.track(
+            action.clone(),
+            args.clone(),
+            destination.to_string(),
+            critical,
+            now_ms,
+        );
+        let payload = serde_json::json!({
+            "packet_id": pending.packet_id,
+            "action": action,
+            "args": args,
+            "phase": pending.phase.as_str(),
+            "duplicate": false,
+        });
+        let frame = self.build_outbound(destination, MessageKind::Command, payload, None);
+        self.emit_outbound(frame);
+        serde_json::json!({
+            "status": "dispatched",
+            "action": format!("{:?}", pending.action),
+            "packet_id": pending.packet_id,
+            "critical": critical,
+        })
+    }
+
+    /// Re-sends any outbound `DeviceAction` still unacked past
+    /// `command_ack_timeout_ms`. Critical actions are re-sent indefinitely;
+    /// non-critical ones are abandoned after `command_ack_max_resends`
+    /// re-sends so a device that will never reply can't grow the in-flight
+    /// set forever.
+    fn resend_due_commands(&mut self, now_ms: u64) {
+        let timeout_ms = self.config.command_ack_timeout_ms.max(200);
+        for packet_id in self.delivery.due_packet_ids(now_ms, timeout_ms) {
+            let Some(pending) = self.delivery.get(packet_id).cloned() else {
+                continue;
+            };
+            if !pending.critical && pending.attempts > self.config.command_ack_max_resends {
+                self.delivery.drop_packet(packet_id);
+                self.status.last_error = Some(format!(
+                    "command_ack_timeout: packet {} ({:?}) abandoned after {} attempts",
+                    packet_id, pending.action, pending.attempts
+                ));
+                continue;
+            }
+
+            self.delivery.record_resend(packet_id, now_ms);
+            let payload = serde_json::json!({
+                "packet_id": packet_id,
+                "action": pending.action,
+                "args": pending.args,
+                "phase": pending.phase.as_str(),
+                "duplicate": true,
+            });
+            let frame = self.build_outbound(&pending.destination, MessageKind::Command, payload, None);
+            self.emit_outbound(frame);
+        }
+    }
+
+    /// Drains `offline_queue` in FIFO order, dispatching each held command
+    /// through the normal delivery path (using its pre-allocated packet
+    /// id) so it's tracked for ack/resend exactly like anything sent live.
+    /// `step` only calls this once the transport reports connected again.
+    fn flush_offline_queue(&mut self, now_ms: u64) {
+        for queued in self.offline_queue.drain() {
+            let pending = self.delivery.track_with_id(
+                queued.packet_id,
+                queued.action,
+                queued.args,
+                queued.destination,
+                queued.critical,
+                now_ms,
+            );
+            let payload = serde_json::json!({
+                "packet_id": pending.packet_id,
+                "action": pending.action,
+                "args": pending.args,
+                "phase": pending.phase.as_str(),
+                "duplicate": false,
+            });
+            let frame = self.build_outbound(&pending.destination, MessageKind::Command, payload, None);
+            self.emit_outbound(frame);
+        }
+    }
+
+    /// Handles a device's `CommandAck` for a previously-dispatched
+    /// `DeviceAction`. A critical action's `received` ack triggers the
+    /// `release` half of the two-phase handshake instead of completing the
+    /// delivery outright; every other ack completes it.
+    fn handle_command_ack(&mut self, frame: &TransportMessage) {
+        let Some(packet_id) = frame.payload.get("packet_id").and_then(|v| v.as_u64()) else {
+            return;
+        };
+        let Some(pending) = self.delivery.get(packet_id).cloned() else {
+            return;
+        };
+        let phase = frame
+            .payload
+            .get("phase")
+            .and_then(|v| v.as_str())
+            .and_then(DeliveryPhase::parse);
+
+        if pending.critical && phase == Some(DeliveryPhase::Received) {
+            self.delivery.set_phase(packet_id, DeliveryPhase::Released);
+            let release = serde_json::json!({
+                "packet_id": packet_id,
+                "action": pending.action,
+                "phase": DeliveryPhase::Released.as_str(),
+            });
+            let frame_out = self.build_outbound(&pending.destination, MessageKind::Command, release, None);
+            self.emit_outbound(frame_out);
+            return;
+        }
+
+        if self.delivery.drop_packet(packet_id).is_some() {
+            self.status.commands_acked = self.status.commands_acked.saturating_add(1);
+        }
     }
 
     fn handle_scheduled_task(&mut self, task: ScheduledTask) -> Result<Option<TransportMessage>, HostError> {
@@ -607,8 +1159,14 @@ impl Host {
         }
 
         let now = now_ms();
-        if now < self.backend_circuit_until {
-            return Err(HostError("sandbox backend circuit breaker active".to_string()));
+        match self.backend_breaker {
+            BackendBreakerState::Open { until_ms } if now < until_ms => {
+                return Err(HostError("sandbox backend circuit breaker open".to_string()));
+            }
+            BackendBreakerState::Open { .. } => {
+                self.backend_breaker = BackendBreakerState::HalfOpen;
+            }
+            BackendBreakerState::Closed | BackendBreakerState::HalfOpen => {}
         }
 
         let mut spec = RunSpec::new(
@@ -641,14 +1199,22 @@ impl Host {
         match status {
             Ok(result) => {
                 self.backend_failures = 0;
-                self.backend_circuit_until = 0;
+                self.backend_backoff_ms = 0;
+                self.backend_breaker = BackendBreakerState::Closed;
                 Ok(result)
             }
             Err(error) => {
                 self.backend_failures = self.backend_failures.saturating_add(1);
-                let backoff_ms = (1_u64 << self.backend_failures.min(12))
-                    .saturating_mul(self.config.queue_retry_backoff_ms);
-                self.backend_circuit_until = now.saturating_add(backoff_ms.min(30_000));
+                let base = self.config.queue_retry_backoff_ms.max(200);
+                let cap = self.config.backend_breaker_backoff_cap_ms.max(base);
+                let seed = now.wrapping_add(self.backend_failures);
+                self.backend_backoff_ms =
+                    decorrelated_jitter(base, self.backend_backoff_ms, cap, seed);
+                self.backend_breaker = BackendBreakerState::Open {
+                    until_ms: now.saturating_add(self.backend_backoff_ms),
+                };
+                self.status.backend_breaker_trips =
+                    self.status.backend_breaker_trips.saturating_add(1);
                 Err(HostError(format!("sandbox_run_failed: {}", error)))
             }
         }
@@ -705,26 +1271,36 @@ impl Host {
             return;
         }
 
-        self.transport_recoveries = self.transport_recoveries.saturating_add(1);
-        if self.transport_recoveries > 0 && self.transport_recoveries < 2 {
-            let backoff = self.config.transport_reconnect_backoff_ms.max(200);
-            self.next_transport_retry_ms = now_ms.saturating_add(backoff);
+        self.transport.set_connected(true);
+        if self.transport.connected() {
+            self.transport_recoveries = 0;
+            self.transport_backoff_ms = 0;
             return;
         }
 
-        self.transport_recoveries = 0;
-        self.transport.set_connected(true);
+        self.transport_recoveries = self.transport_recoveries.saturating_add(1);
+        let base = self.config.transport_reconnect_backoff_ms.max(200);
+        let cap = self.config.transport_reconnect_backoff_cap_ms.max(base);
+        let seed = now_ms.wrapping_add(self.transport_recoveries);
+        self.transport_backoff_ms = decorrelated_jitter(base, self.transport_backoff_ms, cap, seed);
+        self.next_transport_retry_ms = now_ms.saturating_add(self.transport_backoff_ms);
     }
 
     fn is_source_allowed(&self, source: &str) -> bool {
-        if self.allowed_sources.is_empty() {
-            true
-        } else {
-            self.allowed_sources.contains(source)
-        }
+        self.allowed_sources.is_empty() || self.allowed_sources.matches(source)
     }
 }
 
+/// Actions that must execute exactly once even across a reconnect, so a
+/// dispatched command for one of these drives the two-phase
+/// request/received/released/complete handshake instead of a plain ack.
+fn is_critical_action(action: &DeviceAction) -> bool {
+    matches!(
+        action,
+        DeviceAction::OtaStart | DeviceAction::Unpair | DeviceAction::EndSession
+    )
+}
+
 fn parse_host_action(raw: &str) -> Option<DeviceAction> {
     match raw.to_ascii_lowercase().as_str() {
         "reconnect" => Some(DeviceAction::Reconnect),
@@ -0,0 +1,212 @@
+use crate::HostStatus;
+
+/// Point-in-time counters/gauges `Host::render_metrics` exposes. Counters
+/// come straight from `HostStatus`; the gauges below live outside it because
+/// they reflect instantaneous state (queue depth, circuit-breaker deadline)
+/// rather than a running total.
+pub struct MetricsSnapshot {
+    pub host_id: String,
+    pub container_backend: String,
+    pub status: HostStatus,
+    pub transport_outbound_depth: usize,
+    pub backend_circuit_until: u64,
+}
+
+/// Escapes `value` per the OpenMetrics label-value grammar: backslash,
+/// double quote, and newline each need a backslash escape.
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn push_metric(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    kind: &str,
+    labels: &str,
+    value: impl std::fmt::Display,
+) {
+    out.push_str(&format!("# HELP {name} {help}\n"));
+    out.push_str(&format!("# TYPE {name} {kind}\n"));
+    out.push_str(&format!("{name}{{{labels}}} {value}\n"));
+}
+
+/// Renders `snapshot` as OpenMetrics/Prometheus text exposition: one
+/// counter per cumulative `HostStatus` field (suffixed `_total`), plus
+/// gauges for the instantaneous fields the request asked for.
+pub fn render(snapshot: &MetricsSnapshot) -> String {
+    let labels = format!(
+        "host_id=\"{}\",container_backend=\"{}\"",
+        escape_label_value(&snapshot.host_id),
+        escape_label_value(&snapshot.container_backend),
+    );
+
+    let mut out = String::new();
+
+    push_metric(
+        &mut out,
+        "nanoclaw_ticks_total",
+        "Total Host::step ticks run.",
+        "counter",
+        &labels,
+        snapshot.status.ticks,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_inbound_frames_total",
+        "Total transport frames received.",
+        "counter",
+        &labels,
+        snapshot.status.inbound_frames,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_outbound_frames_total",
+        "Total transport frames sent.",
+        "counter",
+        &labels,
+        snapshot.status.outbound_frames,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_inbound_filtered_total",
+        "Total inbound frames filtered (unauthorized source or policy).",
+        "counter",
+        &labels,
+        snapshot.status.inbound_filtered,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_bus_replayed_total",
+        "Total frames replayed from the local bus.",
+        "counter",
+        &labels,
+        snapshot.status.bus_replayed,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_scheduler_polls_total",
+        "Total Host::step passes, counted whether or not the scheduler was due to poll.",
+        "counter",
+        &labels,
+        snapshot.status.scheduler_polls,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_scheduled_enqueued_total",
+        "Total scheduled tasks enqueued for execution.",
+        "counter",
+        &labels,
+        snapshot.status.scheduled_enqueued,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_work_completed_total",
+        "Total queued work items that completed successfully.",
+        "counter",
+        &labels,
+        snapshot.status.work_completed,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_work_retries_total",
+        "Total queued work items retried after failure.",
+        "counter",
+        &labels,
+        snapshot.status.work_retries,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_work_failed_total",
+        "Total failed work item runs, including ones that were later retried.",
+        "counter",
+        &labels,
+        snapshot.status.work_failed,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_commands_rejected_total",
+        "Total inbound commands rejected by policy.",
+        "counter",
+        &labels,
+        snapshot.status.commands_rejected,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_commands_deduplicated_total",
+        "Total inbound commands answered from the idempotency cache instead of being re-run.",
+        "counter",
+        &labels,
+        snapshot.status.commands_deduplicated,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_commands_acked_total",
+        "Total outgoing device commands acked by the device.",
+        "counter",
+        &labels,
+        snapshot.status.commands_acked,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_commands_dropped_offline_total",
+        "Total outgoing device commands evicted or rejected from the offline queue.",
+        "counter",
+        &labels,
+        snapshot.status.commands_dropped_offline,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_backend_breaker_trips_total",
+        "Total times the sandbox backend circuit breaker has tripped open.",
+        "counter",
+        &labels,
+        snapshot.status.backend_breaker_trips,
+    );
+
+    push_metric(
+        &mut out,
+        "nanoclaw_offline_queue_depth",
+        "DeviceAction commands currently held for delivery once the transport reconnects.",
+        "gauge",
+        &labels,
+        snapshot.status.offline_queue_depth,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_in_flight",
+        "Work items currently in flight.",
+        "gauge",
+        &labels,
+        snapshot.status.in_flight,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_transport_outbound_depth",
+        "Frames currently queued for outbound delivery.",
+        "gauge",
+        &labels,
+        snapshot.transport_outbound_depth,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_backend_circuit_until",
+        "Unix epoch ms until which the sandbox backend circuit breaker stays open (0 if closed).",
+        "gauge",
+        &labels,
+        snapshot.backend_circuit_until,
+    );
+    push_metric(
+        &mut out,
+        "nanoclaw_duty_cycle",
+        "Fraction of the most recent tick-plus-sleep window spent actively stepping.",
+        "gauge",
+        &labels,
+        snapshot.status.duty_cycle,
+    );
+
+    out
+}
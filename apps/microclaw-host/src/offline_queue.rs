@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+
+use microclaw_protocol::DeviceAction;
+
+/// One `DeviceAction` dispatch that couldn't be sent immediately because
+/// `transport` reported disconnected, held here so it survives the
+/// reconnect window tracked by `next_transport_retry_ms` instead of being
+/// silently lost. Modeled on MQTT clean-session=false queuing: the packet
+/// id is assigned up front so it stays stable from enqueue through the
+/// eventual send.
+#[derive(Clone, Debug)]
+pub struct QueuedCommand {
+    pub packet_id: u64,
+    pub action: DeviceAction,
+    pub args: serde_json::Value,
+    pub destination: String,
+    pub critical: bool,
+}
+
+/// Bounded FIFO of [`QueuedCommand`]s held while the transport is
+/// disconnected, drained in order by `Host::flush_offline_queue` once it
+/// reconnects. At capacity, the oldest non-critical entry is evicted to
+/// make room for a new one; critical actions (`OtaStart`, `Unpair`,
+/// `EndSession`) are only evicted once no non-critical entry remains to
+/// sacrifice instead, so they're never silently dropped while a lower
+/// priority command could be bumped.
+pub struct OfflineQueue {
+    entries: VecDeque<QueuedCommand>,
+    max_len: usize,
+    dropped: u64,
+}
+
+impl OfflineQueue {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_len: max_len.max(1),
+            dropped: 0,
+        }
+    }
+
+    /// Appends `entry`, evicting an older one first if already full.
+    /// Returns `false` if `entry` itself had to be dropped to stay within
+    /// `max_len`, which only happens when every existing entry is critical
+    /// and `entry` is not.
+    pub fn push(&mut self, entry: QueuedCommand) -> bool {
+        if self.entries.len() >= self.max_len {
+            if let Some(index) = self.entries.iter().position(|queued| !queued.critical) {
+                self.entries.remove(index);
+                self.dropped = self.dropped.saturating_add(1);
+            } else if entry.critical {
+                self.entries.pop_front();
+                self.dropped = self.dropped.saturating_add(1);
+            } else {
+                self.dropped = self.dropped.saturating_add(1);
+                return false;
+            }
+        }
+        self.entries.push_back(entry);
+        true
+    }
+
+    /// Drains every queued command in FIFO order, leaving the queue empty.
+    pub fn drain(&mut self) -> Vec<QueuedCommand> {
+        self.entries.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total entries evicted or rejected to stay within `max_len`.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(packet_id: u64, critical: bool) -> QueuedCommand {
+        QueuedCommand {
+            packet_id,
+            action: DeviceAction::SyncNow,
+            args: serde_json::json!({}),
+            destination: "device-a".to_string(),
+            critical,
+        }
+    }
+
+    #[test]
+    fn drains_in_fifo_order() {
+        let mut queue = OfflineQueue::new(8);
+        queue.push(command(1, false));
+        queue.push(command(2, false));
+        queue.push(command(3, false));
+
+        let drained: Vec<u64> = queue.drain().iter().map(|entry| entry.packet_id).collect();
+        assert_eq!(drained, vec![1, 2, 3]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn evicts_oldest_non_critical_when_full() {
+        let mut queue = OfflineQueue::new(2);
+        queue.push(command(1, false));
+        queue.push(command(2, true));
+        assert!(queue.push(command(3, false)));
+
+        let drained: Vec<u64> = queue.drain().iter().map(|entry| entry.packet_id).collect();
+        assert_eq!(drained, vec![2, 3]);
+        assert_eq!(queue.dropped(), 1);
+    }
+
+    #[test]
+    fn critical_entry_evicts_oldest_critical_once_queue_is_all_critical() {
+        let mut queue = OfflineQueue::new(2);
+        queue.push(command(1, true));
+        queue.push(command(2, true));
+        assert!(queue.push(command(3, true)));
+
+        let drained: Vec<u64> = queue.drain().iter().map(|entry| entry.packet_id).collect();
+        assert_eq!(drained, vec![2, 3]);
+    }
+
+    #[test]
+    fn non_critical_entry_is_rejected_when_queue_is_all_critical() {
+        let mut queue = OfflineQueue::new(2);
+        queue.push(command(1, true));
+        queue.push(command(2, true));
+        assert!(!queue.push(command(3, false)));
+
+        let drained: Vec<u64> = queue.drain().iter().map(|entry| entry.packet_id).collect();
+        assert_eq!(drained, vec![1, 2]);
+        assert_eq!(queue.dropped(), 1);
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use mlua::Lua;
+use regex::Regex;
+
+use microclaw_core::create_trigger_pattern;
+
+#[derive(Debug)]
+pub struct ScriptError(pub String);
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// One trigger a script registered via `register_trigger(pattern, callback)`:
+/// `pattern` was fed through [`create_trigger_pattern`], the same way the
+/// compiled-in trigger machinery builds its patterns, so a script trigger
+/// behaves identically to a `requires_trigger` check on the message text.
+struct ScriptTrigger {
+    pattern: Regex,
+    callback_key: mlua::RegistryKey,
+}
+
+/// One `*.lua` file loaded from `scripts_dir`, holding its own Lua state so
+/// a script can't reach into another script's globals or registered
+/// triggers.
+pub struct LoadedScript {
+    name: String,
+    lua: Lua,
+    triggers: Vec<ScriptTrigger>,
+}
+
+impl LoadedScript {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn trigger_count(&self) -> usize {
+        self.triggers.len()
+    }
+
+    /// Tries every trigger this script registered, in registration order,
+    /// and calls the first match's callback with the message text and a
+    /// table of its named capture groups. The callback's return string
+    /// becomes the bot reply.
+    pub fn dispatch(&self, content: &str) -> Result<Option<String>, ScriptError> {
+        for trigger in &self.triggers {
+            let Some(captures) = trigger.pattern.captures(content) else {
+                continue;
+            };
+
+            let callback: mlua::Function = self
+                .lua
+                .registry_value(&trigger.callback_key)
+                .map_err(|error| ScriptError(format!("{}: {}", self.name, error)))?;
+
+            let table = self
+                .lua
+                .create_table()
+                .map_err(|error| ScriptError(format!("{}: {}", self.name, error)))?;
+            for capture_name in trigger.pattern.capture_names().flatten() {
+                if let Some(value) = captures.name(capture_name) {
+                    table
+                        .set(capture_name, value.as_str())
+                        .map_err(|error| ScriptError(format!("{}: {}", self.name, error)))?;
+                }
+            }
+
+            let reply: String = callback
+                .call((content.to_string(), table))
+                .map_err(|error| ScriptError(format!("{}: {}", self.name, error)))?;
+            return Ok(Some(reply));
+        }
+        Ok(None)
+    }
+}
+
+/// Scans `dir` for `*.lua` files (sorted by filename, so load order is
+/// deterministic) and compiles each into its own sandboxed [`LoadedScript`]:
+/// the Lua state keeps the standard libraries `Lua::new` loads, minus the
+/// file, process and module-loading globals (`io`, `os`, `package`,
+/// `require`, `dofile`, `loadfile`, `load`). The one global the host adds is
+/// `register_trigger(pattern, callback)`, which hands the pattern to
+/// [`create_trigger_pattern`] and stashes the callback for
+/// [`LoadedScript::dispatch`] to invoke later.
+/// Returns the first compile/execution error encountered so the caller
+/// (typically `Host::new`) can surface it as an initialization failure
+/// instead of silently running with a broken script.
+pub fn load_scripts_dir(dir: &str) -> Result<Vec<LoadedScript>, ScriptError> {
+    let entries = fs::read_dir(dir)
+        .map_err(|error| ScriptError(format!("scripts_dir {}: {}", dir, error)))?;
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+        .collect();
+    paths.sort();
+
+    paths.iter().map(|path| load_script(path)).collect()
+}
+
+fn load_script(path: &Path) -> Result<LoadedScript, ScriptError> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let source =
+        fs::read_to_string(path).map_err(|error| ScriptError(format!("{}: {}", name, error)))?;
+
+    let lua = Lua::new();
+    for unsafe_global in ["io", "os", "package", "require", "dofile", "loadfile", "load"] {
+        lua.globals()
+            .set(unsafe_global, mlua::Value::Nil)
+            .map_err(|error| ScriptError(format!("{}: {}", name, error)))?;
+    }
+    let triggers: Arc<Mutex<Vec<ScriptTrigger>>> = Arc::new(Mutex::new(Vec::new()));
+
+    let register_trigger = {
+        let triggers = triggers.clone();
+        lua.create_function(move |lua_ctx, (pattern, callback): (String, mlua::Function)| {
+            let regex = create_trigger_pattern(&pattern);
+            let callback_key = lua_ctx.create_registry_value(callback)?;
+            triggers.lock().unwrap().push(ScriptTrigger {
+                pattern: regex,
+                callback_key,
+            });
+            Ok(())
+        })
+    }
+    .map_err(|error| ScriptError(format!("{}: {}", name, error)))?;
+
+    lua.globals()
+        .set("register_trigger", register_trigger)
+        .map_err(|error| ScriptError(format!("{}: {}", name, error)))?;
+
+    lua.load(&source)
+        .set_name(&name)
+        .exec()
+        .map_err(|error| ScriptError(format!("{}: {}", name, error)))?;
+
+    let triggers = Arc::try_unwrap(triggers)
+        .map_err(|_| ScriptError(format!("{}: trigger registry still in use", name)))?
+        .into_inner()
+        .map_err(|_| ScriptError(format!("{}: trigger registry poisoned", name)))?;
+
+    Ok(LoadedScript {
+        name,
+        lua,
+        triggers,
+    })
+}
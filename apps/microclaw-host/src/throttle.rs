@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+
+const WINDOW_SIZE: usize = 20;
+
+#[derive(Clone, Copy, Debug, Default)]
+struct WindowEntry {
+    duration_ms: u64,
+    items_processed: u32,
+}
+
+/// Proportional duty-cycle controller for `Host::run`'s tick sleep.
+/// Maintains a sliding window of the last [`WINDOW_SIZE`] steps'
+/// `(duration, items_processed)`; when every step in the window processed
+/// nothing, the sleep backs off exponentially from `tick_interval_ms` up to
+/// `cap_ms` so an idle host burns near-zero CPU. Otherwise the sleep is
+/// `last_step_duration * tranquility`, clamped to `[min_tick_ms,
+/// tick_interval_ms]`, so a busier host stays responsive without pinning a
+/// core. `tranquility` is the knob operators tune to trade latency for CPU:
+/// the default of `1.0` targets a roughly 50% duty cycle.
+pub struct Tranquilizer {
+    window: VecDeque<WindowEntry>,
+    tick_interval_ms: u64,
+    min_tick_ms: u64,
+    cap_ms: u64,
+    tranquility: f64,
+    idle_backoff_ms: u64,
+    current_sleep_ms: u64,
+    duty_cycle: f64,
+}
+
+impl Tranquilizer {
+    pub fn new(tick_interval_ms: u64, min_tick_ms: u64, cap_ms: u64, tranquility: f64) -> Self {
+        let tick_interval_ms = tick_interval_ms.max(1);
+        Self {
+            window: VecDeque::with_capacity(WINDOW_SIZE),
+            tick_interval_ms,
+            min_tick_ms: min_tick_ms.clamp(1, tick_interval_ms),
+            cap_ms: cap_ms.max(tick_interval_ms),
+            tranquility: tranquility.max(0.0),
+            idle_backoff_ms: tick_interval_ms,
+            current_sleep_ms: tick_interval_ms,
+            duty_cycle: 0.0,
+        }
+    }
+
+    /// Records one step's outcome and returns the sleep to use before the
+    /// next tick.
+    pub fn record_step(&mut self, duration_ms: u64, items_processed: u32) -> u64 {
+        if self.window.len() == WINDOW_SIZE {
+            self.window.pop_front();
+        }
+        self.window.push_back(WindowEntry {
+            duration_ms,
+            items_processed,
+        });
+
+        let window_idle = self.window.iter().all(|entry| entry.items_processed == 0);
+
+        let sleep_ms = if window_idle {
+            let value = self.idle_backoff_ms;
+            self.idle_backoff_ms = value.saturating_mul(2).min(self.cap_ms);
+            value
+        } else {
+            self.idle_backoff_ms = self.tick_interval_ms;
+            let proportional = (duration_ms as f64 * self.tranquility).round() as u64;
+            proportional.clamp(self.min_tick_ms, self.tick_interval_ms)
+        };
+
+        self.current_sleep_ms = sleep_ms;
+        let busy_window_ms = duration_ms.saturating_add(sleep_ms);
+        self.duty_cycle = if busy_window_ms == 0 {
+            0.0
+        } else {
+            duration_ms as f64 / busy_window_ms as f64
+        };
+        sleep_ms
+    }
+
+    pub fn current_sleep_ms(&self) -> u64 {
+        self.current_sleep_ms
+    }
+
+    pub fn duty_cycle(&self) -> f64 {
+        self.duty_cycle
+    }
+}
@@ -0,0 +1,127 @@
+//! MQTT-style hierarchical topic filters for `Host::is_source_allowed`, so
+//! operators can write `fleet/+/commands` or `room1/#` instead of
+//! enumerating every concrete source id.
+
+/// One `/`-separated segment of a parsed filter pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    /// A literal level that must match exactly.
+    Literal(String),
+    /// `+` -- matches exactly one level, whatever it is.
+    SingleLevel,
+    /// `#` -- matches every remaining level (including zero). Only
+    /// meaningful as the last segment; elsewhere it's treated as a literal
+    /// `#` level, since a misplaced one has no sane multi-level meaning.
+    MultiLevel,
+}
+
+/// One configured pattern, parsed once so matching a source is just a walk
+/// over pre-split segments rather than re-parsing the pattern every call.
+#[derive(Clone, Debug)]
+pub struct TopicFilter {
+    segments: Vec<Segment>,
+}
+
+impl TopicFilter {
+    pub fn parse(pattern: &str) -> Self {
+        let levels: Vec<&str> = pattern.split('/').collect();
+        let last = levels.len().saturating_sub(1);
+        let segments = levels
+            .into_iter()
+            .enumerate()
+            .map(|(index, level)| match level {
+                "+" => Segment::SingleLevel,
+                "#" if index == last => Segment::MultiLevel,
+                other => Segment::Literal(other.to_string()),
+            })
+            .collect();
+        Self { segments }
+    }
+
+    pub fn matches(&self, source: &str) -> bool {
+        let levels: Vec<&str> = source.split('/').collect();
+        Self::matches_levels(&self.segments, &levels)
+    }
+
+    fn matches_levels(segments: &[Segment], levels: &[&str]) -> bool {
+        match segments.first() {
+            None => levels.is_empty(),
+            Some(Segment::MultiLevel) => true,
+            Some(segment) => match levels.first() {
+                None => false,
+                Some(level) => {
+                    let level_matches = match segment {
+                        Segment::Literal(literal) => literal == level,
+                        Segment::SingleLevel => true,
+                        Segment::MultiLevel => true,
+                    };
+                    level_matches && Self::matches_levels(&segments[1..], &levels[1..])
+                }
+            },
+        }
+    }
+}
+
+/// A set of parsed [`TopicFilter`]s, one per configured
+/// `allowed_sources` entry. Empty means allow-all, mirroring the flat
+/// allowlist's prior behavior.
+#[derive(Clone, Debug, Default)]
+pub struct TopicMatcher {
+    filters: Vec<TopicFilter>,
+}
+
+impl TopicMatcher {
+    pub fn new(patterns: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            filters: patterns
+                .into_iter()
+                .map(|pattern| TopicFilter::parse(pattern.as_ref()))
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    pub fn matches(&self, source: &str) -> bool {
+        self.filters.is_empty() || self.filters.iter().any(|filter| filter.matches(source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TopicMatcher;
+
+    #[test]
+    fn plus_matches_exactly_one_level() {
+        let matcher = TopicMatcher::new(["fleet/+/commands"]);
+        assert!(matcher.matches("fleet/device-1/commands"));
+        assert!(!matcher.matches("fleet/device-1/extra/commands"));
+        assert!(!matcher.matches("fleet/commands"));
+    }
+
+    #[test]
+    fn hash_matches_trailing_levels_including_none() {
+        let matcher = TopicMatcher::new(["room1/#"]);
+        assert!(matcher.matches("room1"));
+        assert!(matcher.matches("room1/sensors"));
+        assert!(matcher.matches("room1/sensors/temp"));
+        assert!(!matcher.matches("room2"));
+    }
+
+    #[test]
+    fn empty_matcher_allows_everything() {
+        let matcher = TopicMatcher::new(Vec::<String>::new());
+        assert!(matcher.is_empty());
+        assert!(matcher.matches("anything/at/all"));
+    }
+
+    #[test]
+    fn literal_pattern_requires_exact_match() {
+        let matcher = TopicMatcher::new(["device-1"]);
+        assert!(matcher.matches("device-1"));
+        assert!(!matcher.matches("device-2"));
+        assert!(!matcher.matches("device-1/extra"));
+    }
+}
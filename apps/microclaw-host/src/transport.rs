@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+use microclaw_protocol::TransportMessage;
+
+#[cfg(feature = "quic")]
+pub mod quic;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+/// The wire `Host` drives its tick loop over: something that can hand back
+/// newly-arrived frames, queue frames for delivery, and report whether it's
+/// currently reachable. `LoopTransport` below is the in-memory default used
+/// for tests and standalone/loopback mode; `websocket::WebSocketTransport`
+/// and `quic::QuicTransport` are the real-network implementations, selected
+/// at runtime by `Host::create_transport` based on `HostConfig`.
+pub trait Transport {
+    /// Drains and returns every inbound frame received since the last call.
+    fn poll_frames(&mut self) -> Vec<TransportMessage>;
+    /// Queues `frame` for delivery, dropping the oldest queued frame first if
+    /// the implementation is already at its outbound depth limit.
+    fn send_frame(&mut self, frame: TransportMessage);
+    /// Drains and returns whatever is still queued in `send_frame`'s backlog.
+    /// A transport that flushes eagerly (e.g. over a live socket) will
+    /// usually have nothing left to return here.
+    fn take_outbound(&mut self) -> Vec<TransportMessage>;
+    /// Feeds `frame` into the inbound queue `poll_frames` later drains.
+    /// `Host::inject_transport_frame` uses this to inject test/loopback
+    /// traffic uniformly across whichever transport is installed.
+    fn push_inbound(&mut self, frame: TransportMessage);
+    fn connected(&self) -> bool;
+    /// Flips the connected flag. A live transport treats a flip to `true` as
+    /// a cue to (re)establish its underlying connection.
+    fn set_connected(&mut self, connected: bool);
+    fn outbound_depth(&self) -> usize;
+    fn outbound_frame_count(&self) -> u64;
+    fn dropped_outbound_count(&self) -> u64;
+}
+
+/// In-memory loopback transport: frames injected via `push_inbound` (e.g. by
+/// tests) are handed back from `poll_frames`, and frames sent via
+/// `send_frame` just sit in a queue for `take_outbound` to drain. No bytes
+/// ever cross a wire.
+#[derive(Debug)]
+pub struct LoopTransport {
+    inbound: VecDeque<TransportMessage>,
+    outbound: VecDeque<TransportMessage>,
+    connected: bool,
+    max_inbound: usize,
+    max_outbound: usize,
+    drops_in: u64,
+    drops_out: u64,
+    inbound_frames: u64,
+    outbound_frames: u64,
+}
+
+impl LoopTransport {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            inbound: VecDeque::new(),
+            outbound: VecDeque::new(),
+            connected: true,
+            max_inbound: depth,
+            max_outbound: depth,
+            drops_in: 0,
+            drops_out: 0,
+            inbound_frames: 0,
+            outbound_frames: 0,
+        }
+    }
+}
+
+impl Transport for LoopTransport {
+    fn push_inbound(&mut self, frame: TransportMessage) {
+        if self.inbound.len() >= self.max_inbound {
+            self.inbound.pop_front();
+            self.drops_in = self.drops_in.saturating_add(1);
+        }
+        self.inbound.push_back(frame);
+    }
+
+    fn poll_frames(&mut self) -> Vec<TransportMessage> {
+        let mut frames = Vec::with_capacity(self.inbound.len());
+        while let Some(frame) = self.inbound.pop_front() {
+            self.inbound_frames = self.inbound_frames.saturating_add(1);
+            frames.push(frame);
+        }
+        frames
+    }
+
+    fn send_frame(&mut self, frame: TransportMessage) {
+        if self.outbound.len() >= self.max_outbound {
+            self.outbound.pop_front();
+            self.drops_out = self.drops_out.saturating_add(1);
+        }
+        self.outbound.push_back(frame);
+        self.outbound_frames = self.outbound_frames.saturating_add(1);
+    }
+
+    fn take_outbound(&mut self) -> Vec<TransportMessage> {
+        let mut out = Vec::with_capacity(self.outbound.len());
+        while let Some(frame) = self.outbound.pop_front() {
+            out.push(frame);
+        }
+        out
+    }
+
+    fn outbound_depth(&self) -> usize {
+        self.outbound.len()
+    }
+
+    fn connected(&self) -> bool {
+        self.connected
+    }
+
+    fn set_connected(&mut self, connected: bool) {
+        self.connected = connected;
+    }
+
+    fn outbound_frame_count(&self) -> u64 {
+        self.outbound_frames
+    }
+
+    fn dropped_outbound_count(&self) -> u64 {
+        self.drops_out
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use microclaw_config::WebSocketTransportConfig;
+use microclaw_protocol::TransportMessage;
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use super::Transport;
+
+type WsWriter = SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, WsMessage>;
+
+/// `Transport` backed by a persistent WebSocket connection, so `Host` can
+/// talk to a remote peer instead of only looping back to itself. Keeps its
+/// own multi-threaded Tokio runtime, the same way `MatrixConnector` bridges
+/// `matrix-sdk`, so the host's synchronous tick loop doesn't need to become
+/// async: a background task reads frames off the socket and forwards
+/// decoded `TransportMessage`s into an mpsc channel, while `send_frame`
+/// writes straight through the runtime rather than queuing for a caller to
+/// drain later.
+pub struct WebSocketTransport {
+    config: WebSocketTransportConfig,
+    runtime: Runtime,
+    connected: Arc<Mutex<bool>>,
+    writer: Option<Arc<Mutex<WsWriter>>>,
+    inbox_sender: Sender<TransportMessage>,
+    inbox: Receiver<TransportMessage>,
+    outbound: VecDeque<TransportMessage>,
+    max_outbound: usize,
+    drops_out: u64,
+    outbound_frames: u64,
+}
+
+impl WebSocketTransport {
+    pub fn new(config: WebSocketTransportConfig) -> Self {
+        let (inbox_sender, inbox) = channel();
+        let runtime = Runtime::new().expect("tokio runtime should start");
+        Self {
+            config,
+            runtime,
+            connected: Arc::new(Mutex::new(false)),
+            writer: None,
+            inbox_sender,
+            inbox,
+            outbound: VecDeque::new(),
+            max_outbound: 128,
+            drops_out: 0,
+            outbound_frames: 0,
+        }
+    }
+
+    /// Dials `config.url`, splits the socket into read/write halves, and
+    /// spawns a background task that decodes inbound JSON text frames into
+    /// the inbox channel. Called by `recover_transport_if_needed` (via
+    /// `set_connected(true)`) as well as at construction time.
+    fn dial(&mut self) -> Result<(), String> {
+        let url = self.config.url.clone();
+        let sender = self.inbox_sender.clone();
+        let connected = self.connected.clone();
+
+        let (write, mut read) = self
+            .runtime
+            .block_on(async move {
+                let (stream, _response) = tokio_tungstenite::connect_async(&url)
+                    .await
+                    .map_err(|error| format!("websocket connect to {} failed: {}", url, error))?;
+                Ok::<_, String>(stream.split())
+            })?;
+
+        self.runtime.spawn(async move {
+            *connected.lock().unwrap() = true;
+            while let Some(message) = read.next().await {
+                match message {
+                    Ok(WsMessage::Text(text)) => {
+                        if let Ok(frame) = serde_json::from_str::<TransportMessage>(&text) {
+                            let _ = sender.send(frame);
+                        }
+                    }
+                    Ok(WsMessage::Close(_)) | Err(_) => {
+                        *connected.lock().unwrap() = false;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            *connected.lock().unwrap() = false;
+        });
+
+        self.writer = Some(Arc::new(Mutex::new(write)));
+        Ok(())
+    }
+
+    /// Writes every queued outbound frame straight to the socket. Mirrors
+    /// `WsTransport::send_queued_outbound` in `microclaw-device`: a real
+    /// transport has no external driver calling `take_outbound` to move
+    /// bytes, so `send_frame` flushes eagerly instead of waiting for one.
+    fn flush_outbound(&mut self) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        if !*self.connected.lock().unwrap() {
+            return;
+        }
+
+        while let Some(frame) = self.outbound.front() {
+            let Ok(json) = serde_json::to_string(frame) else {
+                self.outbound.pop_front();
+                self.drops_out = self.drops_out.saturating_add(1);
+                continue;
+            };
+            let writer = writer.clone();
+            let sent = self.runtime.block_on(async move {
+                writer.lock().unwrap().send(WsMessage::Text(json)).await
+            });
+            if sent.is_err() {
+                *self.connected.lock().unwrap() = false;
+                break;
+            }
+            self.outbound.pop_front();
+        }
+    }
+}
+
+impl Transport for WebSocketTransport {
+    fn poll_frames(&mut self) -> Vec<TransportMessage> {
+        let mut frames = Vec::new();
+        loop {
+            match self.inbox.try_recv() {
+                Ok(frame) => frames.push(frame),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+        frames
+    }
+
+    fn send_frame(&mut self, frame: TransportMessage) {
+        if self.outbound.len() >= self.max_outbound {
+            self.outbound.pop_front();
+            self.drops_out = self.drops_out.saturating_add(1);
+        }
+        self.outbound.push_back(frame);
+        self.outbound_frames = self.outbound_frames.saturating_add(1);
+        self.flush_outbound();
+    }
+
+    fn take_outbound(&mut self) -> Vec<TransportMessage> {
+        let mut out = Vec::with_capacity(self.outbound.len());
+        while let Some(frame) = self.outbound.pop_front() {
+            out.push(frame);
+        }
+        out
+    }
+
+    fn push_inbound(&mut self, frame: TransportMessage) {
+        let _ = self.inbox_sender.send(frame);
+    }
+
+    fn connected(&self) -> bool {
+        *self.connected.lock().unwrap()
+    }
+
+    fn set_connected(&mut self, connected: bool) {
+        if connected {
+            if !*self.connected.lock().unwrap() {
+                let _ = self.dial();
+            }
+        } else {
+            *self.connected.lock().unwrap() = false;
+            self.writer = None;
+        }
+    }
+
+    fn outbound_depth(&self) -> usize {
+        self.outbound.len()
+    }
+
+    fn outbound_frame_count(&self) -> u64 {
+        self.outbound_frames
+    }
+
+    fn dropped_outbound_count(&self) -> u64 {
+        self.drops_out
+    }
+}
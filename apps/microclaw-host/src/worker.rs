@@ -0,0 +1,84 @@
+use crate::{Host, StepReport};
+
+/// Outcome of one `Worker::run_once` call: `Busy` when the worker did
+/// something this tick (carrying how many items it processed), `Idle` when
+/// it found nothing to do, and `Throttled` when it deliberately skipped
+/// itself until `until_ms` — e.g. the scheduler polling inside its own
+/// interval, or the queue while the sandbox backend's circuit breaker is
+/// open — rather than running and immediately bailing out every tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorkerState {
+    Busy(usize),
+    Idle,
+    Throttled { until_ms: u64 },
+}
+
+/// Per-worker snapshot `HostStatus` surfaces so an operator can see which
+/// subsystem is busy/idle/throttled and why, without digging through logs.
+#[derive(Debug, Clone, Copy)]
+pub struct WorkerStatus {
+    pub name: &'static str,
+    pub state: WorkerState,
+}
+
+/// One per-tick subsystem `Host::step` drives. Implementations carry no
+/// state of their own; they read and write `Host` directly, since the
+/// subsystems share `Host`'s store/bus/queue/transport rather than owning
+/// private copies of it.
+pub trait Worker {
+    fn name(&self) -> &'static str;
+    fn run_once(&mut self, host: &mut Host, report: &mut StepReport, now_ms: u64) -> WorkerState;
+}
+
+/// Drains and classifies frames newly arrived on the transport.
+pub struct InboundWorker;
+
+impl Worker for InboundWorker {
+    fn name(&self) -> &'static str {
+        "inbound"
+    }
+
+    fn run_once(&mut self, host: &mut Host, report: &mut StepReport, now_ms: u64) -> WorkerState {
+        host.process_inbound(now_ms, report)
+    }
+}
+
+/// Replays newly-appended local bus events.
+pub struct BusWorker;
+
+impl Worker for BusWorker {
+    fn name(&self) -> &'static str {
+        "bus"
+    }
+
+    fn run_once(&mut self, host: &mut Host, report: &mut StepReport, _now_ms: u64) -> WorkerState {
+        host.process_bus(report)
+    }
+}
+
+/// Polls for due scheduled tasks, throttled to `scheduler_poll_interval_ms`.
+pub struct SchedulerWorker;
+
+impl Worker for SchedulerWorker {
+    fn name(&self) -> &'static str {
+        "scheduler"
+    }
+
+    fn run_once(&mut self, host: &mut Host, report: &mut StepReport, now_ms: u64) -> WorkerState {
+        host.poll_scheduler(now_ms, report)
+    }
+}
+
+/// Dispatches ready work items, throttled while the sandbox backend's
+/// circuit breaker is open.
+pub struct QueueWorker;
+
+impl Worker for QueueWorker {
+    fn name(&self) -> &'static str {
+        "queue"
+    }
+
+    fn run_once(&mut self, host: &mut Host, report: &mut StepReport, now_ms: u64) -> WorkerState {
+        host.process_queue(now_ms, report)
+    }
+}
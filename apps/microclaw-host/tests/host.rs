@@ -1,7 +1,408 @@
+use std::sync::{Arc, Mutex};
+
+use microclaw_host::connectors::{Connector, ConnectorError, RoomMessage};
 use microclaw_host::Host;
+use microclaw_protocol::{Envelope, MessageId, MessageKind, TransportMessage};
+
+struct FakeConnector {
+    inbox: Vec<RoomMessage>,
+    sent: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl Connector for FakeConnector {
+    fn connect(&mut self) -> Result<(), ConnectorError> {
+        Ok(())
+    }
+
+    fn next_message(&mut self) -> Result<Option<RoomMessage>, ConnectorError> {
+        Ok(self.inbox.pop())
+    }
+
+    fn send_reply(&mut self, room_id: &str, reply: &str) -> Result<(), ConnectorError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push((room_id.to_string(), reply.to_string()));
+        Ok(())
+    }
+}
 
 #[test]
 fn host_initializes() {
     let host = Host::new(microclaw_config::HostConfig::default());
     assert!(host.is_ok());
 }
+
+#[test]
+fn host_loads_scripts_from_scripts_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("weather.lua"),
+        r#"
+            register_trigger("@Andy", function(content, captures)
+                return "it is sunny"
+            end)
+        "#,
+    )
+    .unwrap();
+
+    let mut config = microclaw_config::HostConfig::default();
+    config.scripts_dir = Some(dir.path().to_string_lossy().to_string());
+
+    let host = Host::new(config).expect("host init should succeed");
+    assert_eq!(host.loaded_script_count(), 1);
+    assert_eq!(
+        host.dispatch_script_trigger("@Andy what's the weather")
+            .unwrap(),
+        Some("it is sunny".to_string())
+    );
+}
+
+#[test]
+fn host_surfaces_script_compile_errors_as_init_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("broken.lua"), "this is not valid lua (((").unwrap();
+
+    let mut config = microclaw_config::HostConfig::default();
+    config.scripts_dir = Some(dir.path().to_string_lossy().to_string());
+
+    let host = Host::new(config);
+    assert!(host.is_err());
+}
+
+#[test]
+fn host_routes_connector_message_through_trigger_and_scripts() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("weather.lua"),
+        r#"
+            register_trigger("@Andy", function(content, captures)
+                return "it is sunny"
+            end)
+        "#,
+    )
+    .unwrap();
+
+    let mut config = microclaw_config::HostConfig::default();
+    config.scripts_dir = Some(dir.path().to_string_lossy().to_string());
+
+    let mut host = Host::new(config).expect("host init should succeed");
+
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    host.push_connector(Box::new(FakeConnector {
+        inbox: vec![RoomMessage {
+            room_id: "!room:example.org".to_string(),
+            message: microclaw_core::Message::new("@Andy what's the weather"),
+        }],
+        sent: sent.clone(),
+    }));
+
+    let report = host.step(0);
+    assert_eq!(report.connector_messages, 1);
+    assert_eq!(
+        sent.lock().unwrap().as_slice(),
+        [("!room:example.org".to_string(), "it is sunny".to_string())]
+    );
+}
+
+#[test]
+fn host_ignores_connector_message_without_trigger() {
+    let mut host = Host::new(microclaw_config::HostConfig::default()).unwrap();
+
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    host.push_connector(Box::new(FakeConnector {
+        inbox: vec![RoomMessage {
+            room_id: "!room:example.org".to_string(),
+            message: microclaw_core::Message::new("just chatting, no trigger"),
+        }],
+        sent: sent.clone(),
+    }));
+
+    let report = host.step(0);
+    assert_eq!(report.connector_messages, 1);
+    assert!(sent.lock().unwrap().is_empty());
+}
+
+#[test]
+fn host_reports_worker_status_for_each_subsystem() {
+    let mut host = Host::new(microclaw_config::HostConfig::default()).unwrap();
+
+    host.step(0);
+    let status = host.status();
+    let names: Vec<&str> = status.workers.iter().map(|worker| worker.name).collect();
+    assert_eq!(names, ["inbound", "bus", "scheduler", "queue"]);
+}
+
+#[test]
+fn host_aggregates_batch_command_results_into_one_frame() {
+    let mut host = Host::new(microclaw_config::HostConfig::default()).unwrap();
+
+    let frame = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("batch-1")),
+        MessageKind::Command,
+        serde_json::json!([
+            { "action": "status_get" },
+            { "action": "sync_now" },
+        ]),
+    );
+    host.inject_transport_frame(frame);
+    host.step(0);
+
+    let outbound = host.drain_transport_outbound();
+    assert_eq!(outbound.len(), 1);
+    assert_eq!(outbound[0].kind, MessageKind::CommandResult);
+
+    let results = outbound[0].payload["results"].as_array().expect("results array");
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["index"], 0);
+    assert_eq!(results[0]["status"], "ok");
+    assert_eq!(results[1]["index"], 1);
+    assert_eq!(results[1]["status"], "ok");
+}
+
+#[test]
+fn host_dedupes_replayed_command_frame_using_cached_result() {
+    let mut host = Host::new(microclaw_config::HostConfig::default()).unwrap();
+
+    let mut frame = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("batch-1")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "status_get" }),
+    );
+    frame.nonce = Some("retry-key-1".to_string());
+
+    host.inject_transport_frame(frame.clone());
+    host.step(0);
+    let first = host.drain_transport_outbound();
+    assert_eq!(first.len(), 1);
+
+    host.inject_transport_frame(frame);
+    host.step(1);
+    let second = host.drain_transport_outbound();
+
+    assert_eq!(second.len(), 1);
+    assert_eq!(second[0].payload, first[0].payload);
+    assert_eq!(host.status().commands_deduplicated, 1);
+}
+
+#[test]
+fn host_throttles_scheduler_worker_within_its_poll_interval() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.scheduler_poll_interval_ms = 1_000;
+    let mut host = Host::new(config).unwrap();
+
+    host.step(1);
+    host.step(100);
+
+    let status = host.status();
+    let scheduler = status
+        .workers
+        .iter()
+        .find(|worker| worker.name == "scheduler")
+        .expect("scheduler worker present");
+    assert_eq!(
+        scheduler.state,
+        microclaw_host::worker::WorkerState::Throttled { until_ms: 1_001 }
+    );
+}
+
+#[test]
+fn host_dispatches_device_action_with_packet_id_and_acks_it() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.allowed_host_actions = vec!["reconnect".to_string()];
+    let mut host = Host::new(config).unwrap();
+
+    let frame = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("cmd-1")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "reconnect" }),
+    );
+    host.inject_transport_frame(frame);
+    host.step(0);
+
+    let outbound = host.drain_transport_outbound();
+    let command = outbound
+        .iter()
+        .find(|msg| msg.kind == MessageKind::Command)
+        .expect("device-bound command frame");
+    let packet_id = command.payload["packet_id"].as_u64().expect("packet_id");
+    assert_eq!(command.payload["duplicate"], false);
+    assert_eq!(host.status().commands_acked, 0);
+
+    let ack = TransportMessage::new(
+        Envelope::new("device-a", "peer", "session-a", MessageId::new("ack-1")),
+        MessageKind::CommandAck,
+        serde_json::json!({ "packet_id": packet_id, "phase": "complete" }),
+    );
+    host.inject_transport_frame(ack);
+    host.step(1);
+
+    assert_eq!(host.status().commands_acked, 1);
+}
+
+#[test]
+fn host_resends_unacked_command_as_duplicate_after_timeout() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.allowed_host_actions = vec!["reconnect".to_string()];
+    config.command_ack_timeout_ms = 1_000;
+    let mut host = Host::new(config).unwrap();
+
+    let frame = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("cmd-1")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "reconnect" }),
+    );
+    host.inject_transport_frame(frame);
+    host.step(0);
+    host.drain_transport_outbound();
+
+    host.step(2_000);
+    let outbound = host.drain_transport_outbound();
+    let resend = outbound
+        .iter()
+        .find(|msg| msg.kind == MessageKind::Command)
+        .expect("resent command frame");
+    assert_eq!(resend.payload["duplicate"], true);
+}
+
+#[test]
+fn host_holds_critical_action_through_two_phase_handshake() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.allowed_host_actions = vec!["end_session".to_string()];
+    let mut host = Host::new(config).unwrap();
+
+    let frame = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("cmd-1")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "end_session" }),
+    );
+    host.inject_transport_frame(frame);
+    host.step(0);
+    let requested = host.drain_transport_outbound();
+    let packet_id = requested
+        .iter()
+        .find(|msg| msg.kind == MessageKind::Command)
+        .expect("requested command frame")
+        .payload["packet_id"]
+        .as_u64()
+        .expect("packet_id");
+
+    let received_ack = TransportMessage::new(
+        Envelope::new("device-a", "peer", "session-a", MessageId::new("ack-1")),
+        MessageKind::CommandAck,
+        serde_json::json!({ "packet_id": packet_id, "phase": "received" }),
+    );
+    host.inject_transport_frame(received_ack);
+    host.step(1);
+
+    let released = host.drain_transport_outbound();
+    let release = released
+        .iter()
+        .find(|msg| msg.kind == MessageKind::Command)
+        .expect("released command frame");
+    assert_eq!(release.payload["phase"], "released");
+    assert_eq!(host.status().commands_acked, 0);
+
+    let complete_ack = TransportMessage::new(
+        Envelope::new("device-a", "peer", "session-a", MessageId::new("ack-2")),
+        MessageKind::CommandAck,
+        serde_json::json!({ "packet_id": packet_id, "phase": "complete" }),
+    );
+    host.inject_transport_frame(complete_ack);
+    host.step(2);
+
+    assert_eq!(host.status().commands_acked, 1);
+}
+
+#[test]
+fn host_allows_sources_matching_a_wildcard_pattern() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.allowed_sources = vec!["fleet/+/commands".to_string()];
+    let mut host = Host::new(config).unwrap();
+
+    let allowed = TransportMessage::new(
+        Envelope::new("fleet/device-a/commands", "peer", "session-a", MessageId::new("msg-1")),
+        MessageKind::Heartbeat,
+        serde_json::json!({}),
+    );
+    host.inject_transport_frame(allowed);
+
+    let filtered = TransportMessage::new(
+        Envelope::new("fleet/device-a/extra/commands", "peer", "session-a", MessageId::new("msg-2")),
+        MessageKind::Heartbeat,
+        serde_json::json!({}),
+    );
+    host.inject_transport_frame(filtered);
+
+    host.step(0);
+
+    assert_eq!(host.status().inbound_filtered, 1);
+}
+
+#[test]
+fn host_queues_device_action_while_transport_disconnected_then_flushes_on_reconnect() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.allowed_host_actions = vec!["reconnect".to_string()];
+    let mut host = Host::new(config).unwrap();
+    host.set_transport_connected(false);
+
+    let frame = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("cmd-1")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "reconnect" }),
+    );
+    host.inject_transport_frame(frame);
+    host.step(0);
+
+    assert!(host
+        .drain_transport_outbound()
+        .iter()
+        .all(|msg| msg.kind != MessageKind::Command));
+    assert_eq!(host.status().offline_queue_depth, 1);
+
+    host.set_transport_connected(true);
+    host.step(1);
+
+    let outbound = host.drain_transport_outbound();
+    assert!(outbound.iter().any(|msg| msg.kind == MessageKind::Command));
+    assert_eq!(host.status().offline_queue_depth, 0);
+}
+
+#[test]
+fn host_offline_queue_evicts_oldest_non_critical_before_a_critical_action() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.allowed_host_actions = vec!["reconnect".to_string(), "end_session".to_string()];
+    config.offline_queue_max_len = 1;
+    let mut host = Host::new(config).unwrap();
+    host.set_transport_connected(false);
+
+    let non_critical = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("cmd-1")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "reconnect" }),
+    );
+    host.inject_transport_frame(non_critical);
+    host.step(0);
+    assert_eq!(host.status().offline_queue_depth, 1);
+
+    let critical = TransportMessage::new(
+        Envelope::new("peer", "device-a", "session-a", MessageId::new("cmd-2")),
+        MessageKind::Command,
+        serde_json::json!({ "action": "end_session" }),
+    );
+    host.inject_transport_frame(critical);
+    host.step(1);
+
+    assert_eq!(host.status().offline_queue_depth, 1);
+    assert_eq!(host.status().commands_dropped_offline, 1);
+
+    host.set_transport_connected(true);
+    host.step(2);
+
+    let outbound = host.drain_transport_outbound();
+    let dispatched = outbound
+        .iter()
+        .find(|msg| msg.kind == MessageKind::Command)
+        .expect("the surviving critical action should be dispatched");
+    assert_eq!(dispatched.payload["action"], "end_session");
+}
@@ -0,0 +1,17 @@
+#[test]
+fn render_metrics_includes_counters_gauges_and_labels() {
+    let mut config = microclaw_config::HostConfig::default();
+    config.host_id = "host-a".to_string();
+    config.container_backend = "docker".to_string();
+
+    let mut host = microclaw_host::Host::new(config).expect("host init should succeed");
+    host.step(0);
+
+    let rendered = host.render_metrics();
+
+    assert!(rendered.contains("# TYPE nanoclaw_work_completed_total counter"));
+    assert!(rendered.contains("nanoclaw_work_completed_total{host_id=\"host-a\",container_backend=\"docker\"} 0"));
+    assert!(rendered.contains("# TYPE nanoclaw_in_flight gauge"));
+    assert!(rendered.contains("# TYPE nanoclaw_transport_outbound_depth gauge"));
+    assert!(rendered.contains("# TYPE nanoclaw_backend_circuit_until gauge"));
+}
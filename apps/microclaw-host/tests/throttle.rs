@@ -0,0 +1,62 @@
+use microclaw_host::throttle::Tranquilizer;
+
+#[test]
+fn idle_steps_back_off_exponentially_up_to_cap() {
+    let mut tranquilizer = Tranquilizer::new(100, 10, 800, 1.0);
+    assert_eq!(tranquilizer.record_step(1, 0), 100);
+    assert_eq!(tranquilizer.record_step(1, 0), 200);
+    assert_eq!(tranquilizer.record_step(1, 0), 400);
+    assert_eq!(tranquilizer.record_step(1, 0), 800);
+    assert_eq!(tranquilizer.record_step(1, 0), 800);
+}
+
+#[test]
+fn busy_step_switches_to_proportional_sleep() {
+    let mut tranquilizer = Tranquilizer::new(100, 10, 800, 1.0);
+    assert_eq!(tranquilizer.record_step(1, 0), 100);
+    assert_eq!(tranquilizer.record_step(1, 0), 200);
+
+    // A busy step is proportional to its own duration...
+    assert_eq!(tranquilizer.record_step(50, 3), 50);
+    // ...and while that busy step is still in the window, later idle steps
+    // stay on the proportional path too rather than resuming the idle
+    // backoff, so a single stray item doesn't get immediately forgotten.
+    assert_eq!(tranquilizer.record_step(1, 0), 10);
+}
+
+#[test]
+fn idle_backoff_restarts_once_the_busy_entry_ages_out_of_the_window() {
+    let mut tranquilizer = Tranquilizer::new(100, 10, 800, 1.0);
+    tranquilizer.record_step(50, 3);
+    // Fill the rest of the window with idle steps so the next push evicts
+    // the busy entry above.
+    for _ in 0..19 {
+        tranquilizer.record_step(1, 0);
+    }
+    assert_eq!(tranquilizer.record_step(1, 0), 100);
+}
+
+#[test]
+fn proportional_sleep_is_clamped_to_tick_interval() {
+    let mut tranquilizer = Tranquilizer::new(100, 10, 800, 1.0);
+    assert_eq!(tranquilizer.record_step(500, 1), 100);
+}
+
+#[test]
+fn proportional_sleep_is_clamped_to_min_tick() {
+    let mut tranquilizer = Tranquilizer::new(100, 10, 800, 1.0);
+    assert_eq!(tranquilizer.record_step(1, 1), 10);
+}
+
+#[test]
+fn tranquility_ratio_scales_the_proportional_sleep() {
+    let mut tranquilizer = Tranquilizer::new(1000, 1, 2000, 0.5);
+    assert_eq!(tranquilizer.record_step(100, 1), 50);
+}
+
+#[test]
+fn duty_cycle_reflects_the_busy_fraction_of_the_window() {
+    let mut tranquilizer = Tranquilizer::new(1000, 1, 2000, 1.0);
+    tranquilizer.record_step(50, 1);
+    assert!((tranquilizer.duty_cycle() - 0.5).abs() < 1e-9);
+}
@@ -0,0 +1,71 @@
+use microclaw_host::transport::{LoopTransport, Transport};
+use microclaw_protocol::{Envelope, MessageId, MessageKind, TransportMessage};
+
+fn frame(id: &str) -> TransportMessage {
+    TransportMessage::new(
+        Envelope::new("test", "device", "session", MessageId::new(id)),
+        MessageKind::Heartbeat,
+        serde_json::json!({}),
+    )
+}
+
+#[test]
+fn loop_transport_starts_connected() {
+    let transport = LoopTransport::new(4);
+    assert!(transport.connected());
+}
+
+#[test]
+fn loop_transport_round_trips_inbound_frames() {
+    let mut transport = LoopTransport::new(4);
+    transport.push_inbound(frame("a"));
+    transport.push_inbound(frame("b"));
+
+    let frames = transport.poll_frames();
+    assert_eq!(frames.len(), 2);
+    assert!(transport.poll_frames().is_empty());
+}
+
+#[test]
+fn loop_transport_drops_oldest_inbound_when_full() {
+    let mut transport = LoopTransport::new(1);
+    transport.push_inbound(frame("a"));
+    transport.push_inbound(frame("b"));
+
+    let frames = transport.poll_frames();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].envelope.message_id.as_str(), "b");
+}
+
+#[test]
+fn loop_transport_queues_outbound_frames_for_take_outbound() {
+    let mut transport = LoopTransport::new(4);
+    transport.send_frame(frame("a"));
+    transport.send_frame(frame("b"));
+
+    assert_eq!(transport.outbound_depth(), 2);
+    assert_eq!(transport.outbound_frame_count(), 2);
+
+    let frames = transport.take_outbound();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(transport.outbound_depth(), 0);
+}
+
+#[test]
+fn loop_transport_counts_dropped_outbound_when_full() {
+    let mut transport = LoopTransport::new(1);
+    transport.send_frame(frame("a"));
+    transport.send_frame(frame("b"));
+
+    assert_eq!(transport.dropped_outbound_count(), 1);
+    assert_eq!(transport.take_outbound().len(), 1);
+}
+
+#[test]
+fn loop_transport_set_connected_toggles_state() {
+    let mut transport = LoopTransport::new(4);
+    transport.set_connected(false);
+    assert!(!transport.connected());
+    transport.set_connected(true);
+    assert!(transport.connected());
+}
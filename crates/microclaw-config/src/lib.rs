@@ -1,3 +1,41 @@
+/// Connection details for the optional Matrix connector: which homeserver
+/// to log into, the account credentials, and which rooms to join on
+/// startup. `HostConfig::matrix` is `None` unless a deployment opts in, and
+/// the connector itself is only compiled when the host crate's `matrix`
+/// feature is enabled.
+#[derive(Clone, Debug)]
+pub struct MatrixConnectorConfig {
+    pub homeserver_url: String,
+    pub username: String,
+    pub password: String,
+    pub joined_rooms: Vec<String>,
+}
+
+/// Connection details for the optional `WebSocketTransport`: the remote
+/// endpoint `Host` dials to exchange `TransportMessage` frames. `HostConfig::
+/// websocket` is `None` unless a deployment opts in, and the transport
+/// itself is only compiled when the host crate's `websocket` feature is
+/// enabled; without it (or without this config) `Host` falls back to the
+/// in-memory loopback transport.
+#[derive(Clone, Debug)]
+pub struct WebSocketTransportConfig {
+    pub url: String,
+}
+
+/// Connection details for the optional `QuicTransport`: the remote endpoint
+/// `Host` dials over QUIC to exchange `TransportMessage` frames.
+/// `HostConfig::quic` is `None` unless a deployment opts in, and the
+/// transport itself is only compiled when the host crate's `quic` feature
+/// is enabled; without it (or without this config) `Host` falls back to
+/// the in-memory loopback transport (or `websocket`, if that's configured
+/// instead). `server_name` is the TLS SNI / certificate name QUIC's
+/// handshake verifies against, since `server_addr` alone is just an IP.
+#[derive(Clone, Debug)]
+pub struct QuicTransportConfig {
+    pub server_addr: String,
+    pub server_name: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct HostConfig {
     pub host_id: String,
@@ -16,8 +54,63 @@ pub struct HostConfig {
     pub allowed_sources: Vec<String>,
     pub allowed_host_actions: Vec<String>,
     pub transport_reconnect_backoff_ms: u64,
+    pub transport_reconnect_backoff_cap_ms: u64,
+    /// Consecutive failed reconnect attempts after which `HostStatus`
+    /// reports reconnection as exhausted, so operators can alert on a
+    /// transport that's been down for a while. `Host` keeps retrying with
+    /// capped, jittered backoff past this threshold -- it never gives up.
+    pub transport_reconnect_max_attempts: u64,
     pub health_log_interval_ms: u64,
     pub dry_run: bool,
+    /// Floor `Host::run`'s adaptive tick sleep can't go below, even under
+    /// heavy load.
+    pub min_tick_ms: u64,
+    /// Ceiling the tranquilizer's idle backoff can grow to before it stops
+    /// doubling.
+    pub tranquilizer_cap_ms: u64,
+    /// Multiplier applied to the last step's duration to compute the next
+    /// sleep when there's work to do: `sleep = last_step_duration *
+    /// tranquility`. Lower values favor responsiveness; higher values favor
+    /// idling cheaply. Default `1.0` targets a roughly 50% duty cycle.
+    pub tranquility: f64,
+    /// Directory of `*.lua` scripts `Host::new` loads at startup to register
+    /// runtime-defined triggers without recompiling the crate. `None` skips
+    /// script loading entirely.
+    pub scripts_dir: Option<String>,
+    /// Trigger a connector-sourced message must contain before the host
+    /// processes it, same semantics as the `trigger` argument to
+    /// `microclaw_core::should_process`.
+    pub connector_trigger: String,
+    /// Matrix connector configuration. `None` disables the connector even
+    /// when the crate was built with the `matrix` feature.
+    pub matrix: Option<MatrixConnectorConfig>,
+    /// Real-transport configuration. `None` keeps `Host` on the in-memory
+    /// loopback transport even when the crate was built with the
+    /// `websocket` feature.
+    pub websocket: Option<WebSocketTransportConfig>,
+    /// QUIC transport configuration. `None` keeps `Host` on whichever
+    /// fallback transport is configured even when the crate was built with
+    /// the `quic` feature.
+    pub quic: Option<QuicTransportConfig>,
+    /// Max entries in the idempotency cache `Host` uses to dedupe replayed
+    /// inbound commands (same `source` + nonce/`corr_id` seen again within
+    /// the cache's retention). The oldest entry is evicted once exceeded.
+    pub idempotency_cache_capacity: usize,
+    /// Ceiling the sandbox backend circuit breaker's decorrelated-jitter
+    /// backoff can grow to between trips.
+    pub backend_breaker_backoff_cap_ms: u64,
+    /// How long `Host` waits for a device's `CommandAck` on a dispatched
+    /// `DeviceAction` before re-sending it (flagged as a duplicate).
+    pub command_ack_timeout_ms: u64,
+    /// Resend attempts for a non-critical `DeviceAction` before `Host`
+    /// abandons it. Critical actions (`OtaStart`, `Unpair`, `EndSession`)
+    /// ignore this and retry indefinitely.
+    pub command_ack_max_resends: u32,
+    /// Max `DeviceAction`s `Host` holds in `offline_queue` while the
+    /// transport is disconnected. Beyond this, the oldest non-critical
+    /// entry is evicted to make room; critical actions (`OtaStart`,
+    /// `Unpair`, `EndSession`) are only evicted once none remain.
+    pub offline_queue_max_len: usize,
 }
 
 impl Default for HostConfig {
@@ -42,8 +135,23 @@ impl Default for HostConfig {
                 "sync_now".to_string(),
             ],
             transport_reconnect_backoff_ms: 1_000,
+            transport_reconnect_backoff_cap_ms: 30_000,
+            transport_reconnect_max_attempts: 10,
             health_log_interval_ms: 5_000,
             dry_run: false,
+            scripts_dir: None,
+            connector_trigger: "@Andy".to_string(),
+            matrix: None,
+            websocket: None,
+            quic: None,
+            min_tick_ms: 10,
+            tranquilizer_cap_ms: 2_000,
+            tranquility: 1.0,
+            idempotency_cache_capacity: 256,
+            backend_breaker_backoff_cap_ms: 30_000,
+            command_ack_timeout_ms: 5_000,
+            command_ack_max_resends: 5,
+            offline_queue_max_len: 64,
         }
     }
 }
@@ -156,6 +264,18 @@ impl HostConfig {
             }
         }
 
+        if let Ok(raw) = std::env::var("NANOCLAW_TRANSPORT_RECONNECT_BACKOFF_CAP_MS") {
+            if let Ok(value) = raw.trim().parse::<u64>() {
+                config.transport_reconnect_backoff_cap_ms = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_TRANSPORT_RECONNECT_MAX_ATTEMPTS") {
+            if let Ok(value) = raw.trim().parse::<u64>() {
+                config.transport_reconnect_max_attempts = value.max(1);
+            }
+        }
+
         if let Ok(raw) = std::env::var("NANOCLAW_HEALTH_LOG_INTERVAL_MS") {
             if let Ok(value) = raw.trim().parse::<u64>() {
                 config.health_log_interval_ms = value.max(500);
@@ -168,6 +288,102 @@ impl HostConfig {
             }
         }
 
+        if let Ok(raw) = std::env::var("NANOCLAW_SCRIPTS_DIR") {
+            if !raw.trim().is_empty() {
+                config.scripts_dir = Some(raw);
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_CONNECTOR_TRIGGER") {
+            if !raw.trim().is_empty() {
+                config.connector_trigger = raw;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_MIN_TICK_MS") {
+            if let Ok(value) = raw.trim().parse::<u64>() {
+                config.min_tick_ms = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_TRANQUILIZER_CAP_MS") {
+            if let Ok(value) = raw.trim().parse::<u64>() {
+                config.tranquilizer_cap_ms = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_TRANQUILITY") {
+            if let Ok(value) = raw.trim().parse::<f64>() {
+                config.tranquility = value.max(0.0);
+            }
+        }
+
+        if let Ok(homeserver_url) = std::env::var("NANOCLAW_MATRIX_HOMESERVER_URL") {
+            if !homeserver_url.trim().is_empty() {
+                let username = std::env::var("NANOCLAW_MATRIX_USERNAME").unwrap_or_default();
+                let password = std::env::var("NANOCLAW_MATRIX_PASSWORD").unwrap_or_default();
+                let joined_rooms = std::env::var("NANOCLAW_MATRIX_ROOMS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|entry| entry.trim().to_string())
+                    .filter(|entry| !entry.is_empty())
+                    .collect();
+                config.matrix = Some(MatrixConnectorConfig {
+                    homeserver_url,
+                    username,
+                    password,
+                    joined_rooms,
+                });
+            }
+        }
+
+        if let Ok(url) = std::env::var("NANOCLAW_WEBSOCKET_URL") {
+            if !url.trim().is_empty() {
+                config.websocket = Some(WebSocketTransportConfig { url });
+            }
+        }
+
+        if let Ok(server_addr) = std::env::var("NANOCLAW_QUIC_SERVER_ADDR") {
+            if !server_addr.trim().is_empty() {
+                let server_name = std::env::var("NANOCLAW_QUIC_SERVER_NAME")
+                    .unwrap_or_else(|_| "localhost".to_string());
+                config.quic = Some(QuicTransportConfig {
+                    server_addr,
+                    server_name,
+                });
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_IDEMPOTENCY_CACHE_CAPACITY") {
+            if let Ok(value) = raw.trim().parse::<usize>() {
+                config.idempotency_cache_capacity = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_BACKEND_BREAKER_BACKOFF_CAP_MS") {
+            if let Ok(value) = raw.trim().parse::<u64>() {
+                config.backend_breaker_backoff_cap_ms = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_COMMAND_ACK_TIMEOUT_MS") {
+            if let Ok(value) = raw.trim().parse::<u64>() {
+                config.command_ack_timeout_ms = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_COMMAND_ACK_MAX_RESENDS") {
+            if let Ok(value) = raw.trim().parse::<u32>() {
+                config.command_ack_max_resends = value;
+            }
+        }
+
+        if let Ok(raw) = std::env::var("NANOCLAW_OFFLINE_QUEUE_MAX_LEN") {
+            if let Ok(value) = raw.trim().parse::<usize>() {
+                config.offline_queue_max_len = value;
+            }
+        }
+
         config
     }
 }
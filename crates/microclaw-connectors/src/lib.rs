@@ -7,6 +7,7 @@ pub trait Connector {
 
 use std::path::Path;
 use std::process::Command;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 use lettre::Transport;
@@ -108,11 +109,22 @@ pub struct IMessageMessage {
     pub sender: String,
 }
 
-pub struct DiscordConnector;
+pub struct DiscordConnector {
+    agent: ureq::Agent,
+}
 
 impl DiscordConnector {
     pub fn new() -> Self {
-        Self
+        Self {
+            agent: default_http_agent(),
+        }
+    }
+
+    /// Builds a connector around a caller-supplied agent, e.g. one shared
+    /// across connectors or tuned with different timeouts than
+    /// [`default_http_agent`].
+    pub fn with_agent(agent: ureq::Agent) -> Self {
+        Self { agent }
     }
 
     pub fn message_url(channel_id: &str) -> String {
@@ -127,13 +139,16 @@ impl DiscordConnector {
     }
 
     pub fn send_message(
+        &self,
         base_url: &str,
         token: &str,
         channel_id: &str,
         content: &str,
     ) -> Result<DiscordMessage, String> {
         let url = join_url(base_url, &format!("channels/{}/messages", channel_id));
-        let response = ureq::post(&url)
+        let response = self
+            .agent
+            .post(&url)
             .set("Authorization", &format!("Bot {}", token))
             .send_json(serde_json::json!({ "content": content }))
             .map_err(ureq_error)?;
@@ -143,13 +158,17 @@ impl DiscordConnector {
     }
 
     pub fn fetch_messages(
+        &self,
         base_url: &str,
         token: &str,
         channel_id: &str,
         after: Option<&str>,
     ) -> Result<Vec<DiscordMessage>, String> {
         let url = join_url(base_url, &format!("channels/{}/messages", channel_id));
-        let mut request = ureq::get(&url).set("Authorization", &format!("Bot {}", token));
+        let mut request = self
+            .agent
+            .get(&url)
+            .set("Authorization", &format!("Bot {}", token));
         if let Some(after) = after {
             request = request.query("after", after);
         }
@@ -172,11 +191,205 @@ pub struct DiscordMessage {
     pub content: String,
 }
 
-pub struct TelegramConnector;
+/// Gateway events [`DiscordGatewayClient::handle_frame`] surfaces to a
+/// caller driving the connection. This is a small subset of Discord's full
+/// opcode table - just enough to receive `MESSAGE_CREATE` pushes and keep
+/// the connection alive.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GatewayEvent {
+    Hello { heartbeat_interval_ms: u64 },
+    HeartbeatAck,
+    MessageCreate(DiscordMessage),
+    Reconnect,
+    InvalidSession { resumable: bool },
+}
+
+/// Minimal duplex JSON socket [`DiscordGatewayClient::run`] is driven over,
+/// so the IDENTIFY/heartbeat/RESUME state machine can be tested without
+/// opening a real WebSocket connection to Discord.
+pub trait GatewaySocket {
+    fn send(&mut self, payload: serde_json::Value) -> Result<(), String>;
+    fn recv(&mut self) -> Result<serde_json::Value, String>;
+}
+
+/// Drives the Discord Gateway handshake/heartbeat/resume state machine
+/// (`IDENTIFY` with the bot token, one opcode 1 heartbeat in answer to
+/// opcode 10 `Hello`, `RESUME` with `session_id`/`seq` after a disconnect)
+/// so `MESSAGE_CREATE` events arrive pushed instead of via REST polling.
+/// No further heartbeats are sent on the `heartbeat_interval` that `Hello`
+/// carries, and opcode 1 heartbeat requests from the gateway are not
+/// answered. `DiscordConnector::fetch_messages`/`send_message` remain the
+/// REST path for sending and catch-up reads.
+pub struct DiscordGatewayClient {
+    token: String,
+    session_id: Option<String>,
+    last_sequence: Option<u64>,
+}
+
+impl DiscordGatewayClient {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            session_id: None,
+            last_sequence: None,
+        }
+    }
+
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.last_sequence
+    }
+
+    pub fn identify_payload(&self, intents: u32) -> serde_json::Value {
+        serde_json::json!({
+            "op": 2,
+            "d": {
+                "token": self.token,
+                "intents": intents,
+                "properties": {
+                    "os": "linux",
+                    "browser": "nanoclaw",
+                    "device": "nanoclaw",
+                },
+            }
+        })
+    }
+
+    /// `None` until a `READY` dispatch has handed us a `session_id` to
+    /// resume from.
+    pub fn resume_payload(&self) -> Option<serde_json::Value> {
+        let session_id = self.session_id.clone()?;
+        let seq = self.last_sequence?;
+        Some(serde_json::json!({
+            "op": 6,
+            "d": {
+                "token": self.token,
+                "session_id": session_id,
+                "seq": seq,
+            }
+        }))
+    }
+
+    pub fn heartbeat_payload(&self) -> serde_json::Value {
+        serde_json::json!({ "op": 1, "d": self.last_sequence })
+    }
+
+    /// Decodes one raw gateway frame, updating `last_sequence`/`session_id`
+    /// as a side effect, and returns the typed event this connector cares
+    /// about (if any - most dispatch types besides `READY`/`MESSAGE_CREATE`
+    /// decode to `None`).
+    pub fn handle_frame(
+        &mut self,
+        frame: &serde_json::Value,
+    ) -> Result<Option<GatewayEvent>, String> {
+        let op = frame
+            .get("op")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or("gateway frame missing op")?;
+        if let Some(seq) = frame.get("s").and_then(serde_json::Value::as_u64) {
+            self.last_sequence = Some(seq);
+        }
+        match op {
+            10 => {
+                let interval = frame
+                    .get("d")
+                    .and_then(|d| d.get("heartbeat_interval"))
+                    .and_then(serde_json::Value::as_u64)
+                    .ok_or("hello frame missing heartbeat_interval")?;
+                Ok(Some(GatewayEvent::Hello {
+                    heartbeat_interval_ms: interval,
+                }))
+            }
+            11 => Ok(Some(GatewayEvent::HeartbeatAck)),
+            7 => Ok(Some(GatewayEvent::Reconnect)),
+            9 => {
+                let resumable = frame
+                    .get("d")
+                    .and_then(serde_json::Value::as_bool)
+                    .unwrap_or(false);
+                Ok(Some(GatewayEvent::InvalidSession { resumable }))
+            }
+            0 => self.handle_dispatch(frame),
+            _ => Ok(None),
+        }
+    }
+
+    fn handle_dispatch(
+        &mut self,
+        frame: &serde_json::Value,
+    ) -> Result<Option<GatewayEvent>, String> {
+        let event_type = frame.get("t").and_then(serde_json::Value::as_str).unwrap_or("");
+        let data = frame.get("d");
+        match event_type {
+            "READY" => {
+                self.session_id = data
+                    .and_then(|d| d.get("session_id"))
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::to_string);
+                Ok(None)
+            }
+            "MESSAGE_CREATE" => {
+                let data = data.ok_or("MESSAGE_CREATE frame missing d")?;
+                let message: DiscordMessage = serde_json::from_value(data.clone())
+                    .map_err(|err| format!("parse error: {}", err))?;
+                Ok(Some(GatewayEvent::MessageCreate(message)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Drives one gateway connection end-to-end over `socket`: sends
+    /// `IDENTIFY` (or `RESUME`, if a prior session is known), then loops
+    /// reading frames, answering `Hello` with a single heartbeat, and invoking
+    /// `on_message` for every `MESSAGE_CREATE`. Returns once the socket
+    /// reports `Reconnect`/`InvalidSession` so the caller can reopen the
+    /// underlying WebSocket and call `run` again (resuming automatically if
+    /// `session_id` survived).
+    pub fn run<S: GatewaySocket>(
+        &mut self,
+        socket: &mut S,
+        intents: u32,
+        mut on_message: impl FnMut(DiscordMessage),
+    ) -> Result<(), String> {
+        let handshake = self
+            .resume_payload()
+            .unwrap_or_else(|| self.identify_payload(intents));
+        socket.send(handshake)?;
+        loop {
+            let frame = socket.recv()?;
+            match self.handle_frame(&frame)? {
+                Some(GatewayEvent::Hello { .. }) => {
+                    socket.send(self.heartbeat_payload())?;
+                }
+                Some(GatewayEvent::MessageCreate(message)) => on_message(message),
+                Some(GatewayEvent::Reconnect) | Some(GatewayEvent::InvalidSession { .. }) => {
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub struct TelegramConnector {
+    agent: ureq::Agent,
+}
 
 impl TelegramConnector {
     pub fn new() -> Self {
-        Self
+        Self {
+            agent: default_http_agent(),
+        }
+    }
+
+    /// Builds a connector around a caller-supplied agent, e.g. one shared
+    /// across connectors or tuned with different timeouts than
+    /// [`default_http_agent`].
+    pub fn with_agent(agent: ureq::Agent) -> Self {
+        Self { agent }
     }
 
     pub fn send_message_url(token: &str) -> String {
@@ -184,13 +397,16 @@ impl TelegramConnector {
     }
 
     pub fn send_message(
+        &self,
         base_url: &str,
         token: &str,
         chat_id: &str,
         text: &str,
     ) -> Result<TelegramMessage, String> {
         let url = join_url(base_url, &format!("bot{}/sendMessage", token));
-        let response = ureq::post(&url)
+        let response = self
+            .agent
+            .post(&url)
             .send_json(serde_json::json!({"chat_id": chat_id, "text": text}))
             .map_err(ureq_error)?;
         let body: TelegramSendResponse = response
@@ -204,12 +420,30 @@ impl TelegramConnector {
     }
 
     pub fn get_updates(
+        &self,
         base_url: &str,
         token: &str,
         offset: Option<i64>,
+    ) -> Result<Vec<TelegramUpdate>, String> {
+        self.get_updates_long_poll(base_url, token, offset, 0)
+    }
+
+    /// Like [`Self::get_updates`], but passes `timeout` (seconds) so
+    /// Telegram holds the request open until an update arrives instead of
+    /// returning immediately - true long-polling rather than tight-loop
+    /// polling.
+    pub fn get_updates_long_poll(
+        &self,
+        base_url: &str,
+        token: &str,
+        offset: Option<i64>,
+        timeout_secs: u64,
     ) -> Result<Vec<TelegramUpdate>, String> {
         let url = join_url(base_url, &format!("bot{}/getUpdates", token));
-        let mut request = ureq::get(&url);
+        let mut request = self
+            .agent
+            .get(&url)
+            .query("timeout", &timeout_secs.to_string());
         if let Some(offset) = offset {
             request = request.query("offset", &offset.to_string());
         }
@@ -223,6 +457,29 @@ impl TelegramConnector {
             Err("telegram getUpdates failed".to_string())
         }
     }
+
+    /// Long-polls once for new updates, advancing and persisting `store`'s
+    /// offset to `last_update_id + 1` afterwards so a restart resumes
+    /// exactly where it left off instead of reprocessing or dropping
+    /// updates. Returns the decoded messages, normalized for downstream
+    /// routing (see [`InboundMessage`]).
+    pub fn poll_loop<S: OffsetStore>(
+        &self,
+        base_url: &str,
+        token: &str,
+        timeout_secs: u64,
+        store: &mut S,
+    ) -> Result<Vec<InboundMessage>, String> {
+        let offset = store.get_offset();
+        let updates = self.get_updates_long_poll(base_url, token, offset, timeout_secs)?;
+        if let Some(last) = updates.last() {
+            store.set_offset(last.update_id.saturating_add(1));
+        }
+        Ok(updates
+            .into_iter()
+            .filter_map(TelegramUpdate::into_inbound)
+            .collect())
+    }
 }
 
 impl Connector for TelegramConnector {
@@ -237,9 +494,70 @@ pub struct TelegramMessage {
     pub text: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelegramFrom {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelegramChat {
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TelegramInboundMessage {
+    pub message_id: i64,
+    pub from: Option<TelegramFrom>,
+    pub chat: TelegramChat,
+    pub text: Option<String>,
+    pub date: i64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TelegramUpdate {
     pub update_id: i64,
+    pub message: Option<TelegramInboundMessage>,
+}
+
+impl TelegramUpdate {
+    /// Decodes the nested `message`, if any, into a normalized
+    /// [`InboundMessage`]. Updates that carry no message (e.g. edited
+    /// message / channel post variants this connector doesn't handle yet)
+    /// decode to `None`.
+    pub fn into_inbound(self) -> Option<InboundMessage> {
+        let message = self.message?;
+        Some(InboundMessage {
+            sender: message
+                .from
+                .map(|from| from.id.to_string())
+                .unwrap_or_default(),
+            chat: message.chat.id.to_string(),
+            text: message.text.unwrap_or_default(),
+            timestamp: message.date,
+        })
+    }
+}
+
+/// A connector-agnostic inbound message shape (mirrors the
+/// sender/timestamp/text fields `microclaw-core`'s `NewMessage` formats),
+/// so callers can route Telegram updates the same way as any other
+/// connector without depending on Telegram's wire format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundMessage {
+    pub sender: String,
+    pub chat: String,
+    pub text: String,
+    pub timestamp: i64,
+}
+
+/// Small key/value persistence hook so [`TelegramConnector::poll_loop`] can
+/// save its `getUpdates` offset across restarts. Kept as a local trait
+/// (rather than depending on an app crate's storage type) so this crate
+/// stays a leaf dependency; callers can back it with whatever storage their
+/// app already has.
+pub trait OffsetStore {
+    fn get_offset(&self) -> Option<i64>;
+    fn set_offset(&mut self, offset: i64);
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -280,7 +598,64 @@ impl EmailConnector {
             to: to.to_string(),
             subject: subject.to_string(),
             body: body.to_string(),
+            uid: None,
+            flags: Vec::new(),
+            message_id: None,
+        }
+    }
+
+    pub fn uid_fetch_items() -> &'static str {
+        "UID FLAGS ENVELOPE BODY.PEEK[HEADER]"
+    }
+
+    pub fn condstore_fetch_items(mod_seq: u64) -> String {
+        format!("(FLAGS) (CHANGEDSINCE {})", mod_seq)
+    }
+
+    /// Incrementally syncs `mailbox`, returning newly-seen mail plus
+    /// cheap flag-only updates (when the server supports CONDSTORE) and the
+    /// cursor to resume from next time. If `UIDVALIDITY` changed since
+    /// `cursor` was captured, the mailbox is treated as reset and refetched
+    /// from UID 1.
+    pub fn fetch_since<C: ImapClient>(
+        client: &mut C,
+        mailbox: &str,
+        cursor: ImapCursor,
+    ) -> Result<(Vec<EmailMessage>, ImapCursor), String> {
+        let status = client.select_mailbox(mailbox)?;
+        let uidvalidity_changed =
+            cursor.uid_validity != 0 && cursor.uid_validity != status.uid_validity;
+        let start_uid = if uidvalidity_changed {
+            1
+        } else {
+            cursor.last_uid.saturating_add(1)
+        };
+
+        let mut messages =
+            client.uid_fetch(&format!("{}:*", start_uid), Self::uid_fetch_items())?;
+
+        if let Some(highest_mod_seq) = status.highest_mod_seq {
+            if !uidvalidity_changed && cursor.mod_seq > 0 && highest_mod_seq > cursor.mod_seq {
+                let flag_updates =
+                    client.uid_fetch("1:*", &Self::condstore_fetch_items(cursor.mod_seq))?;
+                messages.extend(flag_updates);
+            }
         }
+
+        let base_last_uid = if uidvalidity_changed { 0 } else { cursor.last_uid };
+        let last_uid = messages
+            .iter()
+            .filter_map(|message| message.uid)
+            .max()
+            .unwrap_or(base_last_uid)
+            .max(base_last_uid);
+
+        let next_cursor = ImapCursor {
+            uid_validity: status.uid_validity,
+            last_uid,
+            mod_seq: status.highest_mod_seq.unwrap_or(cursor.mod_seq),
+        };
+        Ok((messages, next_cursor))
     }
 
     pub fn smtp_send_with_transport<T: EmailTransport>(
@@ -316,6 +691,15 @@ impl EmailConnector {
         client.idle()
     }
 
+    /// Blocks until the server pushes a new-message/expunge notification or
+    /// `timeout` elapses, whichever comes first.
+    pub fn imap_idle_wait_with_client<C: ImapClient>(
+        client: &mut C,
+        timeout: Duration,
+    ) -> Result<Option<ImapEvent>, String> {
+        client.idle_wait(timeout)
+    }
+
     pub fn connect_imap(
         server: &str,
         port: u16,
@@ -338,26 +722,94 @@ impl Connector for EmailConnector {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct EmailMessage {
     pub from: String,
     pub to: String,
     pub subject: String,
     pub body: String,
+    /// `None` for locally-built outgoing mail; set on anything parsed out of
+    /// a `UID FETCH` response.
+    pub uid: Option<u32>,
+    pub flags: Vec<String>,
+    pub message_id: Option<String>,
+}
+
+/// Resumable position in a mailbox's UID space, plus the last-seen CONDSTORE
+/// `MODSEQ` when the server supports it, so [`EmailConnector::fetch_since`]
+/// can skip mail it has already downloaded instead of re-fetching the whole
+/// mailbox every sync. A `uid_validity` of `0` means "no prior sync" and
+/// always triggers a full refetch from UID 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImapCursor {
+    pub uid_validity: u32,
+    pub last_uid: u32,
+    pub mod_seq: u64,
+}
+
+/// `UIDVALIDITY`/`HIGHESTMODSEQ` reported by a `SELECT`, used by
+/// [`EmailConnector::fetch_since`] to decide whether a cursor is still valid
+/// for the mailbox or whether the mailbox was reset underneath it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MailboxStatus {
+    pub uid_validity: u32,
+    /// `None` when the server doesn't advertise the CONDSTORE extension (or
+    /// the client can't parse its `HIGHESTMODSEQ` response code).
+    pub highest_mod_seq: Option<u64>,
 }
 
 pub trait EmailTransport {
     fn send(&self, message: &EmailMessage) -> Result<(), String>;
 }
 
+/// A server-pushed mailbox change surfaced by [`ImapClient::idle_wait`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImapEvent {
+    NewMessages(u32),
+    Expunged(u32),
+}
+
+/// Most IMAP servers drop an `IDLE` connection after about 29 minutes of
+/// inactivity (RFC 2177 recommends clients re-issue well before the
+/// 30-minute mark), so `ImapSession::idle_wait` re-enters `IDLE` on this
+/// cadence rather than relying on the server to keep it alive indefinitely.
+const IMAP_IDLE_REISSUE_INTERVAL: Duration = Duration::from_secs(25 * 60);
+
 pub trait ImapClient {
     fn idle(&mut self) -> Result<(), String>;
+
+    /// Sends `IDLE`, blocks on untagged responses, and returns the first
+    /// mailbox change observed (or `None` if `timeout` elapses first).
+    /// Transparently sends `DONE` and re-enters `IDLE` every
+    /// `IMAP_IDLE_REISSUE_INTERVAL` so the wait survives past the window
+    /// servers drop an idle connection after.
+    fn idle_wait(&mut self, timeout: Duration) -> Result<Option<ImapEvent>, String>;
+
+    /// `SELECT`s `mailbox` and reports its `UIDVALIDITY`/`HIGHESTMODSEQ`.
+    fn select_mailbox(&mut self, mailbox: &str) -> Result<MailboxStatus, String>;
+
+    /// Issues `UID FETCH <uid_range> <query>` and parses the results.
+    fn uid_fetch(&mut self, uid_range: &str, query: &str) -> Result<Vec<EmailMessage>, String>;
 }
 
 pub struct ImapSession {
     session: imap::Session<imap::Connection>,
 }
 
+fn format_imap_address(address: &imap::types::Address) -> String {
+    let mailbox = address
+        .mailbox
+        .as_ref()
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+        .unwrap_or_default();
+    let host = address
+        .host
+        .as_ref()
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+        .unwrap_or_default();
+    format!("{}@{}", mailbox, host)
+}
+
 impl ImapClient for ImapSession {
     fn idle(&mut self) -> Result<(), String> {
         self.session
@@ -365,6 +817,113 @@ impl ImapClient for ImapSession {
             .map(|_| ())
             .map_err(|err| format!("imap noop error: {}", err))
     }
+
+    fn idle_wait(&mut self, timeout: Duration) -> Result<Option<ImapEvent>, String> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let keepalive = remaining.min(IMAP_IDLE_REISSUE_INTERVAL);
+
+            let mut event = None;
+            let mut idle = self
+                .session
+                .idle()
+                .map_err(|err| format!("imap idle error: {}", err))?;
+            idle.set_keepalive(keepalive);
+            idle.wait_while(|response| match response {
+                imap::types::UnsolicitedResponse::Exists(n) => {
+                    event = Some(ImapEvent::NewMessages(n));
+                    false
+                }
+                imap::types::UnsolicitedResponse::Expunge(n) => {
+                    event = Some(ImapEvent::Expunged(n));
+                    false
+                }
+                _ => true,
+            })
+            .map_err(|err| format!("imap idle wait error: {}", err))?;
+
+            if event.is_some() {
+                return Ok(event);
+            }
+            // `wait_while` returned without an event because the keepalive
+            // window elapsed (DONE was sent automatically) - loop around to
+            // re-enter IDLE unless the overall `timeout` has run out too.
+        }
+    }
+
+    fn select_mailbox(&mut self, mailbox: &str) -> Result<MailboxStatus, String> {
+        let mailbox_info = self
+            .session
+            .select(mailbox)
+            .map_err(|err| format!("imap select error: {}", err))?;
+        Ok(MailboxStatus {
+            uid_validity: mailbox_info.uid_validity.unwrap_or(0),
+            // The base `imap` crate doesn't parse the CONDSTORE
+            // `HIGHESTMODSEQ` response code, so `EmailConnector::fetch_since`
+            // falls back to a plain UID-range fetch until that's added.
+            highest_mod_seq: None,
+        })
+    }
+
+    fn uid_fetch(&mut self, uid_range: &str, query: &str) -> Result<Vec<EmailMessage>, String> {
+        let fetches = self
+            .session
+            .uid_fetch(uid_range, query)
+            .map_err(|err| format!("imap fetch error: {}", err))?;
+        Ok(fetches
+            .iter()
+            .map(|fetch| {
+                let envelope = fetch.envelope();
+                let from = envelope
+                    .and_then(|envelope| envelope.from.as_ref())
+                    .and_then(|addresses| addresses.first())
+                    .map(format_imap_address)
+                    .unwrap_or_default();
+                let to = envelope
+                    .and_then(|envelope| envelope.to.as_ref())
+                    .and_then(|addresses| addresses.first())
+                    .map(format_imap_address)
+                    .unwrap_or_default();
+                let subject = envelope
+                    .and_then(|envelope| envelope.subject.as_ref())
+                    .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+                    .unwrap_or_default();
+                let message_id = envelope
+                    .and_then(|envelope| envelope.message_id.as_ref())
+                    .map(|bytes| String::from_utf8_lossy(bytes).to_string());
+                let flags = fetch
+                    .flags()
+                    .iter()
+                    .map(|flag| format!("{:?}", flag))
+                    .collect();
+                EmailMessage {
+                    from,
+                    to,
+                    subject,
+                    body: String::new(),
+                    uid: fetch.uid,
+                    flags,
+                    message_id,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Shared timeouts/connection pool for the HTTP-polling connectors
+/// (`DiscordConnector`, `TelegramConnector`). Building one `ureq::Agent` per
+/// connector instance - rather than calling the free `ureq::get`/`ureq::post`
+/// functions, which open a fresh connection and TLS session every call -
+/// lets repeated polling against the same host reuse keep-alive connections.
+fn default_http_agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(10))
+        .timeout(Duration::from_secs(30))
+        .build()
 }
 
 fn join_url(base: &str, path: &str) -> String {
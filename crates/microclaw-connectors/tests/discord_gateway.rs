@@ -0,0 +1,125 @@
+use microclaw_connectors::{DiscordGatewayClient, GatewayEvent, GatewaySocket};
+
+struct StubSocket {
+    sent: Vec<serde_json::Value>,
+    incoming: std::collections::VecDeque<serde_json::Value>,
+}
+
+impl StubSocket {
+    fn new(frames: Vec<serde_json::Value>) -> Self {
+        Self {
+            sent: Vec::new(),
+            incoming: frames.into(),
+        }
+    }
+}
+
+impl GatewaySocket for StubSocket {
+    fn send(&mut self, payload: serde_json::Value) -> Result<(), String> {
+        self.sent.push(payload);
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<serde_json::Value, String> {
+        self.incoming.pop_front().ok_or_else(|| "no more frames".to_string())
+    }
+}
+
+#[test]
+fn hello_frame_yields_heartbeat_interval() {
+    let mut client = DiscordGatewayClient::new("bot-token");
+    let event = client
+        .handle_frame(&serde_json::json!({"op": 10, "d": {"heartbeat_interval": 41250}}))
+        .unwrap();
+    assert_eq!(
+        event,
+        Some(GatewayEvent::Hello {
+            heartbeat_interval_ms: 41250
+        })
+    );
+}
+
+#[test]
+fn ready_dispatch_captures_session_id_without_emitting_event() {
+    let mut client = DiscordGatewayClient::new("bot-token");
+    let event = client
+        .handle_frame(&serde_json::json!({
+            "op": 0,
+            "s": 1,
+            "t": "READY",
+            "d": {"session_id": "abc123"}
+        }))
+        .unwrap();
+    assert_eq!(event, None);
+    assert_eq!(client.session_id(), Some("abc123"));
+    assert_eq!(client.last_sequence(), Some(1));
+}
+
+#[test]
+fn message_create_dispatch_decodes_to_discord_message() {
+    let mut client = DiscordGatewayClient::new("bot-token");
+    let event = client
+        .handle_frame(&serde_json::json!({
+            "op": 0,
+            "s": 2,
+            "t": "MESSAGE_CREATE",
+            "d": {"id": "55", "content": "hello"}
+        }))
+        .unwrap();
+    match event {
+        Some(GatewayEvent::MessageCreate(message)) => {
+            assert_eq!(message.id, "55");
+            assert_eq!(message.content, "hello");
+        }
+        other => panic!("expected MessageCreate, got {:?}", other),
+    }
+}
+
+#[test]
+fn resume_payload_is_none_until_session_established() {
+    let client = DiscordGatewayClient::new("bot-token");
+    assert!(client.resume_payload().is_none());
+}
+
+#[test]
+fn run_sends_identify_then_heartbeat_and_surfaces_message() {
+    let mut client = DiscordGatewayClient::new("bot-token");
+    let mut socket = StubSocket::new(vec![
+        serde_json::json!({"op": 10, "d": {"heartbeat_interval": 1000}}),
+        serde_json::json!({
+            "op": 0,
+            "s": 3,
+            "t": "MESSAGE_CREATE",
+            "d": {"id": "1", "content": "hi"}
+        }),
+        serde_json::json!({"op": 7}),
+    ]);
+
+    let mut received = Vec::new();
+    client
+        .run(&mut socket, 512, |message| received.push(message.content))
+        .unwrap();
+
+    assert_eq!(received, vec!["hi".to_string()]);
+    assert_eq!(socket.sent[0]["op"], 2);
+    assert_eq!(socket.sent[1]["op"], 1);
+}
+
+#[test]
+fn run_sends_resume_once_session_is_known() {
+    let mut client = DiscordGatewayClient::new("bot-token");
+    client
+        .handle_frame(&serde_json::json!({
+            "op": 0,
+            "s": 9,
+            "t": "READY",
+            "d": {"session_id": "xyz"}
+        }))
+        .unwrap();
+
+    let mut socket = StubSocket::new(vec![serde_json::json!({"op": 7})]);
+    client.run(&mut socket, 512, |_| {}).unwrap();
+
+    assert_eq!(socket.sent[0]["op"], 6);
+    assert_eq!(socket.sent[0]["d"]["session_id"], "xyz");
+}
@@ -1,4 +1,8 @@
-use microclaw_connectors::{EmailConnector, EmailMessage, EmailTransport, ImapClient};
+use std::time::Duration;
+
+use microclaw_connectors::{
+    EmailConnector, EmailMessage, EmailTransport, ImapClient, ImapCursor, ImapEvent, MailboxStatus,
+};
 
 struct StubTransport {
     sent: std::sync::Mutex<Vec<EmailMessage>>,
@@ -21,6 +25,25 @@ impl EmailTransport for StubTransport {
 
 struct StubImap {
     called: bool,
+    pending_event: Option<ImapEvent>,
+    status: MailboxStatus,
+    by_uid_range: std::collections::HashMap<String, Vec<EmailMessage>>,
+}
+
+impl StubImap {
+    fn new(status: MailboxStatus) -> Self {
+        Self {
+            called: false,
+            pending_event: None,
+            status,
+            by_uid_range: std::collections::HashMap::new(),
+        }
+    }
+
+    fn with_fetch(mut self, uid_range: &str, messages: Vec<EmailMessage>) -> Self {
+        self.by_uid_range.insert(uid_range.to_string(), messages);
+        self
+    }
 }
 
 impl ImapClient for StubImap {
@@ -28,6 +51,25 @@ impl ImapClient for StubImap {
         self.called = true;
         Ok(())
     }
+
+    fn idle_wait(&mut self, _timeout: Duration) -> Result<Option<ImapEvent>, String> {
+        Ok(self.pending_event.take())
+    }
+
+    fn select_mailbox(&mut self, _mailbox: &str) -> Result<MailboxStatus, String> {
+        Ok(self.status)
+    }
+
+    fn uid_fetch(&mut self, uid_range: &str, _query: &str) -> Result<Vec<EmailMessage>, String> {
+        Ok(self.by_uid_range.get(uid_range).cloned().unwrap_or_default())
+    }
+}
+
+fn fetched(uid: u32) -> EmailMessage {
+    EmailMessage {
+        uid: Some(uid),
+        ..EmailConnector::build_message("a@example.com", "b@example.com", "hi", "body")
+    }
 }
 
 #[test]
@@ -40,9 +82,116 @@ fn smtp_send_with_transport_records_message() {
     assert_eq!(sent[0].subject, "hi");
 }
 
+fn default_status() -> MailboxStatus {
+    MailboxStatus {
+        uid_validity: 1,
+        highest_mod_seq: None,
+    }
+}
+
 #[test]
 fn imap_idle_with_client_calls_idle() {
-    let mut client = StubImap { called: false };
+    let mut client = StubImap::new(default_status());
     EmailConnector::imap_idle_with_client(&mut client).unwrap();
     assert!(client.called);
 }
+
+#[test]
+fn imap_idle_wait_with_client_returns_pushed_event() {
+    let mut client = StubImap::new(default_status());
+    client.pending_event = Some(ImapEvent::NewMessages(3));
+    let event =
+        EmailConnector::imap_idle_wait_with_client(&mut client, Duration::from_secs(1)).unwrap();
+    assert_eq!(event, Some(ImapEvent::NewMessages(3)));
+}
+
+#[test]
+fn imap_idle_wait_with_client_returns_none_when_nothing_pending() {
+    let mut client = StubImap::new(default_status());
+    let event =
+        EmailConnector::imap_idle_wait_with_client(&mut client, Duration::from_secs(1)).unwrap();
+    assert_eq!(event, None);
+}
+
+#[test]
+fn fetch_since_first_sync_fetches_from_uid_one() {
+    let mut client = StubImap::new(MailboxStatus {
+        uid_validity: 7,
+        highest_mod_seq: None,
+    })
+    .with_fetch("1:*", vec![fetched(1), fetched(2)]);
+
+    let (messages, cursor) =
+        EmailConnector::fetch_since(&mut client, "INBOX", ImapCursor::default()).unwrap();
+
+    assert_eq!(messages.len(), 2);
+    assert_eq!(
+        cursor,
+        ImapCursor {
+            uid_validity: 7,
+            last_uid: 2,
+            mod_seq: 0,
+        }
+    );
+}
+
+#[test]
+fn fetch_since_resumes_after_last_uid() {
+    let mut client = StubImap::new(MailboxStatus {
+        uid_validity: 7,
+        highest_mod_seq: None,
+    })
+    .with_fetch("3:*", vec![fetched(3)]);
+    let cursor = ImapCursor {
+        uid_validity: 7,
+        last_uid: 2,
+        mod_seq: 0,
+    };
+
+    let (messages, next_cursor) = EmailConnector::fetch_since(&mut client, "INBOX", cursor).unwrap();
+
+    assert_eq!(messages, vec![fetched(3)]);
+    assert_eq!(next_cursor.last_uid, 3);
+}
+
+#[test]
+fn fetch_since_refetches_from_one_when_uidvalidity_changes() {
+    let mut client = StubImap::new(MailboxStatus {
+        uid_validity: 99,
+        highest_mod_seq: None,
+    })
+    .with_fetch("1:*", vec![fetched(1)]);
+    let stale_cursor = ImapCursor {
+        uid_validity: 7,
+        last_uid: 50,
+        mod_seq: 0,
+    };
+
+    let (messages, next_cursor) =
+        EmailConnector::fetch_since(&mut client, "INBOX", stale_cursor).unwrap();
+
+    assert_eq!(messages, vec![fetched(1)]);
+    assert_eq!(next_cursor.uid_validity, 99);
+    assert_eq!(next_cursor.last_uid, 1);
+}
+
+#[test]
+fn fetch_since_picks_up_condstore_flag_updates() {
+    let mut client = StubImap::new(MailboxStatus {
+        uid_validity: 7,
+        highest_mod_seq: Some(42),
+    })
+    .with_fetch("3:*", vec![])
+    .with_fetch("1:*", vec![fetched(1)]);
+    let cursor = ImapCursor {
+        uid_validity: 7,
+        last_uid: 2,
+        mod_seq: 10,
+    };
+
+    let (messages, next_cursor) = EmailConnector::fetch_since(&mut client, "INBOX", cursor).unwrap();
+
+    assert_eq!(messages, vec![fetched(1)]);
+    assert_eq!(next_cursor.mod_seq, 42);
+    assert_eq!(next_cursor.last_uid, 2);
+}
@@ -1,7 +1,60 @@
-use microclaw_connectors::TelegramConnector;
+use microclaw_connectors::{
+    OffsetStore, TelegramChat, TelegramConnector, TelegramFrom, TelegramInboundMessage,
+    TelegramUpdate,
+};
 
 #[test]
 fn builds_telegram_send_message_url() {
     let url = TelegramConnector::send_message_url("token");
     assert_eq!(url, "https://api.telegram.org/bottoken/sendMessage");
 }
+
+#[test]
+fn update_decodes_into_normalized_inbound_message() {
+    let update = TelegramUpdate {
+        update_id: 5,
+        message: Some(TelegramInboundMessage {
+            message_id: 42,
+            from: Some(TelegramFrom { id: 7 }),
+            chat: TelegramChat { id: 99 },
+            text: Some("hi".to_string()),
+            date: 1_700_000_000,
+        }),
+    };
+
+    let inbound = update.into_inbound().expect("message present");
+    assert_eq!(inbound.sender, "7");
+    assert_eq!(inbound.chat, "99");
+    assert_eq!(inbound.text, "hi");
+    assert_eq!(inbound.timestamp, 1_700_000_000);
+}
+
+#[test]
+fn update_with_no_message_decodes_to_none() {
+    let update = TelegramUpdate {
+        update_id: 5,
+        message: None,
+    };
+    assert!(update.into_inbound().is_none());
+}
+
+struct StubOffsetStore {
+    offset: Option<i64>,
+}
+
+impl OffsetStore for StubOffsetStore {
+    fn get_offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    fn set_offset(&mut self, offset: i64) {
+        self.offset = Some(offset);
+    }
+}
+
+#[test]
+fn offset_store_returns_the_offset_last_set() {
+    let mut store = StubOffsetStore { offset: Some(10) };
+    store.set_offset(11);
+    assert_eq!(store.get_offset(), Some(11));
+}
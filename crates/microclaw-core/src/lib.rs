@@ -1,4 +1,6 @@
-use regex::Regex;
+use std::collections::HashMap;
+
+use regex::{Captures, Regex};
 
 pub fn version() -> &'static str {
     "0.1.0"
@@ -34,36 +36,248 @@ impl Message {
     }
 }
 
-pub fn create_trigger_pattern(trigger: &str) -> Regex {
-    let trimmed = trigger.trim();
-    let normalized = if trimmed.starts_with('@') {
+fn normalize_alias(alias: &str, at_prefix_optional: bool) -> String {
+    let trimmed = alias.trim();
+    if !at_prefix_optional || trimmed.starts_with('@') {
         trimmed.to_string()
     } else {
         format!("@{trimmed}")
+    }
+}
+
+fn normalize_trigger(trigger: &str) -> String {
+    normalize_alias(trigger, true)
+}
+
+/// Where in the content a [`TriggerConfig`]'s aliases are allowed to match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerMode {
+    /// The trigger must appear at the very start of the content, the same
+    /// behavior `create_trigger_pattern` has always had.
+    StartOnly,
+    /// The trigger may appear anywhere in the content, e.g. a mid-message
+    /// mention like "hello @Andy".
+    Anywhere,
+    /// The entire (trimmed) content must be nothing but the trigger.
+    ExactWord,
+}
+
+/// Configuration for [`create_trigger_pattern_with`]: the aliases a bot
+/// answers to, where they're allowed to match, and whether a missing
+/// leading `@` should be normalized in the way `create_trigger_pattern`
+/// already does for its single alias.
+#[derive(Clone, Debug)]
+pub struct TriggerConfig {
+    pub aliases: Vec<String>,
+    pub mode: TriggerMode,
+    pub at_prefix_optional: bool,
+}
+
+impl TriggerConfig {
+    pub fn new(aliases: Vec<String>, mode: TriggerMode) -> Self {
+        Self {
+            aliases,
+            mode,
+            at_prefix_optional: true,
+        }
+    }
+}
+
+/// Compiles one or more aliases into a single trigger-matching pattern,
+/// per `config.mode`:
+/// - `StartOnly`: anchored at the start of the content, same as the
+///   original `create_trigger_pattern`.
+/// - `Anywhere`: matched as a whole word anywhere in the content.
+/// - `ExactWord`: the trimmed content must be the alias and nothing else.
+pub fn create_trigger_pattern_with(config: &TriggerConfig) -> Regex {
+    let alternation = config
+        .aliases
+        .iter()
+        .map(|alias| regex::escape(&normalize_alias(alias, config.at_prefix_optional)))
+        .collect::<Vec<_>>()
+        .join("|");
+    let body = format!("(?:{alternation})");
+    let pattern = match config.mode {
+        TriggerMode::StartOnly => format!("(?i)^{body}\\b"),
+        TriggerMode::Anywhere => format!("(?i)(?:^|\\W){body}\\b"),
+        TriggerMode::ExactWord => format!("(?i)^{body}$"),
     };
-    Regex::new(&format!("(?i)^{}\\b", regex::escape(&normalized)))
-        .expect("trigger regex should compile")
+    Regex::new(&pattern).expect("trigger regex should compile")
+}
+
+/// Single-alias, start-anchored trigger pattern. Thin wrapper over
+/// [`create_trigger_pattern_with`] for the common case of one alias.
+pub fn create_trigger_pattern(trigger: &str) -> Regex {
+    create_trigger_pattern_with(&TriggerConfig::new(
+        vec![trigger.to_string()],
+        TriggerMode::StartOnly,
+    ))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Tri-state result of scanning a chunk of content against a trigger: the
+/// counterpart to evaluating `create_trigger_pattern(trigger).is_match(..)`
+/// one character at a time instead of against a complete string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanState {
+    /// The scanned prefix is the trigger, and the word-boundary check right
+    /// after it has already been satisfied. This is final: feeding more
+    /// input cannot undo it.
+    Matched,
+    /// The scanned prefix has diverged from the trigger and can never
+    /// become a match, no matter what's fed next. This is final.
+    Impossible,
+    /// Still consistent with the trigger so far, but not yet decided —
+    /// either more trigger characters are still needed, or the trigger has
+    /// been fully consumed and the scanner is waiting on one more character
+    /// (or `finish()`) to confirm the word boundary.
+    Pending,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DfaState {
+    Dead,
+    Building(usize),
+    AtBoundary,
+    Confirmed,
+}
+
+/// Incremental, DFA-backed equivalent of `create_trigger_pattern(trigger).is_match(..)`,
+/// built for content that arrives in chunks (a streaming upstream, a
+/// partially-received message) rather than as one complete string. The
+/// trigger is anchored at the very start of the scanned content (matching
+/// `create_trigger_pattern`'s `^` anchor), so a single character mismatch is
+/// permanently `Impossible` — there's no restart-at-the-next-position
+/// search, just a left-to-right walk over the trigger's own characters
+/// followed by a word-boundary check.
+pub struct TriggerScanner {
+    trigger_chars: Vec<char>,
+    state: DfaState,
+}
+
+impl TriggerScanner {
+    pub fn new(trigger: &str) -> Self {
+        let trigger_chars: Vec<char> = normalize_trigger(trigger)
+            .chars()
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let state = if trigger_chars.is_empty() {
+            DfaState::AtBoundary
+        } else {
+            DfaState::Building(0)
+        };
+        Self {
+            trigger_chars,
+            state,
+        }
+    }
+
+    pub fn state(&self) -> ScanState {
+        match self.state {
+            DfaState::Dead => ScanState::Impossible,
+            DfaState::Building(_) | DfaState::AtBoundary => ScanState::Pending,
+            DfaState::Confirmed => ScanState::Matched,
+        }
+    }
+
+    /// Advances the DFA by one character and returns the resulting state.
+    pub fn feed(&mut self, c: char) -> ScanState {
+        self.state = match self.state {
+            DfaState::Dead => DfaState::Dead,
+            DfaState::Building(i) => {
+                if c.to_ascii_lowercase() == self.trigger_chars[i] {
+                    if i + 1 == self.trigger_chars.len() {
+                        DfaState::AtBoundary
+                    } else {
+                        DfaState::Building(i + 1)
+                    }
+                } else {
+                    DfaState::Dead
+                }
+            }
+            DfaState::AtBoundary => {
+                if is_word_char(c) {
+                    DfaState::Dead
+                } else {
+                    DfaState::Confirmed
+                }
+            }
+            DfaState::Confirmed => DfaState::Confirmed,
+        };
+        self.state()
+    }
+
+    /// Feeds a whole chunk of characters at once, short-circuiting as soon
+    /// as the scanner becomes `Impossible`.
+    pub fn feed_str(&mut self, chunk: &str) -> ScanState {
+        for c in chunk.chars() {
+            if self.feed(c) == ScanState::Impossible {
+                break;
+            }
+        }
+        self.state()
+    }
+
+    /// Signals end of input: a trigger left at `AtBoundary` (no trailing
+    /// character ever arrived to check) is treated the same as hitting a
+    /// word boundary at end-of-string, matching `\b`'s own behavior there.
+    pub fn finish(&mut self) -> ScanState {
+        self.state = match self.state {
+            DfaState::AtBoundary | DfaState::Confirmed => DfaState::Confirmed,
+            DfaState::Dead | DfaState::Building(_) => DfaState::Dead,
+        };
+        self.state()
+    }
+}
+
+/// Runs a fresh [`TriggerScanner`] over `content` to completion. Equivalent
+/// to `create_trigger_pattern(trigger).is_match(content)`, but exercised
+/// through the incremental scanner instead of the batch regex — used to
+/// keep the two implementations' semantics identical.
+pub fn matches_trigger_incrementally(trigger: &str, content: &str) -> bool {
+    let mut scanner = TriggerScanner::new(trigger);
+    scanner.feed_str(content);
+    scanner.finish() == ScanState::Matched
 }
 
 pub fn should_require_trigger(is_main_group: bool, requires_trigger: Option<bool>) -> bool {
     !is_main_group && requires_trigger != Some(false)
 }
 
-pub fn should_process(
+/// Like [`should_process`], but takes a full [`TriggerConfig`] so callers can
+/// opt into multiple aliases and a non-default [`TriggerMode`].
+pub fn should_process_with(
     is_main_group: bool,
     requires_trigger: Option<bool>,
-    trigger: &str,
+    config: &TriggerConfig,
     messages: &[Message],
 ) -> bool {
     if !should_require_trigger(is_main_group, requires_trigger) {
         return true;
     }
-    let pattern = create_trigger_pattern(trigger);
+    let pattern = create_trigger_pattern_with(config);
     messages
         .iter()
         .any(|m| pattern.is_match(m.content.trim()))
 }
 
+pub fn should_process(
+    is_main_group: bool,
+    requires_trigger: Option<bool>,
+    trigger: &str,
+    messages: &[Message],
+) -> bool {
+    should_process_with(
+        is_main_group,
+        requires_trigger,
+        &TriggerConfig::new(vec![trigger.to_string()], TriggerMode::StartOnly),
+        messages,
+    )
+}
+
 pub fn escape_xml(input: &str) -> String {
     input
         .replace('&', "&amp;")
@@ -105,6 +319,103 @@ pub fn format_outbound(prefix_assistant_name: bool, assistant_name: &str, raw_te
     }
 }
 
+/// A command matched out of a triggered message: which registered command
+/// matched, plus the named capture groups its regex pulled out of the
+/// remaining text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub name: String,
+    pub captures: HashMap<String, String>,
+}
+
+struct RegisteredCommand {
+    name: String,
+    pattern: Regex,
+    handler: Box<dyn Fn(&Command) -> String>,
+}
+
+/// Matches a triggered message against a registry of named commands and
+/// dispatches to the first one whose regex matches, passing along its named
+/// capture groups. Builds on [`create_trigger_pattern`]/[`should_process`]:
+/// those decide *whether* a message should be processed at all, while
+/// `CommandRouter` decides *which* handler a processed message goes to and
+/// with what arguments.
+pub struct CommandRouter {
+    trigger: Regex,
+    commands: Vec<RegisteredCommand>,
+    fallback: Option<Box<dyn Fn(&str) -> String>>,
+}
+
+impl CommandRouter {
+    pub fn new(trigger: &str) -> Self {
+        Self {
+            trigger: create_trigger_pattern(trigger),
+            commands: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// Registers `name` under `pattern`, tried in registration order by
+    /// `dispatch`. `pattern` is matched against the message with the
+    /// trigger prefix already stripped, e.g. `weather (?P<city>\w+)`.
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        pattern: &str,
+        handler: impl Fn(&Command) -> String + 'static,
+    ) {
+        let pattern = Regex::new(pattern).expect("command regex should compile");
+        self.commands.push(RegisteredCommand {
+            name: name.into(),
+            pattern,
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Sets the handler invoked with the trigger-stripped remainder when no
+    /// registered command matches. Replaces any previously set fallback.
+    pub fn set_fallback(&mut self, handler: impl Fn(&str) -> String + 'static) {
+        self.fallback = Some(Box::new(handler));
+    }
+
+    /// Strips the trigger prefix from `content` (if present), then tries
+    /// each registered command's regex in registration order, dispatching
+    /// the first match's named captures to its handler. Falls through to
+    /// the fallback handler (if set) when nothing matches.
+    pub fn dispatch(&self, content: &str) -> Option<String> {
+        let remainder = self.strip_trigger(content);
+        for registered in &self.commands {
+            if let Some(captures) = registered.pattern.captures(remainder) {
+                let command = Command {
+                    name: registered.name.clone(),
+                    captures: named_captures(&registered.pattern, &captures),
+                };
+                return Some((registered.handler)(&command));
+            }
+        }
+        self.fallback.as_ref().map(|handler| handler(remainder))
+    }
+
+    fn strip_trigger<'a>(&self, content: &'a str) -> &'a str {
+        match self.trigger.find(content) {
+            Some(found) => content[found.end()..].trim_start(),
+            None => content.trim(),
+        }
+    }
+}
+
+fn named_captures(pattern: &Regex, captures: &Captures<'_>) -> HashMap<String, String> {
+    pattern
+        .capture_names()
+        .flatten()
+        .filter_map(|name| {
+            captures
+                .name(name)
+                .map(|value| (name.to_string(), value.as_str().to_string()))
+        })
+        .collect()
+}
+
 pub trait Channel {
     fn owns_jid(&self, jid: &str) -> bool;
     fn is_connected(&self) -> bool;
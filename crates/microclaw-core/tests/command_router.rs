@@ -0,0 +1,51 @@
+use microclaw_core::{Command, CommandRouter};
+
+#[test]
+fn dispatch_strips_trigger_and_extracts_named_captures() {
+    let mut router = CommandRouter::new("@Andy");
+    router.register("weather", r"^weather (?P<city>\w+)$", |cmd: &Command| {
+        format!("weather:{}", cmd.captures.get("city").unwrap())
+    });
+
+    let reply = router.dispatch("@Andy weather London");
+    assert_eq!(reply, Some("weather:London".to_string()));
+}
+
+#[test]
+fn dispatch_tries_commands_in_registration_order() {
+    let mut router = CommandRouter::new("@Andy");
+    router.register("first", r"^\w+$", |_| "matched-first".to_string());
+    router.register("second", r"^hello$", |_| "matched-second".to_string());
+
+    let reply = router.dispatch("@Andy hello");
+    assert_eq!(reply, Some("matched-first".to_string()));
+}
+
+#[test]
+fn dispatch_falls_through_to_fallback_when_nothing_matches() {
+    let mut router = CommandRouter::new("@Andy");
+    router.register("weather", r"^weather (?P<city>\w+)$", |_| "weather".to_string());
+    router.set_fallback(|remainder| format!("unknown command: {remainder}"));
+
+    let reply = router.dispatch("@Andy do a backflip");
+    assert_eq!(reply, Some("unknown command: do a backflip".to_string()));
+}
+
+#[test]
+fn dispatch_returns_none_without_fallback_when_nothing_matches() {
+    let mut router = CommandRouter::new("@Andy");
+    router.register("weather", r"^weather (?P<city>\w+)$", |_| "weather".to_string());
+
+    assert_eq!(router.dispatch("@Andy do a backflip"), None);
+}
+
+#[test]
+fn dispatch_works_without_trigger_prefix_present() {
+    let mut router = CommandRouter::new("@Andy");
+    router.register("weather", r"^weather (?P<city>\w+)$", |cmd: &Command| {
+        cmd.captures.get("city").unwrap().clone()
+    });
+
+    let reply = router.dispatch("weather Paris");
+    assert_eq!(reply, Some("Paris".to_string()));
+}
@@ -1,4 +1,7 @@
-use microclaw_core::{create_trigger_pattern, should_process, should_require_trigger, Message};
+use microclaw_core::{
+    create_trigger_pattern, create_trigger_pattern_with, should_process, should_process_with,
+    should_require_trigger, Message, TriggerConfig, TriggerMode,
+};
 
 #[test]
 fn trigger_pattern_matches_start_case_insensitive() {
@@ -45,3 +48,55 @@ fn should_process_respects_requires_trigger_and_custom_trigger() {
     assert!(should_process(false, Some(true), "@Helper", &trigger_msgs));
     assert!(!should_process(false, Some(true), "@Helper", &[Message::new("@Andy do something")]));
 }
+
+#[test]
+fn trigger_pattern_with_supports_multiple_aliases() {
+    let config = TriggerConfig::new(
+        vec!["@Andy".to_string(), "@Bot".to_string()],
+        TriggerMode::StartOnly,
+    );
+    let pattern = create_trigger_pattern_with(&config);
+    assert!(pattern.is_match("@Andy hello"));
+    assert!(pattern.is_match("@Bot hello"));
+    assert!(!pattern.is_match("hello @Andy"));
+}
+
+#[test]
+fn trigger_pattern_with_anywhere_mode_matches_mid_message() {
+    let config = TriggerConfig::new(vec!["@Andy".to_string()], TriggerMode::Anywhere);
+    let pattern = create_trigger_pattern_with(&config);
+    assert!(pattern.is_match("hello @Andy"));
+    assert!(pattern.is_match("@Andy hello"));
+    assert!(!pattern.is_match("hello @Andrew"));
+}
+
+#[test]
+fn trigger_pattern_with_exact_word_mode_requires_whole_content() {
+    let config = TriggerConfig::new(vec!["@Andy".to_string()], TriggerMode::ExactWord);
+    let pattern = create_trigger_pattern_with(&config);
+    assert!(pattern.is_match("@Andy"));
+    assert!(!pattern.is_match("@Andy hello"));
+    assert!(!pattern.is_match("hello @Andy"));
+}
+
+#[test]
+fn trigger_pattern_with_respects_at_prefix_optional_flag() {
+    let mut config = TriggerConfig::new(vec!["Helper".to_string()], TriggerMode::StartOnly);
+    config.at_prefix_optional = false;
+    let pattern = create_trigger_pattern_with(&config);
+    assert!(pattern.is_match("Helper do thing"));
+    assert!(!pattern.is_match("@Helper do thing"));
+}
+
+#[test]
+fn should_process_with_respects_anywhere_mode() {
+    let config = TriggerConfig::new(vec!["@Andy".to_string()], TriggerMode::Anywhere);
+    let msgs = vec![Message::new("hello @Andy")];
+    assert!(should_process_with(false, Some(true), &config, &msgs));
+    assert!(!should_process_with(
+        false,
+        Some(true),
+        &config,
+        &[Message::new("hello @Andrew")]
+    ));
+}
@@ -0,0 +1,98 @@
+use microclaw_core::{
+    create_trigger_pattern, matches_trigger_incrementally, ScanState, TriggerScanner,
+};
+
+#[test]
+fn scanner_matches_exact_trigger_case_insensitively() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    assert_eq!(scanner.feed_str("@ANDY"), ScanState::Pending);
+    assert_eq!(scanner.finish(), ScanState::Matched);
+}
+
+#[test]
+fn scanner_reports_impossible_on_early_mismatch() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    assert_eq!(scanner.feed_str("hello"), ScanState::Impossible);
+}
+
+#[test]
+fn scanner_is_pending_mid_trigger() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    assert_eq!(scanner.feed_str("@An"), ScanState::Pending);
+}
+
+#[test]
+fn scanner_rejects_a_name_that_diverges_from_the_trigger() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    // "@Andrew" diverges from "@Andy" at the 5th character ('r' vs 'y').
+    assert_eq!(scanner.feed_str("@Andrew"), ScanState::Impossible);
+}
+
+#[test]
+fn scanner_accepts_trailing_punctuation_as_boundary() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    assert_eq!(scanner.feed_str("@Andy's"), ScanState::Matched);
+}
+
+#[test]
+fn scanner_stays_impossible_once_dead() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    scanner.feed_str("nope");
+    assert_eq!(scanner.feed_str(" more text"), ScanState::Impossible);
+}
+
+#[test]
+fn scanner_normalizes_missing_at_prefix() {
+    let mut scanner = TriggerScanner::new("Helper");
+    assert_eq!(scanner.feed_str("@Helper do thing"), ScanState::Matched);
+}
+
+#[test]
+fn scanner_finish_without_trailing_char_still_matches() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    assert_eq!(scanner.feed_str("@Andy"), ScanState::Pending);
+    assert_eq!(scanner.finish(), ScanState::Matched);
+}
+
+#[test]
+fn scanner_finish_mid_trigger_is_impossible() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    scanner.feed_str("@An");
+    assert_eq!(scanner.finish(), ScanState::Impossible);
+}
+
+#[test]
+fn scanner_fed_one_char_at_a_time_matches_streamed_input() {
+    let mut scanner = TriggerScanner::new("@Andy");
+    let mut state = ScanState::Pending;
+    for c in "@Andy hello".chars() {
+        state = scanner.feed(c);
+        if state == ScanState::Impossible {
+            break;
+        }
+    }
+    assert_eq!(state, ScanState::Matched);
+}
+
+#[test]
+fn matches_trigger_incrementally_agrees_with_batch_regex() {
+    let cases = [
+        ("@Andy", "@Andy hello", true),
+        ("@Andy", "@andy hello", true),
+        ("@Andy", "hello @Andy", false),
+        ("@Andy", "@Andrew hello", false),
+        ("@Andy", "@Andy's thing", true),
+        ("Helper", "@Helper do thing", true),
+        ("Helper", "@Andy do thing", false),
+    ];
+
+    for (trigger, content, expected) in cases {
+        let batch = create_trigger_pattern(trigger).is_match(content);
+        let incremental = matches_trigger_incrementally(trigger, content);
+        assert_eq!(batch, expected, "batch mismatch for {trigger:?}/{content:?}");
+        assert_eq!(
+            incremental, expected,
+            "incremental mismatch for {trigger:?}/{content:?}"
+        );
+    }
+}
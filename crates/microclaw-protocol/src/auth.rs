@@ -0,0 +1,262 @@
+//! Ed25519 authentication for [`TransportMessage`] itself: signs a
+//! canonical JSON encoding of the envelope/kind/payload (with `signature`
+//! cleared) and verifies it against a per-`device_id` public key, plus a
+//! small TTL-bounded cache guarding against a replayed `(device_id, nonce)`
+//! pair.
+//!
+//! This is independent of `apps/microclaw-device`'s pluggable
+//! `crypto::SignatureVerifier` backend, which authenticates frames at the
+//! session/transport layer over a length-prefixed byte encoding of the
+//! envelope; this module authenticates a `TransportMessage` value directly
+//! off a fixed ed25519 scheme and JSON canonicalization, for callers (e.g.
+//! a store-backed command queue) that want to check a message's origin
+//! without standing up a full transport session.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use ed25519_dalek::{Signature, Signer as _, SigningKey, Verifier as _, VerifyingKey};
+use rand_core::RngCore;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{hex_decode, hex_encode, ProtocolError, TransportMessage};
+
+const NONCE_LEN: usize = 16;
+
+/// Recursively sorts every JSON object's keys so two semantically equal
+/// `TransportMessage`s always serialize to the same bytes, regardless of
+/// the insertion order `payload` happened to be built in.
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut sorted: BTreeMap<&str, Value> = BTreeMap::new();
+            for (key, val) in map {
+                sorted.insert(key.as_str(), canonicalize(val));
+            }
+            let mut out = Map::new();
+            for (key, val) in sorted {
+                out.insert(key.to_string(), val);
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+/// The SHA-256 digest of `msg`'s canonical JSON encoding with `signature`
+/// cleared first, so signing and verifying always hash the same bytes
+/// regardless of whatever signature (if any) the message currently carries.
+fn canonical_digest(msg: &TransportMessage) -> [u8; 32] {
+    let mut unsigned = msg.clone();
+    unsigned.signature = None;
+    let value = serde_json::to_value(&unsigned).unwrap_or(Value::Null);
+    let canonical = canonicalize(&value);
+    let bytes = serde_json::to_vec(&canonical).unwrap_or_default();
+    Sha256::digest(bytes).into()
+}
+
+/// Bounded, TTL-based record of recently-seen `(device_id, nonce)` pairs,
+/// so [`TransportMessage::verify`] can reject a replayed nonce even when
+/// the underlying transport redelivers a whole frame rather than just
+/// bumping `seq`. Entries are evicted lazily off the front of a FIFO-
+/// ordered queue, the same shape `IdempotencyCache` uses in
+/// `microclaw-host` -- short-lived nonces don't benefit from a real LRU
+/// policy either.
+pub struct NonceCache {
+    ttl_ms: u64,
+    order: VecDeque<(String, String, u64)>,
+    seen: HashMap<(String, String), u64>,
+}
+
+impl NonceCache {
+    pub fn new(ttl_ms: u64) -> Self {
+        Self {
+            ttl_ms,
+            order: VecDeque::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    fn evict_expired(&mut self, now_ms: u64) {
+        while let Some((_, _, seen_at)) = self.order.front() {
+            if now_ms.saturating_sub(*seen_at) <= self.ttl_ms {
+                break;
+            }
+            if let Some((device_id, nonce, _)) = self.order.pop_front() {
+                self.seen.remove(&(device_id, nonce));
+            }
+        }
+    }
+
+    /// Records `(device_id, nonce)` as seen at `now_ms` and returns `true`,
+    /// unless it was already recorded within the TTL window, in which case
+    /// it returns `false` without disturbing the existing entry.
+    pub fn check_and_insert(&mut self, device_id: &str, nonce: &str, now_ms: u64) -> bool {
+        self.evict_expired(now_ms);
+        let key = (device_id.to_string(), nonce.to_string());
+        if self.seen.contains_key(&key) {
+            return false;
+        }
+        self.order.push_back((key.0.clone(), key.1.clone(), now_ms));
+        self.seen.insert(key, now_ms);
+        true
+    }
+}
+
+impl TransportMessage {
+    /// Signs this message in place: assigns a fresh random nonce, then
+    /// signs the SHA-256 digest of its canonical JSON encoding (with
+    /// `signature` cleared) and stores the detached signature hex-encoded
+    /// in `signature`.
+    pub fn sign(&mut self, key: &SigningKey) {
+        self.signature = None;
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        rand_core::OsRng.fill_bytes(&mut nonce_bytes);
+        self.nonce = Some(hex_encode(&nonce_bytes));
+
+        let digest = canonical_digest(self);
+        let signature = key.sign(&digest);
+        self.signature = Some(hex_encode(&signature.to_bytes()));
+    }
+
+    /// Verifies this message against `key`, rejecting it as a
+    /// [`ProtocolError`] if it is expired, its `seq` has already been seen,
+    /// its `nonce` is missing or was already seen within `nonces`' TTL
+    /// window, or its signature doesn't check out.
+    pub fn verify(
+        &self,
+        key: &VerifyingKey,
+        now_ms: u64,
+        last_seq: u64,
+        nonces: &mut NonceCache,
+    ) -> Result<(), ProtocolError> {
+        if self.is_expired(now_ms) {
+            return Err(ProtocolError::new(
+                "transport_expired",
+                "message ttl has elapsed",
+                true,
+            ));
+        }
+        if self.is_replay(last_seq) {
+            return Err(ProtocolError::new(
+                "transport_replayed_seq",
+                "sequence has already been seen",
+                false,
+            ));
+        }
+        let Some(nonce) = self.nonce.as_deref() else {
+            return Err(ProtocolError::new(
+                "transport_missing_nonce",
+                "message has no nonce",
+                false,
+            ));
+        };
+        let Some(signature) = self.signature.as_deref() else {
+            return Err(ProtocolError::new(
+                "transport_missing_signature",
+                "message has no signature",
+                false,
+            ));
+        };
+        let Ok(sig_bytes) = hex_decode(signature) else {
+            return Err(ProtocolError::new(
+                "transport_invalid_signature",
+                "signature is not valid hex",
+                false,
+            ));
+        };
+        let Ok(sig) = Signature::from_slice(&sig_bytes) else {
+            return Err(ProtocolError::new(
+                "transport_invalid_signature",
+                "signature has the wrong length",
+                false,
+            ));
+        };
+        let digest = canonical_digest(self);
+        key.verify_strict(&digest, &sig).map_err(|_| {
+            ProtocolError::new(
+                "transport_invalid_signature",
+                "signature does not match the message",
+                false,
+            )
+        })?;
+        // Only an authenticated message may consume a nonce -- otherwise a
+        // forged copy with a stolen nonce and a bogus signature could mark
+        // it "seen" and cause the genuine message to be rejected as a
+        // replay once it arrives.
+        if !nonces.check_and_insert(&self.envelope.device_id, nonce, now_ms) {
+            return Err(ProtocolError::new(
+                "transport_replayed_nonce",
+                "nonce has already been seen",
+                false,
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Envelope, MessageId, MessageKind};
+
+    fn signed_message(device_id: &str, seq: u64) -> (TransportMessage, SigningKey, VerifyingKey) {
+        let signing_key = SigningKey::generate(&mut rand_core::OsRng);
+        let verifying_key = signing_key.verifying_key();
+
+        let mut envelope = Envelope::new("device", device_id, "session-1", MessageId::new("m1"));
+        envelope.seq = seq;
+        let mut msg = TransportMessage::new(
+            envelope,
+            MessageKind::StatusSnapshot,
+            serde_json::json!({"wifi_ok": true}),
+        );
+        msg.issued_at = Some(1_000);
+        msg.ttl_ms = Some(5_000);
+        msg.sign(&signing_key);
+
+        (msg, signing_key, verifying_key)
+    }
+
+    #[test]
+    fn a_signed_message_verifies_against_the_matching_key() {
+        let (msg, _signing_key, verifying_key) = signed_message("device-1", 1);
+        let mut nonces = NonceCache::new(60_000);
+        assert!(msg.verify(&verifying_key, 1_500, 0, &mut nonces).is_ok());
+    }
+
+    #[test]
+    fn a_tampered_payload_fails_verification() {
+        let (mut msg, _signing_key, verifying_key) = signed_message("device-1", 1);
+        msg.payload = serde_json::json!({"wifi_ok": false});
+        let mut nonces = NonceCache::new(60_000);
+        let err = msg.verify(&verifying_key, 1_500, 0, &mut nonces).unwrap_err();
+        assert_eq!(err.code, "transport_invalid_signature");
+    }
+
+    #[test]
+    fn an_expired_message_is_rejected_even_with_a_valid_signature() {
+        let (msg, _signing_key, verifying_key) = signed_message("device-1", 1);
+        let mut nonces = NonceCache::new(60_000);
+        let err = msg.verify(&verifying_key, 10_000, 0, &mut nonces).unwrap_err();
+        assert_eq!(err.code, "transport_expired");
+    }
+
+    #[test]
+    fn a_replayed_seq_is_rejected() {
+        let (msg, _signing_key, verifying_key) = signed_message("device-1", 5);
+        let mut nonces = NonceCache::new(60_000);
+        let err = msg.verify(&verifying_key, 1_500, 5, &mut nonces).unwrap_err();
+        assert_eq!(err.code, "transport_replayed_seq");
+    }
+
+    #[test]
+    fn replaying_the_same_nonce_is_rejected_on_the_second_delivery() {
+        let (msg, _signing_key, verifying_key) = signed_message("device-1", 1);
+        let mut nonces = NonceCache::new(60_000);
+        assert!(msg.verify(&verifying_key, 1_500, 0, &mut nonces).is_ok());
+        let err = msg.verify(&verifying_key, 1_600, 0, &mut nonces).unwrap_err();
+        assert_eq!(err.code, "transport_replayed_nonce");
+    }
+}
@@ -0,0 +1,273 @@
+//! Optional end-to-end payload encryption for [`TransportMessage`]: the
+//! sender derives a symmetric key by x25519 Diffie-Hellman against the
+//! recipient's static public key, then seals the serialized `payload` JSON
+//! with AES-256-GCM under a fresh random IV. The envelope's `device_id`,
+//! `session_id` and `seq` are folded in as AEAD associated data, so a
+//! captured ciphertext can't be replayed into a different session even if
+//! the attacker controls the envelope fields on the outer frame.
+//!
+//! Key derivation hand-rolls a single-block HKDF-SHA256 extract-then-expand
+//! the same way `apps/microclaw-device`'s handshake module does, rather
+//! than pulling in a dedicated `hkdf` crate for one 32-byte key.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hmac::{Hmac, Mac};
+use rand_core::RngCore;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::{
+    hex_decode, hex_encode, DeviceCommand, DeviceStatus, Envelope, MessageKind, ProtocolError,
+    TouchEventPayload, TransportMessage,
+};
+
+const IV_LEN: usize = 12;
+const SCHEME: &str = "a256gcm";
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+fn derive_payload_key(peer_pub: &PublicKey, my_secret: &StaticSecret) -> [u8; 32] {
+    let shared = my_secret.diffie_hellman(peer_pub);
+    let prk = hmac_sha256(b"nanoclaw-transport-payload-salt", shared.as_bytes());
+    hmac_sha256(&prk, b"transport-payload-key")
+}
+
+fn associated_data(envelope: &Envelope) -> Vec<u8> {
+    format!(
+        "{}:{}:{}",
+        envelope.device_id, envelope.session_id, envelope.seq
+    )
+    .into_bytes()
+}
+
+impl TransportMessage {
+    /// Encrypts `payload` in place under a key derived from `peer_pub` and
+    /// `my_secret`, replacing it with `{"enc": "a256gcm", "iv": <hex>, "ct":
+    /// <hex>}`.
+    pub fn encrypt_payload(&mut self, peer_pub: &PublicKey, my_secret: &StaticSecret) {
+        let key_bytes = derive_payload_key(peer_pub, my_secret);
+        let plaintext = serde_json::to_vec(&self.payload).unwrap_or_default();
+
+        let mut iv = [0u8; IV_LEN];
+        rand_core::OsRng.fill_bytes(&mut iv);
+        let aad = associated_data(&self.envelope);
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&iv),
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .expect("encryption over a bounded payload cannot fail");
+
+        self.payload = serde_json::json!({
+            "enc": SCHEME,
+            "iv": hex_encode(&iv),
+            "ct": hex_encode(&ciphertext),
+        });
+    }
+
+    /// Returns the decrypted `payload`, transparently passing it through
+    /// unchanged if it isn't in encrypted form (no `enc` field).
+    pub fn decrypt_payload(
+        &self,
+        peer_pub: &PublicKey,
+        my_secret: &StaticSecret,
+    ) -> Result<Value, ProtocolError> {
+        let Some(obj) = self.payload.as_object() else {
+            return Ok(self.payload.clone());
+        };
+        let Some(enc) = obj.get("enc").and_then(Value::as_str) else {
+            return Ok(self.payload.clone());
+        };
+        if enc != SCHEME {
+            return Err(ProtocolError::new(
+                "transport_unknown_encryption",
+                format!("unsupported encryption scheme '{enc}'"),
+                false,
+            ));
+        }
+        let iv = obj
+            .get("iv")
+            .and_then(Value::as_str)
+            .ok_or_else(|| bad_payload("missing iv"))?;
+        let ct = obj
+            .get("ct")
+            .and_then(Value::as_str)
+            .ok_or_else(|| bad_payload("missing ct"))?;
+        let iv_bytes = hex_decode(iv).map_err(|_| bad_payload("iv is not valid hex"))?;
+        let ct_bytes = hex_decode(ct).map_err(|_| bad_payload("ct is not valid hex"))?;
+        if iv_bytes.len() != IV_LEN {
+            return Err(bad_payload("iv has the wrong length"));
+        }
+
+        let key_bytes = derive_payload_key(peer_pub, my_secret);
+        let aad = associated_data(&self.envelope);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(&iv_bytes),
+                Payload {
+                    msg: &ct_bytes,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                ProtocolError::new(
+                    "transport_decryption_failed",
+                    "payload did not decrypt or authenticate",
+                    false,
+                )
+            })?;
+
+        serde_json::from_slice(&plaintext).map_err(|_| {
+            ProtocolError::new(
+                "transport_decryption_failed",
+                "decrypted payload is not valid json",
+                false,
+            )
+        })
+    }
+
+    /// Like [`Self::as_device_command`], but transparently decrypts first
+    /// when the payload is in encrypted form.
+    pub fn as_device_command_encrypted(
+        &self,
+        peer_pub: &PublicKey,
+        my_secret: &StaticSecret,
+    ) -> Option<DeviceCommand> {
+        if !matches!(self.kind, MessageKind::Command | MessageKind::HostCommand) {
+            return None;
+        }
+        decode(self.decrypt_payload(peer_pub, my_secret).ok()?)
+    }
+
+    /// Like [`Self::as_status_snapshot`], but transparently decrypts first
+    /// when the payload is in encrypted form.
+    pub fn as_status_snapshot_encrypted(
+        &self,
+        peer_pub: &PublicKey,
+        my_secret: &StaticSecret,
+    ) -> Option<DeviceStatus> {
+        if self.kind != MessageKind::StatusSnapshot && self.kind != MessageKind::StatusDelta {
+            return None;
+        }
+        decode(self.decrypt_payload(peer_pub, my_secret).ok()?)
+    }
+
+    /// Like [`Self::as_touch_event`], but transparently decrypts first when
+    /// the payload is in encrypted form.
+    pub fn as_touch_event_encrypted(
+        &self,
+        peer_pub: &PublicKey,
+        my_secret: &StaticSecret,
+    ) -> Option<TouchEventPayload> {
+        if self.kind != MessageKind::TouchEvent {
+            return None;
+        }
+        decode(self.decrypt_payload(peer_pub, my_secret).ok()?)
+    }
+}
+
+fn decode<T: DeserializeOwned>(value: Value) -> Option<T> {
+    serde_json::from_value(value).ok()
+}
+
+fn bad_payload(detail: &str) -> ProtocolError {
+    ProtocolError::new("transport_malformed_encrypted_payload", detail, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Envelope, MessageId, MessageKind};
+
+    fn keypair() -> (StaticSecret, PublicKey) {
+        let secret = StaticSecret::random_from_rng(rand_core::OsRng);
+        let public = PublicKey::from(&secret);
+        (secret, public)
+    }
+
+    fn message(seq: u64, payload: Value) -> TransportMessage {
+        let mut envelope = Envelope::new("device", "device-1", "session-1", MessageId::new("m1"));
+        envelope.seq = seq;
+        TransportMessage::new(envelope, MessageKind::StatusSnapshot, payload)
+    }
+
+    #[test]
+    fn a_payload_round_trips_through_encrypt_and_decrypt() {
+        let (sender_secret, sender_public) = keypair();
+        let (recipient_secret, recipient_public) = keypair();
+
+        let mut msg = message(1, serde_json::json!({"wifi_ok": true}));
+        msg.encrypt_payload(&recipient_public, &sender_secret);
+        assert_eq!(msg.payload["enc"], "a256gcm");
+
+        let decrypted = msg
+            .decrypt_payload(&sender_public, &recipient_secret)
+            .unwrap();
+        assert_eq!(decrypted, serde_json::json!({"wifi_ok": true}));
+    }
+
+    #[test]
+    fn decrypting_with_the_wrong_key_fails() {
+        let (sender_secret, _sender_public) = keypair();
+        let (_recipient_secret, recipient_public) = keypair();
+        let (wrong_secret, _wrong_public) = keypair();
+
+        let mut msg = message(1, serde_json::json!({"wifi_ok": true}));
+        msg.encrypt_payload(&recipient_public, &sender_secret);
+
+        let err = msg
+            .decrypt_payload(&PublicKey::from(&wrong_secret), &wrong_secret)
+            .unwrap_err();
+        assert_eq!(err.code, "transport_decryption_failed");
+    }
+
+    #[test]
+    fn a_tampered_envelope_breaks_the_associated_data_binding() {
+        let (sender_secret, sender_public) = keypair();
+        let (recipient_secret, recipient_public) = keypair();
+
+        let mut msg = message(1, serde_json::json!({"wifi_ok": true}));
+        msg.encrypt_payload(&recipient_public, &sender_secret);
+        msg.envelope.seq = 2;
+
+        let err = msg
+            .decrypt_payload(&sender_public, &recipient_secret)
+            .unwrap_err();
+        assert_eq!(err.code, "transport_decryption_failed");
+    }
+
+    #[test]
+    fn plaintext_payloads_pass_through_decrypt_unchanged() {
+        let (secret, public) = keypair();
+        let msg = message(1, serde_json::json!({"wifi_ok": false}));
+        let decrypted = msg.decrypt_payload(&public, &secret).unwrap();
+        assert_eq!(decrypted, serde_json::json!({"wifi_ok": false}));
+    }
+
+    #[test]
+    fn as_status_snapshot_encrypted_transparently_decrypts() {
+        let (sender_secret, sender_public) = keypair();
+        let (recipient_secret, recipient_public) = keypair();
+
+        let mut msg = message(1, serde_json::to_value(DeviceStatus::default()).unwrap());
+        msg.encrypt_payload(&recipient_public, &sender_secret);
+
+        let status = msg
+            .as_status_snapshot_encrypted(&sender_public, &recipient_secret)
+            .unwrap();
+        assert_eq!(status, DeviceStatus::default());
+    }
+}
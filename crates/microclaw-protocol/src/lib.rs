@@ -2,6 +2,28 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+mod auth;
+pub use auth::NonceCache;
+
+mod encryption;
+
+/// Lowercase-hex encoding shared by [`auth`] and [`encryption`], which both
+/// need to stuff raw signature/nonce/IV/ciphertext bytes into JSON string
+/// fields -- hex rather than base64, matching the rest of the wire format.
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn hex_decode(raw: &str) -> Result<Vec<u8>, ()> {
+    if raw.len() % 2 != 0 {
+        return Err(());
+    }
+    (0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&raw[i..i + 2], 16).map_err(|_| ()))
+        .collect()
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MessageId(String);
 
@@ -53,6 +75,7 @@ pub enum MessageKind {
     TouchEvent,
     Heartbeat,
     HostCommand,
+    InjectInput,
     #[serde(other)]
     Unknown,
 }
@@ -65,6 +88,28 @@ pub struct DeviceCommand {
     pub args: Value,
 }
 
+/// Accepts either a single `T` or an array of them in the same payload
+/// position, for callers that want to submit one logical unit of work in
+/// one round trip without forcing every caller to wrap a single item in an
+/// array. Variant order matters for `#[serde(untagged)]`: `Many` is tried
+/// first so a JSON array always parses as a batch rather than failing to
+/// match `One` and falling through.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrVec<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+impl<T> OneOrVec<T> {
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            OneOrVec::Many(items) => items,
+            OneOrVec::One(item) => vec![item],
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub struct DeviceStatus {
@@ -124,7 +169,7 @@ pub enum DeviceAction {
     Unknown,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TouchPhase {
     Down,
@@ -149,6 +194,25 @@ pub struct TouchEventPayload {
     pub raw_timestamp_ms: Option<u64>,
 }
 
+/// A host-driven UI automation event, analogous to how rustdesk uses enigo
+/// to replay input on a remote machine: a higher-level gesture description
+/// rather than a raw touch sample, so injected input can be validated and
+/// routed the same way physical touches are.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "gesture")]
+pub enum InputInjectionPayload {
+    Tap { x: u16, y: u16 },
+    LongPress { x: u16, y: u16 },
+    Swipe { direction: SwipeDirectionWire },
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SwipeDirectionWire {
+    Left,
+    Right,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct TransportMessage {
@@ -214,6 +278,18 @@ impl TransportMessage {
         self.payload_as().ok()
     }
 
+    /// Like [`Self::as_device_command`], but also accepts a JSON array of
+    /// commands in the payload position so a caller can submit and correlate
+    /// a logical batch in one frame instead of sending one frame per item.
+    pub fn as_device_commands(&self) -> Option<Vec<DeviceCommand>> {
+        if !matches!(self.kind, MessageKind::Command | MessageKind::HostCommand) {
+            return None;
+        }
+        self.payload_as::<OneOrVec<DeviceCommand>>()
+            .ok()
+            .map(OneOrVec::into_vec)
+    }
+
     pub fn as_status_snapshot(&self) -> Option<DeviceStatus> {
         if self.kind != MessageKind::StatusSnapshot && self.kind != MessageKind::StatusDelta {
             return None;
@@ -221,6 +297,13 @@ impl TransportMessage {
         self.payload_as().ok()
     }
 
+    pub fn as_input_injection(&self) -> Option<InputInjectionPayload> {
+        if self.kind != MessageKind::InjectInput {
+            return None;
+        }
+        self.payload_as().ok()
+    }
+
     pub fn is_expired(&self, now_ms: u64) -> bool {
         match (self.issued_at, self.ttl_ms) {
             (Some(ts), Some(ttl)) => now_ms.saturating_sub(ts) > ttl,
@@ -1,23 +1,112 @@
 use std::collections::{HashMap, VecDeque};
 
+/// Per-group bookkeeping for deficit round-robin draining: how many items
+/// have been dropped because the group was at `capacity`, and how much
+/// "credit" the group currently holds while `drain_round` is in progress.
+#[derive(Default)]
+struct GroupState {
+    dropped: u64,
+    deficit: usize,
+}
+
 pub struct GroupQueue<T> {
     per_group: HashMap<String, VecDeque<T>>,
+    state: HashMap<String, GroupState>,
+    order: VecDeque<String>,
     capacity: usize,
 }
 
 impl<T> GroupQueue<T> {
     pub fn new(capacity: usize) -> Self {
-        Self { per_group: HashMap::new(), capacity }
+        Self {
+            per_group: HashMap::new(),
+            state: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
     }
 
     pub fn push(&mut self, group: &str, item: T) {
+        if !self.per_group.contains_key(group) {
+            self.order.push_back(group.to_string());
+        }
         let q = self.per_group.entry(group.to_string()).or_default();
         if q.len() < self.capacity {
             q.push_back(item);
+        } else {
+            self.state.entry(group.to_string()).or_default().dropped += 1;
         }
     }
 
     pub fn pop(&mut self, group: &str) -> Option<T> {
         self.per_group.get_mut(group).and_then(|q| q.pop_front())
     }
+
+    /// Number of items currently queued for `group`.
+    pub fn depth(&self, group: &str) -> usize {
+        self.per_group.get(group).map_or(0, VecDeque::len)
+    }
+
+    /// Number of items dropped for `group` because it was at `capacity`
+    /// when pushed to.
+    pub fn dropped(&self, group: &str) -> u64 {
+        self.state.get(group).map_or(0, |s| s.dropped)
+    }
+
+    /// Drain up to `max_items` total across all groups in deficit
+    /// round-robin order: each group earns one unit of credit per visit
+    /// of the rotating cursor and may pop one item per unit of credit it
+    /// holds, so a group with a deep backlog keeps draining across
+    /// several cursor passes while an empty group never accumulates an
+    /// unbounded lead. No group is starved by another.
+    pub fn drain_round(&mut self, max_items: usize) -> Vec<(String, T)> {
+        let mut drained = Vec::new();
+        if max_items == 0 || self.order.is_empty() {
+            return drained;
+        }
+
+        let groups = self.order.len();
+        let mut visited_empty_in_a_row = 0;
+
+        while drained.len() < max_items && visited_empty_in_a_row < groups {
+            let group = match self.order.pop_front() {
+                Some(group) => group,
+                None => break,
+            };
+            self.order.push_back(group.clone());
+
+            let has_items = self.per_group.get(&group).is_some_and(|q| !q.is_empty());
+            if !has_items {
+                visited_empty_in_a_row += 1;
+                continue;
+            }
+
+            let deficit = &mut self.state.entry(group.clone()).or_default().deficit;
+            *deficit += 1;
+
+            let mut popped_any = false;
+            while drained.len() < max_items {
+                let Some(deficit) = self.state.get_mut(&group).map(|s| &mut s.deficit) else {
+                    break;
+                };
+                if *deficit == 0 {
+                    break;
+                }
+                let Some(item) = self.per_group.get_mut(&group).and_then(|q| q.pop_front()) else {
+                    break;
+                };
+                *deficit -= 1;
+                drained.push((group.clone(), item));
+                popped_any = true;
+            }
+
+            if let Some(state) = self.state.get_mut(&group) {
+                state.deficit = 0;
+            }
+
+            visited_empty_in_a_row = if popped_any { 0 } else { visited_empty_in_a_row + 1 };
+        }
+
+        drained
+    }
 }
@@ -8,3 +8,55 @@ fn preserves_fifo_per_group() {
     assert_eq!(q.pop("g1"), Some("a"));
     assert_eq!(q.pop("g1"), Some("b"));
 }
+
+#[test]
+fn push_beyond_capacity_is_counted_as_dropped() {
+    let mut q = GroupQueue::new(1);
+    q.push("g1", "a");
+    q.push("g1", "b");
+    assert_eq!(q.depth("g1"), 1);
+    assert_eq!(q.dropped("g1"), 1);
+    assert_eq!(q.pop("g1"), Some("a"));
+}
+
+#[test]
+fn drain_round_visits_groups_fairly_instead_of_starving_a_busy_one() {
+    let mut q = GroupQueue::new(10);
+    for item in ["a1", "a2", "a3"] {
+        q.push("busy", item);
+    }
+    q.push("quiet", "b1");
+
+    let drained = q.drain_round(2);
+    let groups: Vec<&str> = drained.iter().map(|(group, _)| group.as_str()).collect();
+    assert_eq!(groups, vec!["busy", "quiet"]);
+    assert_eq!(q.depth("busy"), 2);
+    assert_eq!(q.depth("quiet"), 0);
+}
+
+#[test]
+fn drain_round_drains_busy_group_across_multiple_rounds_without_starving_others() {
+    let mut q = GroupQueue::new(10);
+    for item in 0..4 {
+        q.push("busy", item);
+    }
+    q.push("quiet", 100);
+
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        seen.extend(q.drain_round(1));
+    }
+
+    let quiet_count = seen.iter().filter(|(group, _)| group == "quiet").count();
+    assert_eq!(quiet_count, 1);
+    assert_eq!(seen.len(), 5);
+}
+
+#[test]
+fn drain_round_stops_when_all_groups_are_empty() {
+    let mut q: GroupQueue<&str> = GroupQueue::new(10);
+    q.push("g1", "a");
+    let drained = q.drain_round(10);
+    assert_eq!(drained.len(), 1);
+    assert_eq!(q.drain_round(10), Vec::new());
+}
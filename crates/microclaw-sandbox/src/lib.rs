@@ -35,31 +35,138 @@ impl Mount {
 #[derive(Debug, Clone)]
 pub enum PolicyError {
     MountNotAllowed(String),
+    /// A mount resolved (via `..` traversal or a symlink) to a path outside
+    /// the allowlist, despite its literal source looking like it belonged
+    /// to an allowed prefix. Distinguished from [`Self::MountNotAllowed`] so
+    /// callers can tell an escape attempt apart from an ordinary allowlist
+    /// miss.
+    MountEscape(String),
+    EgressNotAllowed(String),
+    /// A declared egress host has a port-specific allowlist entry (e.g.
+    /// `api.example.com:443`) but the requested port doesn't match it.
+    PortNotAllowed(String, u16),
+    /// A host's resolved IP wasn't explicitly allowlisted (as an exact
+    /// address or a CIDR range) -- either it's outside the allowlist
+    /// entirely, or it's a private/loopback address a DNS response tried
+    /// to rebind an allowlisted hostname onto.
+    ResolvedIpBlocked(String),
+}
+
+/// How [`MountPolicy::validate_with_resolution`] resolves a mount source
+/// before comparing it against the allowlist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathResolution {
+    /// Canonicalize via the filesystem, following symlinks. The path must
+    /// already exist; used for mounts about to be handed to a running
+    /// container.
+    MustExist,
+    /// Resolve `.`/`..` components lexically without touching the
+    /// filesystem. Used for paths that may not exist yet (e.g. a mount
+    /// target about to be created) and by [`MountPolicy::validate`], which
+    /// needs to work without real files on disk.
+    LexicalOnly,
 }
 
 pub struct MountPolicy {
-    allowed_prefixes: Vec<String>,
+    allowed_prefixes: Vec<std::path::PathBuf>,
 }
 
 impl MountPolicy {
     pub fn new(allowed_prefixes: Vec<String>) -> Self {
+        let allowed_prefixes = allowed_prefixes
+            .iter()
+            .map(|prefix| canonicalize_or_normalize(std::path::Path::new(prefix)))
+            .collect();
         Self { allowed_prefixes }
     }
 
+    /// Validates `mounts` against the allowlist, resolving each source
+    /// lexically (see [`PathResolution::LexicalOnly`]). This still catches
+    /// `..` traversal but not a symlink planted on disk; prefer
+    /// [`Self::validate_with_resolution`] with [`PathResolution::MustExist`]
+    /// once the mount sources are known to exist.
     pub fn validate(&self, mounts: &[Mount]) -> Result<(), PolicyError> {
+        self.validate_with_resolution(mounts, PathResolution::LexicalOnly)
+    }
+
+    pub fn validate_with_resolution(
+        &self,
+        mounts: &[Mount],
+        resolution: PathResolution,
+    ) -> Result<(), PolicyError> {
         for mount in mounts {
+            let raw = std::path::Path::new(&mount.source);
+            let looked_allowed = self
+                .allowed_prefixes
+                .iter()
+                .any(|prefix| raw.starts_with(prefix));
+
+            let resolved = match resolution {
+                PathResolution::MustExist => std::fs::canonicalize(raw)
+                    .map_err(|_| PolicyError::MountNotAllowed(mount.source.clone()))?,
+                PathResolution::LexicalOnly => lexically_normalize(raw),
+            };
             let allowed = self
                 .allowed_prefixes
                 .iter()
-                .any(|prefix| mount.source.starts_with(prefix));
+                .any(|prefix| resolved.starts_with(prefix));
+
             if !allowed {
-                return Err(PolicyError::MountNotAllowed(mount.source.clone()));
+                return Err(if looked_allowed {
+                    PolicyError::MountEscape(mount.source.clone())
+                } else {
+                    PolicyError::MountNotAllowed(mount.source.clone())
+                });
             }
         }
         Ok(())
     }
 }
 
+/// Resolves `.`/`..` path components without touching the filesystem.
+fn lexically_normalize(path: &std::path::Path) -> std::path::PathBuf {
+    let mut out = std::path::PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+/// Canonicalizes `path` via the filesystem when it exists, falling back to
+/// a lexical normalization otherwise (used for allowlist prefixes, which
+/// may be configured ahead of the directories they name existing).
+fn canonicalize_or_normalize(path: &std::path::Path) -> std::path::PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| lexically_normalize(path))
+}
+
+/// Parses a `a.b.c.d/prefix` CIDR entry, returning `None` for a plain IP
+/// or hostname entry (those are handled as a literal/wildcard match
+/// instead).
+fn parse_cidr(entry: &str) -> Option<(std::net::Ipv4Addr, u8)> {
+    let (addr, prefix) = entry.split_once('/')?;
+    let network = addr.parse::<std::net::Ipv4Addr>().ok()?;
+    let prefix_len: u8 = prefix.parse().ok()?;
+    if prefix_len > 32 {
+        return None;
+    }
+    Some((network, prefix_len))
+}
+
+fn ip_in_cidr(ip: std::net::Ipv4Addr, network: std::net::Ipv4Addr, prefix_len: u8) -> bool {
+    let mask = if prefix_len == 0 {
+        0u32
+    } else {
+        u32::MAX << (32 - prefix_len)
+    };
+    (u32::from(ip) & mask) == (u32::from(network) & mask)
+}
+
 pub struct EgressPolicy {
     allowlist: Vec<String>,
 }
@@ -69,17 +176,160 @@ impl EgressPolicy {
         Self { allowlist }
     }
 
+    /// Checks `host` (no port) against the allowlist. Entries may be an
+    /// exact hostname, a `*.domain` wildcard matching any subdomain (but not
+    /// the bare domain itself), or a literal IP address.
     pub fn allows(&self, host: &str) -> bool {
-        self.allowlist.iter().any(|entry| entry == host)
+        self.allowlist.iter().any(|entry| Self::entry_matches_host(entry, host))
+    }
+
+    /// Checks `host:port` against the allowlist. An allowlist entry without
+    /// a `:port` suffix matches any port for that host; an entry with a
+    /// port only matches that exact port.
+    pub fn allows_with_port(&self, host: &str, port: u16) -> bool {
+        self.allowlist.iter().any(|entry| {
+            match entry.rsplit_once(':') {
+                Some((entry_host, entry_port)) if entry_port.parse::<u16>().is_ok() => {
+                    entry_port.parse::<u16>() == Ok(port) && Self::entry_matches_host(entry_host, host)
+                }
+                _ => Self::entry_matches_host(entry, host),
+            }
+        })
+    }
+
+    /// Checks a resolved IP address against the allowlist, for the case
+    /// where the sandbox only has the post-DNS connection target rather
+    /// than the original hostname (a hostname being allowlisted does not
+    /// implicitly allow every IP it might resolve to). An allowlist entry
+    /// may be a literal IP or a CIDR range (e.g. `10.0.0.0/24`); deny-by-
+    /// default means a private/loopback address (RFC1918, `127.0.0.0/8`)
+    /// is rejected unless it matches an allowlist entry explicitly, so a
+    /// hostname resolving -- or being rebound via DNS -- to an internal
+    /// address can't slip through just because the hostname was allowed.
+    pub fn allows_resolved_ip(&self, ip: &str) -> bool {
+        let Ok(parsed) = ip.parse::<std::net::Ipv4Addr>() else {
+            return false;
+        };
+        self.allowlist
+            .iter()
+            .any(|entry| Self::entry_matches_ip(entry, parsed))
+    }
+
+    fn entry_matches_ip(entry: &str, ip: std::net::Ipv4Addr) -> bool {
+        if let Some((network, prefix_len)) = parse_cidr(entry) {
+            return ip_in_cidr(ip, network, prefix_len);
+        }
+        entry.parse::<std::net::Ipv4Addr>() == Ok(ip)
+    }
+
+    fn entry_matches_host(entry: &str, host: &str) -> bool {
+        if let Some(suffix) = entry.strip_prefix("*.") {
+            return host.ends_with(suffix)
+                && host.len() > suffix.len()
+                && host.as_bytes()[host.len() - suffix.len() - 1] == b'.';
+        }
+        entry == host
+    }
+
+    /// Validates every host in `hosts` against the allowlist (wildcards
+    /// included - see [`Self::allows`]), symmetric to
+    /// [`MountPolicy::validate`] for mounts. Fails on the first disallowed
+    /// host.
+    pub fn validate(&self, hosts: &[String]) -> Result<(), PolicyError> {
+        for host in hosts {
+            if !self.allows(host) {
+                return Err(PolicyError::EgressNotAllowed(host.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::validate`], but also checks `spec`'s declared port (via
+    /// [`Self::allows_with_port`]) and any already-resolved IP (via
+    /// [`Self::allows_resolved_ip`]) for each host, for a caller that
+    /// resolved DNS before building the run command and wants that
+    /// resolution checked against the same allowlist rather than trusted
+    /// blindly.
+    pub fn validate_spec(&self, spec: &RunSpec) -> Result<(), PolicyError> {
+        for host in &spec.egress_hosts {
+            match spec.egress_ports.get(host) {
+                Some(&port) => {
+                    if !self.allows_with_port(host, port) {
+                        return Err(PolicyError::PortNotAllowed(host.clone(), port));
+                    }
+                }
+                None => {
+                    if !self.allows(host) {
+                        return Err(PolicyError::EgressNotAllowed(host.clone()));
+                    }
+                }
+            }
+            if let Some(ip) = spec.egress_resolved_ips.get(host) {
+                if !self.allows_resolved_ip(ip) {
+                    return Err(PolicyError::ResolvedIpBlocked(ip.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Isolation flags a container runner should add when this policy is
+    /// attached: `--network none` when nothing is allowed (the common case
+    /// for tool containers with no egress need), otherwise an `--add-host`
+    /// pin per allowed hostname plus proxy env vars pointing at the
+    /// egress-filtering proxy that re-checks the same allowlist at connect
+    /// time. `*.domain` wildcard entries can't be pinned to a single host
+    /// and are enforced by the proxy only; IP and CIDR entries name no
+    /// hostname and get no pin, and a `host:port` entry is pinned by its
+    /// host.
+    pub fn isolation_args(&self) -> Vec<String> {
+        if self.allowlist.is_empty() {
+            return vec!["--network".to_string(), "none".to_string()];
+        }
+        let mut args = Vec::new();
+        for entry in &self.allowlist {
+            if entry.starts_with("*.") {
+                continue;
+            }
+            let host = match entry.rsplit_once(':') {
+                Some((host, port)) if port.parse::<u16>().is_ok() => host,
+                _ => entry.as_str(),
+            };
+            if parse_cidr(host).is_some() || host.parse::<std::net::Ipv4Addr>().is_ok() {
+                continue;
+            }
+            args.push("--add-host".to_string());
+            args.push(format!("{}:127.0.0.1", host));
+        }
+        args.push("--env".to_string());
+        args.push(format!("HTTPS_PROXY=http://127.0.0.1:{}", EGRESS_PROXY_PORT));
+        args.push("--env".to_string());
+        args.push(format!("HTTP_PROXY=http://127.0.0.1:{}", EGRESS_PROXY_PORT));
+        args
     }
 }
 
+const EGRESS_PROXY_PORT: u16 = 3128;
+
 #[derive(Debug, Clone)]
 pub struct RunSpec {
     pub image: String,
     pub command: Vec<String>,
     pub mounts: Vec<Mount>,
     pub env: Vec<(String, String)>,
+    /// Hosts this run declares it needs outbound access to, validated
+    /// against an `EgressPolicy` before the command is built.
+    pub egress_hosts: Vec<String>,
+    /// Port declared for a subset of `egress_hosts`, checked against a
+    /// port-specific allowlist entry via `EgressPolicy::allows_with_port`.
+    /// A host with no entry here is validated by hostname alone.
+    pub egress_ports: std::collections::HashMap<String, u16>,
+    /// The IP a subset of `egress_hosts` already resolved to, when the
+    /// caller resolved DNS before building the command, checked against
+    /// `EgressPolicy::allows_resolved_ip` so a rebind to a private or
+    /// otherwise unlisted address can't slip through on a hostname match
+    /// alone.
+    pub egress_resolved_ips: std::collections::HashMap<String, String>,
 }
 
 impl RunSpec {
@@ -89,6 +339,9 @@ impl RunSpec {
             command,
             mounts: Vec::new(),
             env: Vec::new(),
+            egress_hosts: Vec::new(),
+            egress_ports: std::collections::HashMap::new(),
+            egress_resolved_ips: std::collections::HashMap::new(),
         }
     }
 
@@ -99,13 +352,44 @@ impl RunSpec {
     pub fn add_env(&mut self, key: &str, value: &str) {
         self.env.push((key.to_string(), value.to_string()));
     }
+
+    pub fn add_egress_host(&mut self, host: &str) {
+        self.egress_hosts.push(host.to_string());
+    }
+
+    /// Like [`Self::add_egress_host`], but also declares the port this run
+    /// will connect to `host` on, so a port-specific allowlist entry (e.g.
+    /// `api.example.com:443`) can be enforced.
+    pub fn add_egress_host_with_port(&mut self, host: &str, port: u16) {
+        self.egress_hosts.push(host.to_string());
+        self.egress_ports.insert(host.to_string(), port);
+    }
+
+    /// Records the IP `host` already resolved to, for `EgressPolicy` to
+    /// check via `allows_resolved_ip` in addition to the hostname match.
+    pub fn set_egress_resolved_ip(&mut self, host: &str, ip: &str) {
+        self.egress_resolved_ips.insert(host.to_string(), ip.to_string());
+    }
 }
 
 pub struct AppleContainerRunner;
 
 impl AppleContainerRunner {
-    pub fn build_command(spec: &RunSpec) -> Vec<String> {
+    /// Builds the `container run` invocation for `spec`. When `egress` is
+    /// attached, `spec` is validated against it first (see
+    /// [`EgressPolicy::validate_spec`]), then the policy's isolation flags
+    /// (see [`EgressPolicy::isolation_args`]) are added, symmetric to how
+    /// `spec.mounts` is expected to be checked against a `MountPolicy`
+    /// before calling this.
+    pub fn build_command(
+        spec: &RunSpec,
+        egress: Option<&EgressPolicy>,
+    ) -> Result<Vec<String>, PolicyError> {
         let mut args = vec!["container".to_string(), "run".to_string(), "--rm".to_string()];
+        if let Some(policy) = egress {
+            policy.validate_spec(spec)?;
+            args.extend(policy.isolation_args());
+        }
         for mount in &spec.mounts {
             args.push("--mount".to_string());
             args.push(mount.to_apple_arg());
@@ -116,15 +400,24 @@ impl AppleContainerRunner {
         }
         args.push(spec.image.clone());
         args.extend(spec.command.iter().cloned());
-        args
+        Ok(args)
     }
 }
 
 pub struct DockerRunner;
 
 impl DockerRunner {
-    pub fn build_command(spec: &RunSpec) -> Vec<String> {
+    /// Builds the `docker run` invocation for `spec`; see
+    /// [`AppleContainerRunner::build_command`] for the `egress` contract.
+    pub fn build_command(
+        spec: &RunSpec,
+        egress: Option<&EgressPolicy>,
+    ) -> Result<Vec<String>, PolicyError> {
         let mut args = vec!["docker".to_string(), "run".to_string(), "--rm".to_string()];
+        if let Some(policy) = egress {
+            policy.validate_spec(spec)?;
+            args.extend(policy.isolation_args());
+        }
         for mount in &spec.mounts {
             args.push("-v".to_string());
             args.push(mount.to_docker_arg());
@@ -135,7 +428,7 @@ impl DockerRunner {
         }
         args.push(spec.image.clone());
         args.extend(spec.command.iter().cloned());
-        args
+        Ok(args)
     }
 }
 
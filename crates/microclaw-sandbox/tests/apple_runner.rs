@@ -1,4 +1,4 @@
-use microclaw_sandbox::{AppleContainerRunner, Mount, RunSpec};
+use microclaw_sandbox::{AppleContainerRunner, EgressPolicy, Mount, PolicyError, RunSpec};
 
 #[test]
 fn builds_apple_container_command() {
@@ -6,7 +6,7 @@ fn builds_apple_container_command() {
     spec.add_mount(Mount::read_only("/host/data", "/workspace/data"));
     spec.add_env("TOKEN", "redacted");
 
-    let args = AppleContainerRunner::build_command(&spec);
+    let args = AppleContainerRunner::build_command(&spec, None).unwrap();
     assert_eq!(args[0], "container");
     assert!(args.contains(&"--rm".to_string()));
     assert!(args.contains(&"--mount".to_string()));
@@ -14,3 +14,71 @@ fn builds_apple_container_command() {
     assert!(args.iter().any(|arg| arg == "TOKEN=redacted"));
     assert!(args.iter().any(|arg| arg == "microclaw-agent:latest"));
 }
+
+#[test]
+fn empty_egress_allowlist_isolates_network() {
+    let spec = RunSpec::new("microclaw-agent:latest", vec!["/bin/sh".into()]);
+    let policy = EgressPolicy::new(vec![]);
+
+    let args = AppleContainerRunner::build_command(&spec, Some(&policy)).unwrap();
+    assert!(args.contains(&"--network".to_string()));
+    assert!(args.contains(&"none".to_string()));
+}
+
+#[test]
+fn allowed_egress_hosts_get_pinned_and_proxied() {
+    let mut spec = RunSpec::new("microclaw-agent:latest", vec!["/bin/sh".into()]);
+    spec.add_egress_host("api.example.com");
+    let policy = EgressPolicy::new(vec!["api.example.com".to_string()]);
+
+    let args = AppleContainerRunner::build_command(&spec, Some(&policy)).unwrap();
+    assert!(args.iter().any(|arg| arg == "api.example.com:127.0.0.1"));
+    assert!(args.iter().any(|arg| arg.starts_with("HTTPS_PROXY=")));
+}
+
+#[test]
+fn undeclared_egress_host_is_rejected_before_building_command() {
+    let mut spec = RunSpec::new("microclaw-agent:latest", vec!["/bin/sh".into()]);
+    spec.add_egress_host("evil.example.com");
+    let policy = EgressPolicy::new(vec!["api.example.com".to_string()]);
+
+    let err = AppleContainerRunner::build_command(&spec, Some(&policy)).unwrap_err();
+    assert!(matches!(err, PolicyError::EgressNotAllowed(host) if host == "evil.example.com"));
+}
+
+#[test]
+fn a_port_outside_the_pinned_entry_is_rejected_before_building_command() {
+    let mut spec = RunSpec::new("microclaw-agent:latest", vec!["/bin/sh".into()]);
+    spec.add_egress_host_with_port("api.example.com", 8080);
+    let policy = EgressPolicy::new(vec!["api.example.com:443".to_string()]);
+
+    let err = AppleContainerRunner::build_command(&spec, Some(&policy)).unwrap_err();
+    assert!(matches!(
+        err,
+        PolicyError::PortNotAllowed(host, port) if host == "api.example.com" && port == 8080
+    ));
+}
+
+#[test]
+fn a_rebind_to_a_private_address_is_rejected_before_building_command() {
+    let mut spec = RunSpec::new("microclaw-agent:latest", vec!["/bin/sh".into()]);
+    spec.add_egress_host("api.example.com");
+    spec.set_egress_resolved_ip("api.example.com", "10.0.0.5");
+    let policy = EgressPolicy::new(vec!["api.example.com".to_string()]);
+
+    let err = AppleContainerRunner::build_command(&spec, Some(&policy)).unwrap_err();
+    assert!(matches!(err, PolicyError::ResolvedIpBlocked(ip) if ip == "10.0.0.5"));
+}
+
+#[test]
+fn an_explicitly_allowlisted_resolved_ip_builds_the_command() {
+    let mut spec = RunSpec::new("microclaw-agent:latest", vec!["/bin/sh".into()]);
+    spec.add_egress_host("api.example.com");
+    spec.set_egress_resolved_ip("api.example.com", "203.0.113.9");
+    let policy = EgressPolicy::new(vec![
+        "api.example.com".to_string(),
+        "203.0.113.0/24".to_string(),
+    ]);
+
+    assert!(AppleContainerRunner::build_command(&spec, Some(&policy)).is_ok());
+}
@@ -1,4 +1,4 @@
-use microclaw_sandbox::{EgressPolicy, Mount, MountPolicy, PolicyError};
+use microclaw_sandbox::{EgressPolicy, Mount, MountPolicy, PathResolution, PolicyError};
 
 #[test]
 fn mount_allowlist_allows_prefix() {
@@ -26,3 +26,93 @@ fn egress_allows_allowlisted() {
     let policy = EgressPolicy::new(vec!["api.example.com".to_string()]);
     assert!(policy.allows("api.example.com"));
 }
+
+#[test]
+fn egress_wildcard_matches_subdomains_only() {
+    let policy = EgressPolicy::new(vec!["*.example.com".to_string()]);
+    assert!(policy.allows("api.example.com"));
+    assert!(!policy.allows("example.com"));
+    assert!(!policy.allows("evilexample.com"));
+}
+
+#[test]
+fn egress_port_specific_entry_restricts_port() {
+    let policy = EgressPolicy::new(vec!["api.example.com:443".to_string()]);
+    assert!(policy.allows_with_port("api.example.com", 443));
+    assert!(!policy.allows_with_port("api.example.com", 8080));
+}
+
+#[test]
+fn egress_entry_without_port_allows_any_port() {
+    let policy = EgressPolicy::new(vec!["api.example.com".to_string()]);
+    assert!(policy.allows_with_port("api.example.com", 8080));
+}
+
+#[test]
+fn egress_resolved_ip_must_be_explicitly_allowlisted() {
+    let policy = EgressPolicy::new(vec!["api.example.com".to_string(), "10.0.0.5".to_string()]);
+    assert!(policy.allows_resolved_ip("10.0.0.5"));
+    assert!(!policy.allows_resolved_ip("10.0.0.6"));
+}
+
+#[test]
+fn egress_resolved_ip_matches_an_allowlisted_cidr() {
+    let policy = EgressPolicy::new(vec!["203.0.113.0/24".to_string()]);
+    assert!(policy.allows_resolved_ip("203.0.113.42"));
+    assert!(!policy.allows_resolved_ip("203.0.114.1"));
+}
+
+#[test]
+fn egress_resolved_ip_rejects_private_and_loopback_addresses_by_default() {
+    let policy = EgressPolicy::new(vec!["api.example.com".to_string()]);
+    assert!(!policy.allows_resolved_ip("10.1.2.3"));
+    assert!(!policy.allows_resolved_ip("192.168.1.1"));
+    assert!(!policy.allows_resolved_ip("127.0.0.1"));
+}
+
+#[test]
+fn egress_resolved_ip_allows_a_private_address_when_explicitly_permitted() {
+    let policy = EgressPolicy::new(vec!["10.0.0.0/8".to_string()]);
+    assert!(policy.allows_resolved_ip("10.1.2.3"));
+}
+
+#[test]
+fn mount_traversal_escape_is_rejected_distinctly() {
+    let policy = MountPolicy::new(vec!["/allowed".to_string()]);
+    let mounts = vec![Mount::read_only("/allowed/../etc/passwd", "/workspace/data")];
+    let err = policy.validate(&mounts).unwrap_err();
+    assert!(matches!(err, PolicyError::MountEscape(ref source) if source == "/allowed/../etc/passwd"));
+}
+
+#[test]
+fn mount_symlink_escape_is_rejected_with_must_exist_resolution() {
+    let tmp = tempfile::tempdir().unwrap();
+    let allowed_dir = tmp.path().join("allowed");
+    let secret_dir = tmp.path().join("secret");
+    std::fs::create_dir(&allowed_dir).unwrap();
+    std::fs::create_dir(&secret_dir).unwrap();
+    let escape_link = allowed_dir.join("escape");
+    std::os::unix::fs::symlink(&secret_dir, &escape_link).unwrap();
+
+    let policy = MountPolicy::new(vec![allowed_dir.to_str().unwrap().to_string()]);
+    let mounts = vec![Mount::read_only(escape_link.to_str().unwrap(), "/workspace/data")];
+
+    let err = policy
+        .validate_with_resolution(&mounts, PathResolution::MustExist)
+        .unwrap_err();
+    assert!(matches!(err, PolicyError::MountEscape(_)));
+}
+
+#[test]
+fn egress_validate_accepts_only_allowlisted_hosts() {
+    let policy = EgressPolicy::new(vec!["*.example.com".to_string()]);
+    assert!(policy.validate(&["api.example.com".to_string()]).is_ok());
+    let err = policy.validate(&["other.com".to_string()]).unwrap_err();
+    assert!(matches!(err, PolicyError::EgressNotAllowed(host) if host == "other.com"));
+}
+
+#[test]
+fn egress_isolation_args_are_none_network_when_allowlist_empty() {
+    let policy = EgressPolicy::new(vec![]);
+    assert_eq!(policy.isolation_args(), vec!["--network", "none"]);
+}
@@ -1,15 +1,157 @@
-use rusqlite::{params, Connection, Result as SqlResult};
+use microclaw_protocol::TransportMessage;
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use std::collections::HashMap;
+use std::fmt;
 use std::path::Path;
 
+/// How many delivery attempts (the original send plus every `nack`) a
+/// queued command gets before [`Store::nack`] drops it to `dead_letter`
+/// instead of rescheduling it again.
+const MAX_DELIVERY_ATTEMPTS: i64 = 5;
+const BACKOFF_BASE_MS: u64 = 1_000;
+const BACKOFF_CAP_MS: u64 = 5 * 60_000;
+
+fn backoff_for_attempt(attempt: u32) -> u64 {
+    BACKOFF_BASE_MS
+        .saturating_mul(1u64 << attempt.min(16))
+        .min(BACKOFF_CAP_MS)
+}
+
 pub struct Store {
     conn: Connection,
+    /// Whether `messages_fts` was successfully created at open time.
+    /// `false` on a SQLite build compiled without FTS5, in which case
+    /// [`Store::search_messages`] falls back to a `LIKE` scan instead of
+    /// failing outright.
+    fts_available: bool,
+}
+
+/// Why a `Store` failed to open or migrate, distinct from a plain
+/// `rusqlite::Error` so [`Store::open`] can report the one failure mode
+/// that isn't a SQLite error at all: a database written by a newer binary
+/// than the one trying to open it.
+#[derive(Debug)]
+pub enum StoreError {
+    Sql(rusqlite::Error),
+    /// `found` is the `schema_version` already on disk; `max_known` is the
+    /// highest version this binary's migration list knows how to produce.
+    /// Opening proceeds no further, so an old binary never runs against a
+    /// schema shape it doesn't understand.
+    UnsupportedSchemaVersion { found: i64, max_known: i64 },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Sql(err) => write!(f, "{err}"),
+            StoreError::UnsupportedSchemaVersion { found, max_known } => write!(
+                f,
+                "database schema_version {found} is newer than this binary's highest known migration ({max_known})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(err: rusqlite::Error) -> Self {
+        StoreError::Sql(err)
+    }
+}
+
+/// The outcome of a successful [`Store::atomic_commit`]: the global
+/// versionstamp every mutated key was just stamped with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitResult {
+    pub versionstamp: u64,
+}
+
+/// Why an [`Store::atomic_commit`] call didn't apply.
+#[derive(Debug)]
+pub enum ConflictError {
+    Sql(rusqlite::Error),
+    /// One of the `checks` didn't match what's on disk: `key` was at
+    /// `found`, not the `expected` version the caller compared against.
+    VersionMismatch { key: String, expected: u64, found: u64 },
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConflictError::Sql(err) => write!(f, "{err}"),
+            ConflictError::VersionMismatch { key, expected, found } => write!(
+                f,
+                "key '{key}' is at version {found}, expected {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConflictError {}
+
+impl From<rusqlite::Error> for ConflictError {
+    fn from(err: rusqlite::Error) -> Self {
+        ConflictError::Sql(err)
+    }
 }
 
-const SCHEMA_SQL: &str = r#"
-CREATE TABLE IF NOT EXISTS schema_version (
-  version INTEGER
+/// The highest schema version this binary's [`MIGRATIONS`] list produces.
+/// [`apply_migrations`] refuses to proceed if the database already claims
+/// a version above this.
+const CURRENT_SCHEMA_VERSION: i64 = 4;
+
+/// Ordered `(target_version, sql)` migration steps. Each step is applied at
+/// most once, inside its own `BEGIN IMMEDIATE` transaction, in ascending
+/// order starting just above the database's current `schema_version`.
+const MIGRATIONS: &[(i64, &str)] = &[
+    (1, SCHEMA_V1_SQL),
+    (2, SCHEMA_V2_SQL),
+    (3, SCHEMA_V3_SQL),
+    (4, SCHEMA_V4_SQL),
+];
+
+/// Adds the same versionstamp scheme `router_state` uses to
+/// `registered_groups`, plus a `deleted` tombstone flag, so gossip
+/// replication between router instances can converge on a last-writer-wins
+/// basis per `jid` and propagate deletions instead of just insert/update.
+const SCHEMA_V4_SQL: &str = r#"
+ALTER TABLE registered_groups ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE registered_groups ADD COLUMN deleted INTEGER NOT NULL DEFAULT 0;
+"#;
+
+/// A durable outbound delivery queue for `TransportMessage`s awaiting
+/// delivery to a device, so the router gets at-least-once delivery of
+/// `Command`/`HostCommand` frames across reconnects. `payload` stores the
+/// whole message as JSON rather than splitting `ttl_ms`/`issued_at` into
+/// their own columns, since [`Store::dequeue`] reconstructs the message
+/// anyway and `TransportMessage::is_expired` is the single source of truth
+/// for expiry.
+const SCHEMA_V3_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS command_queue (
+  message_id TEXT PRIMARY KEY,
+  device_id TEXT NOT NULL,
+  payload TEXT NOT NULL,
+  retry_count INTEGER NOT NULL DEFAULT 0,
+  visible_at INTEGER NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending'
+);
+CREATE INDEX IF NOT EXISTS idx_command_queue_dequeue ON command_queue(device_id, status, visible_at);
+"#;
+
+/// Adds a per-key versionstamp to `router_state` plus a single-row global
+/// counter it's stamped from, so [`Store::atomic_commit`] can hand out a
+/// monotonically increasing versionstamp shared across every key.
+const SCHEMA_V2_SQL: &str = r#"
+ALTER TABLE router_state ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
+CREATE TABLE IF NOT EXISTS kv_versionstamp (
+  id INTEGER PRIMARY KEY CHECK (id = 0),
+  value INTEGER NOT NULL
 );
+INSERT INTO kv_versionstamp (id, value) VALUES (0, 0);
+"#;
 
+const SCHEMA_V1_SQL: &str = r#"
 CREATE TABLE IF NOT EXISTS chats (
   jid TEXT PRIMARY KEY,
   name TEXT,
@@ -76,30 +218,223 @@ CREATE TABLE IF NOT EXISTS registered_groups (
 );
 "#;
 
-fn create_schema(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
-    conn.execute_batch(SCHEMA_SQL)?;
+/// Runs every migration in [`MIGRATIONS`] whose target version is greater
+/// than the database's current `schema_version` and no greater than
+/// `ceiling`, each inside its own `BEGIN IMMEDIATE` transaction so a
+/// failing step rolls back cleanly and never leaves `schema_version`
+/// pointing at a half-applied step.
+fn apply_migrations(conn: &Connection, ceiling: i64) -> Result<(), StoreError> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER)",
         [],
     )?;
     let count: i64 = conn.query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))?;
     if count == 0 {
-        conn.execute("INSERT INTO schema_version (version) VALUES (1)", [])?;
+        conn.execute("INSERT INTO schema_version (version) VALUES (0)", [])?;
+    }
+    let mut current: i64 =
+        conn.query_row("SELECT version FROM schema_version LIMIT 1", [], |row| {
+            row.get(0)
+        })?;
+
+    if current > CURRENT_SCHEMA_VERSION {
+        return Err(StoreError::UnsupportedSchemaVersion {
+            found: current,
+            max_known: CURRENT_SCHEMA_VERSION,
+        });
+    }
+
+    for (target_version, sql) in MIGRATIONS {
+        if *target_version <= current || *target_version > ceiling {
+            continue;
+        }
+        conn.execute_batch("BEGIN IMMEDIATE")?;
+        let step: rusqlite::Result<()> = (|| {
+            conn.execute_batch(sql)?;
+            conn.execute(
+                "UPDATE schema_version SET version = ?1",
+                params![target_version],
+            )?;
+            Ok(())
+        })();
+        match step {
+            Ok(()) => {
+                conn.execute_batch("COMMIT")?;
+                current = *target_version;
+            }
+            Err(err) => {
+                conn.execute_batch("ROLLBACK")?;
+                return Err(StoreError::from(err));
+            }
+        }
     }
     Ok(())
 }
 
+/// Creates (or, on a later open, verifies/backfills) the FTS5 shadow index
+/// over `messages(content, sender_name)` plus the triggers that keep it in
+/// sync with `INSERT`/`UPDATE`/`DELETE` on `messages`. Kept outside the
+/// versioned [`MIGRATIONS`] list since its success is a runtime property of
+/// the linked SQLite build, not a fixed schema shape: a build without FTS5
+/// simply fails this `execute_batch` and `Store` falls back to a `LIKE`
+/// scan, rather than refusing to open at all.
+fn ensure_search_index(conn: &Connection) -> bool {
+    conn.execute_batch(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+           content, sender_name, content='messages', content_rowid='rowid'
+         );
+         CREATE TRIGGER IF NOT EXISTS messages_fts_ai AFTER INSERT ON messages BEGIN
+           INSERT INTO messages_fts(rowid, content, sender_name)
+           VALUES (new.rowid, new.content, new.sender_name);
+         END;
+         CREATE TRIGGER IF NOT EXISTS messages_fts_ad AFTER DELETE ON messages BEGIN
+           INSERT INTO messages_fts(messages_fts, rowid, content, sender_name)
+           VALUES ('delete', old.rowid, old.content, old.sender_name);
+         END;
+         CREATE TRIGGER IF NOT EXISTS messages_fts_au AFTER UPDATE ON messages BEGIN
+           INSERT INTO messages_fts(messages_fts, rowid, content, sender_name)
+           VALUES ('delete', old.rowid, old.content, old.sender_name);
+           INSERT INTO messages_fts(rowid, content, sender_name)
+           VALUES (new.rowid, new.content, new.sender_name);
+         END;
+         INSERT INTO messages_fts(messages_fts) VALUES ('rebuild');",
+    )
+    .is_ok()
+}
+
 impl Store {
-    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StoreError> {
         let conn = Connection::open(path.as_ref())?;
-        create_schema(&conn)?;
-        Ok(Self { conn })
+        apply_migrations(&conn, CURRENT_SCHEMA_VERSION)?;
+        let fts_available = ensure_search_index(&conn);
+        Ok(Self { conn, fts_available })
     }
 
-    pub fn open_in_memory() -> rusqlite::Result<Self> {
+    pub fn open_in_memory() -> Result<Self, StoreError> {
         let conn = rusqlite::Connection::open_in_memory()?;
-        create_schema(&conn)?;
-        Ok(Self { conn })
+        apply_migrations(&conn, CURRENT_SCHEMA_VERSION)?;
+        let fts_available = ensure_search_index(&conn);
+        Ok(Self { conn, fts_available })
+    }
+
+    /// Runs any not-yet-applied migrations up to (and including) `version`,
+    /// without requiring the caller to reopen the store.
+    pub fn migrate_to(&self, version: i64) -> Result<(), StoreError> {
+        apply_migrations(&self.conn, version)
+    }
+
+    /// The target versions of migrations that haven't been applied yet,
+    /// for diagnostics (e.g. an admin command reporting a store is behind).
+    pub fn pending_migrations(&self) -> rusqlite::Result<Vec<i64>> {
+        let current = self.schema_version()?;
+        Ok(MIGRATIONS
+            .iter()
+            .map(|(version, _)| *version)
+            .filter(|version| *version > current)
+            .collect())
+    }
+
+    /// The current value and versionstamp of `router_state[key]`, or
+    /// `None` if it isn't set. A key that was never written behaves as
+    /// versionstamp `0` for [`Self::atomic_commit`]'s check purposes.
+    pub fn get_versioned(&self, key: &str) -> SqlResult<Option<(String, u64)>> {
+        self.conn
+            .query_row(
+                "SELECT value, version FROM router_state WHERE key = ?1",
+                params![key],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64)),
+            )
+            .optional()
+    }
+
+    /// Deno-KV-style atomic compare-and-set: inside one `BEGIN IMMEDIATE`
+    /// transaction, verifies every `checks` key is still at its expected
+    /// versionstamp (an absent key reads as `0`), then writes every
+    /// `mutations` pair under one freshly bumped global versionstamp. Any
+    /// mismatch aborts the whole write and reports the first conflicting
+    /// key, so callers get all-or-nothing multi-key updates without a
+    /// lost-update race.
+    pub fn atomic_commit(
+        &self,
+        checks: &[(&str, u64)],
+        mutations: &[(&str, &str)],
+    ) -> Result<CommitResult, ConflictError> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        match self.atomic_commit_locked(checks, mutations) {
+            Ok(versionstamp) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(CommitResult { versionstamp })
+            }
+            Err(err) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(err)
+            }
+        }
+    }
+
+    fn atomic_commit_locked(
+        &self,
+        checks: &[(&str, u64)],
+        mutations: &[(&str, &str)],
+    ) -> Result<u64, ConflictError> {
+        for (key, expected) in checks {
+            let found = self.router_state_version(key)?;
+            if found != *expected {
+                return Err(ConflictError::VersionMismatch {
+                    key: (*key).to_string(),
+                    expected: *expected,
+                    found,
+                });
+            }
+        }
+
+        let versionstamp = self.bump_versionstamp()?;
+        for (key, value) in mutations {
+            self.conn.execute(
+                "INSERT INTO router_state (key, value, version) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value, version = excluded.version",
+                params![key, value, versionstamp as i64],
+            )?;
+        }
+        Ok(versionstamp)
+    }
+
+    fn router_state_version(&self, key: &str) -> rusqlite::Result<u64> {
+        let version: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT version FROM router_state WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(version.unwrap_or(0) as u64)
+    }
+
+    fn bump_versionstamp(&self) -> rusqlite::Result<u64> {
+        self.conn
+            .execute("UPDATE kv_versionstamp SET value = value + 1 WHERE id = 0", [])?;
+        let value: i64 =
+            self.conn
+                .query_row("SELECT value FROM kv_versionstamp WHERE id = 0", [], |row| {
+                    row.get(0)
+                })?;
+        Ok(value as u64)
+    }
+
+    /// Raises the shared versionstamp counter to at least `value` if it's
+    /// currently behind, so a subsequent local `bump_versionstamp()` always
+    /// issues something higher than any version this node has already
+    /// observed (locally or via gossip). Without this, ingesting a remote
+    /// row ahead of our own counter would let the *next* local write stamp
+    /// that same jid with a lower version than what's already stored,
+    /// making a genuinely newer edit look older to every other peer.
+    fn advance_versionstamp_to(&self, value: u64) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "UPDATE kv_versionstamp SET value = ?1 WHERE id = 0 AND value < ?1",
+            params![value as i64],
+        )?;
+        Ok(())
     }
 
     pub fn conn(&self) -> &rusqlite::Connection {
@@ -113,17 +448,24 @@ impl Store {
             })
     }
 
+    /// Upserts `group` and stamps it with a freshly bumped versionstamp
+    /// (shared with [`Self::atomic_commit`]'s counter), so peers can tell
+    /// which of two copies of a group is newer during gossip replication.
+    /// Always clears any tombstone, since a local upsert is an un-delete.
     pub fn upsert_registered_group(&self, group: &RegisteredGroup) -> SqlResult<()> {
+        let version = self.bump_versionstamp()?;
         self.conn.execute(
-            "INSERT INTO registered_groups (jid, name, folder, trigger_pattern, added_at, container_config, requires_trigger)
-             VALUES (?, ?, ?, ?, ?, ?, ?)
+            "INSERT INTO registered_groups (jid, name, folder, trigger_pattern, added_at, container_config, requires_trigger, version, deleted)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0)
              ON CONFLICT(jid) DO UPDATE SET
                name = excluded.name,
                folder = excluded.folder,
                trigger_pattern = excluded.trigger_pattern,
                added_at = excluded.added_at,
                container_config = excluded.container_config,
-               requires_trigger = excluded.requires_trigger",
+               requires_trigger = excluded.requires_trigger,
+               version = excluded.version,
+               deleted = 0",
             params![
                 group.jid,
                 group.name,
@@ -131,16 +473,30 @@ impl Store {
                 group.trigger_pattern,
                 group.added_at,
                 group.container_config,
-                if group.requires_trigger { 1 } else { 0 }
+                if group.requires_trigger { 1 } else { 0 },
+                version as i64,
             ],
         )?;
         Ok(())
     }
 
+    /// Tombstones a group instead of hard-deleting it, so the deletion
+    /// itself has a versionstamp and can propagate via gossip to peers
+    /// that still have an older, undeleted copy.
+    pub fn delete_registered_group(&self, jid: &str) -> SqlResult<()> {
+        let version = self.bump_versionstamp()?;
+        self.conn.execute(
+            "UPDATE registered_groups SET deleted = 1, version = ?1 WHERE jid = ?2",
+            params![version as i64, jid],
+        )?;
+        Ok(())
+    }
+
     pub fn load_registered_groups(&self) -> SqlResult<Vec<RegisteredGroup>> {
         let mut stmt = self.conn.prepare(
             "SELECT jid, name, folder, trigger_pattern, added_at, container_config, requires_trigger
              FROM registered_groups
+             WHERE deleted = 0
              ORDER BY added_at ASC",
         )?;
         let rows = stmt.query_map([], |row| {
@@ -161,6 +517,121 @@ impl Store {
         Ok(groups)
     }
 
+    /// All jid -> versionstamp pairs, including tombstoned groups, for a
+    /// peer to compare against its own state via [`Self::diff_against`].
+    pub fn export_group_digest(&self) -> SqlResult<HashMap<String, u64>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT jid, version FROM registered_groups")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        let mut digest = HashMap::new();
+        for row in rows {
+            let (jid, version) = row?;
+            digest.insert(jid, version);
+        }
+        Ok(digest)
+    }
+
+    /// Compares a peer's digest against our own and returns the jids where
+    /// the peer is strictly ahead, i.e. the ones we should pull from them.
+    /// A jid absent locally reads as versionstamp `0`.
+    pub fn diff_against(&self, remote_digest: &HashMap<String, u64>) -> SqlResult<Vec<String>> {
+        let local = self.export_group_digest()?;
+        Ok(remote_digest
+            .iter()
+            .filter(|(jid, remote_version)| **remote_version > local.get(*jid).copied().unwrap_or(0))
+            .map(|(jid, _)| jid.clone())
+            .collect())
+    }
+
+    /// Fetches full rows (including version/tombstone state) for the given
+    /// jids, for a peer to pull after [`Self::diff_against`] flags them as
+    /// stale locally.
+    pub fn export_groups(&self, jids: &[String]) -> SqlResult<Vec<GossipGroupRow>> {
+        if jids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders = std::iter::repeat("?")
+            .take(jids.len())
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql = format!(
+            "SELECT jid, name, folder, trigger_pattern, added_at, container_config, requires_trigger, version, deleted
+             FROM registered_groups
+             WHERE jid IN ({})",
+            placeholders
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(jids.iter()), |row| {
+            Ok(GossipGroupRow {
+                group: RegisteredGroup {
+                    jid: row.get(0)?,
+                    name: row.get(1)?,
+                    folder: row.get(2)?,
+                    trigger_pattern: row.get(3)?,
+                    added_at: row.get(4)?,
+                    container_config: row.get(5)?,
+                    requires_trigger: row.get::<_, i64>(6)? != 0,
+                },
+                version: row.get::<_, i64>(7)? as u64,
+                deleted: row.get::<_, i64>(8)? != 0,
+            })
+        })?;
+        let mut groups = Vec::new();
+        for row in rows {
+            groups.push(row?);
+        }
+        Ok(groups)
+    }
+
+    /// Last-writer-wins merge of remote rows into our own table: a row is
+    /// only applied if it's strictly newer than what we already have,
+    /// so replaying the same gossip round twice is harmless.
+    pub fn apply_remote_groups(&self, rows: &[GossipGroupRow]) -> SqlResult<()> {
+        for row in rows {
+            let local_version = self
+                .conn
+                .query_row(
+                    "SELECT version FROM registered_groups WHERE jid = ?1",
+                    params![row.group.jid],
+                    |r| r.get::<_, i64>(0),
+                )
+                .optional()?
+                .unwrap_or(0) as u64;
+            if row.version <= local_version {
+                continue;
+            }
+            self.advance_versionstamp_to(row.version)?;
+            self.conn.execute(
+                "INSERT INTO registered_groups (jid, name, folder, trigger_pattern, added_at, container_config, requires_trigger, version, deleted)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(jid) DO UPDATE SET
+                   name = excluded.name,
+                   folder = excluded.folder,
+                   trigger_pattern = excluded.trigger_pattern,
+                   added_at = excluded.added_at,
+                   container_config = excluded.container_config,
+                   requires_trigger = excluded.requires_trigger,
+                   version = excluded.version,
+                   deleted = excluded.deleted",
+                params![
+                    row.group.jid,
+                    row.group.name,
+                    row.group.folder,
+                    row.group.trigger_pattern,
+                    row.group.added_at,
+                    row.group.container_config,
+                    if row.group.requires_trigger { 1 } else { 0 },
+                    row.version as i64,
+                    if row.deleted { 1 } else { 0 },
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
     pub fn store_message(&self, msg: &StoredMessage) -> SqlResult<()> {
         self.conn.execute(
             "INSERT OR IGNORE INTO chats (jid, name, last_message_time) VALUES (?, ?, ?)",
@@ -224,6 +695,252 @@ impl Store {
         }
         Ok(messages)
     }
+
+    /// Persists `msg` in the outbound queue for `msg.envelope.device_id`,
+    /// visible immediately. Re-enqueuing the same `message_id` replaces the
+    /// stored payload but keeps its existing retry/backoff state, so a
+    /// caller that re-sends an in-flight message doesn't reset its
+    /// delivery history.
+    pub fn enqueue(&self, msg: &TransportMessage, now_ms: u64) -> SqlResult<()> {
+        let payload = serde_json::to_string(msg)
+            .expect("TransportMessage always serializes to JSON");
+        self.conn.execute(
+            "INSERT INTO command_queue (message_id, device_id, payload, retry_count, visible_at, status)
+             VALUES (?1, ?2, ?3, 0, ?4, 'pending')
+             ON CONFLICT(message_id) DO UPDATE SET payload = excluded.payload",
+            params![
+                msg.envelope.message_id.as_str(),
+                msg.envelope.device_id,
+                payload,
+                now_ms as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Atomically selects the oldest visible, non-expired, still-`pending`
+    /// message queued for `device_id`, pushes its `visible_at` forward by
+    /// `lease_ms` so it isn't handed out again while in flight, and returns
+    /// it. Expired messages are deleted as they're scanned past, rather
+    /// than waiting on a separate sweep.
+    pub fn dequeue(
+        &self,
+        device_id: &str,
+        now_ms: u64,
+        lease_ms: u64,
+    ) -> SqlResult<Option<TransportMessage>> {
+        self.conn.execute_batch("BEGIN IMMEDIATE")?;
+        let result = self.dequeue_locked(device_id, now_ms, lease_ms);
+        match result {
+            Ok(msg) => {
+                self.conn.execute_batch("COMMIT")?;
+                Ok(msg)
+            }
+            Err(err) => {
+                let _ = self.conn.execute_batch("ROLLBACK");
+                Err(err)
+            }
+        }
+    }
+
+    fn dequeue_locked(
+        &self,
+        device_id: &str,
+        now_ms: u64,
+        lease_ms: u64,
+    ) -> SqlResult<Option<TransportMessage>> {
+        loop {
+            let row: Option<(String, String)> = self
+                .conn
+                .query_row(
+                    "SELECT message_id, payload FROM command_queue
+                     WHERE device_id = ?1 AND status = 'pending' AND visible_at <= ?2
+                     ORDER BY visible_at ASC LIMIT 1",
+                    params![device_id, now_ms as i64],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            let Some((message_id, payload)) = row else {
+                return Ok(None);
+            };
+
+            let parsed = serde_json::from_str::<TransportMessage>(&payload).ok();
+            let expired = match &parsed {
+                Some(msg) => msg.is_expired(now_ms),
+                None => true,
+            };
+            if expired {
+                self.conn.execute(
+                    "DELETE FROM command_queue WHERE message_id = ?1",
+                    params![message_id],
+                )?;
+                continue;
+            }
+
+            self.conn.execute(
+                "UPDATE command_queue SET visible_at = ?2 WHERE message_id = ?1",
+                params![message_id, now_ms.saturating_add(lease_ms) as i64],
+            )?;
+            return Ok(parsed);
+        }
+    }
+
+    /// Removes a successfully delivered message from the queue.
+    pub fn ack(&self, message_id: &str) -> SqlResult<()> {
+        self.conn.execute(
+            "DELETE FROM command_queue WHERE message_id = ?1",
+            params![message_id],
+        )?;
+        Ok(())
+    }
+
+    /// Records a failed delivery attempt: reschedules `message_id` after an
+    /// exponential backoff (capped) and bumps its retry counter, or drops
+    /// it to `status = 'dead_letter'` once [`MAX_DELIVERY_ATTEMPTS`] is
+    /// reached. A `message_id` no longer in the queue is a no-op.
+    pub fn nack(&self, message_id: &str, now_ms: u64) -> SqlResult<()> {
+        let retry_count: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT retry_count FROM command_queue WHERE message_id = ?1",
+                params![message_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(retry_count) = retry_count else {
+            return Ok(());
+        };
+        let next_retry_count = retry_count + 1;
+
+        if next_retry_count >= MAX_DELIVERY_ATTEMPTS {
+            self.conn.execute(
+                "UPDATE command_queue SET status = 'dead_letter', retry_count = ?2 WHERE message_id = ?1",
+                params![message_id, next_retry_count],
+            )?;
+            return Ok(());
+        }
+
+        let backoff_ms = backoff_for_attempt(next_retry_count as u32);
+        self.conn.execute(
+            "UPDATE command_queue SET retry_count = ?2, visible_at = ?3 WHERE message_id = ?1",
+            params![
+                message_id,
+                next_retry_count,
+                now_ms.saturating_add(backoff_ms) as i64
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Whether `search_messages` is backed by the FTS5 index rather than a
+    /// `LIKE` scan, for diagnostics.
+    pub fn fts_available(&self) -> bool {
+        self.fts_available
+    }
+
+    /// Full-text searches `content`/`sender_name` across `jids`, most
+    /// relevant first, bounded to `limit` rows. `query` accepts the
+    /// standard FTS5 query grammar (phrase quoting, prefix `*`, `AND`/`OR`/
+    /// `NOT`) when FTS5 is available; otherwise it's matched as a plain
+    /// substring of `content` only via `LIKE`, newest first.
+    pub fn search_messages(
+        &self,
+        jids: &[String],
+        query: &str,
+        limit: usize,
+    ) -> SqlResult<Vec<StoredMessage>> {
+        if jids.is_empty() || query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        if self.fts_available {
+            self.search_messages_fts(jids, query, limit)
+        } else {
+            self.search_messages_like(jids, query, limit)
+        }
+    }
+
+    fn search_messages_fts(
+        &self,
+        jids: &[String],
+        query: &str,
+        limit: usize,
+    ) -> SqlResult<Vec<StoredMessage>> {
+        let placeholders = std::iter::repeat("?")
+            .take(jids.len())
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql = format!(
+            "SELECT m.id, m.chat_jid, m.sender, m.sender_name, m.content, m.timestamp, m.is_from_me
+             FROM messages_fts
+             JOIN messages m ON m.rowid = messages_fts.rowid
+             WHERE messages_fts MATCH ? AND m.chat_jid IN ({placeholders})
+             ORDER BY bm25(messages_fts)
+             LIMIT ?"
+        );
+        let limit = limit as i64;
+        let mut query_params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(jids.len() + 2);
+        query_params.push(&query);
+        for jid in jids {
+            query_params.push(jid);
+        }
+        query_params.push(&limit);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(query_params.as_slice(), row_to_stored_message)?;
+        let mut messages = Vec::new();
+        for row in rows {
+            messages.push(row?);
+        }
+        Ok(messages)
+    }
+
+    fn search_messages_like(
+        &self,
+        jids: &[String],
+        query: &str,
+        limit: usize,
+    ) -> SqlResult<Vec<StoredMessage>> {
+        let placeholders = std::iter::repeat("?")
+            .take(jids.len())
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql = format!(
+            "SELECT id, chat_jid, sender, sender_name, content, timestamp, is_from_me
+             FROM messages
+             WHERE chat_jid IN ({placeholders}) AND content LIKE ?
+             ORDER BY timestamp DESC
+             LIMIT ?"
+        );
+        let like_pattern = format!("%{query}%");
+        let limit = limit as i64;
+        let mut query_params: Vec<&dyn rusqlite::ToSql> = Vec::with_capacity(jids.len() + 2);
+        for jid in jids {
+            query_params.push(jid);
+        }
+        query_params.push(&like_pattern);
+        query_params.push(&limit);
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt.query_map(query_params.as_slice(), row_to_stored_message)?;
+        let mut messages = Vec::new();
+        for row in rows {
+            messages.push(row?);
+        }
+        Ok(messages)
+    }
+}
+
+fn row_to_stored_message(row: &rusqlite::Row) -> rusqlite::Result<StoredMessage> {
+    Ok(StoredMessage {
+        id: row.get(0)?,
+        chat_jid: row.get(1)?,
+        sender: row.get(2)?,
+        sender_name: row.get(3)?,
+        content: row.get(4)?,
+        timestamp: row.get(5)?,
+        is_from_me: row.get::<_, i64>(6)? != 0,
+    })
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -237,6 +954,16 @@ pub struct RegisteredGroup {
     pub requires_trigger: bool,
 }
 
+/// A [`RegisteredGroup`] row as seen by gossip replication: the group's
+/// own fields plus the versionstamp and tombstone state needed to resolve
+/// last-writer-wins conflicts in [`Store::apply_remote_groups`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GossipGroupRow {
+    pub group: RegisteredGroup,
+    pub version: u64,
+    pub deleted: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StoredMessage {
     pub id: String,
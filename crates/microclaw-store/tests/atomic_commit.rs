@@ -0,0 +1,38 @@
+use microclaw_store::{ConflictError, Store};
+
+#[test]
+fn atomic_commit_applies_mutations_and_bumps_the_versionstamp() {
+    let store = Store::open_in_memory().unwrap();
+    assert_eq!(store.get_versioned("a").unwrap(), None);
+
+    let result = store
+        .atomic_commit(&[("a", 0)], &[("a", "1"), ("b", "2")])
+        .unwrap();
+
+    assert_eq!(store.get_versioned("a").unwrap(), Some(("1".to_string(), result.versionstamp)));
+    assert_eq!(store.get_versioned("b").unwrap(), Some(("2".to_string(), result.versionstamp)));
+}
+
+#[test]
+fn a_stale_check_aborts_the_whole_commit() {
+    let store = Store::open_in_memory().unwrap();
+    let first = store.atomic_commit(&[], &[("a", "1")]).unwrap();
+
+    let err = store
+        .atomic_commit(&[("a", 0)], &[("a", "2")])
+        .unwrap_err();
+    match err {
+        ConflictError::VersionMismatch { key, expected, found } => {
+            assert_eq!(key, "a");
+            assert_eq!(expected, 0);
+            assert_eq!(found, first.versionstamp);
+        }
+        ConflictError::Sql(e) => panic!("unexpected sql error: {e}"),
+    }
+
+    // The mutation must not have applied.
+    assert_eq!(
+        store.get_versioned("a").unwrap(),
+        Some(("1".to_string(), first.versionstamp))
+    );
+}
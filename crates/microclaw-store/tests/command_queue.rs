@@ -0,0 +1,59 @@
+use microclaw_protocol::{Envelope, MessageId, MessageKind, TransportMessage};
+use microclaw_store::Store;
+
+fn message(message_id: &str, ttl_ms: Option<u64>, issued_at: Option<u64>) -> TransportMessage {
+    let envelope = Envelope::new("host", "device-1", "session-1", MessageId::new(message_id));
+    let mut msg = TransportMessage::new(envelope, MessageKind::Command, serde_json::json!({}));
+    msg.ttl_ms = ttl_ms;
+    msg.issued_at = issued_at;
+    msg
+}
+
+#[test]
+fn dequeue_leases_a_message_and_ack_removes_it() {
+    let store = Store::open_in_memory().unwrap();
+    store.enqueue(&message("m1", None, None), 0).unwrap();
+
+    let leased = store.dequeue("device-1", 0, 10_000).unwrap().unwrap();
+    assert_eq!(leased.envelope.message_id.as_str(), "m1");
+
+    // Still leased, so a second dequeue before the lease expires sees nothing.
+    assert!(store.dequeue("device-1", 100, 10_000).unwrap().is_none());
+
+    store.ack("m1").unwrap();
+    assert!(store.dequeue("device-1", 20_000, 10_000).unwrap().is_none());
+}
+
+#[test]
+fn nack_reschedules_with_backoff_then_dead_letters_after_max_attempts() {
+    let store = Store::open_in_memory().unwrap();
+    store.enqueue(&message("m1", None, None), 0).unwrap();
+
+    // Backoff is capped well under this step, so each `now` is always past
+    // whatever `visible_at` the previous nack scheduled.
+    let mut now = 0u64;
+    for _ in 0..5 {
+        let leased = store.dequeue("device-1", now, 1).unwrap();
+        assert!(leased.is_some());
+        store.nack("m1", now).unwrap();
+        now += 1_000_000;
+    }
+
+    // The fifth failure should have dead-lettered it, so it never comes back.
+    assert!(store.dequeue("device-1", now, 1).unwrap().is_none());
+}
+
+#[test]
+fn an_expired_message_is_skipped_and_garbage_collected() {
+    let store = Store::open_in_memory().unwrap();
+    store
+        .enqueue(&message("m1", Some(1_000), Some(0)), 0)
+        .unwrap();
+
+    assert!(store.dequeue("device-1", 5_000, 10_000).unwrap().is_none());
+
+    // It was garbage-collected, so re-enqueuing with the same id starts fresh.
+    store.enqueue(&message("m1", None, None), 5_000).unwrap();
+    let leased = store.dequeue("device-1", 5_000, 10_000).unwrap().unwrap();
+    assert_eq!(leased.envelope.message_id.as_str(), "m1");
+}
@@ -0,0 +1,115 @@
+use microclaw_store::{GossipGroupRow, RegisteredGroup, Store};
+
+fn group(jid: &str, name: &str) -> RegisteredGroup {
+    RegisteredGroup {
+        jid: jid.to_string(),
+        name: name.to_string(),
+        folder: "default".to_string(),
+        trigger_pattern: "!bot".to_string(),
+        added_at: "2024-01-01T00:00:00Z".to_string(),
+        container_config: None,
+        requires_trigger: true,
+    }
+}
+
+#[test]
+fn diff_against_finds_jids_the_remote_is_ahead_on() {
+    let store = Store::open_in_memory().unwrap();
+    store.upsert_registered_group(&group("jid-1", "Team")).unwrap();
+
+    let local_digest = store.export_group_digest().unwrap();
+    let local_version = local_digest["jid-1"];
+
+    let mut remote_digest = local_digest.clone();
+    remote_digest.insert("jid-1".to_string(), local_version + 1);
+    remote_digest.insert("jid-2".to_string(), 1);
+
+    let mut stale = store.diff_against(&remote_digest).unwrap();
+    stale.sort();
+    assert_eq!(stale, vec!["jid-1".to_string(), "jid-2".to_string()]);
+}
+
+#[test]
+fn apply_remote_groups_ignores_an_older_version_and_applies_a_newer_one() {
+    let store = Store::open_in_memory().unwrap();
+    store.upsert_registered_group(&group("jid-1", "Original")).unwrap();
+    let local_version = store.export_group_digest().unwrap()["jid-1"];
+
+    store
+        .apply_remote_groups(&[GossipGroupRow {
+            group: group("jid-1", "Stale"),
+            version: local_version.saturating_sub(1),
+            deleted: false,
+        }])
+        .unwrap();
+    assert_eq!(store.load_registered_groups().unwrap()[0].name, "Original");
+
+    store
+        .apply_remote_groups(&[GossipGroupRow {
+            group: group("jid-1", "Updated"),
+            version: local_version + 1,
+            deleted: false,
+        }])
+        .unwrap();
+    assert_eq!(store.load_registered_groups().unwrap()[0].name, "Updated");
+}
+
+#[test]
+fn a_remote_tombstone_deletes_the_group_and_still_propagates_in_the_digest() {
+    let store = Store::open_in_memory().unwrap();
+    store.upsert_registered_group(&group("jid-1", "Team")).unwrap();
+    let local_version = store.export_group_digest().unwrap()["jid-1"];
+
+    store
+        .apply_remote_groups(&[GossipGroupRow {
+            group: group("jid-1", "Team"),
+            version: local_version + 1,
+            deleted: true,
+        }])
+        .unwrap();
+
+    assert!(store.load_registered_groups().unwrap().is_empty());
+    assert_eq!(store.export_group_digest().unwrap()["jid-1"], local_version + 1);
+}
+
+#[test]
+fn a_local_write_after_ingesting_gossip_stays_ahead_of_the_ingested_version() {
+    let store = Store::open_in_memory().unwrap();
+    store.upsert_registered_group(&group("jid-1", "Team")).unwrap();
+    let local_version = store.export_group_digest().unwrap()["jid-1"];
+
+    // A remote row arrives far ahead of our own versionstamp counter.
+    let far_ahead = local_version + 100;
+    store
+        .apply_remote_groups(&[GossipGroupRow {
+            group: group("jid-2", "Other Team"),
+            version: far_ahead,
+            deleted: false,
+        }])
+        .unwrap();
+
+    // A subsequent local edit of a *different* jid must still be stamped
+    // strictly ahead of everything already observed, or it will look
+    // stale to every other peer's diff_against forever.
+    store
+        .upsert_registered_group(&group("jid-1", "Renamed"))
+        .unwrap();
+    let new_local_version = store.export_group_digest().unwrap()["jid-1"];
+
+    assert!(new_local_version > far_ahead);
+}
+
+#[test]
+fn delete_registered_group_tombstones_instead_of_hard_deleting() {
+    let store = Store::open_in_memory().unwrap();
+    store.upsert_registered_group(&group("jid-1", "Team")).unwrap();
+
+    store.delete_registered_group("jid-1").unwrap();
+
+    assert!(store.load_registered_groups().unwrap().is_empty());
+    assert!(store.export_group_digest().unwrap().contains_key("jid-1"));
+
+    let exported = store.export_groups(&["jid-1".to_string()]).unwrap();
+    assert_eq!(exported.len(), 1);
+    assert!(exported[0].deleted);
+}
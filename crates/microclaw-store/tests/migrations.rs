@@ -4,5 +4,5 @@ use microclaw_store::Store;
 fn applies_migrations() {
     let store = Store::open_in_memory().unwrap();
     let version = store.schema_version().unwrap();
-    assert_eq!(version, 1);
+    assert_eq!(version, 4);
 }
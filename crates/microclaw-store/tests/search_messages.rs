@@ -0,0 +1,64 @@
+use microclaw_store::{Store, StoredMessage};
+
+fn message(id: &str, chat_jid: &str, content: &str) -> StoredMessage {
+    StoredMessage {
+        id: id.to_string(),
+        chat_jid: chat_jid.to_string(),
+        sender: "user".to_string(),
+        sender_name: "User".to_string(),
+        content: content.to_string(),
+        timestamp: "2024-01-01T00:00:00Z".to_string(),
+        is_from_me: false,
+    }
+}
+
+#[test]
+fn search_messages_finds_matching_content_within_the_given_jids() {
+    let store = Store::open_in_memory().unwrap();
+    store
+        .store_message(&message("m1", "jid-1", "let's deploy the new release"))
+        .unwrap();
+    store
+        .store_message(&message("m2", "jid-1", "completely unrelated chatter"))
+        .unwrap();
+    store
+        .store_message(&message("m3", "jid-2", "deploy that release too"))
+        .unwrap();
+
+    let found = store
+        .search_messages(&["jid-1".to_string()], "deploy", 10)
+        .unwrap();
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id, "m1");
+}
+
+#[test]
+fn search_messages_respects_the_limit() {
+    let store = Store::open_in_memory().unwrap();
+    for i in 0..5 {
+        store
+            .store_message(&message(&format!("m{i}"), "jid-1", "deploy status update"))
+            .unwrap();
+    }
+
+    let found = store
+        .search_messages(&["jid-1".to_string()], "deploy", 2)
+        .unwrap();
+
+    assert_eq!(found.len(), 2);
+}
+
+#[test]
+fn an_empty_query_or_jid_list_returns_no_results() {
+    let store = Store::open_in_memory().unwrap();
+    store
+        .store_message(&message("m1", "jid-1", "deploy status update"))
+        .unwrap();
+
+    assert!(store
+        .search_messages(&["jid-1".to_string()], "", 10)
+        .unwrap()
+        .is_empty());
+    assert!(store.search_messages(&[], "deploy", 10).unwrap().is_empty());
+}